@@ -0,0 +1,25 @@
+fn main() {
+    #[cfg(feature = "generate-header")]
+    generate_header();
+}
+
+/// Regenerates the header so that it can't drift from the exported API. The committed
+/// copy in `include/` is what C consumers build against, and CI checks that it is up to
+/// date.
+#[cfg(feature = "generate-header")]
+fn generate_header() {
+    use std::env;
+    use std::path::PathBuf;
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file(crate_dir.join("include").join("age.h"));
+        }
+        Err(e) => println!("cargo:warning=Failed to generate age.h: {}", e),
+    }
+}
@@ -0,0 +1,267 @@
+/*
+ * C API for the age encryption library.
+ *
+ * This file is generated by cbindgen from the age-ffi crate. Do not edit it by hand.
+ */
+
+#ifndef AGE_H
+#define AGE_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+
+// Status codes returned by every fallible function in the age C API.
+//
+// When a function returns anything other than `AGE_STATUS_OK`, a description of the
+// failure is available from `age_last_error_message` on the same thread.
+typedef enum AgeStatus {
+  // The operation succeeded.
+  AGE_STATUS_OK = 0,
+  // A required pointer was null, or a string was not valid UTF-8.
+  AGE_STATUS_INVALID_ARGUMENT,
+  // A key string could not be parsed.
+  AGE_STATUS_INVALID_KEY,
+  // A read or write callback failed, or some other I/O error occurred.
+  AGE_STATUS_IO,
+  // The age file failed to decrypt.
+  AGE_STATUS_DECRYPTION_FAILED,
+  // The age file used an excessive work factor for passphrase encryption.
+  AGE_STATUS_EXCESSIVE_WORK,
+  // The age header was invalid.
+  AGE_STATUS_INVALID_HEADER,
+  // The MAC in the age header was invalid.
+  AGE_STATUS_INVALID_MAC,
+  // Failed to decrypt an encrypted key.
+  AGE_STATUS_KEY_DECRYPTION_FAILED,
+  // A required plugin could not be found.
+  AGE_STATUS_MISSING_PLUGIN,
+  // None of the provided identities could be used to decrypt the age file.
+  AGE_STATUS_NO_MATCHING_KEYS,
+  // A plugin returned an error.
+  AGE_STATUS_PLUGIN,
+  // An unknown age format, probably from a newer version.
+  AGE_STATUS_UNKNOWN_FORMAT,
+  // The age file is encrypted with a passphrase, which this API does not support.
+  AGE_STATUS_PASSPHRASE_ENCRYPTED,
+  // An error occurred while decrypting passphrase-encrypted identities.
+  AGE_STATUS_ENCRYPTED_IDENTITIES,
+  // The library panicked. The handles involved should be freed and not reused.
+  AGE_STATUS_PANIC,
+} AgeStatus;
+
+// An opaque handle to an in-progress decryption.
+//
+// Created with `age_decryptor_new`, which reads and checks the age header. Plaintext
+// is read out with `age_decryptor_read`.
+typedef struct AgeDecryptor AgeDecryptor;
+
+// An opaque handle to an in-progress encryption.
+//
+// Created with `age_encryptor_new`. Plaintext is passed in with `age_encryptor_write`,
+// and the encryption must be completed with `age_encryptor_finish`; otherwise the
+// output will be truncated and fail to decrypt.
+typedef struct AgeEncryptor AgeEncryptor;
+
+// An opaque handle to an age identity (a secret key).
+//
+// Only X25519 identities are supported; SSH private keys can't be used to decrypt.
+// Created with `age_identity_generate` or `age_identity_parse`, and freed with
+// `age_identity_free`.
+typedef struct AgeIdentity AgeIdentity;
+
+// An opaque handle to an age recipient (a public key).
+//
+// Created with `age_recipient_parse` or `age_identity_to_public`, and freed with
+// `age_recipient_free`.
+typedef struct AgeRecipient AgeRecipient;
+
+// Callback used to read encrypted input.
+//
+// Called with the `ctx` pointer given when the decryptor was created, and a buffer with
+// space for `len` bytes. Returns the number of bytes read, `0` at end of input, or a
+// negative value on error.
+typedef ptrdiff_t (*AgeReadFn)(void *ctx, uint8_t *buf, size_t len);
+
+// Callback used to write encrypted output.
+//
+// Called with the `ctx` pointer given when the encryptor was created, and a buffer of
+// `len` bytes. Returns the number of bytes consumed (which may be less than `len`), or a
+// negative value on error.
+typedef ptrdiff_t (*AgeWriteFn)(void *ctx, const uint8_t *buf, size_t len);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Frees a decryptor. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `decryptor` must be `NULL` or a handle returned by this library that has not already
+// been freed.
+void age_decryptor_free(AgeDecryptor *decryptor);
+
+// Starts decrypting an age file with the given identities.
+//
+// The input may be either binary or ASCII-armored. This reads the age header from
+// `read`, and fails if none of the identities can decrypt it. Passphrase-encrypted
+// files are rejected with `AGE_STATUS_PASSPHRASE_ENCRYPTED`.
+//
+// # Safety
+//
+// - `identities` must point to `identities_len` valid identity handles. The handles
+//   are not consumed, and may be freed as soon as this function returns.
+// - `read` must be a valid callback, and `ctx` must remain valid for it until the
+//   decryptor is freed.
+// - `out` must be a valid pointer. On success, `*out` is set to a new decryptor that
+//   must be freed with `age_decryptor_free`.
+AgeStatus age_decryptor_new(const AgeIdentity *const *identities,
+                            size_t identities_len,
+                            AgeReadFn read,
+                            void *ctx,
+                            AgeDecryptor **out);
+
+// Reads up to `len` bytes of decrypted plaintext into `buf`.
+//
+// `*read` is set to the number of bytes read, which is `0` once the end of the file
+// has been reached.
+//
+// # Safety
+//
+// `decryptor` must be a valid decryptor handle, `buf` must point to `len` writable
+// bytes, and `read` must be a valid pointer.
+AgeStatus age_decryptor_read(AgeDecryptor *decryptor, uint8_t *buf, size_t len, size_t *read);
+
+// Completes the encryption, writing out the final chunk and any armor footer.
+//
+// The encryptor is consumed by this call, whether or not it succeeds.
+//
+// # Safety
+//
+// `encryptor` must be a valid encryptor handle, which must not be used again.
+AgeStatus age_encryptor_finish(AgeEncryptor *encryptor);
+
+// Frees an encryptor without completing the encryption. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `encryptor` must be `NULL` or a handle returned by this library that has not already
+// been finished or freed.
+void age_encryptor_free(AgeEncryptor *encryptor);
+
+// Starts encrypting to the given recipients.
+//
+// If `armor` is true, the output is encoded with the age ASCII armor format.
+//
+// # Safety
+//
+// - `recipients` must point to `recipients_len` valid recipient handles. The handles
+//   are not consumed, and may be freed as soon as this function returns.
+// - `write` must be a valid callback, and `ctx` must remain valid for it until the
+//   encryptor is finished or freed.
+// - `out` must be a valid pointer. On success, `*out` is set to a new encryptor that
+//   must be passed to either `age_encryptor_finish` or `age_encryptor_free`.
+AgeStatus age_encryptor_new(const AgeRecipient *const *recipients,
+                            size_t recipients_len,
+                            bool armor,
+                            AgeWriteFn write,
+                            void *ctx,
+                            AgeEncryptor **out);
+
+// Encrypts `len` bytes of plaintext from `buf`.
+//
+// # Safety
+//
+// `encryptor` must be a valid encryptor handle, and `buf` must point to `len` readable
+// bytes (or may be `NULL` if `len` is zero).
+AgeStatus age_encryptor_write(AgeEncryptor *encryptor, const uint8_t *buf, size_t len);
+
+// Frees an identity. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `identity` must be `NULL` or a handle returned by this library that has not already
+// been freed.
+void age_identity_free(AgeIdentity *identity);
+
+// Generates a new random X25519 identity.
+//
+// # Safety
+//
+// `out` must be a valid pointer. On success, `*out` is set to a new identity that must
+// be freed with `age_identity_free`.
+AgeStatus age_identity_generate(AgeIdentity **out);
+
+// Parses an X25519 identity from its `AGE-SECRET-KEY-1...` encoding.
+//
+// # Safety
+//
+// `s` must be a valid NUL-terminated string, and `out` must be a valid pointer. On
+// success, `*out` is set to a new identity that must be freed with
+// `age_identity_free`.
+AgeStatus age_identity_parse(const char *s, AgeIdentity **out);
+
+// Returns the recipient corresponding to an identity.
+//
+// # Safety
+//
+// `identity` must be a valid identity handle, and `out` must be a valid pointer. On
+// success, `*out` is set to a new recipient that must be freed with
+// `age_recipient_free`.
+AgeStatus age_identity_to_public(const AgeIdentity *identity, AgeRecipient **out);
+
+// Serializes an identity to its `AGE-SECRET-KEY-1...` encoding.
+//
+// # Safety
+//
+// `identity` must be a valid identity handle, and `out` must be a valid pointer. On
+// success, `*out` is set to a new string that must be freed with `age_string_free`.
+// The string contains secret key material.
+AgeStatus age_identity_to_string(const AgeIdentity *identity, char **out);
+
+// Returns a description of the last error that occurred on the calling thread, or
+// `NULL` if the last call succeeded.
+//
+// The returned string is owned by the library, and is valid until the next call into
+// the age C API on the same thread. It must not be freed.
+const char *age_last_error_message(void);
+
+// Frees a recipient. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `recipient` must be `NULL` or a handle returned by this library that has not already
+// been freed.
+void age_recipient_free(AgeRecipient *recipient);
+
+// Parses a recipient from either an `age1...` X25519 encoding or an SSH public key.
+//
+// # Safety
+//
+// `s` must be a valid NUL-terminated string, and `out` must be a valid pointer. On
+// success, `*out` is set to a new recipient that must be freed with
+// `age_recipient_free`.
+AgeStatus age_recipient_parse(const char *s, AgeRecipient **out);
+
+// Serializes a recipient to its string encoding.
+//
+// # Safety
+//
+// `recipient` must be a valid recipient handle, and `out` must be a valid pointer. On
+// success, `*out` is set to a new string that must be freed with `age_string_free`.
+AgeStatus age_recipient_to_string(const AgeRecipient *recipient, char **out);
+
+// Frees a string returned by this library. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `s` must be `NULL` or a string returned by this library that has not already been
+// freed.
+void age_string_free(char *s);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* AGE_H */
@@ -0,0 +1,128 @@
+//! Status codes and error reporting.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use age::{DecryptError, EncryptError};
+
+/// Status codes returned by every fallible function in the age C API.
+///
+/// When a function returns anything other than `AGE_STATUS_OK`, a description of the
+/// failure is available from `age_last_error_message` on the same thread.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgeStatus {
+    /// The operation succeeded.
+    Ok = 0,
+    /// A required pointer was null, or a string was not valid UTF-8.
+    InvalidArgument,
+    /// A key string could not be parsed.
+    InvalidKey,
+    /// A read or write callback failed, or some other I/O error occurred.
+    Io,
+    /// The age file failed to decrypt.
+    DecryptionFailed,
+    /// The age file used an excessive work factor for passphrase encryption.
+    ExcessiveWork,
+    /// The age header was invalid.
+    InvalidHeader,
+    /// The MAC in the age header was invalid.
+    InvalidMac,
+    /// Failed to decrypt an encrypted key.
+    KeyDecryptionFailed,
+    /// A required plugin could not be found.
+    MissingPlugin,
+    /// None of the provided identities could be used to decrypt the age file.
+    NoMatchingKeys,
+    /// A plugin returned an error.
+    Plugin,
+    /// An unknown age format, probably from a newer version.
+    UnknownFormat,
+    /// The age file is encrypted with a passphrase, which this API does not support.
+    PassphraseEncrypted,
+    /// An error occurred while decrypting passphrase-encrypted identities.
+    EncryptedIdentities,
+    /// The library panicked. The handles involved should be freed and not reused.
+    Panic,
+}
+
+impl From<&DecryptError> for AgeStatus {
+    fn from(e: &DecryptError) -> Self {
+        match e {
+            DecryptError::DecryptionFailed => AgeStatus::DecryptionFailed,
+            DecryptError::ExcessiveWork { .. } => AgeStatus::ExcessiveWork,
+            DecryptError::InvalidHeader => AgeStatus::InvalidHeader,
+            DecryptError::InvalidMac => AgeStatus::InvalidMac,
+            DecryptError::Io(_) => AgeStatus::Io,
+            DecryptError::KeyDecryptionFailed => AgeStatus::KeyDecryptionFailed,
+            DecryptError::MissingPlugin { .. } => AgeStatus::MissingPlugin,
+            DecryptError::NoMatchingKeys => AgeStatus::NoMatchingKeys,
+            DecryptError::Plugin(_) => AgeStatus::Plugin,
+            DecryptError::UnknownFormat => AgeStatus::UnknownFormat,
+        }
+    }
+}
+
+impl From<&EncryptError> for AgeStatus {
+    fn from(e: &EncryptError) -> Self {
+        match e {
+            EncryptError::EncryptedIdentities(_) => AgeStatus::EncryptedIdentities,
+            EncryptError::Io(_) => AgeStatus::Io,
+            EncryptError::MissingPlugin { .. } => AgeStatus::MissingPlugin,
+            EncryptError::Plugin(_) => AgeStatus::Plugin,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Records `msg` as the last error on this thread, and returns `status`.
+pub(crate) fn fail(status: AgeStatus, msg: impl fmt::Display) -> AgeStatus {
+    // Interior NUL bytes would truncate the message on the C side anyway.
+    let msg = msg.to_string().replace('\0', "");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(msg).ok());
+    status
+}
+
+pub(crate) fn decrypt_error(e: DecryptError) -> AgeStatus {
+    fail((&e).into(), e)
+}
+
+pub(crate) fn encrypt_error(e: EncryptError) -> AgeStatus {
+    fail((&e).into(), e)
+}
+
+/// Runs `f`, converting its result into a status code and catching any panic so that
+/// it does not unwind across the FFI boundary.
+pub(crate) fn guard<F>(f: F) -> AgeStatus
+where
+    F: FnOnce() -> Result<(), AgeStatus>,
+{
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => AgeStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => fail(AgeStatus::Panic, "age panicked"),
+    }
+}
+
+/// Returns a description of the last error that occurred on the calling thread, or
+/// `NULL` if the last call succeeded.
+///
+/// The returned string is owned by the library, and is valid until the next call into
+/// the age C API on the same thread. It must not be freed.
+#[no_mangle]
+pub extern "C" fn age_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|msg| msg.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
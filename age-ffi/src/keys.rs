@@ -0,0 +1,236 @@
+//! Identity and recipient handles.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+use age::secrecy::ExposeSecret;
+
+use crate::error::{fail, guard, AgeStatus};
+
+/// An opaque handle to an age identity (a secret key).
+///
+/// Only X25519 identities are supported; SSH private keys can't be used to decrypt.
+/// Created with `age_identity_generate` or `age_identity_parse`, and freed with
+/// `age_identity_free`.
+pub struct AgeIdentity(pub(crate) age::x25519::Identity);
+
+/// An opaque handle to an age recipient (a public key).
+///
+/// Created with `age_recipient_parse` or `age_identity_to_public`, and freed with
+/// `age_recipient_free`.
+pub struct AgeRecipient(pub(crate) Recipient);
+
+#[derive(Clone)]
+pub(crate) enum Recipient {
+    X25519(age::x25519::Recipient),
+    Ssh(age::ssh::Recipient),
+}
+
+impl Recipient {
+    pub(crate) fn to_boxed(&self) -> Box<dyn age::Recipient> {
+        match self {
+            Recipient::X25519(r) => Box::new(r.clone()),
+            Recipient::Ssh(r) => Box::new(r.clone()),
+        }
+    }
+}
+
+/// Reads a NUL-terminated UTF-8 string from the caller.
+pub(crate) unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, AgeStatus> {
+    if s.is_null() {
+        return Err(fail(AgeStatus::InvalidArgument, "string argument is NULL"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| fail(AgeStatus::InvalidArgument, "string is not valid UTF-8"))
+}
+
+/// Checks that an output pointer is non-null.
+pub(crate) fn out_arg<T>(out: *mut T) -> Result<*mut T, AgeStatus> {
+    if out.is_null() {
+        Err(fail(AgeStatus::InvalidArgument, "output argument is NULL"))
+    } else {
+        Ok(out)
+    }
+}
+
+/// Passes ownership of `s` to the caller, to be freed with `age_string_free`.
+fn string_out(s: String) -> Result<*mut c_char, AgeStatus> {
+    CString::new(s)
+        .map(CString::into_raw)
+        .map_err(|_| fail(AgeStatus::InvalidKey, "encoding contains a NUL byte"))
+}
+
+/// Generates a new random X25519 identity.
+///
+/// # Safety
+///
+/// `out` must be a valid pointer. On success, `*out` is set to a new identity that must
+/// be freed with `age_identity_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_identity_generate(out: *mut *mut AgeIdentity) -> AgeStatus {
+    guard(|| {
+        let out = out_arg(out)?;
+        *out = Box::into_raw(Box::new(AgeIdentity(age::x25519::Identity::generate())));
+        Ok(())
+    })
+}
+
+/// Parses an X25519 identity from its `AGE-SECRET-KEY-1...` encoding.
+///
+/// # Safety
+///
+/// `s` must be a valid NUL-terminated string, and `out` must be a valid pointer. On
+/// success, `*out` is set to a new identity that must be freed with
+/// `age_identity_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_identity_parse(
+    s: *const c_char,
+    out: *mut *mut AgeIdentity,
+) -> AgeStatus {
+    guard(|| {
+        let s = str_arg(s)?;
+        let out = out_arg(out)?;
+        let identity = s
+            .parse::<age::x25519::Identity>()
+            .map_err(|e| fail(AgeStatus::InvalidKey, e))?;
+        *out = Box::into_raw(Box::new(AgeIdentity(identity)));
+        Ok(())
+    })
+}
+
+/// Serializes an identity to its `AGE-SECRET-KEY-1...` encoding.
+///
+/// # Safety
+///
+/// `identity` must be a valid identity handle, and `out` must be a valid pointer. On
+/// success, `*out` is set to a new string that must be freed with `age_string_free`.
+/// The string contains secret key material.
+#[no_mangle]
+pub unsafe extern "C" fn age_identity_to_string(
+    identity: *const AgeIdentity,
+    out: *mut *mut c_char,
+) -> AgeStatus {
+    guard(|| {
+        let identity = identity
+            .as_ref()
+            .ok_or_else(|| fail(AgeStatus::InvalidArgument, "identity is NULL"))?;
+        let out = out_arg(out)?;
+        *out = string_out(identity.0.to_string().expose_secret().clone())?;
+        Ok(())
+    })
+}
+
+/// Returns the recipient corresponding to an identity.
+///
+/// # Safety
+///
+/// `identity` must be a valid identity handle, and `out` must be a valid pointer. On
+/// success, `*out` is set to a new recipient that must be freed with
+/// `age_recipient_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_identity_to_public(
+    identity: *const AgeIdentity,
+    out: *mut *mut AgeRecipient,
+) -> AgeStatus {
+    guard(|| {
+        let identity = identity
+            .as_ref()
+            .ok_or_else(|| fail(AgeStatus::InvalidArgument, "identity is NULL"))?;
+        let out = out_arg(out)?;
+        *out = Box::into_raw(Box::new(AgeRecipient(Recipient::X25519(
+            identity.0.to_public(),
+        ))));
+        Ok(())
+    })
+}
+
+/// Frees an identity. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `identity` must be `NULL` or a handle returned by this library that has not already
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn age_identity_free(identity: *mut AgeIdentity) {
+    if !identity.is_null() {
+        drop(Box::from_raw(identity));
+    }
+}
+
+/// Parses a recipient from either an `age1...` X25519 encoding or an SSH public key.
+///
+/// # Safety
+///
+/// `s` must be a valid NUL-terminated string, and `out` must be a valid pointer. On
+/// success, `*out` is set to a new recipient that must be freed with
+/// `age_recipient_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_recipient_parse(
+    s: *const c_char,
+    out: *mut *mut AgeRecipient,
+) -> AgeStatus {
+    guard(|| {
+        let s = str_arg(s)?;
+        let out = out_arg(out)?;
+        let recipient = if let Ok(pk) = s.parse::<age::x25519::Recipient>() {
+            Recipient::X25519(pk)
+        } else if let Ok(pk) = s.parse::<age::ssh::Recipient>() {
+            Recipient::Ssh(pk)
+        } else {
+            return Err(fail(AgeStatus::InvalidKey, "invalid recipient"));
+        };
+        *out = Box::into_raw(Box::new(AgeRecipient(recipient)));
+        Ok(())
+    })
+}
+
+/// Serializes a recipient to its string encoding.
+///
+/// # Safety
+///
+/// `recipient` must be a valid recipient handle, and `out` must be a valid pointer. On
+/// success, `*out` is set to a new string that must be freed with `age_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_recipient_to_string(
+    recipient: *const AgeRecipient,
+    out: *mut *mut c_char,
+) -> AgeStatus {
+    guard(|| {
+        let recipient = recipient
+            .as_ref()
+            .ok_or_else(|| fail(AgeStatus::InvalidArgument, "recipient is NULL"))?;
+        let out = out_arg(out)?;
+        *out = string_out(match &recipient.0 {
+            Recipient::X25519(pk) => pk.to_string(),
+            Recipient::Ssh(pk) => pk.to_string(),
+        })?;
+        Ok(())
+    })
+}
+
+/// Frees a recipient. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `recipient` must be `NULL` or a handle returned by this library that has not already
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn age_recipient_free(recipient: *mut AgeRecipient) {
+    if !recipient.is_null() {
+        drop(Box::from_raw(recipient));
+    }
+}
+
+/// Frees a string returned by this library. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by this library that has not already been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn age_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,205 @@
+//! *C API for the age encryption library*
+//!
+//! This crate exposes a stable C ABI over the [`age`] crate, so that C and C++ programs
+//! can encrypt and decrypt age files without shelling out to `rage`. The corresponding
+//! header is `include/age.h`, which is generated from this crate by [cbindgen] when
+//! the `generate-header` feature flag is enabled.
+//!
+//! The API is built around opaque handles:
+//! - `AgeIdentity` holds an X25519 identity, and `AgeRecipient` holds an X25519 or SSH
+//!   recipient. SSH identities are not supported. They are created by
+//!   `age_identity_generate`, `age_identity_parse`, `age_identity_to_public`, and
+//!   `age_recipient_parse`.
+//! - `AgeEncryptor` and `AgeDecryptor` stream data through caller-provided write and
+//!   read callbacks. Encryption can optionally apply the age ASCII armor format, and
+//!   decryption accepts either format.
+//!
+//! Every fallible function returns an `AgeStatus`. On failure, a description of the
+//! error is available from `age_last_error_message`. Strings returned by the library
+//! must be freed with `age_string_free`, and every handle has a matching `_free`
+//! function.
+//!
+//! [cbindgen]: https://github.com/eqrion/cbindgen
+
+// Catch documentation errors caused by code changes.
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(missing_docs)]
+
+mod error;
+mod keys;
+mod stream;
+
+pub use error::{age_last_error_message, AgeStatus};
+pub use keys::{
+    age_identity_free, age_identity_generate, age_identity_parse, age_identity_to_public,
+    age_identity_to_string, age_recipient_free, age_recipient_parse, age_recipient_to_string,
+    age_string_free, AgeIdentity, AgeRecipient,
+};
+pub use stream::{
+    age_decryptor_free, age_decryptor_new, age_decryptor_read, age_encryptor_finish,
+    age_encryptor_free, age_encryptor_new, age_encryptor_write, AgeDecryptor, AgeEncryptor,
+    AgeReadFn, AgeWriteFn,
+};
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::os::raw::c_void;
+    use std::ptr;
+
+    use super::*;
+
+    unsafe extern "C" fn write_vec(ctx: *mut c_void, buf: *const u8, len: usize) -> isize {
+        let out = &mut *(ctx as *mut Vec<u8>);
+        out.extend_from_slice(std::slice::from_raw_parts(buf, len));
+        len as isize
+    }
+
+    unsafe extern "C" fn read_slice(ctx: *mut c_void, buf: *mut u8, len: usize) -> isize {
+        let input = &mut *(ctx as *mut &[u8]);
+        let n = std::cmp::min(len, input.len());
+        ptr::copy_nonoverlapping(input.as_ptr(), buf, n);
+        *input = &input[n..];
+        n as isize
+    }
+
+    unsafe fn encrypt(recipient: *const AgeRecipient, armor: bool, plaintext: &[u8]) -> Vec<u8> {
+        let mut encrypted = vec![];
+        let mut enc = ptr::null_mut();
+        assert_eq!(
+            age_encryptor_new(
+                &recipient,
+                1,
+                armor,
+                Some(write_vec),
+                &mut encrypted as *mut Vec<u8> as *mut c_void,
+                &mut enc,
+            ),
+            AgeStatus::Ok
+        );
+        assert_eq!(
+            age_encryptor_write(enc, plaintext.as_ptr(), plaintext.len()),
+            AgeStatus::Ok
+        );
+        assert_eq!(age_encryptor_finish(enc), AgeStatus::Ok);
+        encrypted
+    }
+
+    unsafe fn decrypt(
+        identity: *const AgeIdentity,
+        encrypted: &[u8],
+    ) -> Result<Vec<u8>, AgeStatus> {
+        let mut input = encrypted;
+        let mut dec = ptr::null_mut();
+        match age_decryptor_new(
+            &identity,
+            1,
+            Some(read_slice),
+            &mut input as *mut &[u8] as *mut c_void,
+            &mut dec,
+        ) {
+            AgeStatus::Ok => (),
+            e => return Err(e),
+        }
+
+        let mut decrypted = vec![];
+        let mut buf = [0; 100];
+        loop {
+            let mut read = 0;
+            let status = age_decryptor_read(dec, buf.as_mut_ptr(), buf.len(), &mut read);
+            if status != AgeStatus::Ok {
+                age_decryptor_free(dec);
+                return Err(status);
+            }
+            if read == 0 {
+                break;
+            }
+            decrypted.extend_from_slice(&buf[..read]);
+        }
+        age_decryptor_free(dec);
+        Ok(decrypted)
+    }
+
+    #[test]
+    fn round_trip() {
+        let plaintext = b"Hello from C!";
+        unsafe {
+            let mut identity = ptr::null_mut();
+            assert_eq!(age_identity_generate(&mut identity), AgeStatus::Ok);
+            let mut recipient = ptr::null_mut();
+            assert_eq!(
+                age_identity_to_public(identity, &mut recipient),
+                AgeStatus::Ok
+            );
+
+            for armor in [false, true] {
+                let encrypted = encrypt(recipient, armor, plaintext);
+                assert_eq!(encrypted.starts_with(b"-----BEGIN"), armor);
+                assert_eq!(decrypt(identity, &encrypted).unwrap(), plaintext);
+            }
+
+            age_recipient_free(recipient);
+            age_identity_free(identity);
+        }
+    }
+
+    #[test]
+    fn wrong_identity() {
+        unsafe {
+            let mut identity = ptr::null_mut();
+            let mut other = ptr::null_mut();
+            let mut recipient = ptr::null_mut();
+            assert_eq!(age_identity_generate(&mut identity), AgeStatus::Ok);
+            assert_eq!(age_identity_generate(&mut other), AgeStatus::Ok);
+            assert_eq!(
+                age_identity_to_public(identity, &mut recipient),
+                AgeStatus::Ok
+            );
+
+            let encrypted = encrypt(recipient, false, b"secret");
+            assert_eq!(decrypt(other, &encrypted), Err(AgeStatus::NoMatchingKeys));
+            assert!(!age_last_error_message().is_null());
+
+            age_recipient_free(recipient);
+            age_identity_free(other);
+            age_identity_free(identity);
+        }
+    }
+
+    #[test]
+    fn key_encoding() {
+        let sk = "AGE-SECRET-KEY-1GQ9778VQXMMJVE8SK7J6VT8UJ4HDQAJUVSFCWCM02D8GEWQ72PVQ2Y5J33\0";
+        let pk = "age1t7rxyev2z3rw82stdlrrepyc39nvn86l5078zqkf5uasdy86jp6svpy7pa";
+        unsafe {
+            let mut identity = ptr::null_mut();
+            assert_eq!(
+                age_identity_parse(sk.as_ptr().cast(), &mut identity),
+                AgeStatus::Ok
+            );
+            let mut recipient = ptr::null_mut();
+            assert_eq!(
+                age_identity_to_public(identity, &mut recipient),
+                AgeStatus::Ok
+            );
+            let mut encoded = ptr::null_mut();
+            assert_eq!(
+                age_recipient_to_string(recipient, &mut encoded),
+                AgeStatus::Ok
+            );
+            assert_eq!(CStr::from_ptr(encoded).to_str().unwrap(), pk);
+            age_string_free(encoded);
+
+            assert_eq!(
+                age_recipient_parse(b"age1invalid\0".as_ptr().cast(), &mut recipient),
+                AgeStatus::InvalidKey
+            );
+            assert_eq!(
+                age_identity_parse(ptr::null(), &mut identity),
+                AgeStatus::InvalidArgument
+            );
+
+            age_recipient_free(recipient);
+            age_identity_free(identity);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Encryption and decryption streams.
+
+use std::io::{self, BufReader, Read, Write};
+use std::os::raw::c_void;
+use std::slice;
+
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    stream::{StreamReader, StreamWriter},
+};
+
+use crate::{
+    error::{decrypt_error, encrypt_error, fail, guard, AgeStatus},
+    keys::{out_arg, AgeIdentity, AgeRecipient},
+};
+
+/// Callback used to write encrypted output.
+///
+/// Called with the `ctx` pointer given when the encryptor was created, and a buffer of
+/// `len` bytes. Returns the number of bytes consumed (which may be less than `len`), or a
+/// negative value on error.
+pub type AgeWriteFn =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, buf: *const u8, len: usize) -> isize>;
+
+/// Callback used to read encrypted input.
+///
+/// Called with the `ctx` pointer given when the decryptor was created, and a buffer with
+/// space for `len` bytes. Returns the number of bytes read, `0` at end of input, or a
+/// negative value on error.
+pub type AgeReadFn =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, buf: *mut u8, len: usize) -> isize>;
+
+struct CallbackWriter {
+    write: unsafe extern "C" fn(*mut c_void, *const u8, usize) -> isize,
+    ctx: *mut c_void,
+}
+
+impl Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe { (self.write)(self.ctx, buf.as_ptr(), buf.len()) };
+        if n < 0 || n as usize > buf.len() {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "write callback failed",
+            ))
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct CallbackReader {
+    read: unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> isize,
+    ctx: *mut c_void,
+}
+
+impl Read for CallbackReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe { (self.read)(self.ctx, buf.as_mut_ptr(), buf.len()) };
+        if n < 0 || n as usize > buf.len() {
+            Err(io::Error::new(io::ErrorKind::Other, "read callback failed"))
+        } else {
+            Ok(n as usize)
+        }
+    }
+}
+
+/// An opaque handle to an in-progress encryption.
+///
+/// Created with `age_encryptor_new`. Plaintext is passed in with `age_encryptor_write`,
+/// and the encryption must be completed with `age_encryptor_finish`; otherwise the
+/// output will be truncated and fail to decrypt.
+pub struct AgeEncryptor(StreamWriter<ArmoredWriter<CallbackWriter>>);
+
+/// An opaque handle to an in-progress decryption.
+///
+/// Created with `age_decryptor_new`, which reads and checks the age header. Plaintext
+/// is read out with `age_decryptor_read`.
+pub struct AgeDecryptor(StreamReader<ArmoredReader<BufReader<CallbackReader>>>);
+
+/// Starts encrypting to the given recipients.
+///
+/// If `armor` is true, the output is encoded with the age ASCII armor format.
+///
+/// # Safety
+///
+/// - `recipients` must point to `recipients_len` valid recipient handles. The handles
+///   are not consumed, and may be freed as soon as this function returns.
+/// - `write` must be a valid callback, and `ctx` must remain valid for it until the
+///   encryptor is finished or freed.
+/// - `out` must be a valid pointer. On success, `*out` is set to a new encryptor that
+///   must be passed to either `age_encryptor_finish` or `age_encryptor_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_encryptor_new(
+    recipients: *const *const AgeRecipient,
+    recipients_len: usize,
+    armor: bool,
+    write: AgeWriteFn,
+    ctx: *mut c_void,
+    out: *mut *mut AgeEncryptor,
+) -> AgeStatus {
+    guard(|| {
+        let out = out_arg(out)?;
+        let write = write.ok_or_else(|| fail(AgeStatus::InvalidArgument, "write is NULL"))?;
+        if recipients.is_null() || recipients_len == 0 {
+            return Err(fail(AgeStatus::InvalidArgument, "no recipients provided"));
+        }
+        let recipients = slice::from_raw_parts(recipients, recipients_len)
+            .iter()
+            .map(|r| {
+                r.as_ref()
+                    .map(|r| r.0.to_boxed())
+                    .ok_or_else(|| fail(AgeStatus::InvalidArgument, "recipient is NULL"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let output = ArmoredWriter::wrap_output(
+            CallbackWriter { write, ctx },
+            if armor {
+                Format::AsciiArmor
+            } else {
+                Format::Binary
+            },
+        )
+        .map_err(|e| fail(AgeStatus::Io, e))?;
+        let writer = age::Encryptor::with_recipients(recipients)
+            .wrap_output(output)
+            .map_err(encrypt_error)?;
+
+        *out = Box::into_raw(Box::new(AgeEncryptor(writer)));
+        Ok(())
+    })
+}
+
+/// Encrypts `len` bytes of plaintext from `buf`.
+///
+/// # Safety
+///
+/// `encryptor` must be a valid encryptor handle, and `buf` must point to `len` readable
+/// bytes (or may be `NULL` if `len` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn age_encryptor_write(
+    encryptor: *mut AgeEncryptor,
+    buf: *const u8,
+    len: usize,
+) -> AgeStatus {
+    guard(|| {
+        let encryptor = encryptor
+            .as_mut()
+            .ok_or_else(|| fail(AgeStatus::InvalidArgument, "encryptor is NULL"))?;
+        if len == 0 {
+            return Ok(());
+        }
+        if buf.is_null() {
+            return Err(fail(AgeStatus::InvalidArgument, "buf is NULL"));
+        }
+        encryptor
+            .0
+            .write_all(slice::from_raw_parts(buf, len))
+            .map_err(|e| fail(AgeStatus::Io, e))
+    })
+}
+
+/// Completes the encryption, writing out the final chunk and any armor footer.
+///
+/// The encryptor is consumed by this call, whether or not it succeeds.
+///
+/// # Safety
+///
+/// `encryptor` must be a valid encryptor handle, which must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn age_encryptor_finish(encryptor: *mut AgeEncryptor) -> AgeStatus {
+    guard(|| {
+        if encryptor.is_null() {
+            return Err(fail(AgeStatus::InvalidArgument, "encryptor is NULL"));
+        }
+        let encryptor = Box::from_raw(encryptor);
+        encryptor
+            .0
+            .finish()
+            .and_then(|armor| armor.finish())
+            .map(|_| ())
+            .map_err(|e| fail(AgeStatus::Io, e))
+    })
+}
+
+/// Frees an encryptor without completing the encryption. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `encryptor` must be `NULL` or a handle returned by this library that has not already
+/// been finished or freed.
+#[no_mangle]
+pub unsafe extern "C" fn age_encryptor_free(encryptor: *mut AgeEncryptor) {
+    if !encryptor.is_null() {
+        drop(Box::from_raw(encryptor));
+    }
+}
+
+/// Starts decrypting an age file with the given identities.
+///
+/// The input may be either binary or ASCII-armored. This reads the age header from
+/// `read`, and fails if none of the identities can decrypt it. Passphrase-encrypted
+/// files are rejected with `AGE_STATUS_PASSPHRASE_ENCRYPTED`.
+///
+/// # Safety
+///
+/// - `identities` must point to `identities_len` valid identity handles. The handles
+///   are not consumed, and may be freed as soon as this function returns.
+/// - `read` must be a valid callback, and `ctx` must remain valid for it until the
+///   decryptor is freed.
+/// - `out` must be a valid pointer. On success, `*out` is set to a new decryptor that
+///   must be freed with `age_decryptor_free`.
+#[no_mangle]
+pub unsafe extern "C" fn age_decryptor_new(
+    identities: *const *const AgeIdentity,
+    identities_len: usize,
+    read: AgeReadFn,
+    ctx: *mut c_void,
+    out: *mut *mut AgeDecryptor,
+) -> AgeStatus {
+    guard(|| {
+        let out = out_arg(out)?;
+        let read = read.ok_or_else(|| fail(AgeStatus::InvalidArgument, "read is NULL"))?;
+        if identities.is_null() || identities_len == 0 {
+            return Err(fail(AgeStatus::InvalidArgument, "no identities provided"));
+        }
+        let identities = slice::from_raw_parts(identities, identities_len)
+            .iter()
+            .map(|i| {
+                i.as_ref()
+                    .map(|i| &i.0)
+                    .ok_or_else(|| fail(AgeStatus::InvalidArgument, "identity is NULL"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let input = ArmoredReader::new(CallbackReader { read, ctx });
+        let reader = match age::Decryptor::new(input).map_err(decrypt_error)? {
+            age::Decryptor::Recipients(d) => d
+                .decrypt(identities.iter().map(|i| *i as &dyn age::Identity))
+                .map_err(decrypt_error)?,
+            age::Decryptor::Passphrase(_) => {
+                return Err(fail(
+                    AgeStatus::PassphraseEncrypted,
+                    "file is encrypted with a passphrase",
+                ))
+            }
+        };
+
+        *out = Box::into_raw(Box::new(AgeDecryptor(reader)));
+        Ok(())
+    })
+}
+
+/// Reads up to `len` bytes of decrypted plaintext into `buf`.
+///
+/// `*read` is set to the number of bytes read, which is `0` once the end of the file
+/// has been reached.
+///
+/// # Safety
+///
+/// `decryptor` must be a valid decryptor handle, `buf` must point to `len` writable
+/// bytes, and `read` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn age_decryptor_read(
+    decryptor: *mut AgeDecryptor,
+    buf: *mut u8,
+    len: usize,
+    read: *mut usize,
+) -> AgeStatus {
+    guard(|| {
+        let decryptor = decryptor
+            .as_mut()
+            .ok_or_else(|| fail(AgeStatus::InvalidArgument, "decryptor is NULL"))?;
+        let read = out_arg(read)?;
+        if len == 0 {
+            *read = 0;
+            return Ok(());
+        }
+        if buf.is_null() {
+            return Err(fail(AgeStatus::InvalidArgument, "buf is NULL"));
+        }
+        let n = decryptor
+            .0
+            .read(slice::from_raw_parts_mut(buf, len))
+            .map_err(|e| {
+                // The STREAM reader reports authentication failures as InvalidData.
+                if e.kind() == io::ErrorKind::InvalidData {
+                    fail(AgeStatus::DecryptionFailed, e)
+                } else {
+                    fail(AgeStatus::Io, e)
+                }
+            })?;
+        *read = n;
+        Ok(())
+    })
+}
+
+/// Frees a decryptor. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `decryptor` must be `NULL` or a handle returned by this library that has not already
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn age_decryptor_free(decryptor: *mut AgeDecryptor) {
+    if !decryptor.is_null() {
+        drop(Box::from_raw(decryptor));
+    }
+}
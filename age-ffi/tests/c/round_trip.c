@@ -0,0 +1,156 @@
+/*
+ * Round-trip test for the age C API.
+ *
+ * Built and run by tests/c_api.rs against the static library.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "age.h"
+
+#define CHECK(expr)                                                            \
+    do {                                                                       \
+        AgeStatus status_ = (expr);                                            \
+        if (status_ != AGE_STATUS_OK) {                                        \
+            const char *msg_ = age_last_error_message();                       \
+            fprintf(stderr, "%s:%d: %s failed with %d: %s\n", __FILE__,        \
+                    __LINE__, #expr, (int)status_, msg_ ? msg_ : "(none)");    \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define EXPECT(cond)                                                           \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            fprintf(stderr, "%s:%d: expected %s\n", __FILE__, __LINE__, #cond); \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+typedef struct {
+    uint8_t *data;
+    size_t len;
+    size_t cap;
+    size_t pos;
+} buffer;
+
+static ptrdiff_t buffer_write(void *ctx, const uint8_t *buf, size_t len) {
+    buffer *b = ctx;
+    if (b->len + len > b->cap) {
+        b->cap = (b->len + len) * 2;
+        b->data = realloc(b->data, b->cap);
+        if (b->data == NULL) {
+            return -1;
+        }
+    }
+    memcpy(b->data + b->len, buf, len);
+    b->len += len;
+    return (ptrdiff_t)len;
+}
+
+static ptrdiff_t buffer_read(void *ctx, uint8_t *buf, size_t len) {
+    buffer *b = ctx;
+    size_t n = b->len - b->pos;
+    if (n > len) {
+        n = len;
+    }
+    memcpy(buf, b->data + b->pos, n);
+    b->pos += n;
+    return (ptrdiff_t)n;
+}
+
+static const char PLAINTEXT[] = "Hello from C!";
+
+static buffer encrypt(const AgeRecipient *recipient, bool armor) {
+    buffer out = {0};
+    AgeEncryptor *enc = NULL;
+    CHECK(age_encryptor_new(&recipient, 1, armor, buffer_write, &out, &enc));
+    CHECK(age_encryptor_write(enc, (const uint8_t *)PLAINTEXT, strlen(PLAINTEXT)));
+    CHECK(age_encryptor_finish(enc));
+    return out;
+}
+
+static AgeStatus decrypt(const AgeIdentity *identity, buffer *in, char *out, size_t cap,
+                         size_t *out_len) {
+    AgeDecryptor *dec = NULL;
+    AgeStatus status = age_decryptor_new(&identity, 1, buffer_read, in, &dec);
+    if (status != AGE_STATUS_OK) {
+        return status;
+    }
+
+    *out_len = 0;
+    for (;;) {
+        size_t read = 0;
+        status = age_decryptor_read(dec, (uint8_t *)out + *out_len, cap - *out_len, &read);
+        if (status != AGE_STATUS_OK || read == 0) {
+            break;
+        }
+        *out_len += read;
+    }
+    age_decryptor_free(dec);
+    return status;
+}
+
+int main(void) {
+    AgeIdentity *identity = NULL;
+    AgeRecipient *recipient = NULL;
+    CHECK(age_identity_generate(&identity));
+    CHECK(age_identity_to_public(identity, &recipient));
+
+    /* Keys survive a round trip through their string encodings. */
+    char *sk = NULL;
+    char *pk = NULL;
+    CHECK(age_identity_to_string(identity, &sk));
+    CHECK(age_recipient_to_string(recipient, &pk));
+    EXPECT(strncmp(sk, "AGE-SECRET-KEY-1", 16) == 0);
+    EXPECT(strncmp(pk, "age1", 4) == 0);
+
+    AgeIdentity *parsed_identity = NULL;
+    AgeRecipient *parsed_recipient = NULL;
+    CHECK(age_identity_parse(sk, &parsed_identity));
+    CHECK(age_recipient_parse(pk, &parsed_recipient));
+    age_string_free(sk);
+    age_string_free(pk);
+
+    /* Encrypt to the parsed recipient, and decrypt with the parsed identity. */
+    for (int armor = 0; armor <= 1; armor++) {
+        buffer encrypted = encrypt(parsed_recipient, armor);
+        EXPECT((memcmp(encrypted.data, "-----BEGIN", 10) == 0) == armor);
+
+        char decrypted[64];
+        size_t decrypted_len = 0;
+        CHECK(decrypt(parsed_identity, &encrypted, decrypted, sizeof(decrypted),
+                      &decrypted_len));
+        EXPECT(decrypted_len == strlen(PLAINTEXT));
+        EXPECT(memcmp(decrypted, PLAINTEXT, decrypted_len) == 0);
+        free(encrypted.data);
+    }
+
+    /* A different identity is rejected with a specific status code. */
+    AgeIdentity *other = NULL;
+    CHECK(age_identity_generate(&other));
+    buffer encrypted = encrypt(recipient, false);
+    char decrypted[64];
+    size_t decrypted_len = 0;
+    EXPECT(decrypt(other, &encrypted, decrypted, sizeof(decrypted), &decrypted_len) ==
+           AGE_STATUS_NO_MATCHING_KEYS);
+    EXPECT(age_last_error_message() != NULL);
+    free(encrypted.data);
+
+    /* Invalid inputs are reported rather than crashing. */
+    AgeRecipient *invalid = NULL;
+    EXPECT(age_recipient_parse("age1invalid", &invalid) == AGE_STATUS_INVALID_KEY);
+    EXPECT(invalid == NULL);
+    EXPECT(age_identity_parse(NULL, &other) == AGE_STATUS_INVALID_ARGUMENT);
+
+    age_identity_free(other);
+    age_identity_free(parsed_identity);
+    age_recipient_free(parsed_recipient);
+    age_recipient_free(recipient);
+    age_identity_free(identity);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,48 @@
+//! Builds and runs the C test program against the static library.
+//!
+//! This uses a Unix C toolchain and link flags, so it is not run on Windows.
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the directory containing the library artifacts for the current profile.
+fn artifact_dir() -> PathBuf {
+    // Integration tests run from `target/<profile>/deps/`.
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn c_round_trip() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib = artifact_dir().join("libage_ffi.a");
+    assert!(lib.exists(), "{} not found", lib.display());
+
+    let bin = Path::new(env!("CARGO_TARGET_TMPDIR")).join("age_ffi_round_trip");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(cc)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests").join("c").join("round_trip.c"))
+        .arg(&lib)
+        .args(&["-lpthread", "-ldl", "-lm"])
+        .arg("-o")
+        .arg(&bin)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "failed to compile round_trip.c");
+
+    let output = Command::new(&bin).output().unwrap();
+    assert!(
+        output.status.success(),
+        "round_trip failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"ok\n");
+}
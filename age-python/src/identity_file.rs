@@ -0,0 +1,58 @@
+//! The `pyrage.IdentityFile` class.
+
+use std::io::BufReader;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{plugin, x25519};
+
+/// A list of identities parsed from an age identity file.
+///
+/// Identity files contain one identity per line, with `#` comments and blank lines
+/// ignored. Both native and plugin identities are supported.
+#[pyclass(module = "pyrage")]
+pub(crate) struct IdentityFile(Vec<age::IdentityFileEntry>);
+
+#[pymethods]
+impl IdentityFile {
+    /// Reads an identity file from the given path.
+    #[staticmethod]
+    #[pyo3(text_signature = "(path)")]
+    fn from_file(path: String) -> PyResult<Self> {
+        Ok(IdentityFile(
+            age::IdentityFile::from_file(path)?.into_identities(),
+        ))
+    }
+
+    /// Parses an identity file from its contents.
+    #[staticmethod]
+    #[pyo3(text_signature = "(buf)")]
+    fn from_buffer(buf: &[u8]) -> PyResult<Self> {
+        Ok(IdentityFile(
+            age::IdentityFile::from_buffer(BufReader::new(buf))?.into_identities(),
+        ))
+    }
+
+    /// Returns the identities in this file, as a list of `pyrage.x25519.Identity` and
+    /// `pyrage.plugin.Identity` objects.
+    #[pyo3(text_signature = "($self)")]
+    fn identities(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        self.0
+            .iter()
+            .map(|entry| match entry {
+                age::IdentityFileEntry::Native(sk) => Ok(x25519::Identity(sk.clone()).into_py(py)),
+                age::IdentityFileEntry::Plugin(sk) => Ok(plugin::Identity(sk.clone()).into_py(py)),
+                // Other identity types can be enabled on the age crate by other crates
+                // in the same build, but are not exposed by these bindings.
+                #[allow(unreachable_patterns)]
+                _ => Err(PyValueError::new_err(
+                    "identity file contains an unsupported identity type",
+                )),
+            })
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
@@ -0,0 +1,330 @@
+//! *Python bindings for the age encryption library*
+//!
+//! This crate builds the `pyrage` Python extension module with [PyO3]. It wraps the
+//! [`age`] crate's [`Encryptor`] and [`Decryptor`] APIs, offering both bytes-in,
+//! bytes-out helpers (`pyrage.encrypt` and `pyrage.decrypt`) and file-like streaming
+//! objects (`pyrage.Encryptor` and `pyrage.Decryptor`).
+//!
+//! Keys are provided by submodules:
+//! - `pyrage.x25519` for native age keys.
+//! - `pyrage.ssh` for SSH keys.
+//! - `pyrage.plugin` for keys handled by age plugins.
+//! - `pyrage.passphrase` for passphrase-based encryption.
+//!
+//! [PyO3]: https://pyo3.rs
+//! [`Encryptor`]: age::Encryptor
+//! [`Decryptor`]: age::Decryptor
+
+// Catch documentation errors caused by code changes.
+#![deny(rustdoc::broken_intra_doc_links)]
+
+use std::io::{Read, Write};
+
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyTypeError},
+    prelude::*,
+    types::PyBytes,
+};
+
+mod identity_file;
+mod passphrase;
+mod plugin;
+mod ssh;
+mod stream;
+mod x25519;
+
+create_exception!(pyrage, EncryptError, PyException);
+create_exception!(pyrage, DecryptError, PyException);
+
+pub(crate) fn encrypt_error(e: age::EncryptError) -> PyErr {
+    EncryptError::new_err(e.to_string())
+}
+
+pub(crate) fn decrypt_error(e: age::DecryptError) -> PyErr {
+    DecryptError::new_err(e.to_string())
+}
+
+/// Converts a list of Python recipient objects into age recipients.
+///
+/// Plugin recipients are grouped by plugin, so that each plugin binary is started once.
+pub(crate) fn recipients_from(recipients: Vec<&PyAny>) -> PyResult<Vec<Box<dyn age::Recipient>>> {
+    let mut res: Vec<Box<dyn age::Recipient>> = vec![];
+    let mut plugin_recipients: Vec<age::plugin::Recipient> = vec![];
+
+    for recipient in recipients {
+        if let Ok(pk) = recipient.extract::<x25519::Recipient>() {
+            res.push(Box::new(pk.0));
+        } else if let Ok(pk) = recipient.extract::<ssh::Recipient>() {
+            res.push(Box::new(pk.0));
+        } else if let Ok(pk) = recipient.extract::<plugin::Recipient>() {
+            plugin_recipients.push(pk.0);
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "unsupported recipient type: {}",
+                recipient.get_type().name()?
+            )));
+        }
+    }
+
+    let mut plugin_names: Vec<_> = plugin_recipients
+        .iter()
+        .map(|r| r.plugin().to_owned())
+        .collect();
+    plugin_names.sort();
+    plugin_names.dedup();
+    for plugin_name in plugin_names {
+        res.push(Box::new(
+            age::plugin::RecipientPluginV1::new(
+                &plugin_name,
+                &plugin_recipients,
+                &[],
+                plugin::NoCallbacks,
+            )
+            .map_err(encrypt_error)?,
+        ));
+    }
+
+    if res.is_empty() {
+        return Err(EncryptError::new_err("no recipients provided"));
+    }
+    Ok(res)
+}
+
+/// A set of identities converted from Python identity objects.
+///
+/// SSH identities are borrowed from their Python objects, because they cannot be cloned.
+pub(crate) struct Identities<'py> {
+    owned: Vec<Box<dyn age::Identity>>,
+    ssh: Vec<PyRef<'py, ssh::Identity>>,
+}
+
+impl<'py> Identities<'py> {
+    pub(crate) fn from_py(identities: Vec<&'py PyAny>) -> PyResult<Self> {
+        let mut owned: Vec<Box<dyn age::Identity>> = vec![];
+        let mut ssh = vec![];
+        let mut plugin_identities: Vec<age::plugin::Identity> = vec![];
+
+        for identity in identities {
+            if let Ok(sk) = identity.extract::<x25519::Identity>() {
+                owned.push(Box::new(sk.0));
+            } else if let Ok(sk) = identity.extract::<PyRef<ssh::Identity>>() {
+                ssh.push(sk);
+            } else if let Ok(sk) = identity.extract::<plugin::Identity>() {
+                plugin_identities.push(sk.0);
+            } else {
+                return Err(PyTypeError::new_err(format!(
+                    "unsupported identity type: {}",
+                    identity.get_type().name()?
+                )));
+            }
+        }
+
+        let mut plugin_names: Vec<_> = plugin_identities
+            .iter()
+            .map(|i| i.plugin().to_owned())
+            .collect();
+        plugin_names.sort();
+        plugin_names.dedup();
+        for plugin_name in plugin_names {
+            owned.push(Box::new(
+                age::plugin::IdentityPluginV1::new(
+                    &plugin_name,
+                    &plugin_identities,
+                    plugin::NoCallbacks,
+                )
+                .map_err(decrypt_error)?,
+            ));
+        }
+
+        if owned.is_empty() && ssh.is_empty() {
+            return Err(DecryptError::new_err("no identities provided"));
+        }
+        Ok(Identities { owned, ssh })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &dyn age::Identity> {
+        self.owned
+            .iter()
+            .map(|i| i.as_ref())
+            .chain(self.ssh.iter().map(|i| &i.0 as &dyn age::Identity))
+    }
+}
+
+/// Encrypts with the given encryptor, returning the ciphertext.
+pub(crate) fn encrypt_bytes(
+    encryptor: age::Encryptor,
+    plaintext: &[u8],
+    armor: bool,
+) -> PyResult<Vec<u8>> {
+    let mut encrypted = vec![];
+    let output = age::armor::ArmoredWriter::wrap_output(
+        &mut encrypted,
+        if armor {
+            age::armor::Format::AsciiArmor
+        } else {
+            age::armor::Format::Binary
+        },
+    )?;
+    let mut writer = encryptor.wrap_output(output).map_err(encrypt_error)?;
+    writer.write_all(plaintext)?;
+    writer.finish().and_then(|armor| armor.finish())?;
+    Ok(encrypted)
+}
+
+/// Reads an entire decrypted stream, mapping payload errors to `DecryptError`.
+pub(crate) fn read_all(mut reader: impl Read) -> PyResult<Vec<u8>> {
+    let mut decrypted = vec![];
+    reader
+        .read_to_end(&mut decrypted)
+        .map_err(stream::read_error)?;
+    Ok(decrypted)
+}
+
+/// Encrypts `plaintext` to the given list of recipients, returning the age-encrypted
+/// bytes. If `armor` is true, the output uses the age ASCII armor format.
+#[pyfunction]
+#[args(armor = "false")]
+#[pyo3(text_signature = "(plaintext, recipients, armor=False)")]
+fn encrypt<'py>(
+    py: Python<'py>,
+    plaintext: &[u8],
+    recipients: Vec<&PyAny>,
+    armor: bool,
+) -> PyResult<&'py PyBytes> {
+    let encryptor = age::Encryptor::with_recipients(recipients_from(recipients)?);
+    let encrypted = encrypt_bytes(encryptor, plaintext, armor)?;
+    Ok(PyBytes::new(py, &encrypted))
+}
+
+/// Decrypts an age-encrypted (binary or armored) `ciphertext` with any of the given
+/// identities, returning the plaintext.
+#[pyfunction]
+#[pyo3(text_signature = "(ciphertext, identities)")]
+fn decrypt<'py>(
+    py: Python<'py>,
+    ciphertext: &[u8],
+    identities: Vec<&'py PyAny>,
+) -> PyResult<&'py PyBytes> {
+    let identities = Identities::from_py(identities)?;
+    let reader = match age::Decryptor::new(age::armor::ArmoredReader::new(ciphertext))
+        .map_err(decrypt_error)?
+    {
+        age::Decryptor::Recipients(d) => d.decrypt(identities.iter()).map_err(decrypt_error)?,
+        age::Decryptor::Passphrase(_) => {
+            return Err(DecryptError::new_err(
+                "file is passphrase-encrypted; use pyrage.passphrase.decrypt",
+            ))
+        }
+    };
+    Ok(PyBytes::new(py, &read_all(reader)?))
+}
+
+/// Registers `module` as a submodule of `parent`, importable as `pyrage.<name>`.
+fn add_submodule(py: Python, parent: &PyModule, module: &PyModule) -> PyResult<()> {
+    parent.add_submodule(module)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item(format!("pyrage.{}", module.name()?), module)
+}
+
+#[pymodule]
+fn pyrage(py: Python, m: &PyModule) -> PyResult<()> {
+    add_submodule(py, m, x25519::module(py)?)?;
+    add_submodule(py, m, ssh::module(py)?)?;
+    add_submodule(py, m, plugin::module(py)?)?;
+    add_submodule(py, m, passphrase::module(py)?)?;
+
+    m.add("EncryptError", py.get_type::<EncryptError>())?;
+    m.add("DecryptError", py.get_type::<DecryptError>())?;
+    m.add_class::<identity_file::IdentityFile>()?;
+    m.add_class::<stream::Encryptor>()?;
+    m.add_class::<stream::Decryptor>()?;
+    m.add_function(wrap_pyfunction!(encrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use pyo3::{exceptions::PyTypeError, prelude::*, types::PyString};
+
+    use super::{
+        encrypt_bytes, read_all, recipients_from, stream, x25519, DecryptError, EncryptError,
+        Identities,
+    };
+
+    fn with_py<F: FnOnce(Python)>(f: F) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f)
+    }
+
+    #[test]
+    fn round_trip() {
+        with_py(|py| {
+            let sk = age::x25519::Identity::generate();
+            let pk = x25519::Recipient(sk.to_public()).into_py(py).into_ref(py);
+            let sk = x25519::Identity(sk).into_py(py).into_ref(py);
+
+            for armor in [false, true] {
+                let encryptor = age::Encryptor::with_recipients(recipients_from(vec![pk]).unwrap());
+                let encrypted = encrypt_bytes(encryptor, b"Hello world!", armor).unwrap();
+                assert_eq!(
+                    encrypted.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"),
+                    armor
+                );
+
+                let identities = Identities::from_py(vec![sk]).unwrap();
+                let reader =
+                    match age::Decryptor::new(age::armor::ArmoredReader::new(&encrypted[..]))
+                        .unwrap()
+                    {
+                        age::Decryptor::Recipients(d) => d.decrypt(identities.iter()).unwrap(),
+                        age::Decryptor::Passphrase(_) => panic!(),
+                    };
+                assert_eq!(read_all(reader).unwrap(), b"Hello world!");
+            }
+        });
+    }
+
+    #[test]
+    fn rejects_missing_and_unsupported_keys() {
+        with_py(|py| {
+            assert!(recipients_from(vec![])
+                .err()
+                .unwrap()
+                .is_instance_of::<EncryptError>(py));
+            assert!(Identities::from_py(vec![])
+                .err()
+                .unwrap()
+                .is_instance_of::<DecryptError>(py));
+
+            let s: &PyAny = PyString::new(py, "age1...");
+            assert!(recipients_from(vec![s])
+                .err()
+                .unwrap()
+                .is_instance_of::<PyTypeError>(py));
+            assert!(Identities::from_py(vec![s])
+                .err()
+                .unwrap()
+                .is_instance_of::<PyTypeError>(py));
+        });
+    }
+
+    #[test]
+    fn read_errors() {
+        with_py(|py| {
+            for kind in [io::ErrorKind::InvalidData, io::ErrorKind::UnexpectedEof] {
+                assert!(stream::read_error(io::Error::new(kind, "bad"))
+                    .is_instance_of::<DecryptError>(py));
+            }
+            assert!(
+                !stream::read_error(io::Error::new(io::ErrorKind::NotFound, "missing"))
+                    .is_instance_of::<DecryptError>(py)
+            );
+        });
+    }
+}
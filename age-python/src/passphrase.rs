@@ -0,0 +1,49 @@
+//! The `pyrage.passphrase` module.
+
+use age::secrecy::Secret;
+use pyo3::{prelude::*, types::PyBytes};
+
+use crate::{decrypt_error, encrypt_bytes, read_all, DecryptError};
+
+/// Encrypts `plaintext` with a passphrase, returning the age-encrypted bytes. If `armor`
+/// is true, the output uses the age ASCII armor format.
+#[pyfunction]
+#[args(armor = "false")]
+#[pyo3(text_signature = "(plaintext, passphrase, armor=False)")]
+fn encrypt<'py>(
+    py: Python<'py>,
+    plaintext: &[u8],
+    passphrase: &str,
+    armor: bool,
+) -> PyResult<&'py PyBytes> {
+    let encryptor = age::Encryptor::with_user_passphrase(Secret::new(passphrase.to_owned()));
+    let encrypted = encrypt_bytes(encryptor, plaintext, armor)?;
+    Ok(PyBytes::new(py, &encrypted))
+}
+
+/// Decrypts a passphrase-encrypted (binary or armored) `ciphertext`, returning the
+/// plaintext.
+#[pyfunction]
+#[pyo3(text_signature = "(ciphertext, passphrase)")]
+fn decrypt<'py>(py: Python<'py>, ciphertext: &[u8], passphrase: &str) -> PyResult<&'py PyBytes> {
+    let reader = match age::Decryptor::new(age::armor::ArmoredReader::new(ciphertext))
+        .map_err(decrypt_error)?
+    {
+        age::Decryptor::Passphrase(d) => d
+            .decrypt(&Secret::new(passphrase.to_owned()), None)
+            .map_err(decrypt_error)?,
+        age::Decryptor::Recipients(_) => {
+            return Err(DecryptError::new_err(
+                "file is not passphrase-encrypted; use pyrage.decrypt",
+            ))
+        }
+    };
+    Ok(PyBytes::new(py, &read_all(reader)?))
+}
+
+pub(crate) fn module(py: Python) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "passphrase")?;
+    m.add_function(wrap_pyfunction!(encrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt, m)?)?;
+    Ok(m)
+}
@@ -0,0 +1,95 @@
+//! The `pyrage.plugin` module.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// Callbacks for plugins that are run without a user interface.
+///
+/// Messages are printed to stderr, and any request for user input is declined.
+#[derive(Clone)]
+pub(crate) struct NoCallbacks;
+
+impl age::Callbacks for NoCallbacks {
+    fn display_message(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn confirm(&self, _: &str, _: &str, _: Option<&str>) -> Option<bool> {
+        None
+    }
+
+    fn request_public_string(&self, _: &str) -> Option<String> {
+        None
+    }
+
+    fn request_passphrase(&self, _: &str) -> Option<age::secrecy::SecretString> {
+        None
+    }
+}
+
+/// An identity that is handled by an age plugin.
+#[pyclass(module = "pyrage.plugin")]
+#[derive(Clone)]
+pub(crate) struct Identity(pub(crate) age::plugin::Identity);
+
+#[pymethods]
+impl Identity {
+    /// Parses a plugin identity from its `AGE-PLUGIN-...` encoding.
+    #[staticmethod]
+    #[pyo3(text_signature = "(v)")]
+    fn from_str(v: &str) -> PyResult<Self> {
+        v.parse()
+            .map(Identity)
+            .map_err(|e: &str| PyValueError::new_err(e))
+    }
+
+    /// Returns the identity for the given plugin in its default mode.
+    #[staticmethod]
+    #[pyo3(text_signature = "(plugin_name)")]
+    fn default_for_plugin(plugin_name: &str) -> Self {
+        Identity(age::plugin::Identity::default_for_plugin(plugin_name))
+    }
+
+    /// The name of the plugin that handles this identity.
+    #[getter]
+    fn plugin(&self) -> &str {
+        self.0.plugin()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// A recipient that is handled by an age plugin.
+#[pyclass(module = "pyrage.plugin")]
+#[derive(Clone)]
+pub(crate) struct Recipient(pub(crate) age::plugin::Recipient);
+
+#[pymethods]
+impl Recipient {
+    /// Parses a plugin recipient from its `age1<plugin>1...` encoding.
+    #[staticmethod]
+    #[pyo3(text_signature = "(v)")]
+    fn from_str(v: &str) -> PyResult<Self> {
+        v.parse()
+            .map(Recipient)
+            .map_err(|e: &str| PyValueError::new_err(e))
+    }
+
+    /// The name of the plugin that handles this recipient.
+    #[getter]
+    fn plugin(&self) -> &str {
+        self.0.plugin()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+pub(crate) fn module(py: Python) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "plugin")?;
+    m.add_class::<Identity>()?;
+    m.add_class::<Recipient>()?;
+    Ok(m)
+}
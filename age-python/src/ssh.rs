@@ -0,0 +1,61 @@
+//! The `pyrage.ssh` module.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// An SSH private key, which can decrypt files encrypted to the corresponding SSH
+/// public key.
+#[pyclass(module = "pyrage.ssh")]
+pub(crate) struct Identity(pub(crate) age::ssh::Identity);
+
+#[pymethods]
+impl Identity {
+    /// Parses an unencrypted SSH private key from the contents of a key file.
+    #[staticmethod]
+    #[pyo3(text_signature = "(buf)")]
+    fn from_buffer(buf: &[u8]) -> PyResult<Self> {
+        let identity = age::ssh::Identity::from_buffer(buf, None)?;
+        match identity {
+            age::ssh::Identity::Unencrypted(_) => Ok(Identity(identity)),
+            age::ssh::Identity::Encrypted(_) => Err(PyValueError::new_err(
+                "passphrase-protected SSH keys are not supported",
+            )),
+            age::ssh::Identity::Unsupported(key) => Err(PyValueError::new_err(format!(
+                "unsupported SSH key: {:?}",
+                key
+            ))),
+        }
+    }
+}
+
+/// An SSH public key.
+#[pyclass(module = "pyrage.ssh")]
+#[derive(Clone)]
+pub(crate) struct Recipient(pub(crate) age::ssh::Recipient);
+
+#[pymethods]
+impl Recipient {
+    /// Parses an SSH public key in `authorized_keys` format.
+    #[staticmethod]
+    #[pyo3(text_signature = "(v)")]
+    fn from_str(v: &str) -> PyResult<Self> {
+        v.parse().map(Recipient).map_err(|e| {
+            PyValueError::new_err(match e {
+                age::ssh::ParseRecipientKeyError::Unsupported(key_type) => {
+                    format!("unsupported SSH key type: {}", key_type)
+                }
+                _ => "invalid SSH recipient".to_owned(),
+            })
+        })
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+pub(crate) fn module(py: Python) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "ssh")?;
+    m.add_class::<Identity>()?;
+    m.add_class::<Recipient>()?;
+    Ok(m)
+}
@@ -0,0 +1,248 @@
+//! File-like streaming objects.
+
+use std::io::{self, BufReader, Read, Write};
+
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    stream::{StreamReader, StreamWriter},
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+use crate::{decrypt_error, encrypt_error, recipients_from, DecryptError, Identities};
+
+/// Converts an error from a decrypting reader into a Python exception.
+///
+/// The STREAM reader reports authentication failures as `InvalidData`, and truncated
+/// files as `UnexpectedEof`; these become `pyrage.DecryptError` rather than `OSError`.
+pub(crate) fn read_error(e: io::Error) -> PyErr {
+    match e.kind() {
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+            DecryptError::new_err(e.to_string())
+        }
+        _ => e.into(),
+    }
+}
+
+/// Adapts a Python binary file object with a `write` method to [`Write`].
+struct PyWriter(PyObject);
+
+impl Write for PyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Python::with_gil(|py| {
+            let written = self.0.call_method1(py, "write", (PyBytes::new(py, buf),))?;
+            // Unbuffered raw streams may write less than requested, and some file-like
+            // objects return `None` from `write`.
+            if written.is_none(py) {
+                Ok(buf.len())
+            } else {
+                Ok(written.extract(py)?)
+            }
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Python::with_gil(|py| {
+            self.0.call_method0(py, "flush")?;
+            Ok(())
+        })
+    }
+}
+
+/// Adapts a Python binary file object with a `read` method to [`Read`].
+struct PyReader(PyObject);
+
+impl Read for PyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Python::with_gil(|py| {
+            let data = self.0.call_method1(py, "read", (buf.len(),))?;
+            let data: &[u8] = data.extract(py)?;
+            if data.len() > buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "read() returned more data than requested",
+                ));
+            }
+            buf[..data.len()].copy_from_slice(data);
+            Ok(data.len())
+        })
+    }
+}
+
+/// A writable file-like object that encrypts everything written to it, and writes the
+/// age-encrypted output to `fileobj`.
+///
+/// The encryption must be completed by calling `close()` (or by leaving a `with` block
+/// without an exception); otherwise the output will be truncated and fail to decrypt.
+/// `fileobj` itself is not closed.
+#[pyclass(
+    module = "pyrage",
+    text_signature = "(fileobj, recipients, armor=False)"
+)]
+pub(crate) struct Encryptor(Option<StreamWriter<ArmoredWriter<PyWriter>>>);
+
+impl Encryptor {
+    fn writer(&mut self) -> PyResult<&mut StreamWriter<ArmoredWriter<PyWriter>>> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("I/O operation on closed Encryptor"))
+    }
+}
+
+#[pymethods]
+impl Encryptor {
+    #[new]
+    #[args(armor = "false")]
+    fn new(fileobj: PyObject, recipients: Vec<&PyAny>, armor: bool) -> PyResult<Self> {
+        let output = ArmoredWriter::wrap_output(
+            PyWriter(fileobj),
+            if armor {
+                Format::AsciiArmor
+            } else {
+                Format::Binary
+            },
+        )?;
+        let writer = age::Encryptor::with_recipients(recipients_from(recipients)?)
+            .wrap_output(output)
+            .map_err(encrypt_error)?;
+        Ok(Encryptor(Some(writer)))
+    }
+
+    /// Encrypts the given bytes, returning the number of bytes consumed.
+    #[pyo3(text_signature = "($self, data)")]
+    fn write(&mut self, data: &[u8]) -> PyResult<usize> {
+        self.writer()?.write_all(data)?;
+        Ok(data.len())
+    }
+
+    /// Completes the encryption. Calling this more than once has no effect.
+    #[pyo3(text_signature = "($self)")]
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer
+                .finish()
+                .and_then(|armor| armor.finish())
+                .and_then(|mut output| output.flush())?;
+        }
+        Ok(())
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn readable(&self) -> bool {
+        false
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>,
+    ) -> PyResult<bool> {
+        if exc_type.is_none() {
+            self.close()?;
+        } else {
+            // Don't finalize a partial encryption; the truncated output will not
+            // decrypt, which is the safer failure mode.
+            self.0 = None;
+        }
+        Ok(false)
+    }
+}
+
+/// A readable file-like object that reads an age-encrypted (binary or armored) file
+/// from `fileobj` and returns the decrypted plaintext.
+///
+/// The header is read and checked when the object is created, so a `DecryptError` for
+/// a non-matching identity is raised immediately. Errors in the payload are raised by
+/// `read()`.
+#[pyclass(module = "pyrage", text_signature = "(fileobj, identities)")]
+pub(crate) struct Decryptor(Option<StreamReader<ArmoredReader<BufReader<PyReader>>>>);
+
+#[pymethods]
+impl Decryptor {
+    #[new]
+    fn new(fileobj: PyObject, identities: Vec<&PyAny>) -> PyResult<Self> {
+        let identities = Identities::from_py(identities)?;
+        let input = ArmoredReader::new(PyReader(fileobj));
+        let reader = match age::Decryptor::new(input).map_err(decrypt_error)? {
+            age::Decryptor::Recipients(d) => d.decrypt(identities.iter()).map_err(decrypt_error)?,
+            age::Decryptor::Passphrase(_) => {
+                return Err(DecryptError::new_err(
+                    "file is passphrase-encrypted; use pyrage.passphrase.decrypt",
+                ))
+            }
+        };
+        Ok(Decryptor(Some(reader)))
+    }
+
+    /// Reads up to `size` bytes of plaintext, or the rest of the plaintext if `size` is
+    /// negative or omitted. Returns an empty bytes object at the end of the file.
+    #[args(size = "-1")]
+    #[pyo3(text_signature = "($self, size=-1)")]
+    fn read<'py>(&mut self, py: Python<'py>, size: i64) -> PyResult<&'py PyBytes> {
+        let reader = self
+            .0
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("I/O operation on closed Decryptor"))?;
+
+        let mut buf = vec![];
+        if size < 0 {
+            reader.read_to_end(&mut buf).map_err(read_error)?;
+        } else {
+            buf.resize(size as usize, 0);
+            let mut filled = 0;
+            while filled < buf.len() {
+                match reader.read(&mut buf[filled..]).map_err(read_error)? {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+            buf.truncate(filled);
+        }
+        Ok(PyBytes::new(py, &buf))
+    }
+
+    /// Releases the underlying file object. Calling this more than once has no effect.
+    #[pyo3(text_signature = "($self)")]
+    fn close(&mut self) {
+        self.0 = None;
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>,
+    ) -> bool {
+        self.close();
+        false
+    }
+}
@@ -0,0 +1,67 @@
+//! The `pyrage.x25519` module.
+
+use age::secrecy::ExposeSecret;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// The standard age identity type, which can decrypt files encrypted to the
+/// corresponding `Recipient`.
+#[pyclass(module = "pyrage.x25519")]
+#[derive(Clone)]
+pub(crate) struct Identity(pub(crate) age::x25519::Identity);
+
+#[pymethods]
+impl Identity {
+    /// Generates a new secret key.
+    #[staticmethod]
+    #[pyo3(text_signature = "()")]
+    fn generate() -> Self {
+        Identity(age::x25519::Identity::generate())
+    }
+
+    /// Parses a secret key from its `AGE-SECRET-KEY-1...` encoding.
+    #[staticmethod]
+    #[pyo3(text_signature = "(v)")]
+    fn from_str(v: &str) -> PyResult<Self> {
+        v.parse()
+            .map(Identity)
+            .map_err(|e: &str| PyValueError::new_err(e))
+    }
+
+    /// Returns the recipient key for this secret key.
+    #[pyo3(text_signature = "($self)")]
+    fn to_public(&self) -> Recipient {
+        Recipient(self.0.to_public())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string().expose_secret().clone()
+    }
+}
+
+/// The standard age public key.
+#[pyclass(module = "pyrage.x25519")]
+#[derive(Clone)]
+pub(crate) struct Recipient(pub(crate) age::x25519::Recipient);
+
+#[pymethods]
+impl Recipient {
+    /// Parses a recipient key from its `age1...` encoding.
+    #[staticmethod]
+    #[pyo3(text_signature = "(v)")]
+    fn from_str(v: &str) -> PyResult<Self> {
+        v.parse()
+            .map(Recipient)
+            .map_err(|e: &str| PyValueError::new_err(e))
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+pub(crate) fn module(py: Python) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "x25519")?;
+    m.add_class::<Identity>()?;
+    m.add_class::<Recipient>()?;
+    Ok(m)
+}
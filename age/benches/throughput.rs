@@ -10,6 +10,10 @@ use std::iter;
 
 const KB: usize = 1024;
 
+/// The number of chunks to process at a time in the parallel benchmarks.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNKS: usize = 16;
+
 fn bench(c: &mut Criterion<CyclesPerByte>) {
     let identity = x25519::Identity::generate();
     let recipient = identity.to_public();
@@ -62,6 +66,41 @@ fn bench(c: &mut Criterion<CyclesPerByte>) {
 
             ct_buf.clear();
         });
+
+        #[cfg(feature = "parallel")]
+        group.bench_function(BenchmarkId::new("encrypt-parallel", size), |b| {
+            b.iter(|| {
+                let mut output = Encryptor::with_recipients(vec![Box::new(recipient.clone())])
+                    .wrap_output(io::sink())
+                    .unwrap()
+                    .with_parallelism(PARALLEL_CHUNKS);
+                output.write_all(&pt_buf[..size]).unwrap();
+                output.finish().unwrap();
+            })
+        });
+
+        #[cfg(feature = "parallel")]
+        group.bench_function(BenchmarkId::new("decrypt-parallel", size), |b| {
+            let mut output = Encryptor::with_recipients(vec![Box::new(recipient.clone())])
+                .wrap_output(&mut ct_buf)
+                .unwrap();
+            output.write_all(&pt_buf[..size]).unwrap();
+            output.finish().unwrap();
+
+            b.iter(|| {
+                let decryptor = match Decryptor::new(&ct_buf[..]).unwrap() {
+                    Decryptor::Recipients(decryptor) => decryptor,
+                    _ => panic!(),
+                };
+                let mut input = decryptor
+                    .decrypt(iter::once(&identity as &dyn age::Identity))
+                    .unwrap()
+                    .with_parallelism(PARALLEL_CHUNKS);
+                input.read_exact(&mut out_buf[..size]).unwrap();
+            });
+
+            ct_buf.clear();
+        });
     }
 
     group.finish();
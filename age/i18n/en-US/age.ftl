@@ -65,6 +65,16 @@ err-read-identity-not-found = Identity file not found: {$filename}
 
 err-stream-last-chunk-empty = Last STREAM chunk is empty. Please report this, and/or try an older {-rage} version.
 
+err-verify-chunk = Chunk {$index} (at byte offset {$offset}) failed to verify: {$err}
+err-verify-trailing-data = Unexpected data after the last chunk (at byte offset {$offset}).
+
+## Mnemonics
+
+err-mnemonic-length = Expected {$expected} words in the mnemonic, but found {$actual}.
+err-mnemonic-unknown-word = Word {$position} of the mnemonic ('{$word}') is not in the BIP39 English wordlist.
+rec-mnemonic-unknown-word = Did you mean '{$suggestion}'?
+err-mnemonic-checksum = The mnemonic's checksum is invalid. Check that every word is spelled correctly, and that the words are in the correct order.
+
 ## Encrypted identities
 
 encrypted-passphrase-prompt = Type passphrase for encrypted identity '{$filename}'
@@ -13,20 +13,23 @@ use std::fs::File;
 use std::io::{self, BufReader};
 use subtle::ConstantTimeEq;
 
-use crate::{fl, identity::IdentityFile, wfl, Callbacks, Identity};
+use crate::{
+    fl, identity::IdentityFile, util::BIP39_WORDLIST, wfl, Callbacks, EncryptError, Identity,
+    Recipient,
+};
 
 #[cfg(feature = "armor")]
 use crate::armor::ArmoredReader;
 
 pub mod file_io;
 
-const BIP39_WORDLIST: &str = include_str!("../assets/bip39-english.txt");
-
 /// Errors that can occur while reading identities.
 #[derive(Debug)]
 pub enum ReadError {
     /// An age identity was encrypted without a passphrase.
     IdentityEncryptedWithoutPassphrase(String),
+    /// The recipients corresponding to an identity could not be determined.
+    IdentityRecipients(EncryptError),
     /// The given identity file could not be found.
     IdentityNotFound(String),
     /// An I/O error occurred while reading.
@@ -51,6 +54,17 @@ impl From<io::Error> for ReadError {
     }
 }
 
+impl From<EncryptError> for ReadError {
+    fn from(e: EncryptError) -> Self {
+        match e {
+            EncryptError::Io(e) => ReadError::Io(e),
+            #[cfg(feature = "plugin")]
+            EncryptError::MissingPlugin { binary_name } => ReadError::MissingPlugin { binary_name },
+            _ => ReadError::IdentityRecipients(e),
+        }
+    }
+}
+
 impl fmt::Display for ReadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -74,6 +88,7 @@ impl fmt::Display for ReadError {
                     filename = filename.as_str()
                 )
             ),
+            ReadError::IdentityRecipients(e) => write!(f, "{}", e),
             ReadError::Io(e) => write!(f, "{}", e),
             #[cfg(feature = "plugin")]
             ReadError::MissingPlugin { binary_name } => {
@@ -108,7 +123,30 @@ pub fn read_identities(
     filenames: Vec<String>,
     max_work_factor: Option<u8>,
 ) -> Result<Vec<Box<dyn Identity>>, ReadError> {
+    read_identity_files(filenames, max_work_factor, false).map(|(identities, _)| identities)
+}
+
+/// Reads identities from the provided files, along with the recipients that they
+/// correspond to.
+///
+/// This can be used to re-encrypt a file to the same identities that decrypted it. The
+/// passphrase for each passphrase-encrypted identity file is only requested once.
+pub fn read_identities_with_recipients(
+    filenames: Vec<String>,
+    max_work_factor: Option<u8>,
+) -> Result<(Vec<Box<dyn Identity>>, Vec<Box<dyn Recipient>>), ReadError> {
+    read_identity_files(filenames, max_work_factor, true)
+}
+
+/// Reads identities from the provided files, and their recipients if `with_recipients`
+/// is true.
+fn read_identity_files(
+    filenames: Vec<String>,
+    max_work_factor: Option<u8>,
+    with_recipients: bool,
+) -> Result<(Vec<Box<dyn Identity>>, Vec<Box<dyn Recipient>>), ReadError> {
     let mut identities: Vec<Box<dyn Identity>> = vec![];
+    let mut recipients: Vec<Box<dyn Recipient>> = vec![];
 
     for filename in filenames {
         #[cfg(feature = "armor")]
@@ -120,6 +158,9 @@ pub fn read_identities(
             max_work_factor,
         ) {
             if let Some(identity) = identity {
+                if with_recipients {
+                    recipients.extend(identity.recipients()?);
+                }
                 identities.push(Box::new(identity));
                 continue;
             } else {
@@ -137,6 +178,16 @@ pub fn read_identities(
                 return Err(ReadError::UnsupportedKey(filename, k))
             }
             Ok(identity) => {
+                if with_recipients {
+                    let recipient =
+                        crate::ssh::Recipient::try_from(identity.clone()).map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Invalid SSH pubkey in SSH privkey",
+                            )
+                        })?;
+                    recipients.push(Box::new(recipient));
+                }
                 identities.push(Box::new(identity.with_callbacks(UiCallbacks)));
                 continue;
             }
@@ -153,6 +204,9 @@ pub fn read_identities(
             })?;
 
         for entry in identity_file.into_identities() {
+            if with_recipients {
+                recipients.push(entry.to_recipient(UiCallbacks)?);
+            }
             let entry = entry.into_identity(UiCallbacks);
 
             #[cfg(feature = "plugin")]
@@ -173,7 +227,7 @@ pub fn read_identities(
         }
     }
 
-    Ok(identities)
+    Ok((identities, recipients))
 }
 
 fn confirm(query: &str, ok: &str, cancel: Option<&str>) -> pinentry::Result<bool> {
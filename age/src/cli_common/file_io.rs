@@ -1,8 +1,9 @@
 //! File I/O helpers for CLI binaries.
 
 use std::fmt;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
@@ -247,6 +248,8 @@ pub struct LazyFile {
     #[cfg(unix)]
     mode: u32,
     file: Option<io::Result<File>>,
+    /// If set, `filename` is a temporary file that will replace this file when committed.
+    replaces: Option<PathBuf>,
 }
 
 impl LazyFile {
@@ -255,7 +258,13 @@ impl LazyFile {
 
         if self.file.is_none() {
             let mut options = OpenOptions::new();
-            options.write(true).create(true).truncate(true);
+            options.write(true);
+            if self.replaces.is_some() {
+                // Never reuse an existing file as a temporary file.
+                options.create_new(true);
+            } else {
+                options.create(true).truncate(true);
+            }
 
             #[cfg(unix)]
             options.mode(self.mode);
@@ -271,6 +280,66 @@ impl LazyFile {
     }
 }
 
+impl LazyFile {
+    /// Finishes writing the file, replacing the target file if this is a temporary file.
+    fn commit(&mut self) -> io::Result<()> {
+        // Open the file if nothing was written, so that empty output is still created.
+        self.get_file()?.flush()?;
+
+        let target = match self.replaces.take() {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        let file = self
+            .file
+            .take()
+            .expect("file was opened above")
+            .expect("file was opened successfully");
+
+        let res = (|| {
+            // Preserve the permissions of the file being replaced.
+            if let Ok(metadata) = fs::metadata(&target) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.sync_all()?;
+            // Close the file before renaming it, which is required on Windows.
+            drop(file);
+
+            fs::rename(&self.filename, &target)?;
+
+            // Sync the directory, so that the rename itself is durable.
+            #[cfg(unix)]
+            {
+                let dir = match target.parent() {
+                    Some(dir) if dir != Path::new("") => dir,
+                    _ => Path::new("."),
+                };
+                File::open(dir)?.sync_all()?;
+            }
+
+            Ok(())
+        })();
+
+        if res.is_err() {
+            let _ = fs::remove_file(&self.filename);
+        }
+        res
+    }
+}
+
+impl Drop for LazyFile {
+    fn drop(&mut self) {
+        // Remove an uncommitted temporary file, so that we never leave partial output
+        // behind. The file is closed first, which is required on Windows.
+        if self.replaces.is_some() {
+            if let Some(Ok(file)) = self.file.take() {
+                drop(file);
+                let _ = fs::remove_file(&self.filename);
+            }
+        }
+    }
+}
+
 impl io::Write for LazyFile {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.get_file()?.write(buf)
@@ -307,6 +376,7 @@ impl OutputWriter {
                     #[cfg(unix)]
                     mode: _mode,
                     file: None,
+                    replaces: None,
                 }));
             } else {
                 // User explicitly requested stdout; force the format to binary so that we
@@ -327,6 +397,36 @@ impl OutputWriter {
         )))
     }
 
+    /// Writes output to a temporary file in the same directory as `filename`, which
+    /// atomically replaces `filename` when [`OutputWriter::commit`] is called.
+    ///
+    /// `filename` is left untouched until then. If the writer is dropped without being
+    /// committed (for example, because an error occurred or the user cancelled a
+    /// prompt), the temporary file is removed.
+    pub fn new_in_place(filename: String, _mode: u32) -> io::Result<Self> {
+        let target = PathBuf::from(filename);
+        let name = target.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' is not a file", target.display()),
+            )
+        })?;
+
+        let temp = target.with_file_name(format!(
+            ".{}.{:08x}.tmp",
+            name.to_string_lossy(),
+            rand::random::<u32>()
+        ));
+
+        Ok(OutputWriter::File(LazyFile {
+            filename: temp.to_string_lossy().into_owned(),
+            #[cfg(unix)]
+            mode: _mode,
+            file: None,
+            replaces: Some(target),
+        }))
+    }
+
     /// Returns true if this output is to a terminal, and a user will likely see it.
     pub fn is_terminal(&self) -> bool {
         match self {
@@ -334,6 +434,18 @@ impl OutputWriter {
             OutputWriter::Stdout(w) => w.is_tty,
         }
     }
+
+    /// Finishes writing the output.
+    ///
+    /// For outputs created with [`OutputWriter::new_in_place`], this syncs the temporary
+    /// file to disk, gives it the permissions of the file it replaces, and then renames
+    /// it over that file. Other outputs are flushed.
+    pub fn commit(self) -> io::Result<()> {
+        match self {
+            OutputWriter::File(mut f) => f.commit(),
+            OutputWriter::Stdout(mut handle) => handle.flush(),
+        }
+    }
 }
 
 impl Write for OutputWriter {
@@ -354,11 +466,11 @@ impl Write for OutputWriter {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    #[cfg(unix)]
-    use super::{OutputFormat, OutputWriter};
-    #[cfg(unix)]
+    use std::fs;
     use std::io::Write;
 
+    use super::{OutputFormat, OutputWriter};
+
     #[cfg(unix)]
     #[test]
     fn lazy_existing_file() {
@@ -372,4 +484,43 @@ pub(crate) mod tests {
         .flush()
         .unwrap();
     }
+
+    #[test]
+    fn in_place() {
+        let dir = std::env::temp_dir().join(format!("age-in-place-{}", rand::random::<u32>()));
+        fs::create_dir(&dir).unwrap();
+        let target = dir.join("file.txt");
+        let target_name = target.to_str().unwrap().to_owned();
+        fs::write(&target, b"original").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        // Dropping the writer without committing leaves the target untouched, and
+        // removes the temporary file.
+        let mut output = OutputWriter::new_in_place(target_name.clone(), 0o600).unwrap();
+        output.write_all(b"partial").unwrap();
+        drop(output);
+        assert_eq!(fs::read(&target).unwrap(), b"original");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let mut output = OutputWriter::new_in_place(target_name, 0o600).unwrap();
+        output.write_all(b"replaced").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"original");
+        output.commit().unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"replaced");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&target).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
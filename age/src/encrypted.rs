@@ -134,6 +134,27 @@ impl<R: io::Read, C: Callbacks> Identity<R, C> {
         }
     }
 
+    /// Returns the identities contained within this encrypted identity.
+    ///
+    /// If this encrypted identity has not been decrypted yet, calling this method will
+    /// trigger a passphrase request.
+    pub fn identities(&self) -> Result<Vec<IdentityFileEntry>, DecryptError> {
+        match self
+            .state
+            .take()
+            .decrypt(self.filename.as_deref(), self.callbacks.clone())
+        {
+            Ok((identities, _)) => {
+                self.state.set(IdentityState::Decrypted(identities.clone()));
+                Ok(identities)
+            }
+            Err(e) => {
+                self.state.set(IdentityState::Poisoned(Some(e.clone())));
+                Err(e)
+            }
+        }
+    }
+
     /// Attempts to unwrap stanzas with the identities contained within this encrypted
     /// identity.
     ///
@@ -307,4 +328,33 @@ fOrxrKTj7xCdNS3+OrCdnBC8Z9cKDxjCGWW3fkjLsYha0Jo=
         // Unwrapping a second time doesn't re-decrypt.
         identity.unwrap_stanzas(&wrapped);
     }
+
+    #[test]
+    #[cfg(feature = "armor")]
+    fn identities() {
+        use crate::IdentityFileEntry;
+
+        let buf = ArmoredReader::new(BufReader::new(TEST_ENCRYPTED_IDENTITY.as_bytes()));
+        let identity = Identity::from_buffer(
+            buf,
+            None,
+            MockCallbacks::new(TEST_ENCRYPTED_IDENTITY_PASSPHRASE),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+
+        let entries = identity.identities().unwrap();
+        assert_eq!(entries.len(), 1);
+        match &entries[0] {
+            IdentityFileEntry::Native(sk) => {
+                assert_eq!(sk.to_public().to_string(), TEST_RECIPIENT)
+            }
+            #[allow(unreachable_patterns)]
+            _ => panic!("Should be a native identity"),
+        }
+
+        // Listing a second time doesn't re-decrypt.
+        assert_eq!(identity.identities().unwrap().len(), 1);
+    }
 }
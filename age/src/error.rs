@@ -340,3 +340,82 @@ impl std::error::Error for DecryptError {
         }
     }
 }
+
+/// The errors that can be returned while verifying an age file.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The payload key could not be obtained from the age file's header.
+    Header(DecryptError),
+    /// A chunk of the age file's payload could not be authenticated.
+    Chunk {
+        /// The index of the chunk, starting from zero. If the age file is truncated,
+        /// this is the index of the first missing chunk.
+        index: u64,
+        /// The offset in bytes of the chunk from the start of the binary age file. For
+        /// an armored age file, this is an offset within the decoded binary data.
+        offset: u64,
+        /// The cause of the failure. This is [`DecryptError::DecryptionFailed`] if the
+        /// chunk failed to authenticate, or [`DecryptError::Io`] if the age file is
+        /// truncated (with [`io::ErrorKind::UnexpectedEof`]) or could not be read.
+        error: DecryptError,
+    },
+    /// The age file contains data after its last chunk.
+    ///
+    /// Data can only be detected as trailing if the last chunk is full. Otherwise, it
+    /// can't be distinguished from a damaged last chunk, and is reported as
+    /// [`VerifyError::Chunk`].
+    TrailingData {
+        /// The offset in bytes of the trailing data from the start of the binary age
+        /// file. For an armored age file, this is an offset within the decoded binary
+        /// data.
+        offset: u64,
+    },
+}
+
+impl From<DecryptError> for VerifyError {
+    fn from(e: DecryptError) -> Self {
+        VerifyError::Header(e)
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Header(e) => e.fmt(f),
+            VerifyError::Chunk {
+                index,
+                offset,
+                error,
+            } => write!(
+                f,
+                "{}",
+                fl!(
+                    crate::i18n::LANGUAGE_LOADER,
+                    "err-verify-chunk",
+                    index = *index,
+                    offset = *offset,
+                    err = error.to_string(),
+                )
+            ),
+            VerifyError::TrailingData { offset } => write!(
+                f,
+                "{}",
+                fl!(
+                    crate::i18n::LANGUAGE_LOADER,
+                    "err-verify-trailing-data",
+                    offset = *offset,
+                )
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerifyError::Header(inner) => Some(inner),
+            VerifyError::Chunk { error, .. } => Some(error),
+            VerifyError::TrailingData { .. } => None,
+        }
+    }
+}
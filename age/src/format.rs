@@ -11,6 +11,12 @@ use crate::{
 #[cfg(feature = "async")]
 use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+#[cfg(feature = "tokio")]
+use tokio::io::{
+    AsyncRead as TokioAsyncRead, AsyncReadExt as _, AsyncWrite as TokioAsyncWrite,
+    AsyncWriteExt as _,
+};
+
 const AGE_MAGIC: &[u8] = b"age-encryption.org/";
 const V1_MAGIC: &[u8] = b"v1";
 const MAC_TAG: &[u8] = b"---";
@@ -47,6 +53,18 @@ impl HeaderV1 {
         header
     }
 
+    /// Returns the length of this header in bytes, as it appears in the age file.
+    pub(crate) fn encoded_len(&self) -> usize {
+        if let Some(bytes) = &self.encoded_bytes {
+            bytes.len()
+        } else {
+            let mut buf = vec![];
+            cookie_factory::gen(write::header_v1(self), &mut buf)
+                .expect("can serialize Header into Vec");
+            buf.len()
+        }
+    }
+
     pub(crate) fn verify_mac(&self, mac_key: HmacKey) -> Result<(), hmac::digest::MacError> {
         let mut mac = HmacWriter::new(mac_key);
         if let Some(bytes) = &self.encoded_bytes {
@@ -118,6 +136,36 @@ impl Header {
         }
     }
 
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub(crate) async fn read_tokio<R: TokioAsyncRead + Unpin>(
+        mut input: R,
+    ) -> Result<Self, DecryptError> {
+        let mut data = vec![];
+        loop {
+            match read::header(&data) {
+                Ok((_, mut header)) => {
+                    if let Header::V1(h) = &mut header {
+                        h.encoded_bytes = Some(data);
+                    }
+                    break Ok(header);
+                }
+                Err(nom::Err::Incomplete(nom::Needed::Size(n))) => {
+                    // Read the needed additional bytes. We need to be careful how the
+                    // parser is constructed, because if we read more than we need, the
+                    // remainder of the input will be truncated.
+                    let m = data.len();
+                    let new_len = m + n.get();
+                    data.resize(new_len, 0);
+                    input.read_exact(&mut data[m..new_len]).await?;
+                }
+                Err(_) => {
+                    break Err(DecryptError::InvalidHeader);
+                }
+            }
+        }
+    }
+
     pub(crate) fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
         cookie_factory::gen(write::header(self), &mut output)
             .map(|_| ())
@@ -144,6 +192,25 @@ impl Header {
 
         output.write_all(&buf).await
     }
+
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub(crate) async fn write_tokio<W: TokioAsyncWrite + Unpin>(
+        &self,
+        mut output: W,
+    ) -> io::Result<()> {
+        let mut buf = vec![];
+        cookie_factory::gen(write::header(self), &mut buf)
+            .map(|_| ())
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("failed to write header: {}", e),
+                )
+            })?;
+
+        output.write_all(&buf).await
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -242,7 +309,7 @@ mod write {
         ))
     }
 
-    fn header_v1<'a, W: 'a + Write>(h: &'a HeaderV1) -> impl SerializeFn<W> + 'a {
+    pub(super) fn header_v1<'a, W: 'a + Write>(h: &'a HeaderV1) -> impl SerializeFn<W> + 'a {
         tuple((
             header_v1_minus_mac(h),
             string(" "),
@@ -6,11 +6,18 @@ use crate::{x25519, Callbacks, DecryptError, EncryptError};
 #[cfg(feature = "plugin")]
 use crate::plugin;
 
+#[cfg(feature = "pq")]
+use crate::pq;
+
 /// The supported kinds of identities within an [`IdentityFile`].
 #[derive(Clone)]
 pub enum IdentityFileEntry {
     /// The standard age identity type.
     Native(x25519::Identity),
+    /// The post-quantum hybrid age identity type.
+    #[cfg(feature = "pq")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pq")))]
+    PostQuantum(pq::Identity),
     /// A plugin-compatible identity.
     #[cfg(feature = "plugin")]
     #[cfg_attr(docsrs, doc(cfg(feature = "plugin")))]
@@ -25,6 +32,8 @@ impl IdentityFileEntry {
     ) -> Result<Box<dyn crate::Identity>, DecryptError> {
         match self {
             IdentityFileEntry::Native(i) => Ok(Box::new(i)),
+            #[cfg(feature = "pq")]
+            IdentityFileEntry::PostQuantum(i) => Ok(Box::new(i)),
             #[cfg(feature = "plugin")]
             IdentityFileEntry::Plugin(i) => Ok(Box::new(crate::plugin::IdentityPluginV1::new(
                 &i.plugin().to_owned(),
@@ -41,6 +50,8 @@ impl IdentityFileEntry {
     ) -> Result<Box<dyn crate::Recipient>, EncryptError> {
         match self {
             IdentityFileEntry::Native(i) => Ok(Box::new(i.to_public())),
+            #[cfg(feature = "pq")]
+            IdentityFileEntry::PostQuantum(i) => Ok(Box::new(i.to_public())),
             #[cfg(feature = "plugin")]
             IdentityFileEntry::Plugin(i) => Ok(Box::new(crate::plugin::RecipientPluginV1::new(
                 &i.plugin().to_owned(),
@@ -81,6 +92,23 @@ impl IdentityFile {
 
             if let Ok(identity) = line.parse::<x25519::Identity>() {
                 identities.push(IdentityFileEntry::Native(identity));
+            } else if let Some(identity) = {
+                #[cfg(feature = "pq")]
+                {
+                    line.parse::<pq::Identity>().ok()
+                }
+
+                #[cfg(not(feature = "pq"))]
+                None
+            } {
+                #[cfg(feature = "pq")]
+                {
+                    identities.push(IdentityFileEntry::PostQuantum(identity));
+                }
+
+                // Add a binding to provide a type when post-quantum keys are disabled.
+                #[cfg(not(feature = "pq"))]
+                let _: () = identity;
             } else if let Some(identity) = {
                 #[cfg(feature = "plugin")]
                 {
@@ -146,6 +174,8 @@ pub(crate) mod tests {
             IdentityFileEntry::Native(identity) => {
                 assert_eq!(identity.to_string().expose_secret(), TEST_SK)
             }
+            #[cfg(feature = "pq")]
+            IdentityFileEntry::PostQuantum(_) => panic!(),
             #[cfg(feature = "plugin")]
             IdentityFileEntry::Plugin(_) => panic!(),
         }
@@ -198,6 +228,21 @@ pub(crate) mod tests {
         valid_secret_key_encoding(&format!("\r\n\r\n{}", TEST_SK), 1);
     }
 
+    #[cfg(feature = "pq")]
+    #[test]
+    fn pq_secret_key_encoding() {
+        let keydata = format!("{}\n{}", TEST_SK, crate::pq::tests::TEST_SK);
+        let f = IdentityFile::from_buffer(BufReader::new(keydata.as_bytes())).unwrap();
+        assert_eq!(f.identities.len(), 2);
+        match &f.identities[1] {
+            IdentityFileEntry::PostQuantum(identity) => assert_eq!(
+                identity.to_string().expose_secret(),
+                crate::pq::tests::TEST_SK
+            ),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn incomplete_secret_key_encoding() {
         let buf = BufReader::new(&TEST_SK.as_bytes()[..4]);
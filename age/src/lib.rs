@@ -12,6 +12,8 @@
 //!   only be used with passphrases that were provided by (or generated for) a human.
 //! - For compatibility with existing SSH keys, enable the `ssh` feature flag, and use
 //!   [`ssh::Recipient`] and [`ssh::Identity`].
+//! - For protection against future quantum computers, enable the `pq` feature flag, and
+//!   use [`pq::Recipient`] and [`pq::Identity`].
 //!
 //! Age-encrypted files are binary and non-malleable. To encode them as text, use the
 //! wrapping readers and writers in the [`armor`] module, behind the `armor` feature flag.
@@ -149,10 +151,10 @@ mod primitives;
 mod protocol;
 mod util;
 
-pub use error::{DecryptError, EncryptError};
+pub use error::{DecryptError, EncryptError, VerifyError};
 pub use identity::{IdentityFile, IdentityFileEntry};
 pub use primitives::stream;
-pub use protocol::{decryptor, Decryptor, Encryptor};
+pub use protocol::{decryptor, inspect, rekey, Decryptor, Encryptor};
 
 #[cfg(feature = "armor")]
 pub use primitives::armor;
@@ -176,6 +178,10 @@ pub mod x25519;
 #[cfg_attr(docsrs, doc(cfg(feature = "plugin")))]
 pub mod plugin;
 
+#[cfg(feature = "pq")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pq")))]
+pub mod pq;
+
 #[cfg(feature = "ssh")]
 #[cfg_attr(docsrs, doc(cfg(feature = "ssh")))]
 pub mod ssh;
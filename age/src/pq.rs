@@ -0,0 +1,356 @@
+//! The "rage-mlkem768x25519" recipient type, a post-quantum hybrid specific to rage.
+//!
+//! This recipient type combines ML-KEM-768 from [FIPS 203] with X25519, so that a file
+//! encrypted to it remains confidential unless both are broken. This protects files
+//! against adversaries that record them now, in the hope of decrypting them with a
+//! future quantum computer.
+//!
+//! This is **not** the `mlkem768x25519` recipient type from the [age specification],
+//! and is not interoperable with it. Its stanza tag and key encodings (`ragepq1...`
+//! and `RAGE-SECRET-KEY-PQ-1...`) are distinct, so that neither implementation will
+//! mistake the other's files or keys for its own.
+//!
+//! [FIPS 203]: https://csrc.nist.gov/pubs/fips/203/final
+//! [age specification]: https://c2sp.org/age
+
+use age_core::{
+    format::{FileKey, Stanza, FILE_KEY_BYTES},
+    primitives::{aead_decrypt, aead_encrypt, hkdf},
+    secrecy::{ExposeSecret, SecretString},
+};
+use bech32::{ToBase32, Variant};
+use ml_kem::{
+    kem::{Decapsulate, Encapsulate},
+    Ciphertext, Encoded, EncodedSizeUser, KemCore, MlKem768, B32,
+};
+use rand::RngCore;
+use std::fmt;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use crate::{
+    error::{DecryptError, EncryptError},
+    util::{parse_bech32, read::base64_arg},
+    x25519::{ENCRYPTED_FILE_KEY_BYTES, EPK_LEN_BYTES},
+};
+
+// Use lower-case HRP to avoid https://github.com/rust-bitcoin/rust-bech32/issues/40
+const SECRET_KEY_PREFIX: &str = "rage-secret-key-pq-";
+const PUBLIC_KEY_PREFIX: &str = "ragepq";
+
+pub(super) const MLKEM768X25519_RECIPIENT_TAG: &str = "rage-mlkem768x25519";
+const MLKEM768X25519_RECIPIENT_KEY_LABEL: &[u8] = b"rage-mlkem768x25519";
+
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+
+const X25519_KEY_BYTES: usize = 32;
+const MLKEM_SEED_BYTES: usize = 64;
+const MLKEM_EK_BYTES: usize = 1184;
+const MLKEM_CT_BYTES: usize = 1088;
+
+const SECRET_KEY_BYTES: usize = X25519_KEY_BYTES + MLKEM_SEED_BYTES;
+const PUBLIC_KEY_BYTES: usize = X25519_KEY_BYTES + MLKEM_EK_BYTES;
+const ENC_BYTES: usize = MLKEM_CT_BYTES + EPK_LEN_BYTES;
+
+/// Derives the key that wraps the file key from the ML-KEM-768 and X25519 shared
+/// secrets, binding it to the X25519 ephemeral share and recipient key.
+fn wrapping_key(mlkem_ss: &[u8], x25519_ss: &[u8], epk: &PublicKey, pk: &PublicKey) -> [u8; 32] {
+    let mut salt = vec![];
+    salt.extend_from_slice(epk.as_bytes());
+    salt.extend_from_slice(pk.as_bytes());
+
+    let mut ikm = vec![];
+    ikm.extend_from_slice(mlkem_ss);
+    ikm.extend_from_slice(x25519_ss);
+
+    let enc_key = hkdf(&salt, MLKEM768X25519_RECIPIENT_KEY_LABEL, &ikm);
+    ikm.zeroize();
+    enc_key
+}
+
+/// A post-quantum hybrid identity, which can decrypt files encrypted to the
+/// corresponding [`Recipient`].
+#[derive(Clone)]
+pub struct Identity {
+    x25519: StaticSecret,
+    /// The ML-KEM-768 seed `d || z`, from which the decapsulation key is derived.
+    mlkem_seed: [u8; MLKEM_SEED_BYTES],
+}
+
+impl Drop for Identity {
+    fn drop(&mut self) {
+        self.mlkem_seed.zeroize();
+    }
+}
+
+impl std::str::FromStr for Identity {
+    type Err = &'static str;
+
+    /// Parses a post-quantum hybrid identity from a string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bech32(s)
+            .ok_or("invalid Bech32 encoding")
+            .and_then(|(hrp, mut bytes)| {
+                if hrp == SECRET_KEY_PREFIX {
+                    let res = if bytes.len() == SECRET_KEY_BYTES {
+                        Ok(Identity::from_bytes(&bytes))
+                    } else {
+                        Err("incorrect identity length")
+                    };
+                    bytes.zeroize();
+                    res
+                } else {
+                    Err("incorrect HRP")
+                }
+            })
+    }
+}
+
+impl Identity {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut x25519 = [0; X25519_KEY_BYTES];
+        x25519.copy_from_slice(&bytes[..X25519_KEY_BYTES]);
+        let mut mlkem_seed = [0; MLKEM_SEED_BYTES];
+        mlkem_seed.copy_from_slice(&bytes[X25519_KEY_BYTES..]);
+
+        Identity {
+            x25519: StaticSecret::from(x25519),
+            mlkem_seed,
+        }
+    }
+
+    /// Generates a new secret key.
+    pub fn generate() -> Self {
+        let mut bytes = [0; SECRET_KEY_BYTES];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let identity = Identity::from_bytes(&bytes);
+        bytes.zeroize();
+        identity
+    }
+
+    /// Serializes this secret key as a string.
+    pub fn to_string(&self) -> SecretString {
+        let mut sk_bytes = Vec::with_capacity(SECRET_KEY_BYTES);
+        sk_bytes.extend_from_slice(&self.x25519.to_bytes());
+        sk_bytes.extend_from_slice(&self.mlkem_seed);
+        let sk_base32 = sk_bytes.to_base32();
+        let mut encoded =
+            bech32::encode(SECRET_KEY_PREFIX, sk_base32, Variant::Bech32).expect("HRP is valid");
+        let ret = SecretString::new(encoded.to_uppercase());
+
+        // Clear intermediates
+        sk_bytes.zeroize();
+        // TODO: bech32::u5 doesn't implement Zeroize
+        // sk_base32.zeroize();
+        encoded.zeroize();
+
+        ret
+    }
+
+    /// Returns the recipient key for this secret key.
+    pub fn to_public(&self) -> Recipient {
+        let (_, ek) = self.mlkem_keypair();
+        Recipient {
+            x25519: (&self.x25519).into(),
+            mlkem: ek,
+        }
+    }
+
+    fn mlkem_keypair(&self) -> (<MlKem768 as KemCore>::DecapsulationKey, EncapsulationKey) {
+        let d = B32::try_from(&self.mlkem_seed[..32]).expect("correct length");
+        let z = B32::try_from(&self.mlkem_seed[32..]).expect("correct length");
+        MlKem768::generate_deterministic(&d, &z)
+    }
+}
+
+impl crate::Identity for Identity {
+    fn unwrap_stanza(&self, stanza: &Stanza) -> Option<Result<FileKey, DecryptError>> {
+        if stanza.tag != MLKEM768X25519_RECIPIENT_TAG {
+            return None;
+        }
+        if stanza.body.len() != ENCRYPTED_FILE_KEY_BYTES {
+            return Some(Err(DecryptError::InvalidHeader));
+        }
+
+        let enc = base64_arg(stanza.args.get(0)?, vec![0; ENC_BYTES])?;
+        let ct = Ciphertext::<MlKem768>::try_from(&enc[..MLKEM_CT_BYTES]).ok()?;
+        let epk: PublicKey = TryInto::<[u8; EPK_LEN_BYTES]>::try_into(&enc[MLKEM_CT_BYTES..])
+            .ok()?
+            .into();
+        let encrypted_file_key: [u8; ENCRYPTED_FILE_KEY_BYTES] = stanza.body[..].try_into().ok()?;
+
+        // A failure to decrypt is non-fatal (we try to decrypt the recipient
+        // stanza with other hybrid keys), because we cannot tell which key
+        // matches a particular stanza. ML-KEM uses implicit rejection, so a
+        // ciphertext for a different key produces an unrelated shared secret,
+        // and the file key then fails to decrypt.
+
+        let (dk, _) = self.mlkem_keypair();
+        let mlkem_ss = dk.decapsulate(&ct).ok()?;
+
+        let pk: PublicKey = (&self.x25519).into();
+        let x25519_ss = self.x25519.diffie_hellman(&epk);
+
+        let enc_key = wrapping_key(&mlkem_ss, x25519_ss.as_bytes(), &epk, &pk);
+
+        aead_decrypt(&enc_key, FILE_KEY_BYTES, &encrypted_file_key)
+            .ok()
+            .map(|mut pt| {
+                // It's ours!
+                let file_key: [u8; FILE_KEY_BYTES] = pt[..].try_into().unwrap();
+                pt.zeroize();
+                Ok(file_key.into())
+            })
+    }
+}
+
+/// A post-quantum hybrid recipient. Files encrypted to this recipient can be decrypted
+/// with the corresponding [`Identity`].
+///
+/// Like [`x25519::Recipient`], this recipient type is anonymous, in the sense that an
+/// attacker can't tell from the age-encrypted file alone if it is encrypted to a
+/// certain recipient. However, its stanzas are distinguishable from those of other
+/// recipient types.
+///
+/// [`x25519::Recipient`]: crate::x25519::Recipient
+#[derive(Clone)]
+pub struct Recipient {
+    x25519: PublicKey,
+    mlkem: EncapsulationKey,
+}
+
+impl std::str::FromStr for Recipient {
+    type Err = &'static str;
+
+    /// Parses a recipient key from a string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bech32(s)
+            .ok_or("invalid Bech32 encoding")
+            .and_then(|(hrp, bytes)| {
+                if hrp == PUBLIC_KEY_PREFIX {
+                    if bytes.len() == PUBLIC_KEY_BYTES {
+                        let x25519: [u8; X25519_KEY_BYTES] =
+                            bytes[..X25519_KEY_BYTES].try_into().unwrap();
+                        let mlkem =
+                            Encoded::<EncapsulationKey>::try_from(&bytes[X25519_KEY_BYTES..])
+                                .expect("correct length");
+                        Ok(Recipient {
+                            x25519: x25519.into(),
+                            mlkem: EncapsulationKey::from_bytes(&mlkem),
+                        })
+                    } else {
+                        Err("incorrect pubkey length")
+                    }
+                } else {
+                    Err("incorrect HRP")
+                }
+            })
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bytes = Vec::with_capacity(PUBLIC_KEY_BYTES);
+        bytes.extend_from_slice(self.x25519.as_bytes());
+        bytes.extend_from_slice(&self.mlkem.as_bytes());
+
+        write!(
+            f,
+            "{}",
+            bech32::encode(PUBLIC_KEY_PREFIX, bytes.to_base32(), Variant::Bech32)
+                .expect("HRP is valid")
+        )
+    }
+}
+
+impl crate::Recipient for Recipient {
+    fn wrap_file_key(&self, file_key: &FileKey) -> Result<Vec<Stanza>, EncryptError> {
+        let (ct, mlkem_ss) = self
+            .mlkem
+            .encapsulate(&mut rand::rngs::OsRng)
+            .expect("encapsulation is infallible");
+
+        let mut rng = rand_7::rngs::OsRng;
+        let esk = EphemeralSecret::new(&mut rng);
+        let epk: PublicKey = (&esk).into();
+        let x25519_ss = esk.diffie_hellman(&self.x25519);
+
+        let enc_key = wrapping_key(&mlkem_ss, x25519_ss.as_bytes(), &epk, &self.x25519);
+        let encrypted_file_key = aead_encrypt(&enc_key, file_key.expose_secret());
+
+        let mut enc = Vec::with_capacity(ENC_BYTES);
+        enc.extend_from_slice(&ct);
+        enc.extend_from_slice(epk.as_bytes());
+        let encoded_enc = base64::encode_config(&enc, base64::STANDARD_NO_PAD);
+
+        Ok(vec![Stanza {
+            tag: MLKEM768X25519_RECIPIENT_TAG.to_owned(),
+            args: vec![encoded_enc],
+            body: encrypted_file_key,
+        }])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use age_core::secrecy::ExposeSecret;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+
+    use super::{Identity, Recipient, SECRET_KEY_BYTES};
+    use crate::{Identity as _, Recipient as _};
+
+    pub(crate) const TEST_SK: &str = "RAGE-SECRET-KEY-PQ-1LP8AL8RFT4CNQXH60T9N0KELF6GV95AKCUAX5LN2DGR6XWUE8L2P2RGDWLQDH9DEA4HCH7ZEXF7K57EVJVP0HDZM2R7GQ2XNWLQ78R3DWYTY9DEXKPZQZCNU48A6CVH4EPFSLVVS8NZDKQ39SUTEYXJGSY0YEMXQ";
+
+    #[test]
+    fn secret_key_encoding() {
+        let sk: Identity = TEST_SK.parse().unwrap();
+        assert_eq!(sk.to_string().expose_secret(), TEST_SK);
+    }
+
+    #[test]
+    fn pubkey_encoding() {
+        let pk = TEST_SK.parse::<Identity>().unwrap().to_public();
+        let encoded = pk.to_string();
+        assert!(encoded.starts_with("ragepq1"));
+        assert_eq!(encoded.parse::<Recipient>().unwrap().to_string(), encoded);
+    }
+
+    #[test]
+    fn x25519_keys_are_rejected() {
+        assert!(crate::x25519::tests::TEST_SK.parse::<Identity>().is_err());
+        assert!(crate::x25519::tests::TEST_PK.parse::<Recipient>().is_err());
+    }
+
+    #[quickcheck]
+    fn wrap_and_unwrap(sk_bytes: Vec<u8>) -> TestResult {
+        if sk_bytes.len() > SECRET_KEY_BYTES {
+            return TestResult::discard();
+        }
+
+        let file_key = [7; 16].into();
+        let sk = {
+            let mut tmp = [0; SECRET_KEY_BYTES];
+            tmp[..sk_bytes.len()].copy_from_slice(&sk_bytes);
+            Identity::from_bytes(&tmp)
+        };
+
+        let stanzas = sk.to_public().wrap_file_key(&file_key).unwrap();
+        let res = sk.unwrap_stanzas(&stanzas);
+
+        match res {
+            Some(Ok(res)) => TestResult::from_bool(res.expose_secret() == file_key.expose_secret()),
+            _ => TestResult::from_bool(false),
+        }
+    }
+
+    #[test]
+    fn unwrap_fails_for_other_identity() {
+        let file_key = [7; 16].into();
+        let stanzas = Identity::generate()
+            .to_public()
+            .wrap_file_key(&file_key)
+            .unwrap();
+        assert!(Identity::generate().unwrap_stanzas(&stanzas).is_none());
+    }
+}
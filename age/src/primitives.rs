@@ -9,6 +9,17 @@ use scrypt::{errors::InvalidParams, scrypt as scrypt_inner, Params as ScryptPara
 use sha2::Sha256;
 use std::io::{self, Write};
 
+/// Equivalent to `futures::ready!`, for when only the `tokio` feature flag is enabled.
+#[cfg(all(feature = "tokio", not(feature = "async")))]
+macro_rules! ready {
+    ($e:expr $(,)?) => {
+        match $e {
+            std::task::Poll::Ready(t) => t,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        }
+    };
+}
+
 #[cfg(feature = "armor")]
 #[cfg_attr(docsrs, doc(cfg(feature = "armor")))]
 pub mod armor;
@@ -11,10 +11,18 @@ use crate::util::LINE_ENDING;
 use futures::{
     io::{AsyncBufRead, AsyncRead, AsyncWrite, BufReader as AsyncBufReader, Error},
     ready,
+};
+#[cfg(any(feature = "async", feature = "tokio"))]
+use std::{
+    pin::Pin,
     task::{Context, Poll},
 };
-#[cfg(feature = "async")]
-use std::pin::Pin;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{
+    AsyncBufRead as TokioAsyncBufRead, AsyncRead as TokioAsyncRead, AsyncWrite as TokioAsyncWrite,
+    BufReader as TokioBufReader, ReadBuf,
+};
 
 const ARMORED_COLUMNS_PER_LINE: usize = 64;
 const ARMORED_BYTES_PER_LINE: usize = ARMORED_COLUMNS_PER_LINE / 4 * 3;
@@ -34,15 +42,13 @@ pub enum Format {
     AsciiArmor,
 }
 
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[cfg(any(feature = "async", feature = "tokio"))]
 struct EncodedLine {
     bytes: Vec<u8>,
     offset: usize,
 }
 
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[cfg(any(feature = "async", feature = "tokio"))]
 struct EncodedBytes {
     offset: usize,
     end: usize,
@@ -56,11 +62,9 @@ pub(crate) struct LineEndingWriter<W> {
     total_written: usize,
 
     /// None if `AsyncWrite::poll_closed` has been called.
-    #[cfg(feature = "async")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    #[cfg(any(feature = "async", feature = "tokio"))]
     line: Option<Vec<u8>>,
-    #[cfg(feature = "async")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    #[cfg(any(feature = "async", feature = "tokio"))]
     line_with_ending: Option<EncodedLine>,
 }
 
@@ -74,9 +78,9 @@ impl<W: Write> LineEndingWriter<W> {
             inner,
             buf: Vec::with_capacity(8 * 1024),
             total_written: 0,
-            #[cfg(feature = "async")]
+            #[cfg(any(feature = "async", feature = "tokio"))]
             line: None,
-            #[cfg(feature = "async")]
+            #[cfg(any(feature = "async", feature = "tokio"))]
             line_with_ending: None,
         })
     }
@@ -146,9 +150,8 @@ impl<W: Write> Write for LineEndingWriter<W> {
     }
 }
 
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
-impl<W: AsyncWrite> LineEndingWriter<W> {
+#[cfg(any(feature = "async", feature = "tokio"))]
+impl<W> LineEndingWriter<W> {
     fn new_async(inner: W) -> Self {
         // Write the begin marker
         let bytes = [ARMORED_BEGIN_MARKER.as_bytes(), LINE_ENDING.as_bytes()].concat();
@@ -162,6 +165,66 @@ impl<W: AsyncWrite> LineEndingWriter<W> {
         }
     }
 
+    /// Buffers as much of `buf` as fits in the current line, and adds a line ending if
+    /// there is more data to write. Returns the number of bytes buffered.
+    ///
+    /// Must only be called once any previous line has been flushed.
+    fn buffer_line(self: Pin<&mut Self>, mut buf: &[u8]) -> io::Result<usize> {
+        let this = self.project();
+        if let Some(line) = this.line {
+            let mut to_write = ARMORED_COLUMNS_PER_LINE - line.len();
+            if to_write > buf.len() {
+                to_write = buf.len()
+            }
+
+            line.extend_from_slice(&buf[..to_write]);
+            buf = &buf[to_write..];
+
+            // At this point, either buf is empty, or we have a complete line.
+            assert!(buf.is_empty() || line.len() == ARMORED_COLUMNS_PER_LINE);
+
+            // Only add a line ending if we have more data to write, as the last
+            // line must be written when the writer is closed.
+            if !buf.is_empty() {
+                *this.line_with_ending = Some(EncodedLine {
+                    bytes: [line, LINE_ENDING.as_bytes()].concat(),
+                    offset: 0,
+                });
+                line.clear();
+            }
+
+            Ok(to_write)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "AsyncWrite::poll_closed has been called",
+            ))
+        }
+    }
+
+    /// Finishes the armored format with a partial line (if necessary) and the end
+    /// marker, if this has not already been done.
+    fn finish_lines(self: Pin<&mut Self>) {
+        let this = self.project();
+        if let Some(line) = this.line {
+            *this.line_with_ending = Some(EncodedLine {
+                bytes: [
+                    line,
+                    LINE_ENDING.as_bytes(),
+                    ARMORED_END_MARKER.as_bytes(),
+                    LINE_ENDING.as_bytes(),
+                ]
+                .concat(),
+                offset: 0,
+            });
+        }
+        *this.line = None;
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl<W: AsyncWrite> LineEndingWriter<W> {
     fn poll_flush_line(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
         let LineEndingWriterProj {
             mut inner,
@@ -189,71 +252,79 @@ impl<W: AsyncWrite> AsyncWrite for LineEndingWriter<W> {
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context,
-        mut buf: &[u8],
+        buf: &[u8],
     ) -> Poll<io::Result<usize>> {
         ready!(self.as_mut().poll_flush_line(cx))?;
+        Poll::Ready(self.buffer_line(buf))
+    }
 
-        let this = self.as_mut().project();
-        if let Some(line) = this.line {
-            let mut to_write = ARMORED_COLUMNS_PER_LINE - line.len();
-            if to_write > buf.len() {
-                to_write = buf.len()
-            }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_line(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
 
-            line.extend_from_slice(&buf[..to_write]);
-            buf = &buf[to_write..];
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Flush any remaining line bytes.
+        ready!(self.as_mut().poll_flush_line(cx))?;
 
-            // At this point, either buf is empty, or we have a complete line.
-            assert!(buf.is_empty() || line.len() == ARMORED_COLUMNS_PER_LINE);
+        self.as_mut().finish_lines();
 
-            // Only add a line ending if we have more data to write, as the last
-            // line must be written in poll_close().
-            if !buf.is_empty() {
-                *this.line_with_ending = Some(EncodedLine {
-                    bytes: [line, LINE_ENDING.as_bytes()].concat(),
-                    offset: 0,
-                });
-                line.clear();
-            }
+        // Flush the final line (if we didn't in the first call).
+        ready!(self.as_mut().poll_flush_line(cx))?;
+        self.project().inner.poll_close(cx)
+    }
+}
 
-            Poll::Ready(Ok(to_write))
-        } else {
-            Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::WriteZero,
-                "AsyncWrite::poll_closed has been called",
-            )))
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> LineEndingWriter<W> {
+    fn poll_flush_line_tokio(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let LineEndingWriterProj {
+            mut inner,
+            line_with_ending,
+            ..
+        } = self.project();
+
+        if let Some(line) = line_with_ending {
+            loop {
+                line.offset += ready!(inner.as_mut().poll_write(cx, &line.bytes[line.offset..]))?;
+                if line.offset == line.bytes.len() {
+                    break;
+                }
+            }
         }
+        *line_with_ending = None;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> TokioAsyncWrite for LineEndingWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
+        Poll::Ready(self.buffer_line(buf))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        ready!(self.as_mut().poll_flush_line(cx))?;
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
         self.project().inner.poll_flush(cx)
     }
 
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         // Flush any remaining line bytes.
-        ready!(self.as_mut().poll_flush_line(cx))?;
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
 
-        let this = self.as_mut().project();
-        if let Some(line) = this.line {
-            // Finish the armored format with a partial line (if necessary) and the end
-            // marker.
-            *this.line_with_ending = Some(EncodedLine {
-                bytes: [
-                    line,
-                    LINE_ENDING.as_bytes(),
-                    ARMORED_END_MARKER.as_bytes(),
-                    LINE_ENDING.as_bytes(),
-                ]
-                .concat(),
-                offset: 0,
-            });
-        }
-        *this.line = None;
+        self.as_mut().finish_lines();
 
         // Flush the final line (if we didn't in the first call).
-        ready!(self.as_mut().poll_flush_line(cx))?;
-        self.project().inner.poll_close(cx)
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
+        self.project().inner.poll_shutdown(cx)
     }
 }
 
@@ -264,8 +335,7 @@ enum ArmorIs<W> {
         inner: LineEndingWriter<W>,
         byte_buf: Option<Vec<u8>>,
         encoded_buf: [u8; BASE64_CHUNK_SIZE_COLUMNS],
-        #[cfg(feature = "async")]
-        #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+        #[cfg(any(feature = "async", feature = "tokio"))]
         encoded_line: Option<EncodedBytes>,
     },
 
@@ -288,7 +358,7 @@ impl<W: Write> ArmoredWriter<W> {
                     inner: w,
                     byte_buf: Some(Vec::with_capacity(BASE64_CHUNK_SIZE_BYTES)),
                     encoded_buf: [0; BASE64_CHUNK_SIZE_COLUMNS],
-                    #[cfg(feature = "async")]
+                    #[cfg(any(feature = "async", feature = "tokio"))]
                     encoded_line: None,
                 })
             }),
@@ -375,11 +445,9 @@ impl<W: Write> Write for ArmoredWriter<W> {
     }
 }
 
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
-impl<W: AsyncWrite> ArmoredWriter<W> {
-    /// Wraps the given output in an `ArmoredWriter` that will apply the given [`Format`].
-    pub fn wrap_async_output(output: W, format: Format) -> Self {
+#[cfg(any(feature = "async", feature = "tokio"))]
+impl<W> ArmoredWriter<W> {
+    fn wrap_async(output: W, format: Format) -> Self {
         match format {
             Format::AsciiArmor => ArmoredWriter(ArmorIs::Enabled {
                 inner: LineEndingWriter::new_async(output),
@@ -391,6 +459,87 @@ impl<W: AsyncWrite> ArmoredWriter<W> {
         }
     }
 
+    /// Buffers as much of `buf` as fits in the current chunk, and encodes the chunk if
+    /// there is more data to write. Returns the number of bytes buffered.
+    ///
+    /// Must only be called when armoring is enabled, and once any previously-encoded
+    /// chunk has been flushed.
+    fn buffer_bytes(self: Pin<&mut Self>, mut buf: &[u8]) -> io::Result<usize> {
+        match self.project().0.project() {
+            ArmorIsProj::Enabled {
+                byte_buf,
+                encoded_buf,
+                encoded_line,
+                ..
+            } => {
+                if let Some(byte_buf) = byte_buf {
+                    let mut to_write = BASE64_CHUNK_SIZE_BYTES - byte_buf.len();
+                    if to_write > buf.len() {
+                        to_write = buf.len()
+                    }
+
+                    byte_buf.extend_from_slice(&buf[..to_write]);
+                    buf = &buf[to_write..];
+
+                    // At this point, either buf is empty, or we have a full chunk.
+                    assert!(buf.is_empty() || byte_buf.len() == BASE64_CHUNK_SIZE_BYTES);
+
+                    // Only encode the chunk if we have more data to write, as the last
+                    // chunk must be written when the writer is closed.
+                    if !buf.is_empty() {
+                        assert_eq!(
+                            base64::encode_config_slice(&byte_buf, base64::STANDARD, encoded_buf),
+                            BASE64_CHUNK_SIZE_COLUMNS
+                        );
+                        *encoded_line = Some(EncodedBytes {
+                            offset: 0,
+                            end: BASE64_CHUNK_SIZE_COLUMNS,
+                        });
+                        byte_buf.clear();
+                    }
+
+                    Ok(to_write)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "AsyncWrite::poll_closed has been called",
+                    ))
+                }
+            }
+            ArmorIsProj::Disabled { .. } => unreachable!("armoring is enabled"),
+        }
+    }
+
+    /// Encodes the final (possibly-partial) chunk, if armoring is enabled and this has
+    /// not already been done.
+    fn finish_bytes(self: Pin<&mut Self>) {
+        if let ArmorIsProj::Enabled {
+            byte_buf,
+            encoded_buf,
+            encoded_line,
+            ..
+        } = self.project().0.project()
+        {
+            if let Some(byte_buf) = byte_buf {
+                let encoded = base64::encode_config_slice(&byte_buf, base64::STANDARD, encoded_buf);
+                *encoded_line = Some(EncodedBytes {
+                    offset: 0,
+                    end: encoded,
+                });
+            }
+            *byte_buf = None;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl<W: AsyncWrite> ArmoredWriter<W> {
+    /// Wraps the given output in an `ArmoredWriter` that will apply the given [`Format`].
+    pub fn wrap_async_output(output: W, format: Format) -> Self {
+        Self::wrap_async(output, format)
+    }
+
     fn poll_flush_line(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
         if let ArmorIsProj::Enabled {
             mut inner,
@@ -422,51 +571,12 @@ impl<W: AsyncWrite> AsyncWrite for ArmoredWriter<W> {
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context,
-        mut buf: &[u8],
+        buf: &[u8],
     ) -> Poll<io::Result<usize>> {
         ready!(self.as_mut().poll_flush_line(cx))?;
 
-        match self.project().0.project() {
-            ArmorIsProj::Enabled {
-                byte_buf,
-                encoded_buf,
-                encoded_line,
-                ..
-            } => {
-                if let Some(byte_buf) = byte_buf {
-                    let mut to_write = BASE64_CHUNK_SIZE_BYTES - byte_buf.len();
-                    if to_write > buf.len() {
-                        to_write = buf.len()
-                    }
-
-                    byte_buf.extend_from_slice(&buf[..to_write]);
-                    buf = &buf[to_write..];
-
-                    // At this point, either buf is empty, or we have a full line.
-                    assert!(buf.is_empty() || byte_buf.len() == BASE64_CHUNK_SIZE_BYTES);
-
-                    // Only encode the line if we have more data to write, as the last
-                    // line must be written in poll_close().
-                    if !buf.is_empty() {
-                        assert_eq!(
-                            base64::encode_config_slice(&byte_buf, base64::STANDARD, encoded_buf),
-                            ARMORED_COLUMNS_PER_LINE
-                        );
-                        *encoded_line = Some(EncodedBytes {
-                            offset: 0,
-                            end: ARMORED_COLUMNS_PER_LINE,
-                        });
-                        byte_buf.clear();
-                    }
-
-                    Poll::Ready(Ok(to_write))
-                } else {
-                    Poll::Ready(Err(io::Error::new(
-                        io::ErrorKind::WriteZero,
-                        "AsyncWrite::poll_closed has been called",
-                    )))
-                }
-            }
+        match self.as_mut().project().0.project() {
+            ArmorIsProj::Enabled { .. } => Poll::Ready(self.buffer_bytes(buf)),
             ArmorIsProj::Disabled { inner } => inner.poll_write(cx, buf),
         }
     }
@@ -483,31 +593,91 @@ impl<W: AsyncWrite> AsyncWrite for ArmoredWriter<W> {
         // Flush any remaining encoded line bytes.
         ready!(self.as_mut().poll_flush_line(cx))?;
 
+        // Finish the armored format with a partial line (if necessary) and the end
+        // marker.
+        self.as_mut().finish_bytes();
+
+        // Flush the final chunk (if we didn't in the first call).
+        ready!(self.as_mut().poll_flush_line(cx))?;
+
+        match self.project().0.project() {
+            ArmorIsProj::Enabled { inner, .. } => inner.poll_close(cx),
+            ArmorIsProj::Disabled { inner } => inner.poll_close(cx),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> ArmoredWriter<W> {
+    /// Wraps the given output in an `ArmoredWriter` that will apply the given [`Format`].
+    pub fn wrap_tokio_output(output: W, format: Format) -> Self {
+        Self::wrap_async(output, format)
+    }
+
+    fn poll_flush_line_tokio(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
         if let ArmorIsProj::Enabled {
-            byte_buf,
+            mut inner,
             encoded_buf,
             encoded_line,
             ..
-        } = self.as_mut().project().0.project()
+        } = self.project().0.project()
         {
-            if let Some(byte_buf) = byte_buf {
-                // Finish the armored format with a partial line (if necessary) and the end
-                // marker.
-                let encoded = base64::encode_config_slice(&byte_buf, base64::STANDARD, encoded_buf);
-                *encoded_line = Some(EncodedBytes {
-                    offset: 0,
-                    end: encoded,
-                });
+            if let Some(line) = encoded_line {
+                loop {
+                    line.offset += ready!(inner
+                        .as_mut()
+                        .poll_write(cx, &encoded_buf[line.offset..line.end]))?;
+                    if line.offset == line.end {
+                        break;
+                    }
+                }
             }
-            *byte_buf = None;
+            *encoded_line = None;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> TokioAsyncWrite for ArmoredWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
+
+        match self.as_mut().project().0.project() {
+            ArmorIsProj::Enabled { .. } => Poll::Ready(self.buffer_bytes(buf)),
+            ArmorIsProj::Disabled { inner } => inner.poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
+        match self.project().0.project() {
+            ArmorIsProj::Enabled { inner, .. } => inner.poll_flush(cx),
+            ArmorIsProj::Disabled { inner } => inner.poll_flush(cx),
         }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Flush any remaining encoded line bytes.
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
+
+        // Finish the armored format with a partial line (if necessary) and the end
+        // marker.
+        self.as_mut().finish_bytes();
 
         // Flush the final chunk (if we didn't in the first call).
-        ready!(self.as_mut().poll_flush_line(cx))?;
+        ready!(self.as_mut().poll_flush_line_tokio(cx))?;
 
         match self.project().0.project() {
-            ArmorIsProj::Enabled { inner, .. } => inner.poll_close(cx),
-            ArmorIsProj::Disabled { inner } => inner.poll_close(cx),
+            ArmorIsProj::Enabled { inner, .. } => inner.poll_shutdown(cx),
+            ArmorIsProj::Disabled { inner } => inner.poll_shutdown(cx),
         }
     }
 }
@@ -549,6 +719,9 @@ pub struct ArmoredReader<R> {
     found_end: bool,
     data_len: Option<u64>,
     data_read: usize,
+    /// The number of bytes read so far by `tokio::io::AsyncRead` to detect armor.
+    #[cfg(feature = "tokio")]
+    detect_read: usize,
 }
 
 impl<R: Read> ArmoredReader<BufReader<R>> {
@@ -567,6 +740,15 @@ impl<R: AsyncRead + Unpin> ArmoredReader<AsyncBufReader<R>> {
     }
 }
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> ArmoredReader<TokioBufReader<R>> {
+    /// Wraps a reader that may contain an armored age file.
+    pub fn from_tokio_reader(inner: R) -> Self {
+        ArmoredReader::with_buffered(TokioBufReader::new(inner))
+    }
+}
+
 impl<R> ArmoredReader<R> {
     fn with_buffered(inner: R) -> Self {
         ArmoredReader {
@@ -581,9 +763,19 @@ impl<R> ArmoredReader<R> {
             found_end: false,
             data_len: None,
             data_read: 0,
+            #[cfg(feature = "tokio")]
+            detect_read: 0,
         }
     }
 
+    /// Returns whether the wrapped age file is armored.
+    ///
+    /// Returns `None` if nothing has been read yet, as the armor is detected during the
+    /// first read.
+    pub fn is_armored(&self) -> Option<bool> {
+        self.is_armored
+    }
+
     fn count_reader_bytes(&mut self, read: usize) -> usize {
         // We only need to count if we haven't yet worked out the start position.
         if let StartPos::Implicit(offset) = &mut self.start {
@@ -879,6 +1071,103 @@ impl<R: AsyncBufRead + Unpin> AsyncRead for ArmoredReader<R> {
     }
 }
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncBufRead + Unpin> TokioAsyncRead for ArmoredReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match this.is_armored {
+                None => {
+                    // Read exactly MIN_ARMOR_LEN bytes, which might be spread across
+                    // several buffer fills.
+                    while this.detect_read < MIN_ARMOR_LEN {
+                        let available = ready!(Pin::new(&mut this.inner).poll_fill_buf(cx))?;
+                        if available.is_empty() {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "failed to fill whole buffer",
+                            )));
+                        }
+                        let to_read = cmp::min(MIN_ARMOR_LEN - this.detect_read, available.len());
+                        this.byte_buf[this.detect_read..this.detect_read + to_read]
+                            .copy_from_slice(&available[..to_read]);
+                        Pin::new(&mut this.inner).consume(to_read);
+                        this.detect_read += to_read;
+                    }
+                    this.detect_armor()?
+                }
+                Some(false) => {
+                    // Return any leftover data from armor detection.
+                    if let Some(read) = this.read_cached_data(buf.initialize_unfilled()) {
+                        buf.advance(read);
+                    } else {
+                        let filled = buf.filled().len();
+                        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+                        let read = buf.filled().len() - filled;
+                        this.data_read += read;
+                        this.count_reader_bytes(read);
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Some(true) if this.found_end => return Poll::Ready(Ok(())),
+                Some(true) => {
+                    // Output any remaining bytes from the previous line
+                    if let Some(read) = this.read_cached_data(buf.initialize_unfilled()) {
+                        buf.advance(read);
+                        return Poll::Ready(Ok(()));
+                    }
+
+                    // Read the next line, which might be spread across several buffer
+                    // fills.
+                    loop {
+                        let available = ready!(Pin::new(&mut this.inner).poll_fill_buf(cx))?;
+                        if available.is_empty() {
+                            // The line is missing its line ending, which will be caught
+                            // by the parser.
+                            break;
+                        }
+                        let (pos, found_end) = match available.iter().position(|c| *c == b'\n') {
+                            Some(pos) => (pos + 1, true),
+                            None => (available.len(), false),
+                        };
+
+                        this.line_buf
+                            .push_str(std::str::from_utf8(&available[..pos]).map_err(|_| {
+                                io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    "stream did not contain valid UTF-8",
+                                )
+                            })?);
+
+                        Pin::new(&mut this.inner).consume(pos);
+                        this.count_reader_bytes(pos);
+
+                        if found_end {
+                            break;
+                        }
+                    }
+
+                    // Parse the line into bytes.
+                    if !this.parse_armor_line()? {
+                        // Output as much as we can of this line.
+                        let read = this
+                            .read_cached_data(buf.initialize_unfilled())
+                            .unwrap_or(0);
+                        buf.advance(read);
+                    }
+
+                    return Poll::Ready(Ok(()));
+                }
+            }
+        }
+    }
+}
+
 impl<R: Read + Seek> ArmoredReader<R> {
     fn start(&mut self) -> io::Result<u64> {
         match self.start {
@@ -1032,9 +1321,16 @@ mod tests {
         pin_mut,
         task::Poll,
     };
-    #[cfg(feature = "async")]
+    #[cfg(any(feature = "async", feature = "tokio"))]
     use futures_test::task::noop_context;
 
+    #[cfg(feature = "tokio")]
+    use super::BASE64_CHUNK_SIZE_BYTES;
+    #[cfg(feature = "tokio")]
+    use std::task::Poll as TokioPoll;
+    #[cfg(feature = "tokio")]
+    use tokio::io::ReadBuf;
+
     #[test]
     fn armored_round_trip() {
         const MAX_LEN: usize = ARMORED_BYTES_PER_LINE * 50;
@@ -1118,6 +1414,79 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "tokio")]
+    fn armored_tokio_round_trip_data(data: &[u8]) {
+        let mut encoded = vec![];
+        {
+            let mut w = Box::pin(ArmoredWriter::wrap_tokio_output(
+                &mut encoded,
+                Format::AsciiArmor,
+            ));
+
+            let mut cx = noop_context();
+
+            let mut tmp = data;
+            loop {
+                match tokio::io::AsyncWrite::poll_write(w.as_mut(), &mut cx, tmp) {
+                    TokioPoll::Ready(Ok(0)) => break,
+                    TokioPoll::Ready(Ok(written)) => tmp = &tmp[written..],
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+            loop {
+                match tokio::io::AsyncWrite::poll_shutdown(w.as_mut(), &mut cx) {
+                    TokioPoll::Ready(Ok(())) => break,
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        let mut buf = vec![];
+        {
+            let mut input = Box::pin(ArmoredReader::from_tokio_reader(&encoded[..]));
+
+            let mut cx = noop_context();
+
+            let mut tmp = [0; 4096];
+            loop {
+                let mut read_buf = ReadBuf::new(&mut tmp);
+                match tokio::io::AsyncRead::poll_read(input.as_mut(), &mut cx, &mut read_buf) {
+                    TokioPoll::Ready(Ok(())) if read_buf.filled().is_empty() => break,
+                    TokioPoll::Ready(Ok(())) => buf.extend_from_slice(read_buf.filled()),
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        assert_eq!(buf, data);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn armored_tokio_round_trip() {
+        const MAX_LEN: usize = ARMORED_BYTES_PER_LINE * 50;
+
+        let mut data = Vec::with_capacity(MAX_LEN);
+
+        for i in 0..MAX_LEN {
+            data.push(i as u8);
+            armored_tokio_round_trip_data(&data);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn armored_tokio_round_trip_multiple_chunks() {
+        // Written in a single call, which spans several base64 chunks.
+        let data: Vec<u8> = (0..3 * BASE64_CHUNK_SIZE_BYTES + 17)
+            .map(|i| i as u8)
+            .collect();
+        armored_tokio_round_trip_data(&data);
+    }
+
     #[test]
     fn binary_seeking() {
         let mut data = vec![0; 100 * 100];
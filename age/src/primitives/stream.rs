@@ -10,18 +10,31 @@ use std::cmp;
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use zeroize::Zeroize;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use std::collections::VecDeque;
+
 #[cfg(feature = "async")]
 use futures::{
     io::{AsyncRead, AsyncWrite, Error},
     ready,
+};
+#[cfg(any(feature = "async", feature = "tokio"))]
+use std::{
+    pin::Pin,
     task::{Context, Poll},
 };
-#[cfg(feature = "async")]
-use std::pin::Pin;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{
+    AsyncRead as TokioAsyncRead, AsyncSeek as TokioAsyncSeek, AsyncWrite as TokioAsyncWrite,
+    ReadBuf,
+};
 
 const CHUNK_SIZE: usize = 64 * 1024;
-const TAG_SIZE: usize = 16;
-const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
+pub(crate) const TAG_SIZE: usize = 16;
+pub(crate) const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
 
 pub(crate) struct PayloadKey(pub(crate) GenericArray<u8, <ChaCha20Poly1305 as NewAead>::KeySize>);
 
@@ -31,6 +44,97 @@ impl Drop for PayloadKey {
     }
 }
 
+/// Returns the length of the plaintext encrypted in a STREAM of `stream_len` bytes.
+///
+/// `stream_len` is the length of an age file's payload, excluding the 16-byte payload
+/// nonce. Returns `None` if no valid STREAM has that length.
+pub fn plaintext_len(stream_len: u64) -> Option<u64> {
+    // Use ceiling division to determine the number of chunks.
+    let num_chunks = (stream_len + (ENCRYPTED_CHUNK_SIZE as u64 - 1)) / ENCRYPTED_CHUNK_SIZE as u64;
+    let last_chunk_len =
+        stream_len.checked_sub((num_chunks.checked_sub(1)?) * ENCRYPTED_CHUNK_SIZE as u64)?;
+
+    // Every chunk contains a tag, and only the first chunk can be empty.
+    if last_chunk_len < TAG_SIZE as u64 || (last_chunk_len == TAG_SIZE as u64 && num_chunks > 1) {
+        None
+    } else {
+        Some(stream_len - num_chunks * TAG_SIZE as u64)
+    }
+}
+
+/// The progress of a [`StreamWriter`] or [`StreamReader`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    bytes: u64,
+    chunks: u64,
+    total_bytes: Option<u64>,
+}
+
+impl Progress {
+    /// Returns the number of plaintext bytes that have been encrypted or decrypted.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the number of chunks that have been encrypted or decrypted.
+    pub fn chunks(&self) -> u64 {
+        self.chunks
+    }
+
+    /// Returns the total number of plaintext bytes in the stream, if it is known.
+    pub fn total_bytes(&self) -> Option<u64> {
+        self.total_bytes
+    }
+}
+
+/// An observer of the progress of a [`StreamWriter`] or [`StreamReader`].
+///
+/// This is implemented for closures that take a [`Progress`].
+pub trait ProgressObserver: Send + Sync {
+    /// Called each time one or more chunks have been encrypted or decrypted.
+    fn on_progress(&mut self, progress: Progress);
+}
+
+impl<F: FnMut(Progress) + Send + Sync> ProgressObserver for F {
+    fn on_progress(&mut self, progress: Progress) {
+        self(progress)
+    }
+}
+
+/// Tracks the progress of a stream, and reports it to an observer.
+struct ProgressTracker {
+    observer: Box<dyn ProgressObserver>,
+    progress: Progress,
+}
+
+impl ProgressTracker {
+    fn new<O: ProgressObserver + 'static>(total_bytes: Option<u64>, observer: O) -> Self {
+        ProgressTracker {
+            observer: Box::new(observer),
+            progress: Progress {
+                bytes: 0,
+                chunks: 0,
+                total_bytes,
+            },
+        }
+    }
+
+    /// Records that `bytes` plaintext bytes in `chunks` chunks have been processed.
+    fn update(tracker: &mut Option<Self>, bytes: usize, chunks: u64) {
+        if let Some(tracker) = tracker {
+            tracker.progress.bytes += bytes as u64;
+            tracker.progress.chunks += chunks;
+            tracker.observer.on_progress(tracker.progress);
+        }
+    }
+}
+
+/// Returns the number of chunks needed to encrypt `len` bytes of plaintext.
+fn chunk_count(len: usize) -> u64 {
+    // An empty stream still contains a single (empty) chunk.
+    cmp::max(1, (len + CHUNK_SIZE - 1) / CHUNK_SIZE) as u64
+}
+
 /// The nonce used in age's STREAM encryption.
 ///
 /// Structured as an 11 bytes of big endian counter, and 1 byte of last block flag
@@ -65,6 +169,17 @@ impl Nonce {
         }
     }
 
+    /// Returns the nonce for the chunk `n` chunks after this one, with the last-chunk
+    /// flag set to `last`.
+    #[cfg(feature = "parallel")]
+    fn offset(self, n: usize, last: bool) -> Self {
+        let nonce = (self.0 & !1) + ((n as u128) << 8);
+        if nonce >> (8 * 12) != 0 {
+            panic!("We overflowed the nonce!");
+        }
+        Nonce(nonce | u128::from(last))
+    }
+
     fn to_bytes(self) -> [u8; 12] {
         self.0.to_be_bytes()[4..]
             .try_into()
@@ -72,8 +187,7 @@ impl Nonce {
     }
 }
 
-#[cfg(feature = "async")]
-#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[cfg(any(feature = "async", feature = "tokio"))]
 struct EncryptedChunk {
     bytes: Vec<u8>,
     offset: usize,
@@ -111,8 +225,11 @@ impl Stream {
             stream: Self::new(key),
             inner,
             chunk: Vec::with_capacity(CHUNK_SIZE),
-            #[cfg(feature = "async")]
+            #[cfg(any(feature = "async", feature = "tokio"))]
             encrypted_chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            progress: None,
         }
     }
 
@@ -131,6 +248,54 @@ impl Stream {
             inner,
             chunk: Vec::with_capacity(CHUNK_SIZE),
             encrypted_chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            progress: None,
+        }
+    }
+
+    /// Wraps `STREAM` encryption under the given `key` around a writer.
+    ///
+    /// `key` must **never** be repeated across multiple streams. In `age` this is
+    /// achieved by deriving the key with [`HKDF`] from both a random file key and a
+    /// random nonce.
+    ///
+    /// [`HKDF`]: age_core::primitives::hkdf
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub(crate) fn encrypt_tokio<W: TokioAsyncWrite>(key: PayloadKey, inner: W) -> StreamWriter<W> {
+        StreamWriter {
+            stream: Self::new(key),
+            inner,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            encrypted_chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            progress: None,
+        }
+    }
+
+    /// Wraps `STREAM` encryption under the given `key` around a reader of plaintext.
+    ///
+    /// `prefix` is provided by the reader before any ciphertext.
+    ///
+    /// `key` must **never** be repeated across multiple streams. In `age` this is
+    /// achieved by deriving the key with [`HKDF`] from both a random file key and a
+    /// random nonce.
+    ///
+    /// [`HKDF`]: age_core::primitives::hkdf
+    pub(crate) fn encrypt_reader<R>(
+        key: PayloadKey,
+        prefix: Vec<u8>,
+        inner: R,
+    ) -> EncryptingReader<R> {
+        EncryptingReader {
+            stream: Self::new(key),
+            inner,
+            chunk: vec![0; CHUNK_SIZE + 1],
+            chunk_pos: 0,
+            encrypted_chunk: prefix,
+            encrypted_pos: 0,
         }
     }
 
@@ -151,6 +316,15 @@ impl Stream {
             plaintext_len: None,
             cur_plaintext_pos: 0,
             chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            #[cfg(feature = "parallel")]
+            encrypted_batch: vec![],
+            #[cfg(feature = "parallel")]
+            pending: VecDeque::new(),
+            #[cfg(feature = "tokio")]
+            tokio_seek: TokioSeek::default(),
+            progress: None,
         }
     }
 
@@ -173,6 +347,63 @@ impl Stream {
             plaintext_len: None,
             cur_plaintext_pos: 0,
             chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            #[cfg(feature = "parallel")]
+            encrypted_batch: vec![],
+            #[cfg(feature = "parallel")]
+            pending: VecDeque::new(),
+            #[cfg(feature = "tokio")]
+            tokio_seek: TokioSeek::default(),
+            progress: None,
+        }
+    }
+
+    /// Wraps `STREAM` decryption under the given `key` around a reader.
+    ///
+    /// `key` must **never** be repeated across multiple streams. In `age` this is
+    /// achieved by deriving the key with [`HKDF`] from both a random file key and a
+    /// random nonce.
+    ///
+    /// [`HKDF`]: age_core::primitives::hkdf
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub(crate) fn decrypt_tokio<R: TokioAsyncRead>(key: PayloadKey, inner: R) -> StreamReader<R> {
+        StreamReader {
+            stream: Self::new(key),
+            inner,
+            encrypted_chunk: vec![0; ENCRYPTED_CHUNK_SIZE],
+            encrypted_pos: 0,
+            start: StartPos::Implicit(0),
+            plaintext_len: None,
+            cur_plaintext_pos: 0,
+            chunk: None,
+            #[cfg(feature = "parallel")]
+            parallelism: 1,
+            #[cfg(feature = "parallel")]
+            encrypted_batch: vec![],
+            #[cfg(feature = "parallel")]
+            pending: VecDeque::new(),
+            tokio_seek: TokioSeek::default(),
+            progress: None,
+        }
+    }
+
+    /// Wraps `STREAM` decryption under the given `key` around a reader, recovering as
+    /// many chunks as possible from a damaged stream.
+    ///
+    /// `offset` is the position of the start of the stream within the age file, and is
+    /// used when reporting damaged ranges.
+    pub(crate) fn recover<R: Read>(key: PayloadKey, inner: R, offset: u64) -> RecoveredChunks<R> {
+        RecoveredChunks {
+            stream: Self::new(key),
+            inner,
+            encrypted_chunk: vec![0; ENCRYPTED_CHUNK_SIZE],
+            offset,
+            index: 0,
+            state: RecoveryState::Chunks {
+                prev_damaged: false,
+            },
         }
     }
 
@@ -212,6 +443,121 @@ impl Stream {
         Ok(decrypted)
     }
 
+    /// Encrypts a contiguous sequence of chunks in parallel.
+    ///
+    /// `chunks` is split into `CHUNK_SIZE` pieces, all of which except the final one
+    /// must therefore be full. If `last` is true, the final piece is encrypted as the
+    /// last chunk. The output is identical to that of calling `encrypt_chunk` on each
+    /// piece in turn.
+    #[cfg(feature = "parallel")]
+    fn encrypt_chunks(&mut self, chunks: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        if self.nonce.is_last() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "last chunk has been processed",
+            ));
+        }
+
+        // An empty plaintext is encrypted as a single empty chunk.
+        let pieces: Vec<&[u8]> = if chunks.is_empty() {
+            vec![chunks]
+        } else {
+            chunks.chunks(CHUNK_SIZE).collect()
+        };
+        let num_pieces = pieces.len();
+
+        let aead = &self.aead;
+        let nonce = self.nonce;
+        let encrypted: Vec<Vec<u8>> = pieces
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, piece)| {
+                let nonce = nonce.offset(i, last && i + 1 == num_pieces);
+                aead.encrypt(&nonce.to_bytes().into(), piece)
+                    .expect("we will never hit chacha20::MAX_BLOCKS because of the chunk size")
+            })
+            .collect();
+        self.nonce = nonce.offset(num_pieces, last);
+
+        Ok(encrypted.concat())
+    }
+
+    /// Decrypts a contiguous sequence of chunks in parallel.
+    ///
+    /// `chunks` is split into `ENCRYPTED_CHUNK_SIZE` pieces. `at_start` must be true if
+    /// the first piece is the first chunk of the plaintext.
+    ///
+    /// Returns the decrypted chunks in order, ending with the first error (if any). The
+    /// results are identical to those of decrypting each piece in turn with
+    /// `StreamReader`, including the handling of a full last chunk, and the checks that
+    /// the last chunk is non-empty and not followed by any further data.
+    #[cfg(feature = "parallel")]
+    fn decrypt_chunks(&mut self, chunks: &[u8], at_start: bool) -> Vec<io::Result<SecretVec<u8>>> {
+        if self.nonce.is_last() {
+            return vec![Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "last chunk has been processed",
+            ))];
+        }
+
+        let aead = &self.aead;
+        let nonce = self.nonce;
+        let decrypted: Vec<Option<(SecretVec<u8>, bool)>> = chunks
+            .par_chunks(ENCRYPTED_CHUNK_SIZE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let decrypt = |last| {
+                    aead.decrypt(&nonce.offset(i, last).to_bytes().into(), chunk)
+                        .ok()
+                        .map(|pt| (SecretVec::new(pt), last))
+                };
+
+                // A full chunk might still be the last chunk, if the age file is an
+                // integer multiple of the chunk size.
+                let last = chunk.len() < ENCRYPTED_CHUNK_SIZE;
+                decrypt(last).or_else(|| if last { None } else { decrypt(true) })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(decrypted.len());
+        let mut processed = 0;
+        let mut seen_last = false;
+        for (i, res) in decrypted.into_iter().enumerate() {
+            if seen_last {
+                results.push(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "last chunk has been processed",
+                )));
+                break;
+            }
+
+            match res {
+                Some((chunk, _)) if chunk.expose_secret().is_empty() && (i > 0 || !at_start) => {
+                    results.push(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        crate::fl!("err-stream-last-chunk-empty"),
+                    )));
+                    break;
+                }
+                Some((chunk, last)) => {
+                    processed += 1;
+                    seen_last = last;
+                    results.push(Ok(chunk));
+                }
+                None => {
+                    results.push(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "decryption error",
+                    )));
+                    break;
+                }
+            }
+        }
+        self.nonce = nonce.offset(processed, seen_last);
+
+        results
+    }
+
     fn is_complete(&self) -> bool {
         self.nonce.is_last()
     }
@@ -223,21 +569,87 @@ pub struct StreamWriter<W> {
     stream: Stream,
     #[pin]
     inner: W,
+    /// Buffered plaintext. When encrypting in parallel, this can contain several chunks.
     chunk: Vec<u8>,
-    #[cfg(feature = "async")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    #[cfg(any(feature = "async", feature = "tokio"))]
     encrypted_chunk: Option<EncryptedChunk>,
+    /// The number of chunks to encrypt at a time.
+    #[cfg(feature = "parallel")]
+    parallelism: usize,
+    progress: Option<ProgressTracker>,
+}
+
+impl<W> StreamWriter<W> {
+    /// Reports the progress of the encryption to `observer`.
+    ///
+    /// `observer` is called after each chunk (or batch of chunks, when encrypting in
+    /// parallel) has been encrypted. `total_bytes` is the length of the plaintext that
+    /// will be written, if it is known (for example, from the size of an input file).
+    pub fn with_progress<O: ProgressObserver + 'static>(
+        mut self,
+        total_bytes: Option<u64>,
+        observer: O,
+    ) -> Self {
+        self.progress = Some(ProgressTracker::new(total_bytes, observer));
+        self
+    }
+
+    /// Returns the number of plaintext bytes that are buffered before being encrypted.
+    fn buffer_size(&self) -> usize {
+        #[cfg(feature = "parallel")]
+        let chunks = self.parallelism;
+        #[cfg(not(feature = "parallel"))]
+        let chunks = 1;
+
+        chunks * CHUNK_SIZE
+    }
+
+    /// Encrypts the buffered plaintext.
+    fn encrypt_buffer(&mut self, last: bool) -> io::Result<Vec<u8>> {
+        #[cfg(feature = "parallel")]
+        if self.parallelism > 1 {
+            return self.stream.encrypt_chunks(&self.chunk, last);
+        }
+
+        self.stream.encrypt_chunk(&self.chunk, last)
+    }
 }
 
 impl<W: Write> StreamWriter<W> {
+    /// Encrypts up to `chunks` chunks (of 64 KiB each) at a time, in parallel on the
+    /// global `rayon` thread pool.
+    ///
+    /// The ciphertext is identical to that produced without parallelism. Plaintext is
+    /// buffered until `chunks` chunks are available (or [`StreamWriter::finish`] is
+    /// called), which bounds the number of chunks in flight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunks` is zero, or if any data has already been written.
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    pub fn with_parallelism(mut self, chunks: usize) -> Self {
+        assert!(chunks > 0);
+        assert!(self.chunk.is_empty());
+
+        self.parallelism = chunks;
+        self.chunk.reserve(chunks * CHUNK_SIZE);
+        self
+    }
+
     /// Writes the final chunk of the age file.
     ///
     /// You **MUST** call `finish` when you are done writing, in order to finish the
     /// encryption process. Failing to call `finish` will result in a truncated file that
     /// that will fail to decrypt.
     pub fn finish(mut self) -> io::Result<W> {
-        let encrypted = self.stream.encrypt_chunk(&self.chunk, true)?;
+        let encrypted = self.encrypt_buffer(true)?;
         self.inner.write_all(&encrypted)?;
+        ProgressTracker::update(
+            &mut self.progress,
+            self.chunk.len(),
+            chunk_count(self.chunk.len()),
+        );
         Ok(self.inner)
     }
 }
@@ -245,21 +657,27 @@ impl<W: Write> StreamWriter<W> {
 impl<W: Write> Write for StreamWriter<W> {
     fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
         let mut bytes_written = 0;
+        let buffer_size = self.buffer_size();
 
         while !buf.is_empty() {
-            let to_write = cmp::min(CHUNK_SIZE - self.chunk.len(), buf.len());
+            let to_write = cmp::min(buffer_size - self.chunk.len(), buf.len());
             self.chunk.extend_from_slice(&buf[..to_write]);
             bytes_written += to_write;
             buf = &buf[to_write..];
 
-            // At this point, either buf is empty, or we have a full chunk.
-            assert!(buf.is_empty() || self.chunk.len() == CHUNK_SIZE);
+            // At this point, either buf is empty, or we have a full buffer.
+            assert!(buf.is_empty() || self.chunk.len() == buffer_size);
 
-            // Only encrypt the chunk if we have more data to write, as the last
+            // Only encrypt the buffer if we have more data to write, as the last
             // chunk must be written in finish().
             if !buf.is_empty() {
-                let encrypted = self.stream.encrypt_chunk(&self.chunk, false)?;
+                let encrypted = self.encrypt_buffer(false)?;
                 self.inner.write_all(&encrypted)?;
+                ProgressTracker::update(
+                    &mut self.progress,
+                    self.chunk.len(),
+                    chunk_count(self.chunk.len()),
+                );
                 self.chunk.clear();
             }
         }
@@ -272,6 +690,53 @@ impl<W: Write> Write for StreamWriter<W> {
     }
 }
 
+#[cfg(any(feature = "async", feature = "tokio"))]
+impl<W> StreamWriter<W> {
+    /// Buffers as much of `buf` as fits in the current chunk, and encrypts the chunk if
+    /// there is more data to write. Returns the number of bytes buffered.
+    ///
+    /// Must only be called once any previously-encrypted chunk has been flushed.
+    fn buffer_chunk(self: Pin<&mut Self>, mut buf: &[u8]) -> io::Result<usize> {
+        let this = self.project();
+
+        let to_write = cmp::min(CHUNK_SIZE - this.chunk.len(), buf.len());
+
+        this.chunk.extend_from_slice(&buf[..to_write]);
+        buf = &buf[to_write..];
+
+        // At this point, either buf is empty, or we have a full chunk.
+        assert!(buf.is_empty() || this.chunk.len() == CHUNK_SIZE);
+
+        // Only encrypt the chunk if we have more data to write, as the last
+        // chunk must be written when the writer is closed.
+        if !buf.is_empty() {
+            *this.encrypted_chunk = Some(EncryptedChunk {
+                bytes: this.stream.encrypt_chunk(this.chunk, false)?,
+                offset: 0,
+            });
+            ProgressTracker::update(this.progress, this.chunk.len(), 1);
+            this.chunk.clear();
+        }
+
+        Ok(to_write)
+    }
+
+    /// Encrypts the last chunk, if the stream has not already been finished.
+    fn finish_chunks(self: Pin<&mut Self>) -> io::Result<()> {
+        let this = self.project();
+
+        if !this.stream.is_complete() {
+            *this.encrypted_chunk = Some(EncryptedChunk {
+                bytes: this.stream.encrypt_chunk(this.chunk, true)?,
+                offset: 0,
+            });
+            ProgressTracker::update(this.progress, this.chunk.len(), 1);
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "async")]
 #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
 impl<W: AsyncWrite> StreamWriter<W> {
@@ -303,56 +768,82 @@ impl<W: AsyncWrite> AsyncWrite for StreamWriter<W> {
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context,
-        mut buf: &[u8],
+        buf: &[u8],
     ) -> Poll<io::Result<usize>> {
         ready!(self.as_mut().poll_flush_chunk(cx))?;
+        Poll::Ready(self.buffer_chunk(buf))
+    }
 
-        let to_write = cmp::min(CHUNK_SIZE - self.chunk.len(), buf.len());
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_chunk(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
 
-        self.as_mut()
-            .project()
-            .chunk
-            .extend_from_slice(&buf[..to_write]);
-        buf = &buf[to_write..];
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Flush any remaining encrypted chunk bytes.
+        ready!(self.as_mut().poll_flush_chunk(cx))?;
 
-        // At this point, either buf is empty, or we have a full chunk.
-        assert!(buf.is_empty() || self.chunk.len() == CHUNK_SIZE);
+        // Finish the stream.
+        self.as_mut().finish_chunks()?;
 
-        // Only encrypt the chunk if we have more data to write, as the last
-        // chunk must be written in poll_close().
-        if !buf.is_empty() {
-            let this = self.as_mut().project();
-            *this.encrypted_chunk = Some(EncryptedChunk {
-                bytes: this.stream.encrypt_chunk(this.chunk, false)?,
-                offset: 0,
-            });
-            this.chunk.clear();
+        // Flush the final chunk (if we didn't in the first call).
+        ready!(self.as_mut().poll_flush_chunk(cx))?;
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> StreamWriter<W> {
+    fn poll_flush_chunk_tokio(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let StreamWriterProj {
+            mut inner,
+            encrypted_chunk,
+            ..
+        } = self.project();
+
+        if let Some(chunk) = encrypted_chunk {
+            loop {
+                chunk.offset +=
+                    ready!(inner.as_mut().poll_write(cx, &chunk.bytes[chunk.offset..]))?;
+                if chunk.offset == chunk.bytes.len() {
+                    break;
+                }
+            }
         }
+        *encrypted_chunk = None;
+
+        Poll::Ready(Ok(()))
+    }
+}
 
-        Poll::Ready(Ok(to_write))
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<W: TokioAsyncWrite> TokioAsyncWrite for StreamWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.as_mut().poll_flush_chunk_tokio(cx))?;
+        Poll::Ready(self.buffer_chunk(buf))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        ready!(self.as_mut().poll_flush_chunk(cx))?;
+        ready!(self.as_mut().poll_flush_chunk_tokio(cx))?;
         self.project().inner.poll_flush(cx)
     }
 
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         // Flush any remaining encrypted chunk bytes.
-        ready!(self.as_mut().poll_flush_chunk(cx))?;
+        ready!(self.as_mut().poll_flush_chunk_tokio(cx))?;
 
-        if !self.stream.is_complete() {
-            // Finish the stream.
-            let this = self.as_mut().project();
-            *this.encrypted_chunk = Some(EncryptedChunk {
-                bytes: this.stream.encrypt_chunk(this.chunk, true)?,
-                offset: 0,
-            });
-        }
+        // Finish the stream.
+        self.as_mut().finish_chunks()?;
 
         // Flush the final chunk (if we didn't in the first call).
-        ready!(self.as_mut().poll_flush_chunk(cx))?;
-        self.project().inner.poll_close(cx)
+        ready!(self.as_mut().poll_flush_chunk_tokio(cx))?;
+        self.project().inner.poll_shutdown(cx)
     }
 }
 
@@ -371,6 +862,30 @@ enum StartPos {
     Explicit(u64),
 }
 
+/// The state of an in-progress [`tokio::io::AsyncSeek`] operation on a [`StreamReader`].
+///
+/// A seek may need to seek and read the underlying reader several times. Each step
+/// records its progress here, so that it can be resumed if the underlying reader returns
+/// `Poll::Pending`.
+#[cfg(feature = "tokio")]
+#[derive(Default)]
+struct TokioSeek {
+    /// The position passed to `start_seek`, if a seek is in progress.
+    pos: Option<SeekFrom>,
+    /// The target position within the plaintext, once it has been computed.
+    target_pos: Option<u64>,
+    /// Whether the underlying reader has been positioned at the start of the target chunk.
+    at_target_chunk: bool,
+    /// Whether a seek of the underlying reader is in progress.
+    inner_seeking: bool,
+    /// The position of the underlying reader before the plaintext length was computed.
+    len_cur_pos: Option<u64>,
+    /// The end of the ciphertext in the underlying reader.
+    len_ct_end: Option<u64>,
+    /// The last chunk of the ciphertext, and how many of its bytes have been read.
+    len_last_chunk: Option<(Vec<u8>, usize)>,
+}
+
 /// Provides access to a decrypted age file.
 #[pin_project]
 pub struct StreamReader<R> {
@@ -383,9 +898,48 @@ pub struct StreamReader<R> {
     plaintext_len: Option<u64>,
     cur_plaintext_pos: u64,
     chunk: Option<SecretVec<u8>>,
+    /// The number of chunks to decrypt at a time.
+    #[cfg(feature = "parallel")]
+    parallelism: usize,
+    #[cfg(feature = "parallel")]
+    encrypted_batch: Vec<u8>,
+    /// Chunks that have been decrypted in parallel but not yet read, ending with the
+    /// first decryption error (if any).
+    #[cfg(feature = "parallel")]
+    pending: VecDeque<io::Result<SecretVec<u8>>>,
+    #[cfg(feature = "tokio")]
+    tokio_seek: TokioSeek,
+    progress: Option<ProgressTracker>,
 }
 
 impl<R> StreamReader<R> {
+    /// Reports the progress of the decryption to `observer`.
+    ///
+    /// `observer` is called after each chunk has been decrypted. `total_bytes` is the
+    /// length of the plaintext, if it is known. If it is not, the total is reported once
+    /// the reader has computed the plaintext length itself (which happens the first time
+    /// that a seekable reader is seeked).
+    ///
+    /// Chunks that are decrypted again after seeking are counted again.
+    pub fn with_progress<O: ProgressObserver + 'static>(
+        mut self,
+        total_bytes: Option<u64>,
+        observer: O,
+    ) -> Self {
+        self.progress = Some(ProgressTracker::new(total_bytes, observer));
+        self
+    }
+
+    /// Records that a chunk with `bytes` bytes of plaintext has been decrypted.
+    fn update_progress(&mut self, bytes: usize) {
+        if let Some(tracker) = &mut self.progress {
+            if tracker.progress.total_bytes.is_none() {
+                tracker.progress.total_bytes = self.plaintext_len;
+            }
+        }
+        ProgressTracker::update(&mut self.progress, bytes, 1);
+    }
+
     fn count_bytes(&mut self, read: usize) {
         // We only need to count if we haven't yet worked out the start position.
         if let StartPos::Implicit(offset) = &mut self.start {
@@ -425,6 +979,8 @@ impl<R> StreamReader<R> {
                 (Err(_), false) => Some(self.stream.decrypt_chunk(chunk, true)?),
                 (Err(e), true) => return Err(e),
             };
+            let decrypted = self.chunk.as_ref().map_or(0, |c| c.expose_secret().len());
+            self.update_progress(decrypted);
         }
 
         // We've finished with this encrypted chunk.
@@ -455,20 +1011,148 @@ impl<R> StreamReader<R> {
     }
 }
 
-impl<R: Read> Read for StreamReader<R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if self.chunk.is_none() {
-            while self.encrypted_pos < ENCRYPTED_CHUNK_SIZE {
-                match self
-                    .inner
-                    .read(&mut self.encrypted_chunk[self.encrypted_pos..])
-                {
-                    Ok(0) => break,
-                    Ok(n) => self.encrypted_pos += n,
-                    Err(e) => match e.kind() {
-                        io::ErrorKind::Interrupted => (),
-                        _ => return Err(e),
-                    },
+#[cfg(feature = "parallel")]
+#[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+impl<R: Read> StreamReader<R> {
+    /// Decrypts up to `chunks` chunks (of 64 KiB each) at a time, in parallel on the
+    /// global `rayon` thread pool.
+    ///
+    /// The plaintext, and any errors, are identical to those produced without
+    /// parallelism. Ciphertext is read ahead by up to `chunks` chunks, which bounds the
+    /// number of chunks in flight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunks` is zero.
+    pub fn with_parallelism(mut self, chunks: usize) -> Self {
+        assert!(chunks > 0);
+
+        self.parallelism = chunks;
+        self.encrypted_batch = if chunks > 1 {
+            vec![0; chunks * ENCRYPTED_CHUNK_SIZE]
+        } else {
+            vec![]
+        };
+        self
+    }
+
+    /// Makes the next decrypted chunk current, decrypting the next batch of chunks if
+    /// necessary.
+    fn next_chunk_parallel(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            let mut filled = 0;
+            while filled < self.encrypted_batch.len() {
+                match self.inner.read(&mut self.encrypted_batch[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::Interrupted => (),
+                        _ => return Err(e),
+                    },
+                }
+            }
+            self.count_bytes(filled);
+
+            if filled == 0 {
+                return if self.stream.is_complete() {
+                    Ok(())
+                } else {
+                    // Stream has ended before seeing the last chunk.
+                    Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "age file is truncated",
+                    ))
+                };
+            }
+
+            let at_start = self.cur_plaintext_pos == 0;
+            let results = self
+                .stream
+                .decrypt_chunks(&self.encrypted_batch[..filled], at_start);
+            self.pending.extend(results);
+        }
+
+        match self.pending.pop_front() {
+            Some(Ok(chunk)) => {
+                self.update_progress(chunk.expose_secret().len());
+                self.chunk = Some(chunk);
+                Ok(())
+            }
+            Some(Err(e)) => Err(e),
+            None => unreachable!("decrypt_chunks returns at least one result"),
+        }
+    }
+}
+
+/// The reason that [`StreamReader::verify`] failed.
+pub(crate) enum VerifyFailure {
+    /// The chunk with the given index could not be authenticated (or, if the stream is
+    /// truncated, is missing). Contains the index, the offset of the chunk within the
+    /// encrypted stream, and the error.
+    Chunk(u64, u64, io::Error),
+    /// The stream contains data after its last chunk, at the given offset within the
+    /// encrypted stream.
+    TrailingData(u64),
+}
+
+impl<R: Read> StreamReader<R> {
+    /// Authenticates the remainder of the stream, discarding the plaintext.
+    ///
+    /// Returns the plaintext length and the number of chunks.
+    pub(crate) fn verify(mut self) -> Result<(u64, u64), VerifyFailure> {
+        let mut buf = vec![0; CHUNK_SIZE];
+        let res = loop {
+            // Each read returns at most the remainder of the current chunk.
+            match self.read(&mut buf) {
+                Ok(0) => break Ok(()),
+                Ok(_) => (),
+                Err(e) => break Err(e),
+            }
+        };
+        buf.zeroize();
+
+        let chunks = self.cur_plaintext_pos / CHUNK_SIZE as u64;
+        // An empty stream still contains a single (empty) chunk.
+        let chunk_count = cmp::max(
+            1,
+            (self.cur_plaintext_pos + CHUNK_SIZE as u64 - 1) / CHUNK_SIZE as u64,
+        );
+        match res {
+            Ok(()) => Ok((self.cur_plaintext_pos, chunk_count)),
+            // Once the last chunk has been processed, any further data is trailing.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && self.stream.is_complete() => {
+                let offset = self.cur_plaintext_pos + chunk_count * TAG_SIZE as u64;
+                Err(VerifyFailure::TrailingData(offset))
+            }
+            Err(e) => Err(VerifyFailure::Chunk(
+                chunks,
+                chunks * ENCRYPTED_CHUNK_SIZE as u64,
+                e,
+            )),
+        }
+    }
+}
+
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(feature = "parallel")]
+        if self.chunk.is_none() && (self.parallelism > 1 || !self.pending.is_empty()) {
+            self.next_chunk_parallel()?;
+            return Ok(self.read_from_chunk(buf));
+        }
+
+        if self.chunk.is_none() {
+            while self.encrypted_pos < ENCRYPTED_CHUNK_SIZE {
+                match self
+                    .inner
+                    .read(&mut self.encrypted_chunk[self.encrypted_pos..])
+                {
+                    Ok(0) => break,
+                    Ok(n) => self.encrypted_pos += n,
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::Interrupted => (),
+                        _ => return Err(e),
+                    },
                 }
             }
             self.decrypt_chunk()?;
@@ -508,6 +1192,48 @@ impl<R: AsyncRead + Unpin> AsyncRead for StreamReader<R> {
     }
 }
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> StreamReader<R> {
+    /// Reads and decrypts the next chunk, if we have finished with the current chunk.
+    fn poll_fill_chunk_tokio(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if self.chunk.is_none() {
+            while self.encrypted_pos < ENCRYPTED_CHUNK_SIZE {
+                let mut buf = ReadBuf::new(&mut self.encrypted_chunk[self.encrypted_pos..]);
+                match ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf)) {
+                    Ok(()) if buf.filled().is_empty() => break,
+                    Ok(()) => self.encrypted_pos += buf.filled().len(),
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::Interrupted => (),
+                        _ => return Poll::Ready(Err(e)),
+                    },
+                }
+            }
+            self.decrypt_chunk()?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> TokioAsyncRead for StreamReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_fill_chunk_tokio(cx))?;
+
+        let read = this.read_from_chunk(buf.initialize_unfilled());
+        buf.advance(read);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
 impl<R: Read + Seek> StreamReader<R> {
     fn start(&mut self) -> io::Result<u64> {
         match self.start {
@@ -614,6 +1340,8 @@ impl<R: Read + Seek> Seek for StreamReader<R> {
         } else {
             // Clear the current chunk
             self.chunk = None;
+            #[cfg(feature = "parallel")]
+            self.pending.clear();
 
             // Seek to the beginning of the target chunk
             self.inner.seek(SeekFrom::Start(
@@ -649,129 +1377,1051 @@ impl<R: Read + Seek> Seek for StreamReader<R> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use age_core::secrecy::ExposeSecret;
-    use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + TokioAsyncSeek + Unpin> StreamReader<R> {
+    /// Seeks the underlying reader, resuming the seek started by an earlier call if it
+    /// has not yet completed.
+    fn poll_inner_seek_tokio(&mut self, cx: &mut Context, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        if !self.tokio_seek.inner_seeking {
+            Pin::new(&mut self.inner).start_seek(pos)?;
+            self.tokio_seek.inner_seeking = true;
+        }
+        let res = ready!(Pin::new(&mut self.inner).poll_complete(cx));
+        self.tokio_seek.inner_seeking = false;
 
-    use super::{PayloadKey, Stream, CHUNK_SIZE};
+        Poll::Ready(res)
+    }
 
-    #[cfg(feature = "async")]
-    use futures::{
-        io::{AsyncRead, AsyncWrite},
-        pin_mut,
-        task::Poll,
-    };
-    #[cfg(feature = "async")]
-    use futures_test::task::noop_context;
+    fn poll_start_tokio(&mut self, cx: &mut Context) -> Poll<io::Result<u64>> {
+        match self.start {
+            StartPos::Implicit(offset) => {
+                let current = ready!(self.poll_inner_seek_tokio(cx, SeekFrom::Current(0)))?;
+                let start = current - offset;
 
-    #[test]
-    fn chunk_round_trip() {
-        let data = vec![42; CHUNK_SIZE];
+                // Cache the start for future calls.
+                self.start = StartPos::Explicit(start);
 
-        let encrypted = {
-            let mut s = Stream::new(PayloadKey([7; 32].into()));
-            s.encrypt_chunk(&data, false).unwrap()
-        };
+                Poll::Ready(Ok(start))
+            }
+            StartPos::Explicit(start) => Poll::Ready(Ok(start)),
+        }
+    }
 
-        let decrypted = {
-            let mut s = Stream::new(PayloadKey([7; 32].into()));
-            s.decrypt_chunk(&encrypted, false).unwrap()
+    /// Returns the length of the plaintext.
+    ///
+    /// This follows the same steps as `StreamReader::len`.
+    fn poll_len_tokio(&mut self, cx: &mut Context) -> Poll<io::Result<u64>> {
+        if let Some(pt_len) = self.plaintext_len {
+            return Poll::Ready(Ok(pt_len));
+        }
+
+        // Cache the current position, and then grab the start and end ciphertext
+        // positions.
+        let cur_pos = match self.tokio_seek.len_cur_pos {
+            Some(pos) => pos,
+            None => {
+                let pos = ready!(self.poll_inner_seek_tokio(cx, SeekFrom::Current(0)))?;
+                self.tokio_seek.len_cur_pos = Some(pos);
+                pos
+            }
+        };
+        let ct_start = ready!(self.poll_start_tokio(cx))?;
+        let ct_end = match self.tokio_seek.len_ct_end {
+            Some(pos) => pos,
+            None => {
+                let pos = ready!(self.poll_inner_seek_tokio(cx, SeekFrom::End(0)))?;
+                self.tokio_seek.len_ct_end = Some(pos);
+                pos
+            }
         };
+        let ct_len = ct_end - ct_start;
 
-        assert_eq!(decrypted.expose_secret(), &data);
-    }
+        // Use ceiling division to determine the number of chunks.
+        let num_chunks = (ct_len + (ENCRYPTED_CHUNK_SIZE as u64 - 1)) / ENCRYPTED_CHUNK_SIZE as u64;
 
-    #[test]
-    fn last_chunk_round_trip() {
-        let data = vec![42; CHUNK_SIZE];
+        // Read the last chunk.
+        let last_chunk_start = ct_start + ((num_chunks - 1) * ENCRYPTED_CHUNK_SIZE as u64);
+        if self.tokio_seek.len_last_chunk.is_none() {
+            ready!(self.poll_inner_seek_tokio(cx, SeekFrom::Start(last_chunk_start)))?;
+            self.tokio_seek.len_last_chunk =
+                Some((vec![0; (ct_end - last_chunk_start) as usize], 0));
+        }
+        if let Some((last_chunk, filled)) = &mut self.tokio_seek.len_last_chunk {
+            while *filled < last_chunk.len() {
+                let mut buf = ReadBuf::new(&mut last_chunk[*filled..]);
+                ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf))?;
+                if buf.filled().is_empty() {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "age file was truncated while seeking",
+                    )));
+                }
+                *filled += buf.filled().len();
+            }
+        }
 
-        let encrypted = {
-            let mut s = Stream::new(PayloadKey([7; 32].into()));
-            let res = s.encrypt_chunk(&data, true).unwrap();
+        // Authenticate the ciphertext length by checking that we can successfully
+        // decrypt the last chunk _as_ a last chunk.
+        let cur_nonce = self.stream.nonce.0;
+        self.stream.nonce.set_counter(num_chunks - 1);
+        let res = self.stream.decrypt_chunk(
+            &self
+                .tokio_seek
+                .len_last_chunk
+                .as_ref()
+                .expect("read above")
+                .0,
+            true,
+        );
+        self.stream.nonce = Nonce(cur_nonce);
+        res.map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Last chunk is invalid, stream might be truncated",
+            )
+        })?;
 
-            // Further calls return an error
-            assert_eq!(
-                s.encrypt_chunk(&data, false).unwrap_err().kind(),
-                io::ErrorKind::WriteZero
-            );
-            assert_eq!(
-                s.encrypt_chunk(&data, true).unwrap_err().kind(),
-                io::ErrorKind::WriteZero
-            );
+        // Return to the original position.
+        ready!(self.poll_inner_seek_tokio(cx, SeekFrom::Start(cur_pos)))?;
 
-            res
+        // Now that we have authenticated the ciphertext length, we can use it to
+        // calculate the plaintext length.
+        let total_tag_size = num_chunks * TAG_SIZE as u64;
+        let pt_len = ct_len - total_tag_size;
+
+        // Cache the length for future calls.
+        self.plaintext_len = Some(pt_len);
+        self.tokio_seek.len_cur_pos = None;
+        self.tokio_seek.len_ct_end = None;
+        self.tokio_seek.len_last_chunk = None;
+
+        Poll::Ready(Ok(pt_len))
+    }
+
+    /// Performs the seek requested by `start_seek`.
+    ///
+    /// This follows the same steps as `Seek::seek`.
+    fn poll_seek_tokio(&mut self, cx: &mut Context) -> Poll<io::Result<u64>> {
+        let pos = match self.tokio_seek.pos {
+            Some(pos) => pos,
+            // There is no seek in progress.
+            None => return Poll::Ready(Ok(self.cur_plaintext_pos)),
         };
 
-        let decrypted = {
-            let mut s = Stream::new(PayloadKey([7; 32].into()));
-            let res = s.decrypt_chunk(&encrypted, true).unwrap();
+        // Convert the offset into the target position within the plaintext
+        let start = ready!(self.poll_start_tokio(cx))?;
+        let target_pos = match self.tokio_seek.target_pos {
+            Some(target_pos) => target_pos,
+            None => {
+                let target_pos = match pos {
+                    SeekFrom::Start(offset) => offset,
+                    SeekFrom::Current(offset) => {
+                        let res = (self.cur_plaintext_pos as i64) + offset;
+                        if res >= 0 {
+                            res as u64
+                        } else {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "cannot seek before the start",
+                            )));
+                        }
+                    }
+                    SeekFrom::End(offset) => {
+                        let res = (ready!(self.poll_len_tokio(cx))? as i64) + offset;
+                        if res >= 0 {
+                            res as u64
+                        } else {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "cannot seek before the start",
+                            )));
+                        }
+                    }
+                };
+                self.tokio_seek.target_pos = Some(target_pos);
+                target_pos
+            }
+        };
 
-            // Further calls return an error
-            match s.decrypt_chunk(&encrypted, false) {
-                Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
-                _ => panic!("Expected error"),
+        let target_chunk_index = target_pos / CHUNK_SIZE as u64;
+        let target_chunk_offset = target_pos % CHUNK_SIZE as u64;
+
+        if !self.tokio_seek.at_target_chunk {
+            let cur_chunk_index = self.cur_plaintext_pos / CHUNK_SIZE as u64;
+
+            if target_chunk_index == cur_chunk_index {
+                // We just need to reposition ourselves within the current chunk.
+                self.cur_plaintext_pos = target_pos;
+                return Poll::Ready(Ok(target_pos));
             }
-            match s.decrypt_chunk(&encrypted, true) {
-                Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
-                _ => panic!("Expected error"),
+
+            // Seek to the beginning of the target chunk
+            ready!(self.poll_inner_seek_tokio(
+                cx,
+                SeekFrom::Start(start + (target_chunk_index * ENCRYPTED_CHUNK_SIZE as u64)),
+            ))?;
+
+            // Clear the current chunk
+            self.chunk = None;
+            #[cfg(feature = "parallel")]
+            self.pending.clear();
+
+            self.stream.nonce.set_counter(target_chunk_index);
+            self.cur_plaintext_pos = target_chunk_index * CHUNK_SIZE as u64;
+            self.tokio_seek.at_target_chunk = true;
+        }
+
+        if target_chunk_offset > 0 {
+            // Decrypt the target chunk, and skip to the target position within it.
+            ready!(self.poll_fill_chunk_tokio(cx))?;
+            let chunk_len = self
+                .chunk
+                .as_ref()
+                .map(|chunk| chunk.expose_secret().len())
+                .unwrap_or(0);
+            if (chunk_len as u64) < target_chunk_offset {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "failed to fill whole buffer",
+                )));
             }
+            self.cur_plaintext_pos = target_pos;
+        }
+        // As in `Seek::seek`, we need to handle the edge case where the last chunk is
+        // not short, and `target_pos == self.len()`.
+        else if target_pos == ready!(self.poll_len_tokio(cx))? {
+            self.stream
+                .nonce
+                .set_last(true)
+                .expect("We unset the last chunk flag earlier");
+        }
 
-            res
-        };
+        // All done!
+        Poll::Ready(Ok(target_pos))
+    }
+}
 
-        assert_eq!(decrypted.expose_secret(), &data);
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + TokioAsyncSeek + Unpin> TokioAsyncSeek for StreamReader<R> {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        if this.tokio_seek.pos.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "other seek in progress, call poll_complete first",
+            ));
+        }
+        this.tokio_seek.pos = Some(position);
+
+        Ok(())
     }
 
-    fn stream_round_trip(data: &[u8]) {
-        let mut encrypted = vec![];
-        {
-            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
-            w.write_all(data).unwrap();
-            w.finish().unwrap();
-        };
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        let res = ready!(this.poll_seek_tokio(cx));
 
-        let decrypted = {
-            let mut buf = vec![];
-            let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..]);
-            r.read_to_end(&mut buf).unwrap();
-            buf
-        };
+        // The seek has finished (successfully or not), so clear its state.
+        this.tokio_seek = TokioSeek::default();
 
-        assert_eq!(decrypted, data);
+        Poll::Ready(res)
     }
+}
 
-    /// Check that we can encrypt an empty slice.
+/// Provides an encrypted age file as it is read, pulling plaintext from an inner reader.
+///
+/// Created with [`Encryptor::wrap_input`].
+///
+/// [`Encryptor::wrap_input`]: crate::Encryptor::wrap_input
+pub struct EncryptingReader<R> {
+    stream: Stream,
+    inner: R,
+    /// Buffered plaintext. This holds one byte more than a chunk, so that we can tell
+    /// whether the chunk is the last one.
+    chunk: Vec<u8>,
+    chunk_pos: usize,
+    /// Ciphertext that has not yet been read. This initially contains the age header.
+    encrypted_chunk: Vec<u8>,
+    encrypted_pos: usize,
+}
+
+impl<R> EncryptingReader<R> {
+    /// Encrypts the next chunk from the buffered plaintext.
     ///
-    /// This is the sole exception to the "last chunk must be non-empty" rule.
-    #[test]
-    fn stream_round_trip_empty() {
-        stream_round_trip(&[]);
-    }
+    /// Must only be called once the buffer is full, or the inner reader has reached EOF.
+    fn encrypt_chunk(&mut self) -> io::Result<()> {
+        let last = self.chunk_pos <= CHUNK_SIZE;
+        let chunk_len = cmp::min(self.chunk_pos, CHUNK_SIZE);
 
-    #[test]
-    fn stream_round_trip_short() {
-        stream_round_trip(&[42; 1024]);
+        self.encrypted_chunk = self.stream.encrypt_chunk(&self.chunk[..chunk_len], last)?;
+        self.encrypted_pos = 0;
+
+        // Keep the byte that we read ahead.
+        if !last {
+            self.chunk[0] = self.chunk[CHUNK_SIZE];
+        }
+        self.chunk_pos -= chunk_len;
+
+        Ok(())
     }
 
-    #[test]
-    fn stream_round_trip_chunk() {
-        stream_round_trip(&[42; CHUNK_SIZE]);
+    fn read_from_chunk(&mut self, buf: &mut [u8]) -> usize {
+        let to_read = cmp::min(self.encrypted_chunk.len() - self.encrypted_pos, buf.len());
+
+        buf[..to_read].copy_from_slice(
+            &self.encrypted_chunk[self.encrypted_pos..self.encrypted_pos + to_read],
+        );
+        self.encrypted_pos += to_read;
+
+        to_read
     }
 
-    #[test]
-    fn stream_round_trip_long() {
-        stream_round_trip(&[42; 100 * 1024]);
+    /// Returns `true` if we need to encrypt another chunk before we can be read from.
+    fn needs_chunk(&self) -> bool {
+        self.encrypted_pos == self.encrypted_chunk.len() && !self.stream.is_complete()
     }
+}
 
-    #[cfg(feature = "async")]
-    fn stream_async_round_trip(data: &[u8]) {
-        let mut encrypted = vec![];
-        {
-            let w = Stream::encrypt_async(PayloadKey([7; 32].into()), &mut encrypted);
-            pin_mut!(w);
+impl<R: Read> Read for EncryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.needs_chunk() {
+            while self.chunk_pos < self.chunk.len() {
+                match self.inner.read(&mut self.chunk[self.chunk_pos..]) {
+                    Ok(0) => break,
+                    Ok(n) => self.chunk_pos += n,
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::Interrupted => (),
+                        _ => return Err(e),
+                    },
+                }
+            }
+            self.encrypt_chunk()?;
+        }
 
-            let mut cx = noop_context();
+        Ok(self.read_from_chunk(buf))
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        let this = self.get_mut();
+
+        if this.needs_chunk() {
+            while this.chunk_pos < this.chunk.len() {
+                match ready!(
+                    Pin::new(&mut this.inner).poll_read(cx, &mut this.chunk[this.chunk_pos..])
+                ) {
+                    Ok(0) => break,
+                    Ok(n) => this.chunk_pos += n,
+                    Err(e) => match e.kind() {
+                        io::ErrorKind::Interrupted => (),
+                        _ => return Poll::Ready(Err(e)),
+                    },
+                }
+            }
+            this.encrypt_chunk()?;
+        }
+
+        Poll::Ready(Ok(this.read_from_chunk(buf)))
+    }
+}
+
+/// Obtains the payload key from the start of an age file.
+///
+/// Returns the length of the header and nonce along with the payload key, or `None` if
+/// more of the age file is needed.
+pub(crate) type PayloadKeyFn = Box<dyn FnMut(&[u8]) -> io::Result<Option<(usize, PayloadKey)>>>;
+
+enum DecryptingState {
+    /// We are waiting for the rest of the age header.
+    Header(PayloadKeyFn),
+    /// We are decrypting the payload.
+    Payload(Stream),
+    /// Decryption has failed, and every later write returns this error.
+    Failed(io::Error),
+}
+
+/// Returns a copy of an error that decryption failed with.
+fn clone_error(e: &io::Error) -> io::Error {
+    match e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<crate::DecryptError>())
+    {
+        Some(inner) => io::Error::new(e.kind(), inner.clone()),
+        None => io::Error::new(e.kind(), e.to_string()),
+    }
+}
+
+#[cfg(feature = "async")]
+struct DecryptedChunk {
+    bytes: SecretVec<u8>,
+    offset: usize,
+}
+
+/// Decrypts an age file as it is written, writing the plaintext to an inner writer.
+///
+/// Created with [`DecryptingWriter::with_identities`] or
+/// [`DecryptingWriter::with_user_passphrase`].
+pub struct DecryptingWriter<W> {
+    state: DecryptingState,
+    inner: W,
+    /// Buffered ciphertext: the part of the header written so far, or the part of the
+    /// current chunk that wasn't decrypted straight from a write. We only decrypt a full
+    /// chunk once more data follows it, so that we can tell whether it is the last one.
+    encrypted_chunk: Vec<u8>,
+    cur_plaintext_pos: u64,
+    #[cfg(feature = "async")]
+    decrypted_chunk: Option<DecryptedChunk>,
+}
+
+impl<W> DecryptingWriter<W> {
+    pub(crate) fn new(payload_key: PayloadKeyFn, inner: W) -> Self {
+        DecryptingWriter {
+            state: DecryptingState::Header(payload_key),
+            inner,
+            encrypted_chunk: Vec::with_capacity(ENCRYPTED_CHUNK_SIZE),
+            cur_plaintext_pos: 0,
+            #[cfg(feature = "async")]
+            decrypted_chunk: None,
+        }
+    }
+
+    /// Records that decryption has failed, so that later writes return the same error
+    /// instead of trying again (which could prompt the user again).
+    fn latch<T>(&mut self, res: io::Result<T>) -> io::Result<T> {
+        res.map_err(|e| {
+            self.state = DecryptingState::Failed(clone_error(&e));
+            e
+        })
+    }
+
+    /// Buffers `buf` while we are waiting for the rest of the header, and obtains the
+    /// payload key once it is complete.
+    ///
+    /// Returns the number of bytes of `buf` that were part of the header; any remaining
+    /// bytes are the start of the payload.
+    fn buffer_header(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let obtain_payload_key = match &mut self.state {
+            DecryptingState::Header(obtain_payload_key) => obtain_payload_key,
+            DecryptingState::Payload(_) => return Ok(0),
+            DecryptingState::Failed(e) => return Err(clone_error(e)),
+        };
+
+        let prev_len = self.encrypted_chunk.len();
+        self.encrypted_chunk.extend_from_slice(buf);
+
+        let res = obtain_payload_key(&self.encrypted_chunk);
+        match self.latch(res)? {
+            Some((header_len, payload_key)) => {
+                self.encrypted_chunk.clear();
+                self.state = DecryptingState::Payload(Stream::new(payload_key));
+                Ok(header_len.saturating_sub(prev_len))
+            }
+            None => Ok(buf.len()),
+        }
+    }
+
+    /// Consumes ciphertext from the start of `input`, and decrypts the next chunk if we
+    /// have all of it and know that it is not the last chunk.
+    ///
+    /// Full chunks are decrypted straight from `input` where possible, and only the
+    /// remainder of `input` is buffered. Returns the number of bytes consumed, which is
+    /// only zero if a buffered chunk was decrypted.
+    fn decrypt_next_chunk(&mut self, input: &[u8]) -> io::Result<(usize, Option<SecretVec<u8>>)> {
+        let stream = match &mut self.state {
+            DecryptingState::Payload(stream) => stream,
+            DecryptingState::Header(_) => return Ok((0, None)),
+            DecryptingState::Failed(e) => return Err(clone_error(e)),
+        };
+
+        let (consumed, res) =
+            if self.encrypted_chunk.is_empty() && input.len() > ENCRYPTED_CHUNK_SIZE {
+                (
+                    ENCRYPTED_CHUNK_SIZE,
+                    Some(stream.decrypt_chunk(&input[..ENCRYPTED_CHUNK_SIZE], false)),
+                )
+            } else {
+                let to_buffer = cmp::min(
+                    ENCRYPTED_CHUNK_SIZE - self.encrypted_chunk.len(),
+                    input.len(),
+                );
+                self.encrypted_chunk.extend_from_slice(&input[..to_buffer]);
+
+                if self.encrypted_chunk.len() == ENCRYPTED_CHUNK_SIZE && input.len() > to_buffer {
+                    let res = stream.decrypt_chunk(&self.encrypted_chunk, false);
+                    self.encrypted_chunk.clear();
+                    (to_buffer, Some(res))
+                } else {
+                    (to_buffer, None)
+                }
+            };
+
+        match res {
+            Some(res) => {
+                let chunk = self.latch(res)?;
+                self.cur_plaintext_pos += CHUNK_SIZE as u64;
+                Ok((consumed, Some(chunk)))
+            }
+            None => Ok((consumed, None)),
+        }
+    }
+
+    /// Decrypts the buffered ciphertext as the last chunk.
+    fn decrypt_last_chunk(&mut self) -> io::Result<SecretVec<u8>> {
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "age file is truncated");
+
+        let res = match &mut self.state {
+            DecryptingState::Failed(e) => return Err(clone_error(e)),
+            DecryptingState::Payload(stream) if !self.encrypted_chunk.is_empty() => {
+                // A full chunk might not be the last one, in which case the stream has
+                // ended before seeing the last chunk.
+                if self.encrypted_chunk.len() == ENCRYPTED_CHUNK_SIZE
+                    && stream.decrypt_chunk(&self.encrypted_chunk, false).is_ok()
+                {
+                    Err(truncated())
+                } else {
+                    stream.decrypt_chunk(&self.encrypted_chunk, true)
+                }
+            }
+            // Stream has ended before seeing the last chunk.
+            _ => Err(truncated()),
+        };
+
+        let chunk = self.latch(res)?;
+        if chunk.expose_secret().is_empty() && self.cur_plaintext_pos > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                crate::fl!("err-stream-last-chunk-empty"),
+            ));
+        }
+        self.encrypted_chunk.clear();
+        self.cur_plaintext_pos += chunk.expose_secret().len() as u64;
+        Ok(chunk)
+    }
+
+    fn is_complete(&self) -> bool {
+        match &self.state {
+            DecryptingState::Header(_) | DecryptingState::Failed(_) => false,
+            DecryptingState::Payload(stream) => stream.is_complete(),
+        }
+    }
+}
+
+impl<W: Write> DecryptingWriter<W> {
+    /// Decrypts the final chunk of the age file, and returns the inner writer.
+    ///
+    /// You **MUST** call `finish` when you are done writing, in order to finish the
+    /// decryption process. The final chunk is only written by `finish`, which also
+    /// detects whether the age file was truncated.
+    pub fn finish(mut self) -> io::Result<W> {
+        let chunk = self.decrypt_last_chunk()?;
+        self.inner.write_all(chunk.expose_secret())?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for DecryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // As with the async adapter, the write that completes the header only consumes
+        // the rest of the header.
+        let header_len = self.buffer_header(buf)?;
+        if header_len > 0 || buf.is_empty() {
+            return Ok(header_len);
+        }
+
+        // Only decrypt chunks that we know are followed by more data, as the last chunk
+        // must be decrypted in finish().
+        let mut input = buf;
+        while !input.is_empty() {
+            let (consumed, chunk) = self.decrypt_next_chunk(input)?;
+            input = &input[consumed..];
+            if let Some(chunk) = chunk {
+                self.inner.write_all(chunk.expose_secret())?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl<W: AsyncWrite + Unpin> DecryptingWriter<W> {
+    /// Writes out the decrypted chunk, if any, so that more ciphertext can be decrypted.
+    fn poll_flush_chunk(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if let Some(chunk) = &mut self.decrypted_chunk {
+            let bytes = chunk.bytes.expose_secret();
+            while chunk.offset < bytes.len() {
+                match ready!(Pin::new(&mut self.inner).poll_write(cx, &bytes[chunk.offset..]))? {
+                    0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    n => chunk.offset += n,
+                }
+            }
+        }
+        self.decrypted_chunk = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+impl<W: AsyncWrite + Unpin> AsyncWrite for DecryptingWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_flush_chunk(cx))?;
+
+        let header_len = this.buffer_header(buf)?;
+        if header_len > 0 || buf.is_empty() {
+            return Poll::Ready(Ok(header_len));
+        }
+
+        // We can only hold one decrypted chunk at a time, so a full buffered chunk must
+        // be written out before we can consume any more of buf.
+        loop {
+            let (consumed, chunk) = this.decrypt_next_chunk(buf)?;
+            this.decrypted_chunk = chunk.map(|bytes| DecryptedChunk { bytes, offset: 0 });
+            if consumed > 0 {
+                break Poll::Ready(Ok(consumed));
+            }
+            ready!(this.poll_flush_chunk(cx))?;
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_flush_chunk(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_flush_chunk(cx))?;
+
+        // Decrypt and write the final chunk (if we didn't in a previous call).
+        if !this.is_complete() {
+            this.decrypted_chunk = Some(DecryptedChunk {
+                bytes: this.decrypt_last_chunk()?,
+                offset: 0,
+            });
+        }
+        ready!(this.poll_flush_chunk(cx))?;
+
+        Pin::new(&mut this.inner).poll_close(cx)
+    }
+}
+
+/// The reason that part of an age file's payload could not be recovered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageKind {
+    /// The chunk failed to authenticate, either because it has been modified or because
+    /// it does not belong to this age file.
+    TagMismatch,
+    /// The age file ended partway through the chunk, and the partial chunk failed to
+    /// authenticate.
+    ///
+    /// A final chunk that is damaged (rather than truncated) is also reported this way,
+    /// as the two cases cannot be distinguished.
+    Truncated,
+    /// The age file ended at a chunk boundary, without a last chunk.
+    MissingLastChunk,
+    /// The age file contains data after its last chunk.
+    TrailingData,
+}
+
+/// A range of an age file's payload that could not be recovered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Damage {
+    chunk_index: u64,
+    offset: u64,
+    len: u64,
+    kind: DamageKind,
+}
+
+impl Damage {
+    /// Returns the index of the chunk at which the damaged range starts.
+    pub fn chunk_index(&self) -> u64 {
+        self.chunk_index
+    }
+
+    /// Returns the offset in bytes of the damaged range within the age file.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the length in bytes of the damaged range within the age file.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the damaged range is empty, which is the case for
+    /// [`DamageKind::MissingLastChunk`].
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the offset within the plaintext at which the unrecoverable data starts.
+    pub fn plaintext_offset(&self) -> u64 {
+        self.chunk_index * CHUNK_SIZE as u64
+    }
+
+    /// Returns the length of the plaintext that the damaged range would have contained.
+    ///
+    /// This is 64 KiB for a damaged chunk before the last one, and zero for
+    /// [`DamageKind::MissingLastChunk`] and [`DamageKind::TrailingData`], which don't
+    /// cover any plaintext.
+    pub fn plaintext_len(&self) -> u64 {
+        match self.kind {
+            DamageKind::TagMismatch | DamageKind::Truncated => {
+                self.len.saturating_sub(TAG_SIZE as u64)
+            }
+            DamageKind::MissingLastChunk | DamageKind::TrailingData => 0,
+        }
+    }
+
+    /// Returns the reason that this range could not be recovered.
+    pub fn kind(&self) -> DamageKind {
+        self.kind
+    }
+}
+
+/// An item produced by [`RecoveredChunks`].
+#[derive(Debug)]
+pub enum RecoveredChunk {
+    /// A chunk that was successfully authenticated.
+    Authenticated {
+        /// The index of the chunk within the payload.
+        index: u64,
+        /// The decrypted chunk, which starts at offset `index * 64 KiB` within the
+        /// plaintext.
+        plaintext: SecretVec<u8>,
+    },
+    /// A range that could not be recovered.
+    Damaged(Damage),
+}
+
+enum RecoveryState {
+    /// Recovering chunks. `prev_damaged` is `true` if the previous chunk could not be
+    /// authenticated (in which case it might have been the last chunk).
+    Chunks {
+        prev_damaged: bool,
+    },
+    /// The last chunk has been recovered, and any remaining data is trailing.
+    Trailing,
+    Done,
+}
+
+/// Recovers the authentic chunks of a damaged age file.
+///
+/// Unlike [`StreamReader`], which stops at the first chunk that fails to authenticate,
+/// this yields every chunk that can be authenticated, along with a [`Damage`] for each
+/// range that cannot. Each chunk's nonce depends only on its index, so recovery resumes
+/// at the next chunk boundary after a damaged chunk. The last item is returned once the
+/// underlying reader reaches EOF, or after it returns an error.
+///
+/// Chunks that were removed from (or inserted into) the middle of the payload shift all
+/// later chunks away from their expected indices, and so cannot be recovered.
+///
+/// Created with [`RecipientsDecryptor::recover`] or [`PassphraseDecryptor::recover`].
+///
+/// [`RecipientsDecryptor::recover`]: crate::decryptor::RecipientsDecryptor::recover
+/// [`PassphraseDecryptor::recover`]: crate::decryptor::PassphraseDecryptor::recover
+pub struct RecoveredChunks<R> {
+    stream: Stream,
+    inner: R,
+    encrypted_chunk: Vec<u8>,
+    /// The position of the start of the stream within the age file.
+    offset: u64,
+    /// The index of the next chunk.
+    index: u64,
+    state: RecoveryState,
+}
+
+impl<R> RecoveredChunks<R> {
+    /// Returns the offset of the next chunk within the age file.
+    fn chunk_offset(&self) -> u64 {
+        self.offset + self.index * ENCRYPTED_CHUNK_SIZE as u64
+    }
+
+    /// Attempts to decrypt the next chunk from the first `len` bytes of the buffer.
+    ///
+    /// Returns the plaintext, and whether it is the last chunk.
+    fn decrypt_chunk(&mut self, len: usize) -> Option<(SecretVec<u8>, bool)> {
+        // Only a full chunk can be followed by another chunk.
+        let candidates: &[bool] = if len == ENCRYPTED_CHUNK_SIZE {
+            &[false, true]
+        } else {
+            &[true]
+        };
+
+        for &last in candidates {
+            self.stream.nonce.set_counter(self.index);
+            if let Ok(plaintext) = self
+                .stream
+                .decrypt_chunk(&self.encrypted_chunk[..len], last)
+            {
+                return Some((plaintext, last));
+            }
+        }
+        None
+    }
+}
+
+impl<R: Read> RecoveredChunks<R> {
+    /// Reads up to a full chunk into the buffer, returning the number of bytes read.
+    fn read_chunk(&mut self) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < ENCRYPTED_CHUNK_SIZE {
+            match self.inner.read(&mut self.encrypted_chunk[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) => match e.kind() {
+                    io::ErrorKind::Interrupted => (),
+                    _ => return Err(e),
+                },
+            }
+        }
+        Ok(filled)
+    }
+
+    fn next_chunk(&mut self, prev_damaged: bool) -> io::Result<Option<RecoveredChunk>> {
+        let len = self.read_chunk()?;
+        let offset = self.chunk_offset();
+
+        if len == 0 {
+            self.state = RecoveryState::Done;
+            // If the previous chunk was damaged, we can't tell whether it was the last.
+            return Ok(if prev_damaged {
+                None
+            } else {
+                Some(RecoveredChunk::Damaged(Damage {
+                    chunk_index: self.index,
+                    offset,
+                    len: 0,
+                    kind: DamageKind::MissingLastChunk,
+                }))
+            });
+        }
+
+        let index = self.index;
+        let res = match self.decrypt_chunk(len) {
+            Some((plaintext, last)) => {
+                self.state = if last {
+                    RecoveryState::Trailing
+                } else {
+                    RecoveryState::Chunks {
+                        prev_damaged: false,
+                    }
+                };
+                RecoveredChunk::Authenticated { index, plaintext }
+            }
+            None if len == ENCRYPTED_CHUNK_SIZE => {
+                self.state = RecoveryState::Chunks { prev_damaged: true };
+                RecoveredChunk::Damaged(Damage {
+                    chunk_index: index,
+                    offset,
+                    len: len as u64,
+                    kind: DamageKind::TagMismatch,
+                })
+            }
+            None => {
+                // A partial chunk is only read at EOF.
+                self.state = RecoveryState::Done;
+                RecoveredChunk::Damaged(Damage {
+                    chunk_index: index,
+                    offset,
+                    len: len as u64,
+                    kind: DamageKind::Truncated,
+                })
+            }
+        };
+        self.index += 1;
+
+        Ok(Some(res))
+    }
+
+    fn trailing_data(&mut self) -> io::Result<Option<RecoveredChunk>> {
+        self.state = RecoveryState::Done;
+        let offset = self.chunk_offset();
+
+        let len = io::copy(&mut self.inner, &mut io::sink())?;
+        Ok(if len > 0 {
+            Some(RecoveredChunk::Damaged(Damage {
+                chunk_index: self.index,
+                offset,
+                len,
+                kind: DamageKind::TrailingData,
+            }))
+        } else {
+            None
+        })
+    }
+}
+
+impl<R: Read> Iterator for RecoveredChunks<R> {
+    type Item = io::Result<RecoveredChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = match self.state {
+            RecoveryState::Chunks { prev_damaged } => self.next_chunk(prev_damaged),
+            RecoveryState::Trailing => self.trailing_data(),
+            RecoveryState::Done => return None,
+        };
+
+        match res {
+            Ok(chunk) => chunk.map(Ok),
+            Err(e) => {
+                self.state = RecoveryState::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use age_core::secrecy::ExposeSecret;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        Damage, DamageKind, DecryptingWriter, PayloadKey, PayloadKeyFn, Progress, RecoveredChunk,
+        Stream, CHUNK_SIZE, ENCRYPTED_CHUNK_SIZE, TAG_SIZE,
+    };
+
+    #[cfg(feature = "async")]
+    use futures::{
+        io::{AsyncRead, AsyncWrite},
+        pin_mut,
+        task::Poll,
+    };
+    #[cfg(any(feature = "async", feature = "tokio"))]
+    use futures_test::task::noop_context;
+
+    #[cfg(feature = "tokio")]
+    use super::StreamReader;
+    #[cfg(feature = "tokio")]
+    use std::{pin::Pin, task::Poll as TokioPoll};
+    #[cfg(feature = "tokio")]
+    use tokio::io::ReadBuf;
+
+    #[test]
+    fn chunk_round_trip() {
+        let data = vec![42; CHUNK_SIZE];
+
+        let encrypted = {
+            let mut s = Stream::new(PayloadKey([7; 32].into()));
+            s.encrypt_chunk(&data, false).unwrap()
+        };
+
+        let decrypted = {
+            let mut s = Stream::new(PayloadKey([7; 32].into()));
+            s.decrypt_chunk(&encrypted, false).unwrap()
+        };
+
+        assert_eq!(decrypted.expose_secret(), &data);
+    }
+
+    #[test]
+    fn last_chunk_round_trip() {
+        let data = vec![42; CHUNK_SIZE];
+
+        let encrypted = {
+            let mut s = Stream::new(PayloadKey([7; 32].into()));
+            let res = s.encrypt_chunk(&data, true).unwrap();
+
+            // Further calls return an error
+            assert_eq!(
+                s.encrypt_chunk(&data, false).unwrap_err().kind(),
+                io::ErrorKind::WriteZero
+            );
+            assert_eq!(
+                s.encrypt_chunk(&data, true).unwrap_err().kind(),
+                io::ErrorKind::WriteZero
+            );
+
+            res
+        };
+
+        let decrypted = {
+            let mut s = Stream::new(PayloadKey([7; 32].into()));
+            let res = s.decrypt_chunk(&encrypted, true).unwrap();
+
+            // Further calls return an error
+            match s.decrypt_chunk(&encrypted, false) {
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+                _ => panic!("Expected error"),
+            }
+            match s.decrypt_chunk(&encrypted, true) {
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+                _ => panic!("Expected error"),
+            }
+
+            res
+        };
+
+        assert_eq!(decrypted.expose_secret(), &data);
+    }
+
+    fn stream_round_trip(data: &[u8]) {
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let decrypted = {
+            let mut buf = vec![];
+            let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..]);
+            r.read_to_end(&mut buf).unwrap();
+            buf
+        };
+
+        assert_eq!(decrypted, data);
+    }
+
+    /// Check that we can encrypt an empty slice.
+    ///
+    /// This is the sole exception to the "last chunk must be non-empty" rule.
+    #[test]
+    fn stream_round_trip_empty() {
+        stream_round_trip(&[]);
+    }
+
+    #[test]
+    fn stream_round_trip_short() {
+        stream_round_trip(&[42; 1024]);
+    }
+
+    #[test]
+    fn stream_round_trip_chunk() {
+        stream_round_trip(&[42; CHUNK_SIZE]);
+    }
+
+    #[test]
+    fn stream_round_trip_long() {
+        stream_round_trip(&[42; 100 * 1024]);
+    }
+
+    #[cfg(feature = "async")]
+    fn stream_async_round_trip(data: &[u8]) {
+        let mut encrypted = vec![];
+        {
+            let w = Stream::encrypt_async(PayloadKey([7; 32].into()), &mut encrypted);
+            pin_mut!(w);
+
+            let mut cx = noop_context();
 
             let mut tmp = data;
             loop {
@@ -800,34 +2450,268 @@ mod tests {
 
             let mut tmp = [0; 4096];
             loop {
-                match r.as_mut().poll_read(&mut cx, &mut tmp) {
-                    Poll::Ready(Ok(0)) => break buf,
-                    Poll::Ready(Ok(read)) => buf.extend_from_slice(&tmp[..read]),
+                match r.as_mut().poll_read(&mut cx, &mut tmp) {
+                    Poll::Ready(Ok(0)) => break buf,
+                    Poll::Ready(Ok(read)) => buf.extend_from_slice(&tmp[..read]),
+                    Poll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    Poll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        };
+
+        assert_eq!(decrypted, data);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_async_round_trip_short() {
+        stream_async_round_trip(&[42; 1024]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_async_round_trip_chunk() {
+        stream_async_round_trip(&[42; CHUNK_SIZE]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_async_round_trip_long() {
+        stream_async_round_trip(&[42; 100 * 1024]);
+    }
+
+    #[cfg(feature = "tokio")]
+    fn stream_tokio_round_trip(data: &[u8]) {
+        let mut encrypted = vec![];
+        {
+            let mut w = Box::pin(Stream::encrypt_tokio(
+                PayloadKey([7; 32].into()),
+                &mut encrypted,
+            ));
+
+            let mut cx = noop_context();
+
+            let mut tmp = data;
+            loop {
+                match tokio::io::AsyncWrite::poll_write(w.as_mut(), &mut cx, tmp) {
+                    TokioPoll::Ready(Ok(0)) => break,
+                    TokioPoll::Ready(Ok(written)) => tmp = &tmp[written..],
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+            loop {
+                match tokio::io::AsyncWrite::poll_shutdown(w.as_mut(), &mut cx) {
+                    TokioPoll::Ready(Ok(())) => break,
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        };
+
+        let decrypted = {
+            let mut buf = vec![];
+            let mut r = Box::pin(Stream::decrypt_tokio(
+                PayloadKey([7; 32].into()),
+                &encrypted[..],
+            ));
+
+            let mut cx = noop_context();
+
+            let mut tmp = [0; 4096];
+            loop {
+                let mut read_buf = ReadBuf::new(&mut tmp);
+                match tokio::io::AsyncRead::poll_read(r.as_mut(), &mut cx, &mut read_buf) {
+                    TokioPoll::Ready(Ok(())) if read_buf.filled().is_empty() => break buf,
+                    TokioPoll::Ready(Ok(())) => buf.extend_from_slice(read_buf.filled()),
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        };
+
+        assert_eq!(decrypted, data);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn stream_tokio_round_trip_short() {
+        stream_tokio_round_trip(&[42; 1024]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn stream_tokio_round_trip_chunk() {
+        stream_tokio_round_trip(&[42; CHUNK_SIZE]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn stream_tokio_round_trip_long() {
+        stream_tokio_round_trip(&[42; 100 * 1024]);
+    }
+
+    fn payload_key_fn() -> PayloadKeyFn {
+        Box::new(|_| Ok(Some((0, PayloadKey([7; 32].into())))))
+    }
+
+    fn stream_adapters_round_trip(data: &[u8]) {
+        let mut expected = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut expected);
+            w.write_all(data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let mut encrypted = vec![];
+        {
+            let mut r = Stream::encrypt_reader(PayloadKey([7; 32].into()), vec![], data);
+            r.read_to_end(&mut encrypted).unwrap();
+        }
+        assert_eq!(encrypted, expected);
+
+        let decrypted = {
+            let mut w = DecryptingWriter::new(payload_key_fn(), vec![]);
+            for chunk in encrypted.chunks(1000) {
+                w.write_all(chunk).unwrap();
+            }
+            w.finish().unwrap()
+        };
+        assert_eq!(decrypted, data);
+    }
+
+    #[test]
+    fn stream_adapters_round_trip_empty() {
+        stream_adapters_round_trip(&[]);
+    }
+
+    #[test]
+    fn stream_adapters_round_trip_short() {
+        stream_adapters_round_trip(&[42; 1024]);
+    }
+
+    #[test]
+    fn stream_adapters_round_trip_chunk() {
+        stream_adapters_round_trip(&[42; CHUNK_SIZE]);
+    }
+
+    #[test]
+    fn stream_adapters_round_trip_long() {
+        stream_adapters_round_trip(&[42; 100 * 1024]);
+    }
+
+    #[cfg(feature = "async")]
+    fn stream_async_adapters_round_trip(data: &[u8]) {
+        let mut encrypted = vec![];
+        {
+            let r = Stream::encrypt_reader(PayloadKey([7; 32].into()), vec![], data);
+            pin_mut!(r);
+
+            let mut cx = noop_context();
+
+            let mut tmp = [0; 4096];
+            loop {
+                match r.as_mut().poll_read(&mut cx, &mut tmp) {
+                    Poll::Ready(Ok(0)) => break,
+                    Poll::Ready(Ok(read)) => encrypted.extend_from_slice(&tmp[..read]),
+                    Poll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    Poll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        let mut decrypted = vec![];
+        {
+            let w = DecryptingWriter::new(payload_key_fn(), &mut decrypted);
+            pin_mut!(w);
+
+            let mut cx = noop_context();
+
+            let mut tmp = &encrypted[..];
+            loop {
+                match w.as_mut().poll_write(&mut cx, tmp) {
+                    Poll::Ready(Ok(0)) => break,
+                    Poll::Ready(Ok(written)) => tmp = &tmp[written..],
+                    Poll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    Poll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+            loop {
+                match w.as_mut().poll_close(&mut cx) {
+                    Poll::Ready(Ok(())) => break,
                     Poll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
                     Poll::Pending => panic!("Unexpected Pending"),
                 }
             }
-        };
+        }
 
         assert_eq!(decrypted, data);
     }
 
     #[cfg(feature = "async")]
     #[test]
-    fn stream_async_round_trip_short() {
-        stream_async_round_trip(&[42; 1024]);
+    fn stream_async_adapters_round_trip_short() {
+        stream_async_adapters_round_trip(&[42; 1024]);
     }
 
     #[cfg(feature = "async")]
     #[test]
-    fn stream_async_round_trip_chunk() {
-        stream_async_round_trip(&[42; CHUNK_SIZE]);
+    fn stream_async_adapters_round_trip_chunk() {
+        stream_async_adapters_round_trip(&[42; CHUNK_SIZE]);
     }
 
     #[cfg(feature = "async")]
     #[test]
-    fn stream_async_round_trip_long() {
-        stream_async_round_trip(&[42; 100 * 1024]);
+    fn stream_async_adapters_round_trip_long() {
+        stream_async_adapters_round_trip(&[42; 100 * 1024]);
+    }
+
+    #[test]
+    fn decrypting_writer_consumes_only_header() {
+        let data = vec![42; 3 * CHUNK_SIZE + 5];
+        let header = [b'h'; 100];
+
+        let mut encrypted = header.to_vec();
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let payload_key: PayloadKeyFn = Box::new(move |buf| {
+            Ok(if buf.len() >= header.len() {
+                Some((header.len(), PayloadKey([7; 32].into())))
+            } else {
+                None
+            })
+        });
+        let mut w = DecryptingWriter::new(payload_key, vec![]);
+
+        // The write that completes the header only consumes the rest of the header.
+        assert_eq!(w.write(&encrypted[..60]).unwrap(), 60);
+        let mut rest = &encrypted[60..];
+        assert_eq!(w.write(rest).unwrap(), 40);
+        rest = &rest[40..];
+
+        // After that, every write is consumed entirely.
+        assert_eq!(w.write(rest).unwrap(), rest.len());
+        assert_eq!(w.finish().unwrap(), data);
+    }
+
+    #[test]
+    fn decrypting_writer_fails_on_truncated_file() {
+        let data = vec![42; 2 * CHUNK_SIZE];
+
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            // Forget to call w.finish()!
+        };
+
+        let mut w = DecryptingWriter::new(payload_key_fn(), vec![]);
+        w.write_all(&encrypted).unwrap();
+        assert_eq!(w.finish().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
     }
 
     #[test]
@@ -849,6 +2733,104 @@ mod tests {
         );
     }
 
+    /// Recovers `encrypted`, returning the recovered plaintext and the damaged ranges.
+    fn stream_recover(encrypted: &[u8]) -> (Vec<u8>, Vec<Damage>) {
+        let mut plaintext = vec![];
+        let mut damage = vec![];
+        for chunk in Stream::recover(PayloadKey([7; 32].into()), encrypted, 100) {
+            match chunk.unwrap() {
+                RecoveredChunk::Authenticated {
+                    index,
+                    plaintext: p,
+                } => {
+                    assert_eq!(index as usize * CHUNK_SIZE, plaintext.len());
+                    plaintext.extend_from_slice(p.expose_secret());
+                }
+                RecoveredChunk::Damaged(d) => damage.push(d),
+            }
+        }
+        (plaintext, damage)
+    }
+
+    fn stream_encrypt(data: &[u8]) -> Vec<u8> {
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(data).unwrap();
+            w.finish().unwrap();
+        };
+        encrypted
+    }
+
+    #[test]
+    fn stream_recovery_round_trip() {
+        for len in &[0, 1, CHUNK_SIZE, 2 * CHUNK_SIZE + 7] {
+            let data = vec![42; *len];
+            let (plaintext, damage) = stream_recover(&stream_encrypt(&data));
+            assert_eq!(plaintext, data);
+            assert!(damage.is_empty());
+        }
+    }
+
+    #[test]
+    fn stream_recovery_skips_damaged_chunk() {
+        let data = vec![42; 2 * CHUNK_SIZE + 7];
+        let mut encrypted = stream_encrypt(&data);
+        encrypted[ENCRYPTED_CHUNK_SIZE + 5] ^= 0xff;
+
+        let (plaintext, damage) = stream_recover(&encrypted);
+        assert_eq!(&plaintext[..CHUNK_SIZE], &data[..CHUNK_SIZE]);
+        assert_eq!(&plaintext[CHUNK_SIZE..], &data[2 * CHUNK_SIZE..]);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].chunk_index(), 1);
+        assert_eq!(damage[0].offset(), 100 + ENCRYPTED_CHUNK_SIZE as u64);
+        assert_eq!(damage[0].len(), ENCRYPTED_CHUNK_SIZE as u64);
+        assert_eq!(damage[0].plaintext_offset(), CHUNK_SIZE as u64);
+        assert_eq!(damage[0].plaintext_len(), CHUNK_SIZE as u64);
+        assert_eq!(damage[0].kind(), DamageKind::TagMismatch);
+    }
+
+    #[test]
+    fn stream_recovery_reports_truncation() {
+        let data = vec![42; 2 * CHUNK_SIZE + 7];
+        let encrypted = stream_encrypt(&data);
+
+        // Truncated partway through the last chunk.
+        let (plaintext, damage) = stream_recover(&encrypted[..encrypted.len() - 3]);
+        assert_eq!(plaintext, &data[..2 * CHUNK_SIZE]);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].chunk_index(), 2);
+        assert_eq!(damage[0].len(), 7 + 16 - 3);
+        assert_eq!(damage[0].plaintext_len(), 7 - 3);
+        assert_eq!(damage[0].kind(), DamageKind::Truncated);
+
+        // Truncated at a chunk boundary.
+        let (plaintext, damage) = stream_recover(&encrypted[..2 * ENCRYPTED_CHUNK_SIZE]);
+        assert_eq!(plaintext, &data[..2 * CHUNK_SIZE]);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].chunk_index(), 2);
+        assert_eq!(damage[0].offset(), 100 + 2 * ENCRYPTED_CHUNK_SIZE as u64);
+        assert!(damage[0].is_empty());
+        assert_eq!(damage[0].plaintext_len(), 0);
+        assert_eq!(damage[0].kind(), DamageKind::MissingLastChunk);
+    }
+
+    #[test]
+    fn stream_recovery_reports_trailing_data() {
+        let data = vec![42; CHUNK_SIZE];
+        let mut encrypted = stream_encrypt(&data);
+        encrypted.extend_from_slice(&[0; 10]);
+
+        let (plaintext, damage) = stream_recover(&encrypted);
+        assert_eq!(plaintext, data);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].chunk_index(), 1);
+        assert_eq!(damage[0].offset(), 100 + ENCRYPTED_CHUNK_SIZE as u64);
+        assert_eq!(damage[0].len(), 10);
+        assert_eq!(damage[0].plaintext_len(), 0);
+        assert_eq!(damage[0].kind(), DamageKind::TrailingData);
+    }
+
     #[test]
     fn stream_seeking() {
         let mut data = vec![0; 100 * 1024];
@@ -888,6 +2870,77 @@ mod tests {
         assert_eq!(&buf[..], &data[data.len() - 1337..data.len() - 1237]);
     }
 
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn stream_tokio_seeking() {
+        let mut data = vec![0; 100 * 1024];
+        for (i, b) in data.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let mut r = Box::pin(Stream::decrypt_tokio(
+            PayloadKey([7; 32].into()),
+            Cursor::new(encrypted),
+        ));
+
+        fn read_exact(r: &mut Pin<Box<StreamReader<Cursor<Vec<u8>>>>>, buf: &mut [u8]) {
+            let mut cx = noop_context();
+            let mut read_buf = ReadBuf::new(buf);
+            while read_buf.remaining() > 0 {
+                let filled = read_buf.filled().len();
+                match tokio::io::AsyncRead::poll_read(r.as_mut(), &mut cx, &mut read_buf) {
+                    TokioPoll::Ready(Ok(())) if read_buf.filled().len() == filled => {
+                        panic!("Unexpected EOF")
+                    }
+                    TokioPoll::Ready(Ok(())) => (),
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        fn seek(r: &mut Pin<Box<StreamReader<Cursor<Vec<u8>>>>>, pos: SeekFrom) {
+            let mut cx = noop_context();
+            tokio::io::AsyncSeek::start_seek(r.as_mut(), pos).unwrap();
+            loop {
+                match tokio::io::AsyncSeek::poll_complete(r.as_mut(), &mut cx) {
+                    TokioPoll::Ready(Ok(_)) => break,
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        // Read through into the second chunk
+        let mut buf = vec![0; 100];
+        for i in 0..700 {
+            read_exact(&mut r, &mut buf);
+            assert_eq!(&buf[..], &data[100 * i..100 * (i + 1)]);
+        }
+
+        // Seek back into the first chunk
+        seek(&mut r, SeekFrom::Start(250));
+        read_exact(&mut r, &mut buf);
+        assert_eq!(&buf[..], &data[250..350]);
+
+        // Seek forwards within this chunk
+        seek(&mut r, SeekFrom::Current(510));
+        read_exact(&mut r, &mut buf);
+        assert_eq!(&buf[..], &data[860..960]);
+
+        // Seek backwards from the end
+        seek(&mut r, SeekFrom::End(-1337));
+        read_exact(&mut r, &mut buf);
+        assert_eq!(&buf[..], &data[data.len() - 1337..data.len() - 1237]);
+    }
+
     #[test]
     fn seek_from_end_fails_on_truncation() {
         // The plaintext is the string "hello" followed by 65536 zeros, just enough to
@@ -955,4 +3008,270 @@ mod tests {
         reader.read_to_end(&mut buf).unwrap();
         assert_eq!(buf.len(), 0);
     }
+
+    #[cfg(feature = "parallel")]
+    fn stream_parallel_round_trip(data: &[u8], chunks: usize) {
+        let mut serial = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut serial);
+            w.write_all(data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted)
+                .with_parallelism(chunks);
+            // Write in uneven pieces to exercise the buffering.
+            for piece in data.chunks(1000) {
+                w.write_all(piece).unwrap();
+            }
+            w.finish().unwrap();
+        };
+
+        // Parallel encryption must produce exactly the same ciphertext.
+        assert_eq!(encrypted, serial);
+
+        let decrypted = {
+            let mut buf = vec![];
+            let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..])
+                .with_parallelism(chunks);
+            r.read_to_end(&mut buf).unwrap();
+            buf
+        };
+
+        assert_eq!(decrypted, data);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_round_trip_empty() {
+        stream_parallel_round_trip(&[], 4);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_round_trip_short() {
+        stream_parallel_round_trip(&[42; 1024], 4);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_round_trip_chunk() {
+        stream_parallel_round_trip(&[42; CHUNK_SIZE], 4);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_round_trip_exact_batch() {
+        stream_parallel_round_trip(&[42; 4 * CHUNK_SIZE], 4);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_round_trip_long() {
+        stream_parallel_round_trip(&[42; 10 * CHUNK_SIZE + 1337], 3);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_fails_to_decrypt_truncated_file() {
+        let data = vec![42; 5 * CHUNK_SIZE];
+
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            // Forget to call w.finish()!
+        };
+
+        let mut buf = vec![];
+        let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..]).with_parallelism(2);
+        assert_eq!(
+            r.read_to_end(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_fails_on_trailing_data() {
+        let data = vec![42; 1024];
+
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        };
+        encrypted.extend_from_slice(&[0; 32]);
+
+        let mut buf = vec![];
+        let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..]).with_parallelism(4);
+        assert!(r.read_to_end(&mut buf).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn stream_parallel_seeking() {
+        let mut data = vec![0; 10 * CHUNK_SIZE];
+        for (i, b) in data.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        let mut encrypted = vec![];
+        {
+            let mut w =
+                Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted).with_parallelism(4);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        };
+
+        let mut r =
+            Stream::decrypt(PayloadKey([7; 32].into()), Cursor::new(encrypted)).with_parallelism(4);
+
+        // Read into the second chunk, decrypting the first batch.
+        let mut buf = vec![0; 100];
+        r.seek(SeekFrom::Start(CHUNK_SIZE as u64 + 50)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[CHUNK_SIZE + 50..CHUNK_SIZE + 150]);
+
+        // Seek back into the first chunk
+        r.seek(SeekFrom::Start(250)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[250..350]);
+
+        // Seek forwards past the current batch
+        r.seek(SeekFrom::Start(7 * CHUNK_SIZE as u64 + 3)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &data[7 * CHUNK_SIZE + 3..7 * CHUNK_SIZE + 103]);
+
+        // Seek backwards from the end
+        r.seek(SeekFrom::End(-1337)).unwrap();
+        let mut rest = vec![];
+        r.read_to_end(&mut rest).unwrap();
+        assert_eq!(&rest[..], &data[data.len() - 1337..]);
+    }
+
+    #[test]
+    fn plaintext_len() {
+        for &len in &[0, 1024, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE] {
+            let mut encrypted = vec![];
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&vec![42; len]).unwrap();
+            w.finish().unwrap();
+
+            assert_eq!(
+                super::plaintext_len(encrypted.len() as u64),
+                Some(len as u64)
+            );
+        }
+
+        // Too short to contain a tag.
+        assert_eq!(super::plaintext_len(0), None);
+        assert_eq!(super::plaintext_len(TAG_SIZE as u64 - 1), None);
+        // Only the first chunk can be empty.
+        assert_eq!(
+            super::plaintext_len((ENCRYPTED_CHUNK_SIZE + TAG_SIZE) as u64),
+            None
+        );
+    }
+
+    #[test]
+    fn stream_writer_progress() {
+        let data = vec![42; 2 * CHUNK_SIZE + 1024];
+        let reported = Arc::new(Mutex::new(vec![]));
+
+        let mut encrypted = vec![];
+        {
+            let reported = reported.clone();
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted)
+                .with_progress(Some(data.len() as u64), move |p: Progress| {
+                    reported.lock().unwrap().push(p)
+                });
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        }
+
+        let reported = reported.lock().unwrap();
+        let last = reported.last().unwrap();
+        assert_eq!(last.bytes(), data.len() as u64);
+        assert_eq!(last.chunks(), 3);
+        assert_eq!(last.total_bytes(), Some(data.len() as u64));
+        assert!(reported.windows(2).all(|w| w[0].bytes() <= w[1].bytes()));
+    }
+
+    #[test]
+    fn stream_writer_progress_empty() {
+        let reported = Arc::new(Mutex::new(vec![]));
+
+        let mut encrypted = vec![];
+        {
+            let reported = reported.clone();
+            let w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted)
+                .with_progress(Some(0), move |p: Progress| reported.lock().unwrap().push(p));
+            w.finish().unwrap();
+        }
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].bytes(), 0);
+        assert_eq!(reported[0].chunks(), 1);
+    }
+
+    #[test]
+    fn stream_reader_progress() {
+        let data = vec![42; 2 * CHUNK_SIZE + 1024];
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        }
+
+        let reported = Arc::new(Mutex::new(vec![]));
+        let decrypted = {
+            let reported = reported.clone();
+            let mut buf = vec![];
+            let mut r = Stream::decrypt(PayloadKey([7; 32].into()), &encrypted[..])
+                .with_progress(None, move |p: Progress| reported.lock().unwrap().push(p));
+            r.read_to_end(&mut buf).unwrap();
+            buf
+        };
+        assert_eq!(decrypted, data);
+
+        let reported = reported.lock().unwrap();
+        let last = reported.last().unwrap();
+        assert_eq!(last.bytes(), data.len() as u64);
+        assert_eq!(last.chunks(), 3);
+        assert_eq!(last.total_bytes(), None);
+    }
+
+    #[test]
+    fn stream_reader_progress_uses_computed_length() {
+        let data = vec![42; CHUNK_SIZE + 1024];
+        let mut encrypted = vec![];
+        {
+            let mut w = Stream::encrypt(PayloadKey([7; 32].into()), &mut encrypted);
+            w.write_all(&data).unwrap();
+            w.finish().unwrap();
+        }
+
+        let reported = Arc::new(Mutex::new(vec![]));
+        {
+            let reported = reported.clone();
+            let mut r = Stream::decrypt(PayloadKey([7; 32].into()), Cursor::new(encrypted))
+                .with_progress(None, move |p: Progress| reported.lock().unwrap().push(p));
+
+            // Seeking from the end computes the plaintext length.
+            r.seek(SeekFrom::End(-(data.len() as i64))).unwrap();
+            io::copy(&mut r, &mut io::sink()).unwrap();
+        }
+
+        let reported = reported.lock().unwrap();
+        assert!(reported
+            .iter()
+            .all(|p| p.total_bytes() == Some(data.len() as u64)));
+        assert_eq!(reported.last().unwrap().bytes(), data.len() as u64);
+    }
 }
@@ -1,6 +1,9 @@
 //! Encryption and decryption routines for age.
 
-use age_core::{format::grease_the_joint, secrecy::SecretString};
+use age_core::{
+    format::{grease_the_joint, FileKey},
+    secrecy::SecretString,
+};
 use rand::{rngs::OsRng, RngCore};
 use std::io::{self, Read, Write};
 
@@ -8,14 +11,22 @@ use crate::{
     error::{DecryptError, EncryptError},
     format::{Header, HeaderV1},
     keys::{mac_key, new_file_key, v1_payload_key},
-    primitives::stream::{PayloadKey, Stream, StreamWriter},
+    primitives::stream::{EncryptingReader, PayloadKey, Stream, StreamWriter},
     scrypt, Recipient,
 };
 
 #[cfg(feature = "async")]
 use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+#[cfg(feature = "tokio")]
+use tokio::io::{
+    AsyncRead as TokioAsyncRead, AsyncReadExt as _, AsyncWrite as TokioAsyncWrite,
+    AsyncWriteExt as _,
+};
+
 pub mod decryptor;
+pub mod inspect;
+pub mod rekey;
 
 pub(crate) struct Nonce([u8; 16]);
 
@@ -45,6 +56,14 @@ impl Nonce {
         input.read_exact(&mut nonce).await?;
         Ok(Nonce(nonce))
     }
+
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    async fn read_tokio<R: TokioAsyncRead + Unpin>(input: &mut R) -> io::Result<Self> {
+        let mut nonce = [0; 16];
+        input.read_exact(&mut nonce).await?;
+        Ok(Nonce(nonce))
+    }
 }
 
 /// Handles the various types of age encryption.
@@ -77,26 +96,31 @@ impl Encryptor {
         Encryptor(EncryptorType::Passphrase(passphrase))
     }
 
-    /// Creates the header for this age file.
-    fn prepare_header(self) -> Result<(Header, Nonce, PayloadKey), EncryptError> {
-        let file_key = new_file_key();
-
+    /// Wraps the given file key to this encryptor's recipients (or passphrase), and
+    /// returns the corresponding header.
+    fn wrap_file_key(self, file_key: &FileKey) -> Result<HeaderV1, EncryptError> {
         let recipients = match self.0 {
             EncryptorType::Keys(recipients) => {
                 let mut stanzas = Vec::with_capacity(recipients.len() + 1);
                 for recipient in recipients {
-                    stanzas.append(&mut recipient.wrap_file_key(&file_key)?);
+                    stanzas.append(&mut recipient.wrap_file_key(file_key)?);
                 }
                 // Keep the joint well oiled!
                 stanzas.push(grease_the_joint());
                 stanzas
             }
             EncryptorType::Passphrase(passphrase) => {
-                scrypt::Recipient { passphrase }.wrap_file_key(&file_key)?
+                scrypt::Recipient { passphrase }.wrap_file_key(file_key)?
             }
         };
 
-        let header = HeaderV1::new(recipients, mac_key(&file_key));
+        Ok(HeaderV1::new(recipients, mac_key(file_key)))
+    }
+
+    /// Creates the header for this age file.
+    fn prepare_header(self) -> Result<(Header, Nonce, PayloadKey), EncryptError> {
+        let file_key = new_file_key();
+        let header = self.wrap_file_key(&file_key)?;
         let nonce = Nonce::random();
         let payload_key = v1_payload_key(&file_key, &header, &nonce).expect("MAC is correct");
 
@@ -117,6 +141,20 @@ impl Encryptor {
         Ok(Stream::encrypt(payload_key, output))
     }
 
+    /// Creates a wrapper around a reader that will encrypt its input.
+    ///
+    /// The wrapper provides the entire age file (the header followed by the encrypted
+    /// payload) as it is read, pulling plaintext from `input` as needed. It implements
+    /// [`Read`] if `input` does, and `AsyncRead` if `input` does (with the `async` feature
+    /// flag enabled).
+    pub fn wrap_input<R>(self, input: R) -> Result<EncryptingReader<R>, EncryptError> {
+        let (header, nonce, payload_key) = self.prepare_header()?;
+        let mut prefix = vec![];
+        header.write(&mut prefix)?;
+        prefix.extend_from_slice(nonce.as_ref());
+        Ok(Stream::encrypt_reader(payload_key, prefix, input))
+    }
+
     /// Creates a wrapper around a writer that will encrypt its input.
     ///
     /// Returns errors from the underlying writer while writing the header.
@@ -135,6 +173,28 @@ impl Encryptor {
         output.write_all(nonce.as_ref()).await?;
         Ok(Stream::encrypt_async(payload_key, output))
     }
+
+    /// Creates a wrapper around a writer that will encrypt its input.
+    ///
+    /// Returns errors from the underlying writer while writing the header.
+    ///
+    /// You **MUST** call [`AsyncWrite::poll_shutdown`] when you are done writing, in
+    /// order to finish the encryption process. Failing to call
+    /// [`AsyncWrite::poll_shutdown`] will result in a truncated file that will fail to
+    /// decrypt.
+    ///
+    /// [`AsyncWrite::poll_shutdown`]: tokio::io::AsyncWrite::poll_shutdown
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub async fn wrap_tokio_output<W: TokioAsyncWrite + Unpin>(
+        self,
+        mut output: W,
+    ) -> Result<StreamWriter<W>, EncryptError> {
+        let (header, nonce, payload_key) = self.prepare_header()?;
+        header.write_tokio(&mut output).await?;
+        output.write_all(nonce.as_ref()).await?;
+        Ok(Stream::encrypt_tokio(payload_key, output))
+    }
 }
 
 /// Decryptor for an age file.
@@ -158,6 +218,16 @@ impl<R> From<decryptor::PassphraseDecryptor<R>> for Decryptor<R> {
 }
 
 impl<R> Decryptor<R> {
+    /// Returns a read-only view of the age file's header.
+    ///
+    /// This can be used to inspect the file before deciding how to decrypt it.
+    pub fn header(&self) -> decryptor::HeaderInfo<'_> {
+        match self {
+            Decryptor::Recipients(d) => d.header(),
+            Decryptor::Passphrase(d) => d.header(),
+        }
+    }
+
     fn from_v1_header(input: R, header: HeaderV1, nonce: Nonce) -> Result<Self, DecryptError> {
         // Enforce structural requirements on the v1 header.
         let any_scrypt = header
@@ -211,17 +281,36 @@ impl<R: AsyncRead + Unpin> Decryptor<R> {
     }
 }
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> Decryptor<R> {
+    /// Attempts to create a decryptor for an age file.
+    ///
+    /// Returns an error if the input does not contain a valid age file.
+    pub async fn new_tokio(mut input: R) -> Result<Self, DecryptError> {
+        let header = Header::read_tokio(&mut input).await?;
+
+        match header {
+            Header::V1(v1_header) => {
+                let nonce = Nonce::read_tokio(&mut input).await?;
+                Decryptor::from_v1_header(input, v1_header, nonce)
+            }
+            Header::Unknown(_) => Err(DecryptError::UnknownFormat),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use age_core::secrecy::SecretString;
-    use std::io::{BufReader, Read, Write};
-
-    #[cfg(feature = "ssh")]
+    use age_core::secrecy::{ExposeSecret, SecretString};
+    use std::io::{self, BufReader, Read, Write};
     use std::iter;
 
     use super::{Decryptor, Encryptor};
     use crate::{
+        error::{DecryptError, VerifyError},
         identity::{IdentityFile, IdentityFileEntry},
+        stream::{DamageKind, DecryptingWriter, RecoveredChunk},
         x25519, Identity, Recipient,
     };
 
@@ -232,9 +321,14 @@ mod tests {
         task::Poll,
         Future,
     };
-    #[cfg(feature = "async")]
+    #[cfg(any(feature = "async", feature = "tokio"))]
     use futures_test::task::noop_context;
 
+    #[cfg(feature = "tokio")]
+    use std::{future::Future as _, task::Poll as TokioPoll};
+    #[cfg(feature = "tokio")]
+    use tokio::io::ReadBuf;
+
     fn recipient_round_trip<'a>(
         recipients: Vec<Box<dyn Recipient>>,
         identities: impl Iterator<Item = &'a dyn Identity>,
@@ -338,6 +432,81 @@ mod tests {
         assert_eq!(&decrypted[..], &test_msg[..]);
     }
 
+    #[cfg(feature = "tokio")]
+    fn recipient_tokio_round_trip<'a>(
+        recipients: Vec<Box<dyn Recipient>>,
+        identities: impl Iterator<Item = &'a dyn Identity>,
+    ) {
+        let test_msg = b"This is a test message. For testing.";
+        let mut cx = noop_context();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(recipients);
+        {
+            let mut w = {
+                let mut f = Box::pin(e.wrap_tokio_output(&mut encrypted));
+
+                loop {
+                    match f.as_mut().poll(&mut cx) {
+                        TokioPoll::Ready(Ok(w)) => break Box::pin(w),
+                        TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                        TokioPoll::Pending => panic!("Unexpected Pending"),
+                    }
+                }
+            };
+
+            let mut tmp = &test_msg[..];
+            loop {
+                match tokio::io::AsyncWrite::poll_write(w.as_mut(), &mut cx, tmp) {
+                    TokioPoll::Ready(Ok(0)) => break,
+                    TokioPoll::Ready(Ok(written)) => tmp = &tmp[written..],
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+            loop {
+                match tokio::io::AsyncWrite::poll_shutdown(w.as_mut(), &mut cx) {
+                    TokioPoll::Ready(Ok(())) => break,
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        }
+
+        let d = match {
+            let mut f = Box::pin(Decryptor::new_tokio(&encrypted[..]));
+
+            loop {
+                match f.as_mut().poll(&mut cx) {
+                    TokioPoll::Ready(Ok(w)) => break w,
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        } {
+            Decryptor::Recipients(d) => d,
+            _ => panic!(),
+        };
+
+        let decrypted = {
+            let mut buf = vec![];
+            let mut r = Box::pin(d.decrypt_tokio(identities).unwrap());
+
+            let mut tmp = [0; 4096];
+            loop {
+                let mut read_buf = ReadBuf::new(&mut tmp);
+                match tokio::io::AsyncRead::poll_read(r.as_mut(), &mut cx, &mut read_buf) {
+                    TokioPoll::Ready(Ok(())) if read_buf.filled().is_empty() => break buf,
+                    TokioPoll::Ready(Ok(())) => buf.extend_from_slice(read_buf.filled()),
+                    TokioPoll::Ready(Err(e)) => panic!("Unexpected error: {}", e),
+                    TokioPoll::Pending => panic!("Unexpected Pending"),
+                }
+            }
+        };
+
+        assert_eq!(&decrypted[..], &test_msg[..]);
+    }
+
     #[test]
     fn x25519_round_trip() {
         let buf = BufReader::new(crate::x25519::tests::TEST_SK.as_bytes());
@@ -347,12 +516,33 @@ mod tests {
             vec![Box::new(pk)],
             f.into_identities().iter().map(|sk| match sk {
                 IdentityFileEntry::Native(sk) => sk as &dyn Identity,
+                #[cfg(feature = "pq")]
+                IdentityFileEntry::PostQuantum(_) => unreachable!(),
                 #[cfg(feature = "plugin")]
                 IdentityFileEntry::Plugin(_) => unreachable!(),
             }),
         );
     }
 
+    #[cfg(feature = "pq")]
+    #[test]
+    fn pq_round_trip() {
+        let buf = BufReader::new(crate::pq::tests::TEST_SK.as_bytes());
+        let f = IdentityFile::from_buffer(buf).unwrap();
+        let identities = f.into_identities();
+        let pk = match &identities[0] {
+            IdentityFileEntry::PostQuantum(sk) => sk.to_public(),
+            _ => panic!(),
+        };
+        recipient_round_trip(
+            vec![Box::new(pk)],
+            identities.iter().map(|sk| match sk {
+                IdentityFileEntry::PostQuantum(sk) => sk as &dyn Identity,
+                _ => unreachable!(),
+            }),
+        );
+    }
+
     #[cfg(feature = "async")]
     #[test]
     fn x25519_async_round_trip() {
@@ -363,6 +553,26 @@ mod tests {
             vec![Box::new(pk)],
             f.into_identities().iter().map(|sk| match sk {
                 IdentityFileEntry::Native(sk) => sk as &dyn Identity,
+                #[cfg(feature = "pq")]
+                IdentityFileEntry::PostQuantum(_) => unreachable!(),
+                #[cfg(feature = "plugin")]
+                IdentityFileEntry::Plugin(_) => unreachable!(),
+            }),
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn x25519_tokio_round_trip() {
+        let buf = BufReader::new(crate::x25519::tests::TEST_SK.as_bytes());
+        let f = IdentityFile::from_buffer(buf).unwrap();
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+        recipient_tokio_round_trip(
+            vec![Box::new(pk)],
+            f.into_identities().iter().map(|sk| match sk {
+                IdentityFileEntry::Native(sk) => sk as &dyn Identity,
+                #[cfg(feature = "pq")]
+                IdentityFileEntry::PostQuantum(_) => unreachable!(),
                 #[cfg(feature = "plugin")]
                 IdentityFileEntry::Plugin(_) => unreachable!(),
             }),
@@ -394,6 +604,348 @@ mod tests {
         assert_eq!(&decrypted[..], &test_msg[..]);
     }
 
+    #[test]
+    fn x25519_adapters_round_trip() {
+        let test_msg = vec![42; 100 * 1024];
+
+        let sk: x25519::Identity = crate::x25519::tests::TEST_SK.parse().unwrap();
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+        {
+            let mut r = e.wrap_input(&test_msg[..]).unwrap();
+            r.read_to_end(&mut encrypted).unwrap();
+        }
+
+        // The encrypted file can be read by the regular decryptor.
+        let d = match Decryptor::new(&encrypted[..]) {
+            Ok(Decryptor::Recipients(d)) => d,
+            _ => panic!(),
+        };
+        let mut r = d.decrypt(iter::once(&sk as &dyn Identity)).unwrap();
+        let mut decrypted = vec![];
+        r.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, test_msg);
+
+        // Write the encrypted file in small pieces, to exercise header buffering.
+        let decrypted = {
+            let mut w = DecryptingWriter::with_identities(vec![Box::new(sk)], vec![]);
+            for chunk in encrypted.chunks(17) {
+                w.write_all(chunk).unwrap();
+            }
+            w.finish().unwrap()
+        };
+        assert_eq!(decrypted, test_msg);
+
+        // Write the encrypted file in one piece, so that chunks are decrypted straight
+        // from the write.
+        let decrypted = {
+            let sk: x25519::Identity = crate::x25519::tests::TEST_SK.parse().unwrap();
+            let mut w = DecryptingWriter::with_identities(vec![Box::new(sk)], vec![]);
+            assert_eq!(w.write(&encrypted).unwrap(), encrypted.len());
+            w.finish().unwrap()
+        };
+        assert_eq!(decrypted, test_msg);
+    }
+
+    #[test]
+    fn decrypting_writer_latches_errors() {
+        use age_core::format::{FileKey, Stanza};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// An identity that can't unwrap anything, and counts how often it is asked to.
+        struct CountingIdentity(Arc<AtomicUsize>);
+
+        impl Identity for CountingIdentity {
+            fn unwrap_stanza(&self, _: &Stanza) -> Option<Result<FileKey, DecryptError>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+        }
+
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+        {
+            let mut r = e.wrap_input(&[42; 100 * 1024][..]).unwrap();
+            r.read_to_end(&mut encrypted).unwrap();
+        }
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mut w = DecryptingWriter::with_identities(
+            vec![Box::new(CountingIdentity(attempts.clone()))],
+            vec![],
+        );
+        let (header, payload) = encrypted.split_at(encrypted.len() / 2);
+
+        // Every write returns the first error, without trying the identities again.
+        for data in &[header, payload, payload] {
+            let err = w.write(data).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(matches!(
+                err.get_ref().unwrap().downcast_ref::<DecryptError>(),
+                Some(DecryptError::NoMatchingKeys)
+            ));
+        }
+        assert_eq!(w.finish().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn scrypt_adapters_round_trip() {
+        let test_msg = b"This is a test message. For testing.";
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_user_passphrase(SecretString::new("passphrase".to_string()));
+        {
+            let mut r = e.wrap_input(&test_msg[..]).unwrap();
+            r.read_to_end(&mut encrypted).unwrap();
+        }
+
+        let decrypted = {
+            let mut w = DecryptingWriter::with_user_passphrase(
+                SecretString::new("passphrase".to_string()),
+                None,
+                vec![],
+            );
+            w.write_all(&encrypted).unwrap();
+            w.finish().unwrap()
+        };
+        assert_eq!(&decrypted[..], &test_msg[..]);
+
+        // Identities cannot decrypt a passphrase-encrypted file.
+        let sk: x25519::Identity = crate::x25519::tests::TEST_SK.parse().unwrap();
+        let mut w = DecryptingWriter::with_identities(vec![Box::new(sk)], vec![]);
+        assert_eq!(
+            w.write_all(&encrypted).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn x25519_verify() {
+        let test_msg = vec![42; 150 * 1024];
+
+        let sk: x25519::Identity = crate::x25519::tests::TEST_SK.parse().unwrap();
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+        {
+            let mut w = e.wrap_output(&mut encrypted).unwrap();
+            w.write_all(&test_msg).unwrap();
+            w.finish().unwrap();
+        }
+
+        let verify = |encrypted: &[u8]| match Decryptor::new(encrypted) {
+            Ok(Decryptor::Recipients(d)) => d.verify(iter::once(&sk as &dyn Identity)),
+            _ => panic!(),
+        };
+
+        let verified = verify(&encrypted).unwrap();
+        assert_eq!(verified.plaintext_len(), test_msg.len() as u64);
+        assert_eq!(verified.chunk_count(), 3);
+
+        let payload_offset = {
+            let d = Decryptor::new(&encrypted[..]).unwrap();
+            d.header().encoded_len() + 16
+        };
+        let chunk_len = 64 * 1024 + 16;
+
+        // Corrupt the second chunk.
+        let mut corrupted = encrypted.clone();
+        corrupted[payload_offset + chunk_len + 7] ^= 1;
+        match verify(&corrupted) {
+            Err(VerifyError::Chunk {
+                index: 1,
+                offset,
+                error: DecryptError::DecryptionFailed,
+            }) => assert_eq!(offset, (payload_offset + chunk_len) as u64),
+            _ => panic!(),
+        }
+
+        // Drop the last chunk.
+        match verify(&encrypted[..payload_offset + 2 * chunk_len]) {
+            Err(VerifyError::Chunk {
+                index: 2,
+                error: DecryptError::Io(e),
+                ..
+            }) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            _ => panic!(),
+        }
+
+        // Append data after a full last chunk.
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+        let mut trailing = vec![];
+        {
+            let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+            let mut w = e.wrap_output(&mut trailing).unwrap();
+            w.write_all(&test_msg[..2 * 64 * 1024]).unwrap();
+            w.finish().unwrap();
+        }
+        let encrypted_len = trailing.len();
+        trailing.extend_from_slice(&[0; 10]);
+        match verify(&trailing) {
+            Err(VerifyError::TrailingData { offset }) => {
+                assert_eq!(offset, encrypted_len as u64)
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn x25519_recover() {
+        let test_msg = vec![42; 150 * 1024];
+
+        let sk: x25519::Identity = crate::x25519::tests::TEST_SK.parse().unwrap();
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+        {
+            let mut w = e.wrap_output(&mut encrypted).unwrap();
+            w.write_all(&test_msg).unwrap();
+            w.finish().unwrap();
+        }
+
+        let payload_offset = {
+            let d = Decryptor::new(&encrypted[..]).unwrap();
+            d.header().encoded_len() + 16
+        };
+        let chunk_len = 64 * 1024 + 16;
+
+        // Corrupt the second chunk, and truncate the last chunk.
+        let mut corrupted = encrypted.clone();
+        corrupted[payload_offset + chunk_len + 7] ^= 1;
+        corrupted.truncate(corrupted.len() - 1);
+
+        let d = match Decryptor::new(&corrupted[..]) {
+            Ok(Decryptor::Recipients(d)) => d,
+            _ => panic!(),
+        };
+        let mut recovered = vec![];
+        let mut damage = vec![];
+        for chunk in d.recover(iter::once(&sk as &dyn Identity)).unwrap() {
+            match chunk.unwrap() {
+                RecoveredChunk::Authenticated { index, plaintext } => {
+                    recovered.push((index, plaintext.expose_secret().len()))
+                }
+                RecoveredChunk::Damaged(d) => damage.push(d),
+            }
+        }
+
+        assert_eq!(recovered, vec![(0, 64 * 1024)]);
+        assert_eq!(damage.len(), 2);
+        assert_eq!(damage[0].chunk_index(), 1);
+        assert_eq!(damage[0].offset(), (payload_offset + chunk_len) as u64);
+        assert_eq!(damage[0].kind(), DamageKind::TagMismatch);
+        assert_eq!(damage[1].chunk_index(), 2);
+        assert_eq!(damage[1].offset(), (payload_offset + 2 * chunk_len) as u64);
+        assert_eq!(damage[1].len(), (22 * 1024 + 16 - 1) as u64);
+        assert_eq!(damage[1].kind(), DamageKind::Truncated);
+    }
+
+    #[test]
+    fn header_info() {
+        let pk: x25519::Recipient = crate::x25519::tests::TEST_PK.parse().unwrap();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(pk)]);
+        {
+            let w = e.wrap_output(&mut encrypted).unwrap();
+            w.finish().unwrap();
+        }
+
+        let d = Decryptor::new(&encrypted[..]).unwrap();
+        let header = d.header();
+
+        // The X25519 stanza is followed by a grease stanza.
+        assert_eq!(header.stanza_count(), 2);
+        assert_eq!(header.stanzas()[0].tag, "X25519");
+        assert_eq!(header.stanzas()[0].args.len(), 1);
+        assert_eq!(header.scrypt_work_factor(), None);
+
+        // Header, nonce, and a single empty chunk.
+        assert_eq!(encrypted.len(), header.encoded_len() + 16 + 16);
+    }
+
+    #[test]
+    fn scrypt_header_info() {
+        let test_header = "age-encryption.org/v1
+-> scrypt bBjlhJVYZeE4aqUdmtRHfw 15
+ZV/AhotwSGqaPCU43cepl4WYUouAa17a3xpu4G2yi5k
+--- fgMiVLJHMlg9fW7CVG/hPS5EAU4Zeg19LyCP7SoH5nA
+";
+        let mut data = test_header.as_bytes().to_vec();
+        data.extend_from_slice(&[0; 16]);
+
+        let d = match Decryptor::new(&data[..]) {
+            Ok(d @ Decryptor::Passphrase(_)) => d,
+            _ => panic!(),
+        };
+        let header = d.header();
+
+        assert_eq!(header.stanza_count(), 1);
+        assert_eq!(header.stanzas()[0].tag, "scrypt");
+        assert_eq!(header.scrypt_work_factor(), Some(15));
+        assert_eq!(header.encoded_len(), test_header.len());
+    }
+
+    #[test]
+    fn x25519_rekey() {
+        let test_msg = b"This is a test message. For testing.";
+
+        let old_sk = x25519::Identity::generate();
+        let new_sk = x25519::Identity::generate();
+
+        let mut encrypted = vec![];
+        let e = Encryptor::with_recipients(vec![Box::new(old_sk.to_public())]);
+        {
+            let mut w = e.wrap_output(&mut encrypted).unwrap();
+            w.write_all(test_msg).unwrap();
+            w.finish().unwrap();
+        }
+
+        let (rekeyer, old_payload_start) = match Decryptor::new(&encrypted[..]) {
+            Ok(Decryptor::Recipients(d)) => {
+                let payload_start = d.header().encoded_len() + 16;
+                let rekeyer = d.rekey(iter::once(&old_sk as &dyn Identity)).unwrap();
+                (rekeyer, payload_start)
+            }
+            _ => panic!(),
+        };
+        let rekeyed = rekeyer
+            .rekey_to(
+                Encryptor::with_recipients(vec![Box::new(new_sk.to_public())]),
+                vec![],
+            )
+            .unwrap();
+
+        // The nonce and payload are copied verbatim.
+        let d = match Decryptor::new(&rekeyed[..]) {
+            Ok(Decryptor::Recipients(d)) => d,
+            _ => panic!(),
+        };
+        let new_payload_start = d.header().encoded_len() + 16;
+        assert_eq!(
+            &rekeyed[new_payload_start - 16..],
+            &encrypted[old_payload_start - 16..]
+        );
+
+        // The old identity can no longer decrypt the file, and the new one can.
+        assert!(d.decrypt(iter::once(&old_sk as &dyn Identity)).is_err());
+        let d = match Decryptor::new(&rekeyed[..]) {
+            Ok(Decryptor::Recipients(d)) => d,
+            _ => panic!(),
+        };
+        let mut r = d.decrypt(iter::once(&new_sk as &dyn Identity)).unwrap();
+        let mut decrypted = vec![];
+        r.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(&decrypted[..], &test_msg[..]);
+    }
+
     #[cfg(feature = "ssh")]
     #[test]
     fn ssh_rsa_round_trip() {
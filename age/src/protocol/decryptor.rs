@@ -4,20 +4,91 @@ use age_core::{
     format::{FileKey, Stanza},
     secrecy::SecretString,
 };
-use std::io::Read;
+use std::io::{self, Read};
 
-use super::Nonce;
+use super::{rekey::Rekeyer, Decryptor, Nonce};
 use crate::{
-    error::DecryptError,
-    format::Header,
-    keys::v1_payload_key,
-    primitives::stream::{PayloadKey, Stream, StreamReader},
+    error::{DecryptError, VerifyError},
+    format::{Header, HeaderV1},
+    keys::{mac_key, v1_payload_key},
+    primitives::stream::{
+        DecryptingWriter, PayloadKey, PayloadKeyFn, RecoveredChunks, Stream, StreamReader,
+        VerifyFailure,
+    },
     scrypt, Identity,
 };
 
 #[cfg(feature = "async")]
 use futures::io::AsyncRead;
 
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncRead as TokioAsyncRead;
+
+/// A read-only view of an age file's header.
+///
+/// This can be used to inspect an age file (for example, to learn which recipients it
+/// is encrypted to) without decrypting it.
+pub struct HeaderInfo<'a>(pub(super) &'a HeaderV1);
+
+impl<'a> HeaderInfo<'a> {
+    /// Returns the recipient stanzas in this header, in the order they appear.
+    ///
+    /// Each stanza's tag identifies the type of recipient it was created for (such as
+    /// `X25519`, `scrypt`, `ssh-ed25519`, or a plugin-specific tag), and its arguments
+    /// contain the non-secret per-recipient data (such as the X25519 ephemeral share or
+    /// the SSH key tag).
+    pub fn stanzas(&self) -> &'a [Stanza] {
+        &self.0.recipients
+    }
+
+    /// Returns the number of recipient stanzas in this header.
+    pub fn stanza_count(&self) -> usize {
+        self.0.recipients.len()
+    }
+
+    /// Returns the scrypt work factor (log2 of the scrypt N parameter) that was used to
+    /// encrypt this file, if it is encrypted with a passphrase.
+    pub fn scrypt_work_factor(&self) -> Option<u8> {
+        self.0
+            .recipients
+            .iter()
+            .find(|r| r.tag == scrypt::SCRYPT_RECIPIENT_TAG)
+            .and_then(|r| r.args.get(1))
+            .and_then(|log_n| log_n.parse().ok())
+    }
+
+    /// Returns the length of this header in bytes.
+    ///
+    /// The header is followed in the age file by a 16-byte nonce, and then the payload.
+    pub fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+/// The result of successfully verifying an age file.
+///
+/// Returned by [`RecipientsDecryptor::verify`] and [`PassphraseDecryptor::verify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifiedPayload {
+    plaintext_len: u64,
+    chunk_count: u64,
+}
+
+impl VerifiedPayload {
+    /// Returns the length in bytes of the age file's plaintext.
+    pub fn plaintext_len(&self) -> u64 {
+        self.plaintext_len
+    }
+
+    /// Returns the number of chunks in the age file's payload.
+    ///
+    /// This is always at least one, as the final chunk is present (and may be empty)
+    /// in every age file.
+    pub fn chunk_count(&self) -> u64 {
+        self.chunk_count
+    }
+}
+
 struct BaseDecryptor<R> {
     /// The age file.
     input: R,
@@ -28,6 +99,13 @@ struct BaseDecryptor<R> {
 }
 
 impl<R> BaseDecryptor<R> {
+    fn header(&self) -> HeaderInfo<'_> {
+        match &self.header {
+            Header::V1(header) => HeaderInfo(header),
+            Header::Unknown(_) => unreachable!(),
+        }
+    }
+
     fn obtain_payload_key<F>(&self, mut filter: F) -> Result<PayloadKey, DecryptError>
     where
         F: FnMut(&[Stanza]) -> Option<Result<FileKey, DecryptError>>,
@@ -39,6 +117,67 @@ impl<R> BaseDecryptor<R> {
             Header::Unknown(_) => unreachable!(),
         }
     }
+
+    fn obtain_file_key<F>(&self, mut filter: F) -> Result<FileKey, DecryptError>
+    where
+        F: FnMut(&[Stanza]) -> Option<Result<FileKey, DecryptError>>,
+    {
+        match &self.header {
+            Header::V1(header) => filter(&header.recipients)
+                .unwrap_or(Err(DecryptError::NoMatchingKeys))
+                .and_then(|file_key| {
+                    // Verify the MAC, to ensure we have the correct file key.
+                    header.verify_mac(mac_key(&file_key))?;
+                    Ok(file_key)
+                }),
+            Header::Unknown(_) => unreachable!(),
+        }
+    }
+
+    fn rekey<F>(self, filter: F) -> Result<Rekeyer<R>, DecryptError>
+    where
+        F: FnMut(&[Stanza]) -> Option<Result<FileKey, DecryptError>>,
+    {
+        self.obtain_file_key(filter)
+            .map(|file_key| Rekeyer::new(self.input, file_key, self.nonce))
+    }
+}
+
+impl<R: Read> BaseDecryptor<R> {
+    /// Returns the offset of the payload within the age file.
+    fn payload_offset(&self) -> u64 {
+        // The payload begins after the header and nonce.
+        (self.header().encoded_len() + self.nonce.as_ref().len()) as u64
+    }
+
+    fn verify(self, payload_key: PayloadKey) -> Result<VerifiedPayload, VerifyError> {
+        let payload_offset = self.payload_offset();
+
+        Stream::decrypt(payload_key, self.input)
+            .verify()
+            .map(|(plaintext_len, chunk_count)| VerifiedPayload {
+                plaintext_len,
+                chunk_count,
+            })
+            .map_err(|e| match e {
+                VerifyFailure::Chunk(index, offset, e) => VerifyError::Chunk {
+                    index,
+                    offset: payload_offset + offset,
+                    error: match e.kind() {
+                        io::ErrorKind::InvalidData => DecryptError::DecryptionFailed,
+                        _ => DecryptError::Io(e),
+                    },
+                },
+                VerifyFailure::TrailingData(offset) => VerifyError::TrailingData {
+                    offset: payload_offset + offset,
+                },
+            })
+    }
+
+    fn recover(self, payload_key: PayloadKey) -> RecoveredChunks<R> {
+        let payload_offset = self.payload_offset();
+        Stream::recover(payload_key, self.input, payload_offset)
+    }
 }
 
 /// Decryptor for an age file encrypted to a list of recipients.
@@ -53,6 +192,11 @@ impl<R> RecipientsDecryptor<R> {
         })
     }
 
+    /// Returns a read-only view of the age file's header.
+    pub fn header(&self) -> HeaderInfo<'_> {
+        self.0.header()
+    }
+
     fn obtain_payload_key<'a>(
         &self,
         mut identities: impl Iterator<Item = &'a dyn Identity>,
@@ -73,6 +217,53 @@ impl<R: Read> RecipientsDecryptor<R> {
         self.obtain_payload_key(identities)
             .map(|payload_key| Stream::decrypt(payload_key, self.0.input))
     }
+
+    /// Attempts to recover the file key of the age file, so that it can be re-keyed to
+    /// a new set of recipients without re-encrypting the payload.
+    ///
+    /// See [`Rekeyer`] for details.
+    pub fn rekey<'a>(
+        self,
+        mut identities: impl Iterator<Item = &'a dyn Identity>,
+    ) -> Result<Rekeyer<R>, DecryptError> {
+        self.0
+            .rekey(|r| identities.find_map(|key| key.unwrap_stanzas(r)))
+    }
+
+    /// Attempts to decrypt and authenticate the entire age file, without returning any
+    /// of the plaintext.
+    ///
+    /// This checks that every chunk of the payload is authentic, and that the age file
+    /// is not truncated. If successful, returns the length of the plaintext and the
+    /// number of chunks. Otherwise, [`VerifyError::Chunk`] describes the first chunk
+    /// that failed to verify, or [`VerifyError::TrailingData`] reports data after the
+    /// last chunk.
+    ///
+    /// The reported offsets are positions within the binary age file. If the age file
+    /// is armored, they refer to the decoded binary data rather than the armored text.
+    pub fn verify<'a>(
+        self,
+        identities: impl Iterator<Item = &'a dyn Identity>,
+    ) -> Result<VerifiedPayload, VerifyError> {
+        let payload_key = self.obtain_payload_key(identities)?;
+        self.0.verify(payload_key)
+    }
+
+    /// Attempts to decrypt the age file, recovering as much of the plaintext as
+    /// possible if the payload is damaged.
+    ///
+    /// If successful, returns an iterator over the authenticated chunks of the payload,
+    /// and the ranges that could not be recovered. See [`RecoveredChunks`] for details.
+    ///
+    /// This should only be used for recovering data from damaged age files. Use
+    /// [`RecipientsDecryptor::decrypt`] to decrypt age files in general.
+    pub fn recover<'a>(
+        self,
+        identities: impl Iterator<Item = &'a dyn Identity>,
+    ) -> Result<RecoveredChunks<R>, DecryptError> {
+        self.obtain_payload_key(identities)
+            .map(|payload_key| self.0.recover(payload_key))
+    }
 }
 
 #[cfg(feature = "async")]
@@ -90,6 +281,21 @@ impl<R: AsyncRead + Unpin> RecipientsDecryptor<R> {
     }
 }
 
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> RecipientsDecryptor<R> {
+    /// Attempts to decrypt the age file.
+    ///
+    /// If successful, returns a reader that will provide the plaintext.
+    pub fn decrypt_tokio<'a>(
+        self,
+        identities: impl Iterator<Item = &'a dyn Identity>,
+    ) -> Result<StreamReader<R>, DecryptError> {
+        self.obtain_payload_key(identities)
+            .map(|payload_key| Stream::decrypt_tokio(payload_key, self.0.input))
+    }
+}
+
 /// Decryptor for an age file encrypted with a passphrase.
 pub struct PassphraseDecryptor<R>(BaseDecryptor<R>);
 
@@ -102,6 +308,11 @@ impl<R> PassphraseDecryptor<R> {
         })
     }
 
+    /// Returns a read-only view of the age file's header.
+    pub fn header(&self) -> HeaderInfo<'_> {
+        self.0.header()
+    }
+
     fn obtain_payload_key(
         &self,
         passphrase: &SecretString,
@@ -131,6 +342,69 @@ impl<R: Read> PassphraseDecryptor<R> {
         self.obtain_payload_key(passphrase, max_work_factor)
             .map(|payload_key| Stream::decrypt(payload_key, self.0.input))
     }
+
+    /// Attempts to recover the file key of the age file, so that it can be re-keyed to
+    /// a new set of recipients (or a new passphrase) without re-encrypting the payload.
+    ///
+    /// `max_work_factor` is the maximum accepted work factor. If `None`, the default
+    /// maximum is adjusted to around 16 seconds of work.
+    ///
+    /// See [`Rekeyer`] for details.
+    pub fn rekey(
+        self,
+        passphrase: &SecretString,
+        max_work_factor: Option<u8>,
+    ) -> Result<Rekeyer<R>, DecryptError> {
+        let identity = scrypt::Identity {
+            passphrase,
+            max_work_factor,
+        };
+
+        self.0.rekey(|r| identity.unwrap_stanzas(r))
+    }
+
+    /// Attempts to decrypt and authenticate the entire age file, without returning any
+    /// of the plaintext.
+    ///
+    /// `max_work_factor` is the maximum accepted work factor. If `None`, the default
+    /// maximum is adjusted to around 16 seconds of work.
+    ///
+    /// This checks that every chunk of the payload is authentic, and that the age file
+    /// is not truncated. If successful, returns the length of the plaintext and the
+    /// number of chunks. Otherwise, [`VerifyError::Chunk`] describes the first chunk
+    /// that failed to verify, or [`VerifyError::TrailingData`] reports data after the
+    /// last chunk.
+    ///
+    /// The reported offsets are positions within the binary age file. If the age file
+    /// is armored, they refer to the decoded binary data rather than the armored text.
+    pub fn verify(
+        self,
+        passphrase: &SecretString,
+        max_work_factor: Option<u8>,
+    ) -> Result<VerifiedPayload, VerifyError> {
+        let payload_key = self.obtain_payload_key(passphrase, max_work_factor)?;
+        self.0.verify(payload_key)
+    }
+
+    /// Attempts to decrypt the age file, recovering as much of the plaintext as
+    /// possible if the payload is damaged.
+    ///
+    /// `max_work_factor` is the maximum accepted work factor. If `None`, the default
+    /// maximum is adjusted to around 16 seconds of work.
+    ///
+    /// If successful, returns an iterator over the authenticated chunks of the payload,
+    /// and the ranges that could not be recovered. See [`RecoveredChunks`] for details.
+    ///
+    /// This should only be used for recovering data from damaged age files. Use
+    /// [`PassphraseDecryptor::decrypt`] to decrypt age files in general.
+    pub fn recover(
+        self,
+        passphrase: &SecretString,
+        max_work_factor: Option<u8>,
+    ) -> Result<RecoveredChunks<R>, DecryptError> {
+        self.obtain_payload_key(passphrase, max_work_factor)
+            .map(|payload_key| self.0.recover(payload_key))
+    }
 }
 
 #[cfg(feature = "async")]
@@ -151,3 +425,145 @@ impl<R: AsyncRead + Unpin> PassphraseDecryptor<R> {
             .map(|payload_key| Stream::decrypt_async(payload_key, self.0.input))
     }
 }
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: TokioAsyncRead + Unpin> PassphraseDecryptor<R> {
+    /// Attempts to decrypt the age file.
+    ///
+    /// `max_work_factor` is the maximum accepted work factor. If `None`, the default
+    /// maximum is adjusted to around 16 seconds of work.
+    ///
+    /// If successful, returns a reader that will provide the plaintext.
+    pub fn decrypt_tokio(
+        self,
+        passphrase: &SecretString,
+        max_work_factor: Option<u8>,
+    ) -> Result<StreamReader<R>, DecryptError> {
+        self.obtain_payload_key(passphrase, max_work_factor)
+            .map(|payload_key| Stream::decrypt_tokio(payload_key, self.0.input))
+    }
+}
+
+/// The first line of an age header.
+const HEADER_INTRO: &[u8] = b"age-encryption.org/v1\n";
+
+/// The start of the last line of an age header, which contains its MAC.
+const MAC_LINE_START: &[u8] = b"\n--- ";
+
+/// The length of the payload nonce, which follows the header.
+const NONCE_SIZE: usize = 16;
+
+/// Returns `true` if `data` might contain an entire age header and the payload nonce.
+///
+/// `searched` is the length of the prefix of `data` that is known not to contain the
+/// MAC line, so that the header doesn't need to be searched again on every write.
+fn may_have_header(data: &[u8], searched: &mut usize) -> bool {
+    let start = searched.saturating_sub(MAC_LINE_START.len() - 1);
+    let mac_line = match data[start..]
+        .windows(MAC_LINE_START.len())
+        .position(|w| w == MAC_LINE_START)
+    {
+        Some(i) => start + i + 1,
+        None => {
+            *searched = data.len();
+            return false;
+        }
+    };
+    *searched = mac_line;
+
+    match data[mac_line..].iter().position(|&b| b == b'\n') {
+        Some(len) => data.len() >= mac_line + len + 1 + NONCE_SIZE,
+        None => false,
+    }
+}
+
+/// Adapts `obtain_payload_key` to process the header of an age file as it is written to a
+/// [`DecryptingWriter`].
+fn decrypting_writer_header<F>(mut obtain_payload_key: F) -> PayloadKeyFn
+where
+    F: FnMut(Decryptor<&[u8]>) -> Result<PayloadKey, DecryptError> + 'static,
+{
+    let mut searched = 0;
+    Box::new(move |data: &[u8]| {
+        // Only parse the header once it might be complete, rather than on every write.
+        // Data that isn't an age header is parsed straight away, so that it is rejected
+        // early.
+        let is_header = data.iter().zip(HEADER_INTRO).all(|(a, b)| a == b);
+        if is_header && !may_have_header(data, &mut searched) {
+            return Ok(None);
+        }
+
+        let decryptor = match Decryptor::new(data) {
+            Ok(decryptor) => decryptor,
+            // We don't have the entire header and nonce yet.
+            Err(DecryptError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(None)
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+
+        let payload_len = match &decryptor {
+            Decryptor::Recipients(d) => d.0.input.len(),
+            Decryptor::Passphrase(d) => d.0.input.len(),
+        };
+
+        obtain_payload_key(decryptor)
+            .map(|payload_key| Some((data.len() - payload_len, payload_key)))
+            .map_err(|e| match e {
+                DecryptError::Io(e) => e,
+                e => io::Error::new(io::ErrorKind::InvalidData, e),
+            })
+    })
+}
+
+impl<W> DecryptingWriter<W> {
+    /// Creates a wrapper around a writer that will decrypt an age file as it is written,
+    /// using any of the given identities, and write the plaintext to `output`.
+    ///
+    /// The header is processed once all of it has been written. Any [`DecryptError`]
+    /// (such as [`DecryptError::NoMatchingKeys`], which is also returned if the age file
+    /// is encrypted with a passphrase) is returned from `write` wrapped in an
+    /// [`io::Error`].
+    ///
+    /// You **MUST** call [`DecryptingWriter::finish`] (or `AsyncWrite::poll_close`) when
+    /// you are done writing, in order to finish the decryption process.
+    pub fn with_identities(identities: Vec<Box<dyn Identity>>, output: W) -> Self {
+        DecryptingWriter::new(
+            decrypting_writer_header(move |decryptor| match decryptor {
+                Decryptor::Recipients(d) => {
+                    d.obtain_payload_key(identities.iter().map(|i| i.as_ref()))
+                }
+                Decryptor::Passphrase(_) => Err(DecryptError::NoMatchingKeys),
+            }),
+            output,
+        )
+    }
+
+    /// Creates a wrapper around a writer that will decrypt an age file as it is written,
+    /// using the given passphrase, and write the plaintext to `output`.
+    ///
+    /// `max_work_factor` is the maximum accepted work factor. If `None`, the default
+    /// maximum is adjusted to around 16 seconds of work.
+    ///
+    /// The header is processed once all of it has been written. Any [`DecryptError`]
+    /// (such as [`DecryptError::NoMatchingKeys`], which is returned if the age file is
+    /// not encrypted with a passphrase) is returned from `write` wrapped in an
+    /// [`io::Error`].
+    ///
+    /// You **MUST** call [`DecryptingWriter::finish`] (or `AsyncWrite::poll_close`) when
+    /// you are done writing, in order to finish the decryption process.
+    pub fn with_user_passphrase(
+        passphrase: SecretString,
+        max_work_factor: Option<u8>,
+        output: W,
+    ) -> Self {
+        DecryptingWriter::new(
+            decrypting_writer_header(move |decryptor| match decryptor {
+                Decryptor::Passphrase(d) => d.obtain_payload_key(&passphrase, max_work_factor),
+                Decryptor::Recipients(_) => Err(DecryptError::NoMatchingKeys),
+            }),
+            output,
+        )
+    }
+}
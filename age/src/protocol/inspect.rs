@@ -0,0 +1,354 @@
+//! Inspection of age files without decrypting them.
+
+use age_core::format::Stanza;
+use std::io::{self, Read};
+
+use super::decryptor::HeaderInfo;
+use crate::{
+    error::DecryptError,
+    format::{Header, HeaderV1},
+    primitives::stream::{ENCRYPTED_CHUNK_SIZE, TAG_SIZE},
+    x25519,
+};
+
+#[cfg(feature = "plugin")]
+use crate::plugin;
+
+#[cfg(feature = "pq")]
+use crate::pq;
+
+#[cfg(feature = "ssh")]
+use crate::ssh;
+
+/// The length of the payload nonce that precedes the STREAM chunks.
+const NONCE_SIZE: u64 = 16;
+
+/// The tags of the recipient stanzas that are implemented by this crate, rather than by
+/// plugins.
+const NATIVE_TAGS: &[&str] = &[
+    "X25519",
+    "scrypt",
+    "ssh-rsa",
+    "ssh-ed25519",
+    "rage-mlkem768x25519",
+];
+
+/// The suffix of the tags of the random stanzas that age adds to headers.
+const GREASE_SUFFIX: &str = "-grease";
+
+/// Whether an age file's header contains a stanza for a particular recipient.
+///
+/// Returned by the `match_*_recipient` methods of [`HeaderInfo`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecipientMatch {
+    /// The header contains a stanza for the recipient.
+    Found,
+    /// The header does not contain a stanza for the recipient.
+    NotFound,
+    /// The header contains stanzas that might be for the recipient, but they don't
+    /// identify who they were created for.
+    Unknown,
+}
+
+impl<'a> HeaderInfo<'a> {
+    /// Returns [`RecipientMatch::Unknown`] if the header contains a stanza for which
+    /// `f` returns `true`, and [`RecipientMatch::NotFound`] otherwise.
+    fn match_unidentified(&self, f: impl Fn(&Stanza) -> bool) -> RecipientMatch {
+        if self.stanzas().iter().any(f) {
+            RecipientMatch::Unknown
+        } else {
+            RecipientMatch::NotFound
+        }
+    }
+
+    /// Returns whether this header contains a stanza for the given X25519 recipient.
+    ///
+    /// X25519 stanzas don't identify their recipient, so this is
+    /// [`RecipientMatch::Unknown`] if the header contains any X25519 stanzas.
+    pub fn match_x25519_recipient(&self, _recipient: &x25519::Recipient) -> RecipientMatch {
+        self.match_unidentified(|s| s.tag == x25519::X25519_RECIPIENT_TAG)
+    }
+
+    /// Returns whether this header contains a stanza for the given post-quantum hybrid
+    /// recipient.
+    ///
+    /// Post-quantum hybrid stanzas don't identify their recipient, so this is
+    /// [`RecipientMatch::Unknown`] if the header contains any such stanzas.
+    #[cfg(feature = "pq")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pq")))]
+    pub fn match_pq_recipient(&self, _recipient: &pq::Recipient) -> RecipientMatch {
+        self.match_unidentified(|s| s.tag == pq::MLKEM768X25519_RECIPIENT_TAG)
+    }
+
+    /// Returns whether this header contains a stanza for the given SSH recipient.
+    ///
+    /// SSH stanzas contain a 4-byte tag of the recipient's public key, so this is never
+    /// [`RecipientMatch::Unknown`]. A different key with the same tag would also be
+    /// reported as [`RecipientMatch::Found`], but such collisions are not expected in
+    /// practice.
+    #[cfg(feature = "ssh")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ssh")))]
+    pub fn match_ssh_recipient(&self, recipient: &ssh::Recipient) -> RecipientMatch {
+        if self.stanzas().iter().any(|s| recipient.matches_stanza(s)) {
+            RecipientMatch::Found
+        } else {
+            RecipientMatch::NotFound
+        }
+    }
+
+    /// Returns whether this header contains a stanza for the given plugin recipient.
+    ///
+    /// Age files don't record which plugin created a stanza, so this is
+    /// [`RecipientMatch::Unknown`] if the header contains any stanzas that could have
+    /// been created by a plugin.
+    #[cfg(feature = "plugin")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "plugin")))]
+    pub fn match_plugin_recipient(&self, _recipient: &plugin::Recipient) -> RecipientMatch {
+        self.match_unidentified(|s| {
+            !(NATIVE_TAGS.contains(&s.tag.as_str()) || s.tag.ends_with(GREASE_SUFFIX))
+        })
+    }
+}
+
+/// The structure of an age file, read without any identities.
+///
+/// This describes the header (including the non-secret contents of its recipient
+/// stanzas) and the layout of the payload. Because the payload can only be authenticated
+/// with the file key, a well-formed payload is not necessarily a valid one: use
+/// [`RecipientsDecryptor::verify`] or [`PassphraseDecryptor::verify`] for that.
+///
+/// [`RecipientsDecryptor::verify`]: crate::decryptor::RecipientsDecryptor::verify
+/// [`PassphraseDecryptor::verify`]: crate::decryptor::PassphraseDecryptor::verify
+pub struct Inspection {
+    header: HeaderV1,
+    payload_len: u64,
+}
+
+impl Inspection {
+    /// Reads an entire age file from `input`, and returns its structure.
+    ///
+    /// `input` must be a binary age file; to inspect armored age files, wrap `input` in
+    /// an `age::armor::ArmoredReader` first. Returns an error if `input` does not start
+    /// with a valid age header.
+    pub fn new<R: Read>(mut input: R) -> Result<Self, DecryptError> {
+        let header = match Header::read(&mut input)? {
+            Header::V1(header) => header,
+            Header::Unknown(_) => return Err(DecryptError::UnknownFormat),
+        };
+        let payload_len = io::copy(&mut input, &mut io::sink())?;
+
+        Ok(Inspection {
+            header,
+            payload_len,
+        })
+    }
+
+    /// Returns the version of the age format that this file uses.
+    ///
+    /// Files with other versions are rejected by [`Inspection::new`], so this is always
+    /// `"v1"`.
+    pub fn version(&self) -> &'static str {
+        "v1"
+    }
+
+    /// Returns a read-only view of the age file's header.
+    pub fn header(&self) -> HeaderInfo<'_> {
+        HeaderInfo(&self.header)
+    }
+
+    /// Returns the length in bytes of the age file's payload (everything after the
+    /// header, including the 16-byte payload nonce).
+    pub fn payload_len(&self) -> u64 {
+        self.payload_len
+    }
+
+    /// Returns the number of (possibly partial) STREAM chunks in the payload.
+    pub fn chunk_count(&self) -> u64 {
+        let stream_len = self.payload_len.saturating_sub(NONCE_SIZE);
+        (stream_len + ENCRYPTED_CHUNK_SIZE as u64 - 1) / ENCRYPTED_CHUNK_SIZE as u64
+    }
+
+    /// Returns `true` if the payload ends with a chunk of a valid length.
+    ///
+    /// Every chunk must contain a 16-byte tag, and the final chunk may only be empty if
+    /// it is the only chunk. A `false` result means that the file is truncated or has
+    /// been damaged. A `true` result does not mean the final chunk is authentic.
+    pub fn last_chunk_is_well_formed(&self) -> bool {
+        let chunk_count = self.chunk_count();
+        if self.payload_len < NONCE_SIZE || chunk_count == 0 {
+            return false;
+        }
+
+        let last_chunk_len =
+            self.payload_len - NONCE_SIZE - (chunk_count - 1) * ENCRYPTED_CHUNK_SIZE as u64;
+        match last_chunk_len.cmp(&(TAG_SIZE as u64)) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => chunk_count == 1,
+            std::cmp::Ordering::Greater => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use age_core::secrecy::SecretString;
+
+    use super::{Inspection, RecipientMatch, GREASE_SUFFIX};
+    use crate::{x25519, Encryptor, Recipient};
+
+    fn encrypt(plaintext: &[u8], recipient_count: usize) -> Vec<u8> {
+        let recipients = (0..recipient_count)
+            .map(|_| Box::new(x25519::Identity::generate().to_public()) as Box<dyn Recipient>)
+            .collect();
+        encrypt_to(plaintext, recipients)
+    }
+
+    fn encrypt_to(plaintext: &[u8], recipients: Vec<Box<dyn Recipient>>) -> Vec<u8> {
+        let mut encrypted = vec![];
+        let mut w = Encryptor::with_recipients(recipients)
+            .wrap_output(&mut encrypted)
+            .unwrap();
+        w.write_all(plaintext).unwrap();
+        w.finish().unwrap();
+        encrypted
+    }
+
+    #[test]
+    fn inspect_empty_file() {
+        let encrypted = encrypt(&[], 2);
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+
+        assert_eq!(inspection.version(), "v1");
+        // The X25519 stanzas are followed by a grease stanza.
+        assert_eq!(inspection.header().stanza_count(), 3);
+        assert!(inspection
+            .header()
+            .stanzas()
+            .iter()
+            .filter(|s| !s.tag.ends_with(GREASE_SUFFIX))
+            .all(|s| s.tag == "X25519"));
+        assert!(inspection.header().stanzas()[2]
+            .tag
+            .ends_with(GREASE_SUFFIX));
+        assert_eq!(inspection.header().scrypt_work_factor(), None);
+        assert_eq!(
+            inspection.header().encoded_len() as u64 + inspection.payload_len(),
+            encrypted.len() as u64
+        );
+        assert_eq!(inspection.payload_len(), 16 + 16);
+        assert_eq!(inspection.chunk_count(), 1);
+        assert!(inspection.last_chunk_is_well_formed());
+    }
+
+    #[test]
+    fn inspect_chunks() {
+        let encrypted = encrypt(&vec![42; 2 * 64 * 1024 + 1], 1);
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(inspection.chunk_count(), 3);
+        assert!(inspection.last_chunk_is_well_formed());
+
+        // A payload that is a whole number of full chunks is well-formed.
+        let encrypted = encrypt(&vec![42; 2 * 64 * 1024], 1);
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(inspection.chunk_count(), 2);
+        assert!(inspection.last_chunk_is_well_formed());
+    }
+
+    #[test]
+    fn inspect_truncated() {
+        let encrypted = encrypt(&vec![42; 2 * 64 * 1024], 1);
+        let header_len = Inspection::new(&encrypted[..])
+            .unwrap()
+            .header()
+            .encoded_len();
+
+        // Truncated within the payload nonce.
+        let inspection = Inspection::new(&encrypted[..header_len + 10]).unwrap();
+        assert_eq!(inspection.chunk_count(), 0);
+        assert!(!inspection.last_chunk_is_well_formed());
+
+        // Truncated to a final chunk that is too short to contain a tag.
+        let inspection =
+            Inspection::new(&encrypted[..header_len + 16 + 64 * 1024 + 16 + 10]).unwrap();
+        assert_eq!(inspection.chunk_count(), 2);
+        assert!(!inspection.last_chunk_is_well_formed());
+
+        // Truncated to an empty final chunk, after a full chunk.
+        let inspection =
+            Inspection::new(&encrypted[..header_len + 16 + 64 * 1024 + 16 + 16]).unwrap();
+        assert_eq!(inspection.chunk_count(), 2);
+        assert!(!inspection.last_chunk_is_well_formed());
+
+        // Truncated within the header.
+        assert!(Inspection::new(&encrypted[..header_len - 1]).is_err());
+    }
+
+    #[test]
+    fn match_x25519_recipient() {
+        let recipient = x25519::Identity::generate().to_public();
+
+        let encrypted = encrypt(&[], 1);
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(
+            inspection.header().match_x25519_recipient(&recipient),
+            RecipientMatch::Unknown
+        );
+
+        let encrypted = {
+            let mut encrypted = vec![];
+            let mut w = Encryptor::with_user_passphrase(SecretString::new("passphrase".to_owned()))
+                .wrap_output(&mut encrypted)
+                .unwrap();
+            w.write_all(&[]).unwrap();
+            w.finish().unwrap();
+            encrypted
+        };
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(
+            inspection.header().match_x25519_recipient(&recipient),
+            RecipientMatch::NotFound
+        );
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn match_ssh_recipient() {
+        use crate::ssh::{
+            self,
+            recipient::tests::{TEST_SSH_ED25519_PK, TEST_SSH_RSA_PK},
+        };
+
+        let rsa: ssh::Recipient = TEST_SSH_RSA_PK.parse().unwrap();
+        let ed25519: ssh::Recipient = TEST_SSH_ED25519_PK.parse().unwrap();
+
+        let encrypted = encrypt_to(
+            &[],
+            vec![
+                Box::new(ed25519.clone()),
+                Box::new(x25519::Identity::generate().to_public()),
+            ],
+        );
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(
+            inspection.header().match_ssh_recipient(&ed25519),
+            RecipientMatch::Found
+        );
+        assert_eq!(
+            inspection.header().match_ssh_recipient(&rsa),
+            RecipientMatch::NotFound
+        );
+
+        let encrypted = encrypt_to(&[], vec![Box::new(rsa.clone())]);
+        let inspection = Inspection::new(&encrypted[..]).unwrap();
+        assert_eq!(
+            inspection.header().match_ssh_recipient(&rsa),
+            RecipientMatch::Found
+        );
+        assert_eq!(
+            inspection.header().match_ssh_recipient(&ed25519),
+            RecipientMatch::NotFound
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Re-keying of age files.
+
+use age_core::format::FileKey;
+use std::io::{self, Read, Write};
+
+use super::{Encryptor, Nonce};
+use crate::{error::EncryptError, format::Header};
+
+/// An age file whose file key has been recovered, and that can be re-keyed to a new set
+/// of recipients (or a new passphrase) without re-encrypting its payload.
+///
+/// The payload of an age file is encrypted with a key derived from the file key and the
+/// payload nonce. Re-keying wraps the same file key in a new header, and then copies the
+/// nonce and payload verbatim.
+///
+/// Created with [`RecipientsDecryptor::rekey`] or [`PassphraseDecryptor::rekey`].
+///
+/// # Security
+///
+/// Because the file key is unchanged, anyone who was able to decrypt the original file
+/// (and who retained its file key) will also be able to decrypt the re-keyed file. Use
+/// this to change how a file is protected at rest, not to revoke access from someone who
+/// may already have decrypted it.
+///
+/// The payload is not authenticated while it is being copied. If the original payload
+/// was corrupted, the re-keyed file will fail to decrypt in the same way.
+///
+/// [`RecipientsDecryptor::rekey`]: crate::decryptor::RecipientsDecryptor::rekey
+/// [`PassphraseDecryptor::rekey`]: crate::decryptor::PassphraseDecryptor::rekey
+pub struct Rekeyer<R> {
+    /// The remainder of the age file, positioned at the start of the payload.
+    input: R,
+    /// The file key recovered from the original header.
+    file_key: FileKey,
+    /// The age file's AEAD nonce.
+    nonce: Nonce,
+}
+
+impl<R> Rekeyer<R> {
+    pub(super) fn new(input: R, file_key: FileKey, nonce: Nonce) -> Self {
+        Rekeyer {
+            input,
+            file_key,
+            nonce,
+        }
+    }
+}
+
+impl<R: Read> Rekeyer<R> {
+    /// Writes the re-keyed age file to `output`.
+    ///
+    /// The new header is created by `encryptor` (using its recipients or passphrase), and
+    /// is followed by the original nonce and payload. Returns `output` once the entire
+    /// payload has been copied.
+    pub fn rekey_to<W: Write>(
+        mut self,
+        encryptor: Encryptor,
+        mut output: W,
+    ) -> Result<W, EncryptError> {
+        let header = encryptor.wrap_file_key(&self.file_key)?;
+        Header::V1(header).write(&mut output)?;
+        output.write_all(self.nonce.as_ref())?;
+        io::copy(&mut self.input, &mut output)?;
+        Ok(output)
+    }
+}
@@ -21,11 +21,11 @@ use super::{
     identity::{Identity, UnencryptedKey},
     read_ssh, ssh_tag, EncryptedKey, UnsupportedKey, SSH_ED25519_KEY_PREFIX,
     SSH_ED25519_RECIPIENT_KEY_LABEL, SSH_ED25519_RECIPIENT_TAG, SSH_RSA_KEY_PREFIX,
-    SSH_RSA_OAEP_LABEL, SSH_RSA_RECIPIENT_TAG,
+    SSH_RSA_OAEP_LABEL, SSH_RSA_RECIPIENT_TAG, TAG_LEN_BYTES,
 };
 use crate::{
     error::EncryptError,
-    util::read::{encoded_str, str_while_encoded},
+    util::read::{base64_arg, encoded_str, str_while_encoded},
 };
 
 /// A key that can be used to encrypt a file to a recipient.
@@ -113,6 +113,26 @@ impl TryFrom<Identity> for Recipient {
     }
 }
 
+impl Recipient {
+    /// Returns `true` if `stanza` was created for this recipient.
+    ///
+    /// SSH stanzas contain a 4-byte tag of the recipient's public key, so this can be
+    /// checked without the corresponding identity.
+    pub(crate) fn matches_stanza(&self, stanza: &Stanza) -> bool {
+        let (recipient_tag, ssh_key) = match self {
+            Recipient::SshRsa(ssh_key, _) => (SSH_RSA_RECIPIENT_TAG, ssh_key),
+            Recipient::SshEd25519(ssh_key, _) => (SSH_ED25519_RECIPIENT_TAG, ssh_key),
+        };
+
+        stanza.tag == recipient_tag
+            && stanza
+                .args
+                .get(0)
+                .and_then(|tag| base64_arg(tag, [0; TAG_LEN_BYTES]))
+                .map_or(false, |tag| tag == ssh_tag(ssh_key))
+    }
+}
+
 impl crate::Recipient for Recipient {
     fn wrap_file_key(&self, file_key: &FileKey) -> Result<Vec<Stanza>, EncryptError> {
         match self {
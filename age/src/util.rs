@@ -5,6 +5,9 @@ pub(crate) const LINE_ENDING: &str = "\r\n";
 #[cfg(all(any(feature = "armor", feature = "cli-common"), not(windows)))]
 pub(crate) const LINE_ENDING: &str = "\n";
 
+/// The BIP39 English wordlist, one word per line.
+pub(crate) const BIP39_WORDLIST: &str = include_str!("../assets/bip39-english.txt");
+
 pub(crate) fn parse_bech32(s: &str) -> Option<(String, Vec<u8>)> {
     bech32::decode(s).ok().and_then(|(hrp, data, variant)| {
         if let Variant::Bech32 = variant {
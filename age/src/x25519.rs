@@ -6,14 +6,17 @@ use age_core::{
     secrecy::{ExposeSecret, SecretString},
 };
 use bech32::{ToBase32, Variant};
+use i18n_embed_fl::fl;
 use rand_7::rngs::OsRng;
+use sha2::{Digest, Sha256};
 use std::fmt;
 use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 use zeroize::Zeroize;
 
 use crate::{
     error::{DecryptError, EncryptError},
-    util::{parse_bech32, read::base64_arg},
+    util::{parse_bech32, read::base64_arg, BIP39_WORDLIST},
+    wfl,
 };
 
 // Use lower-case HRP to avoid https://github.com/rust-bitcoin/rust-bech32/issues/40
@@ -26,6 +29,152 @@ const X25519_RECIPIENT_KEY_LABEL: &[u8] = b"age-encryption.org/v1/X25519";
 pub(super) const EPK_LEN_BYTES: usize = 32;
 pub(super) const ENCRYPTED_FILE_KEY_BYTES: usize = FILE_KEY_BYTES + 16;
 
+/// The number of words in the mnemonic encoding of an identity: 256 bits of key material
+/// plus an 8-bit checksum, at 11 bits per word.
+const MNEMONIC_WORDS: usize = 24;
+
+/// Errors that can occur while parsing an identity from a mnemonic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// The mnemonic did not contain 24 words. The value is the number of words found.
+    InvalidLength(usize),
+    /// A word in the mnemonic is not in the BIP39 English wordlist.
+    UnknownWord {
+        /// The position of the word in the mnemonic, starting from 1.
+        position: usize,
+        /// The unknown word.
+        word: String,
+        /// The word that was probably intended, if there is a unique candidate.
+        suggestion: Option<&'static str>,
+    },
+    /// The mnemonic's checksum is invalid; a word has been mistyped for another valid
+    /// word, or the words are in the wrong order.
+    InvalidChecksum,
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::InvalidLength(words) => write!(
+                f,
+                "{}",
+                fl!(
+                    crate::i18n::LANGUAGE_LOADER,
+                    "err-mnemonic-length",
+                    expected = MNEMONIC_WORDS,
+                    actual = *words,
+                )
+            ),
+            MnemonicError::UnknownWord {
+                position,
+                word,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::i18n::LANGUAGE_LOADER,
+                        "err-mnemonic-unknown-word",
+                        position = *position,
+                        word = word.as_str(),
+                    )
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(
+                        f,
+                        " {}",
+                        fl!(
+                            crate::i18n::LANGUAGE_LOADER,
+                            "rec-mnemonic-unknown-word",
+                            suggestion = *suggestion,
+                        )
+                    )?;
+                }
+                Ok(())
+            }
+            MnemonicError::InvalidChecksum => wfl!(f, "err-mnemonic-checksum"),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+/// Encodes 32 bytes as a 24-word BIP39 mnemonic.
+fn encode_mnemonic(entropy: &[u8; 32]) -> SecretString {
+    let checksum = Sha256::digest(entropy)[0];
+    let words: Vec<_> = BIP39_WORDLIST.lines().collect();
+
+    let mnemonic = (0..MNEMONIC_WORDS)
+        .map(|i| {
+            let index = (0..11).fold(0, |acc, j| {
+                let bit = i * 11 + j;
+                let byte = entropy.get(bit / 8).unwrap_or(&checksum);
+                (acc << 1) | usize::from((byte >> (7 - bit % 8)) & 1)
+            });
+            words[index]
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    SecretString::new(mnemonic)
+}
+
+/// Returns the word in the BIP39 English wordlist that `word` was probably intended to
+/// be. Every word in the list is uniquely identified by its first four letters.
+fn suggest_word(word: &str) -> Option<&'static str> {
+    let prefix: String = word.chars().take(4).collect();
+    if prefix.chars().count() < 4 {
+        return None;
+    }
+
+    let mut candidates = BIP39_WORDLIST.lines().filter(|w| w.starts_with(&prefix));
+    match (candidates.next(), candidates.next()) {
+        (Some(candidate), None) => Some(candidate),
+        _ => None,
+    }
+}
+
+/// Decodes a 24-word BIP39 mnemonic, checking its checksum.
+fn decode_mnemonic(mnemonic: &str) -> Result<[u8; 32], MnemonicError> {
+    let words: Vec<_> = mnemonic.split_whitespace().collect();
+    if words.len() != MNEMONIC_WORDS {
+        return Err(MnemonicError::InvalidLength(words.len()));
+    }
+
+    // 264 bits: the entropy followed by the checksum.
+    let mut bits = [0; 33];
+    for (i, word) in words.into_iter().enumerate() {
+        let word = word.to_lowercase();
+        let index = BIP39_WORDLIST
+            .lines()
+            .position(|w| w == word)
+            .ok_or_else(|| MnemonicError::UnknownWord {
+                position: i + 1,
+                suggestion: suggest_word(&word),
+                word,
+            })?;
+        for j in 0..11 {
+            if (index >> (10 - j)) & 1 == 1 {
+                let bit = i * 11 + j;
+                bits[bit / 8] |= 1 << (7 - bit % 8);
+            }
+        }
+    }
+
+    let mut entropy = [0; 32];
+    entropy.copy_from_slice(&bits[..32]);
+    let checksum = bits[32];
+    bits.zeroize();
+
+    if Sha256::digest(&entropy)[0] == checksum {
+        Ok(entropy)
+    } else {
+        entropy.zeroize();
+        Err(MnemonicError::InvalidChecksum)
+    }
+}
+
 /// The standard age identity type, which can decrypt files encrypted to the corresponding
 /// [`Recipient`].
 #[derive(Clone)]
@@ -79,6 +228,32 @@ impl Identity {
     pub fn to_public(&self) -> Recipient {
         Recipient((&self.0).into())
     }
+
+    /// Encodes this secret key as a 24-word [BIP39] mnemonic, suitable for paper
+    /// backups.
+    ///
+    /// The identity can be restored with [`Identity::from_mnemonic`].
+    ///
+    /// [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+    pub fn to_mnemonic(&self) -> SecretString {
+        let mut sk_bytes = self.0.to_bytes();
+        let ret = encode_mnemonic(&sk_bytes);
+        sk_bytes.zeroize();
+        ret
+    }
+
+    /// Parses a secret key from its 24-word [BIP39] mnemonic encoding.
+    ///
+    /// Words are separated by whitespace, and are case-insensitive. The mnemonic's
+    /// checksum is validated.
+    ///
+    /// [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self, MnemonicError> {
+        let mut sk_bytes = decode_mnemonic(mnemonic)?;
+        let ret = Identity(StaticSecret::from(sk_bytes));
+        sk_bytes.zeroize();
+        Ok(ret)
+    }
 }
 
 impl crate::Identity for Identity {
@@ -191,7 +366,7 @@ pub(crate) mod tests {
     use quickcheck_macros::quickcheck;
     use x25519_dalek::{PublicKey, StaticSecret};
 
-    use super::{Identity, Recipient};
+    use super::{decode_mnemonic, encode_mnemonic, Identity, MnemonicError, Recipient};
     use crate::{Identity as _, Recipient as _};
 
     pub(crate) const TEST_SK: &str =
@@ -211,6 +386,78 @@ pub(crate) mod tests {
         assert_eq!(key.to_public().to_string(), TEST_PK);
     }
 
+    #[test]
+    fn mnemonic_test_vectors() {
+        // From https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+        let vectors = [
+            (
+                [0x00; 32],
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon art",
+            ),
+            (
+                [0x7f; 32],
+                "legal winner thank year wave sausage worth useful legal winner thank year \
+                 wave sausage worth useful legal winner thank year wave sausage worth title",
+            ),
+            (
+                [0xff; 32],
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo \
+                 zoo zoo zoo zoo vote",
+            ),
+        ];
+
+        for (entropy, mnemonic) in vectors {
+            assert_eq!(encode_mnemonic(&entropy).expose_secret(), mnemonic);
+            assert_eq!(decode_mnemonic(mnemonic), Ok(entropy));
+        }
+    }
+
+    #[test]
+    fn mnemonic_round_trip() {
+        let sk = TEST_SK.parse::<Identity>().unwrap();
+        let mnemonic = sk.to_mnemonic();
+        let restored = Identity::from_mnemonic(mnemonic.expose_secret()).unwrap();
+        assert_eq!(restored.to_string().expose_secret(), TEST_SK);
+
+        // Words are case-insensitive and can be separated by any whitespace.
+        let reformatted = mnemonic.expose_secret().to_uppercase().replace(' ', "\n");
+        assert!(Identity::from_mnemonic(&reformatted).is_ok());
+    }
+
+    #[test]
+    fn mnemonic_errors() {
+        let valid = "legal winner thank year wave sausage worth useful legal winner thank \
+                     year wave sausage worth useful legal winner thank year wave sausage \
+                     worth title";
+
+        assert_eq!(
+            Identity::from_mnemonic("legal winner thank").err(),
+            Some(MnemonicError::InvalidLength(3))
+        );
+        assert_eq!(
+            Identity::from_mnemonic(&valid.replacen("sausage", "sausag", 1)).err(),
+            Some(MnemonicError::UnknownWord {
+                position: 6,
+                word: "sausag".into(),
+                suggestion: Some("sausage"),
+            })
+        );
+        assert_eq!(
+            Identity::from_mnemonic(&valid.replacen("thank", "xy", 1)).err(),
+            Some(MnemonicError::UnknownWord {
+                position: 3,
+                word: "xy".into(),
+                suggestion: None,
+            })
+        );
+        assert_eq!(
+            Identity::from_mnemonic(&valid.replacen("legal", "winner", 1)).err(),
+            Some(MnemonicError::InvalidChecksum)
+        );
+    }
+
     #[quickcheck]
     fn wrap_and_unwrap(sk_bytes: Vec<u8>) -> TestResult {
         if sk_bytes.len() > 32 {
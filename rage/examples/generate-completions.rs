@@ -48,6 +48,16 @@ fn rage_completions() {
         .arg(Arg::new("input"))
         .arg(Arg::new("encrypt").short('e').long("encrypt"))
         .arg(Arg::new("decrypt").short('d').long("decrypt"))
+        .arg(Arg::new("verify").long("verify"))
+        .arg(Arg::new("recover").long("recover"))
+        .arg(Arg::new("rekey").long("rekey"))
+        .arg(Arg::new("recursive").long("recursive"))
+        .arg(Arg::new("in-place").long("in-place"))
+        .arg(Arg::new("inspect").long("inspect"))
+        .arg(Arg::new("who-can-decrypt").long("who-can-decrypt"))
+        .arg(Arg::new("json").long("json"))
+        .arg(Arg::new("progress").long("progress"))
+        .arg(Arg::new("no-config").long("no-config"))
         .arg(Arg::new("passphrase").short('p').long("passphrase"))
         .arg(
             Arg::new("max-work-factor")
@@ -87,12 +97,20 @@ fn rage_completions() {
 }
 
 fn rage_keygen_completions() {
-    let app = Command::new("rage-keygen").arg(
-        Arg::new("output")
-            .takes_value(true)
-            .short('o')
-            .long("output"),
-    );
+    let app = Command::new("rage-keygen")
+        .arg(Arg::new("input").multiple_occurrences(true))
+        .arg(
+            Arg::new("output")
+                .takes_value(true)
+                .short('o')
+                .long("output"),
+        )
+        .arg(Arg::new("pq").long("pq"))
+        .arg(Arg::new("passphrase").short('p').long("passphrase"))
+        .arg(Arg::new("change-passphrase").long("change-passphrase"))
+        .arg(Arg::new("mnemonic").long("mnemonic"))
+        .arg(Arg::new("from-mnemonic").long("from-mnemonic"))
+        .arg(Arg::new("convert").short('y'));
 
     generate_completions(app, "rage-keygen");
 }
@@ -113,7 +131,9 @@ fn rage_mount_completions() {
                 .multiple_occurrences(true)
                 .short('i')
                 .long("identity"),
-        );
+        )
+        .arg(Arg::new("plugin-name").takes_value(true).short('j'))
+        .arg(Arg::new("read-write").long("read-write"));
 
     generate_completions(app, "rage-mount");
 }
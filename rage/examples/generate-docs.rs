@@ -42,6 +42,77 @@ fn rage_page() {
                 .long("--decrypt")
                 .help("Decrypt the input. By default, the input is encrypted."),
         )
+        .flag(
+            Flag::new().long("--verify").help(
+                "With --decrypt, check that every chunk of the input decrypts, without \
+                 writing any output.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--recover").help(
+                "With --decrypt, write every chunk of a damaged input that decrypts to the \
+                 output, skipping the chunks that don't. A report of the damaged chunks is \
+                 printed to standard error.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--rekey").help(
+                "Re-encrypt the header of the input to new recipients (or a new passphrase), \
+                 without re-encrypting the payload.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--recursive").help(
+                "Encrypt every file in the input directory to the same path (with an .age \
+                 suffix) in the output directory. With --decrypt, decrypt every .age file in \
+                 the input directory, removing the suffix. Recipients and identities are only \
+                 read once, and a summary of any failures is printed at the end.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--in-place").help(
+                "Replace the input file with the result. The result is written to a \
+                 temporary file in the same directory, and only renamed over the input \
+                 (keeping its permissions) once it has been written successfully.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--inspect").help(
+                "Print the structure of the input without decrypting it: the format \
+                 version, whether it is armored, the header size, the type and non-secret \
+                 details of each recipient stanza, the payload size, the number of chunks, \
+                 and whether the final chunk is well-formed. No identities are needed.",
+            ),
+        )
+        .flag(
+            Flag::new().long("--who-can-decrypt").help(
+                "Report, for each recipient given with -r/--recipient or \
+                 -R/--recipients-file, whether the input is encrypted to it, without \
+                 decrypting it. This is definitive for SSH recipients; for other recipient \
+                 types it is \"unknown\" if the input contains stanzas that don't identify \
+                 their recipient.",
+            ),
+        )
+        .flag(
+            Flag::new()
+                .long("--json")
+                .help("Print the output of --inspect or --who-can-decrypt as JSON."),
+        )
+        .flag(
+            Flag::new().long("--progress").help(
+                "Show a progress bar with the throughput and estimated time remaining on \
+                 standard error while encrypting or decrypting, if standard error is a \
+                 terminal. The total size is only known when the input is a file (and, \
+                 when decrypting, is not armored).",
+            ),
+        )
+        .flag(Flag::new().long("--no-config").help(
+            "Don't read the config file ($XDG_CONFIG_HOME/rage/config.toml, or \
+             %APPDATA%\\rage\\config.toml on Windows). It can set default identity \
+             files for decryption (identities = [\"~/keys.txt\"]), armor = true, and \
+             max-work-factor, and define recipient aliases in a [recipients] table \
+             (ops = [\"age1...\", \"@alice\"]) that can be used as -r @ops.",
+        ))
         .flag(
             Flag::new()
                 .short("-p")
@@ -70,7 +141,13 @@ fn rage_page() {
             Opt::new("IDENTITY")
                 .short("-i")
                 .long("--identity")
-                .help("Use the identity file at IDENTITY. May be repeated."),
+                .help(
+                    "Use the identity file at IDENTITY. May be repeated. When decrypting \
+                     without -i or -j, the identity files listed in the config file are \
+                     used, or else those of $XDG_CONFIG_HOME/age/keys.txt, \
+                     ~/.ssh/id_ed25519, and ~/.ssh/id_rsa that exist and might be able to \
+                     decrypt the input (set RUST_LOG=info to see which one did).",
+                ),
         )
         .option(
             Opt::new("OUTPUT")
@@ -122,6 +199,54 @@ fn rage_page() {
             Example::new()
                 .text("Decryption with identities")
                 .command("rage -d -o hello -i keyA.txt -i keyB.txt hello.age"),
+        )
+        .example(
+            Example::new()
+                .text("Verifying that a file decrypts, without writing the plaintext")
+                .command("rage -d --verify -i key.txt backup.tar.age"),
+        )
+        .example(
+            Example::new()
+                .text("Recovering what can be decrypted from a damaged file")
+                .command("rage -d --recover -i key.txt -o backup.tar backup.tar.age"),
+        )
+        .example(
+            Example::new()
+                .text("Re-keying a file to a new list of recipients")
+                .command("rage --rekey -i key.txt -R recipients.txt -o new.tar.age xxx.tar.age"),
+        )
+        .example(
+            Example::new()
+                .text("Encrypting a directory tree, and decrypting it again")
+                .command(
+                    "rage --recursive -R recipients.txt -o backup/ data/ && \
+                     rage -d --recursive -i key.txt -o restored/ backup/",
+                ),
+        )
+        .example(
+            Example::new()
+                .text("Encrypting a file in place")
+                .command("rage --in-place -R recipients.txt secrets.yaml"),
+        )
+        .example(
+            Example::new()
+                .text("Inspecting an encrypted file without decrypting it")
+                .command("rage --inspect --json backup.tar.age"),
+        )
+        .example(
+            Example::new()
+                .text("Checking which SSH keys can decrypt a file")
+                .command("rage --who-can-decrypt -R ~/.ssh/authorized_keys secrets.age"),
+        )
+        .example(
+            Example::new()
+                .text("Decrypting a large file while showing its progress")
+                .command("rage -d --progress -i key.txt -o backup.tar backup.tar.age"),
+        )
+        .example(
+            Example::new()
+                .text("Encrypting to a recipient alias from the config file")
+                .command("rage -r @ops -o secrets.yaml.age secrets.yaml"),
         );
     let page = builder.render();
 
@@ -149,6 +274,31 @@ fn rage_keygen_page() {
                 "Write the key pair to the file at path OUTPUT. Defaults to standard output.",
             ),
         )
+        .flag(Flag::new().long("--pq").help(
+            "Generate a post-quantum hybrid key pair, which combines ML-KEM-768 with X25519. \
+             Requires rage to be built with the pq feature flag.",
+        ))
+        .flag(Flag::new().short("-p").long("--passphrase").help(
+            "Encrypt the generated identity file with a passphrase, using the age \
+                     ASCII armor format. The public key is printed to standard error.",
+        ))
+        .flag(Flag::new().long("--change-passphrase").help(
+            "Re-encrypt the passphrase-encrypted identity file at path INPUT (or standard \
+             input) with a new passphrase, and write it to OUTPUT.",
+        ))
+        .flag(Flag::new().long("--mnemonic").help(
+            "Write the 24-word BIP39 mnemonic backup of the native age identity in the file \
+             at path INPUT (or standard input) to OUTPUT.",
+        ))
+        .flag(Flag::new().long("--from-mnemonic").help(
+            "Restore an identity from its 24-word mnemonic backup, instead of generating a \
+             new one. The mnemonic is requested interactively.",
+        ))
+        .flag(Flag::new().short("-y").help(
+            "Convert the identity files at paths INPUT (or standard input) to recipients, \
+             and write them to OUTPUT, one per line.",
+        ))
+        .arg(Arg::new("[INPUT...]"))
         .example(
             Example::new()
                 .text("Generate a new key pair")
@@ -162,6 +312,43 @@ fn rage_keygen_page() {
                     "Public key: age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
                 ),
         )
+        .example(
+            Example::new()
+                .text("Generate a new post-quantum key pair and save it to a file")
+                .command("rage-keygen --pq -o pq-key.txt"),
+        )
+        .example(
+            Example::new()
+                .text("Generate a new passphrase-encrypted key pair and save it to a file")
+                .command("rage-keygen -p -o key.age")
+                .output(
+                    "Public key: age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
+                ),
+        )
+        .example(
+            Example::new()
+                .text("Change the passphrase of an encrypted key file")
+                .command("rage-keygen --change-passphrase -o key.age key.age"),
+        )
+        .example(
+            Example::new()
+                .text("Print a paper backup of a key")
+                .command("rage-keygen --mnemonic key.txt"),
+        )
+        .example(
+            Example::new()
+                .text("Restore a key from its paper backup")
+                .command("rage-keygen --from-mnemonic -o key.txt")
+                .output(
+                    "Public key: age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
+                ),
+        )
+        .example(
+            Example::new()
+                .text("Convert an identity file to a recipient")
+                .command("rage-keygen -y key.txt")
+                .output("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"),
+        )
         .render();
 
     generate_manpage(page, "rage-keygen");
@@ -183,18 +370,27 @@ fn rage_mount_page() {
                 .long("--version")
                 .help("Display version info and exit."),
         )
-        .flag(
-            Flag::new()
-                .short("-t")
-                .long("--types")
-                .help("The type of the filesystem (one of \"tar\", \"zip\")."),
-        )
+        .flag(Flag::new().short("-t").long("--types").help(
+            "The type of the filesystem (one of \"tar\", \"tar.gz\", \"tar.zst\", \
+            \"tar.xz\", \"zip\"). If not given, it is detected from the decrypted \
+            contents.",
+        ))
+        .flag(Flag::new().long("--read-write").help(
+            "Allow the filesystem to be modified. When it is unmounted, the changes are \
+            written to a new archive, which is encrypted to the recipients of the \
+            identities (or the same passphrase) and replaces the mounted file.",
+        ))
         .option(
             Opt::new("IDENTITY")
                 .short("-i")
                 .long("--identity")
                 .help("Use the private key file at IDENTITY. May be repeated."),
         )
+        .option(
+            Opt::new("PLUGIN-NAME")
+                .short("-j")
+                .help("Use age-plugin-PLUGIN-NAME in its default mode as an identity."),
+        )
         .arg(Arg::new("filename"))
         .arg(Arg::new("mountpoint"))
         .example(
@@ -208,6 +404,26 @@ fn rage_mount_page() {
                 .command("rage-mount -t zip encrypted.zip.age ./tmp")
                 .output("Type passphrase:"),
         )
+        .example(
+            Example::new()
+                .text("Mounting a compressed archive")
+                .command("rage-mount -t tar.gz -i key.txt encrypted.tar.gz.age ./tmp"),
+        )
+        .example(
+            Example::new()
+                .text("Mounting an archive of a type detected from its contents")
+                .command("rage-mount -i key.txt encrypted.age ./tmp"),
+        )
+        .example(
+            Example::new()
+                .text("Mounting an archive encrypted to a plugin, such as a hardware token")
+                .command("rage-mount -t tar -j yubikey encrypted.tar.age ./tmp"),
+        )
+        .example(
+            Example::new()
+                .text("Editing an archive, and re-encrypting it when unmounted")
+                .command("rage-mount --read-write -t tar -i key.txt encrypted.tar.age ./tmp"),
+        )
         .render();
 
     generate_manpage(page, "rage-mount");
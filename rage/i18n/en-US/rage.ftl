@@ -16,13 +16,30 @@
 -flag-armor = -a/--armor
 -flag-decrypt = -d/--decrypt
 -flag-encrypt = -e/--encrypt
+-flag-rekey = --rekey
+-flag-verify = --verify
+-flag-recover = --recover
+-flag-recursive = --recursive
+-flag-in-place = --in-place
+-flag-inspect = --inspect
+-flag-who-can-decrypt = --who-can-decrypt
+-flag-json = --json
+-flag-progress = --progress
+-flag-no-config = --no-config
 -flag-identity = -i/--identity
+-flag-output = -o/--output
 -flag-recipient = -r/--recipient
 -flag-recipients-file = -R/--recipients-file
 -flag-passphrase = -p/--passphrase
 -flag-plugin-name = -j
 -flag-max-work-factor = --max-work-factor
+-flag-pq = --pq
+-flag-convert = -y
+-flag-change-passphrase = --change-passphrase
+-flag-mnemonic = --mnemonic
+-flag-from-mnemonic = --from-mnemonic
 -flag-unstable = --features unstable
+-flag-feature-pq = --features pq
 
 ## Usage
 
@@ -38,6 +55,7 @@ rage-usage =
     {usage-header}
     {"  "}{$usage_a}
     {"  "}{$usage_b}
+    {"  "}{$usage_c}
 
     {$flags}
 
@@ -46,6 +64,8 @@ rage-usage =
     {-recipient} can be:
     - An {-age} public key, as generated by {$keygen_name} ("age1...").
     - An SSH public key ("ssh-ed25519 AAAA...", "ssh-rsa AAAA...").
+    - A post-quantum {-age} public key, as generated by {$keygen_name} {-flag-pq}
+      ("ragepq1..."), if {-rage} was built with {-flag-feature-pq}.
 
     {-recipients-file} is a path to a file containing {-age} recipients, one per line
     (ignoring "#" prefixed comments and empty lines).
@@ -55,6 +75,50 @@ rage-usage =
     Passphrase-encrypted {-age} identity files can be used as identity files.
     Multiple identities may be provided, and any unused ones will be ignored.
 
+    {-flag-rekey} decrypts the header of {-input} with the given identities (or
+    passphrase), and writes it to {-output} encrypted to the given recipients (or a
+    new passphrase). The payload is copied without being re-encrypted.
+
+    {-flag-decrypt} {-flag-verify} checks that every chunk of {-input} decrypts,
+    without writing any output.
+
+    {-flag-decrypt} {-flag-recover} writes every chunk of a damaged {-input} that
+    decrypts to {-output}, skipping the chunks that don't, and reports the damaged
+    chunks.
+
+    {-flag-recursive} encrypts every file in the directory {-input} to the same path
+    (with an .age suffix) in the directory {-output}. With {-flag-decrypt}, it
+    decrypts every .age file in {-input}, removing the suffix.
+
+    {-flag-in-place} writes the result to a temporary file next to {-input}, and only
+    replaces {-input} with it (keeping its permissions) once it has been written
+    successfully. {-input} is left untouched if anything fails or is cancelled.
+
+    {-flag-inspect} prints the structure of {-input} (its header, recipient stanzas,
+    and payload layout) without decrypting it, or as JSON with {-flag-json}. Sizes
+    are those of the binary age file, even if {-input} is armored.
+
+    {-flag-who-can-decrypt} reports, for each recipient given with {-flag-recipient}
+    or {-flag-recipients-file}, whether {-input} is encrypted to it. This is
+    definitive for SSH recipients, but "unknown" for other recipient types if
+    {-input} contains stanzas that don't identify their recipient.
+
+    {-flag-progress} shows a progress bar on standard error while encrypting or
+    decrypting, if it is a terminal. The total size is only known when {-input} is a
+    file (and, when decrypting, is not armored).
+
+    If neither {-flag-identity} nor {-flag-plugin-name} is given, {-flag-decrypt} uses
+    the identity files in the config file, or else the identities in the default
+    locations ~/.config/age/keys.txt, ~/.ssh/id_ed25519, and ~/.ssh/id_rsa that
+    might be able to decrypt {-input}. Run with RUST_LOG=info to see which one did.
+
+    Defaults are read from the config file $XDG_CONFIG_HOME/rage/config.toml
+    (%APPDATA%\rage\config.toml on Windows), unless {-flag-no-config} is given. It
+    can list identity files to decrypt with when {-flag-identity} isn't given
+    (identities = ["~/keys.txt"]), set armor = true and max-work-factor, and define
+    recipient aliases in a [recipients] table (ops = ["age1...", "@alice"]) that
+    can be used as {-flag-recipient} @ops.
+
     Example:
     {"  "}{$example_a}
     {"  "}{tty-pubkey}: {$example_a_output}
@@ -71,6 +135,22 @@ tty-pubkey = Public key
 identity-file-created = created
 identity-file-pubkey = public key
 
+keygen-current-passphrase = Type the current passphrase for {$filename}
+keygen-type-mnemonic = Type the 24-word mnemonic of the identity to restore
+prompt-mnemonic = Mnemonic
+
+err-keygen-unexpected-input = Input files can only be given with {-flag-convert}, {-flag-change-passphrase}, or {-flag-mnemonic}.
+err-keygen-mixed-convert-pq = {-flag-convert} can't be used with {-flag-pq}.
+err-keygen-mixed-convert-passphrase = {-flag-convert} can't be used with {-flag-passphrase} or {-flag-change-passphrase}.
+err-keygen-mixed-change-passphrase-generate = {-flag-change-passphrase} can't be used with {-flag-passphrase} or {-flag-pq}.
+err-keygen-mixed-mnemonic = {-flag-mnemonic} can't be used with {-flag-convert}, {-flag-change-passphrase}, {-flag-from-mnemonic}, {-flag-passphrase}, or {-flag-pq}.
+err-keygen-mixed-from-mnemonic = {-flag-from-mnemonic} can't be used with {-flag-convert}, {-flag-change-passphrase}, or {-flag-pq}.
+err-keygen-change-passphrase-multiple-inputs = {-flag-change-passphrase} takes a single identity file.
+err-keygen-mnemonic-multiple-inputs = {-flag-mnemonic} takes a single identity file.
+err-keygen-identity-not-encrypted = {$filename} is not a passphrase-encrypted identity file.
+err-keygen-mnemonic-unsupported-identity = {$filename} must contain a single native {-age} identity to be encoded as a mnemonic.
+err-keygen-plugin-identity = {$filename} contains a plugin identity, which can't be converted to a recipient.
+
 ## Encryption messages
 
 autogenerated-passphrase = Using an autogenerated passphrase:
@@ -79,14 +159,45 @@ prompt-passphrase = Passphrase
 
 warn-double-encrypting = Encrypting an already-encrypted file
 
+## Progress messages
+
+progress-with-total = {$percent}  {$bytes} / {$total}  {$throughput}  ETA {$eta}
+progress-without-total = {$bytes}  {$throughput}
+
+## Config errors
+
+err-config-read = Failed to read config file {$path}: {$err}
+err-config-parse = Failed to parse config file {$path}: {$err}
+rec-config = Fix the config file, or use {-flag-no-config} to ignore it.
+err-config-alias-cycle = Recipient alias {$alias} refers to itself.
+err-config-unknown-alias = Unknown recipient alias {$alias}.
+rec-config-unknown-alias = Define it in the [recipients] table of your config file.
+
 ## General errors
 
 err-failed-to-open-output = Failed to open output: {$err}
 err-failed-to-write-output = Failed to write to output: {$err}
-err-identity-ambiguous = {-flag-identity} requires either {-flag-encrypt} or {-flag-decrypt}.
+err-identity-ambiguous = {-flag-identity} requires either {-flag-encrypt}, {-flag-decrypt}, or {-flag-rekey}.
+err-in-place-unsupported-mode = {-flag-in-place} can't be used with {-flag-recursive}, {-flag-verify}, or {-flag-recover}.
+err-in-place-without-input = {-flag-in-place} requires an input file.
+err-inspect-unsupported-mode = {-flag-inspect} and {-flag-who-can-decrypt} can't be used with {-flag-encrypt}, {-flag-decrypt}, {-flag-rekey}, {-flag-recursive}, or {-flag-in-place}.
+err-json-without-inspect = {-flag-json} requires {-flag-inspect} or {-flag-who-can-decrypt}.
 err-mixed-encrypt-decrypt = {-flag-encrypt} can't be used with {-flag-decrypt}.
+err-mixed-in-place-output = {-flag-in-place} can't be used with {-flag-output}.
+err-mixed-inspect-who-can-decrypt = {-flag-inspect} can't be used with {-flag-who-can-decrypt}.
+err-mixed-rekey = {-flag-rekey} can't be used with {-flag-encrypt} or {-flag-decrypt}.
 err-passphrase-timed-out = Timed out waiting for passphrase input.
+err-recover-without-decrypt = {-flag-recover} requires {-flag-decrypt}.
 err-same-input-and-output = Input and output are the same file '{$filename}'.
+err-verify-without-decrypt = {-flag-verify} requires {-flag-decrypt}.
+
+err-recursive-unsupported-mode = {-flag-recursive} can't be used with {-flag-rekey}, {-flag-verify}, or {-flag-recover}.
+err-recursive-without-input = {-flag-recursive} requires an input directory.
+err-recursive-without-output = {-flag-recursive} requires an output directory, given with {-flag-output}.
+err-recursive-input-not-directory = '{$filename}' is not a directory.
+err-recursive-non-utf8-path = Path '{$filename}' is not valid UTF-8.
+err-recursive-encrypt-failures = Failed to encrypt {$count} of {$total} files:
+err-recursive-decrypt-failures = Failed to decrypt {$count} of {$total} files:
 
 err-ux-A = Did {-rage} not do what you expected? Could an error be more useful?
 err-ux-B = Tell us
@@ -128,7 +239,10 @@ err-dec-identity-encrypted-without-passphrase =
 err-dec-identity-not-found = Identity file not found: {$filename}
 
 err-dec-missing-identities = Missing identities.
-rec-dec-missing-identities = Did you forget to specify {-flag-identity}?
+rec-dec-missing-identities =
+    Did you forget to specify {-flag-identity}? No identities in your config file or
+    default locations (such as ~/.config/age/keys.txt or ~/.ssh/id_ed25519) can
+    decrypt this file.
 
 err-dec-mixed-identity-passphrase = {-flag-identity} can't be used with passphrase-encrypted files.
 
@@ -146,17 +260,76 @@ err-dec-recipient-flag = {-flag-recipient} can't be used with {-flag-decrypt}.
 err-dec-recipients-file-flag = {-flag-recipients-file} can't be used with {-flag-decrypt}.
 rec-dec-recipient-flag = Did you mean to use {-flag-identity} to specify a private key?
 
+err-dec-verify-output-flag = {-flag-verify} can't be used with {-flag-output}.
+err-dec-mixed-verify-recover = {-flag-verify} can't be used with {-flag-recover}.
+
+err-dec-recover-damaged = {$count} damaged ranges could not be recovered.
+rec-dec-recover-damaged = The chunks that could be recovered were written to the output.
+
+## Default identity messages
+
+debug-default-identity-skipped = Skipping default identity {$filename}, which can't decrypt any stanza in the header
+warn-default-identity-unreadable = Skipping default identity {$filename}: {$err}
+info-default-identity-used = Decrypted with default identity {$filename}
+
+## Recursive messages
+
+recursive-skip-not-file = Skipping '{$filename}', which is not a regular file or directory.
+recursive-skip-not-encrypted = Skipping '{$filename}', which doesn't end in .age.
+
+## Inspect messages
+
+inspect-version = Version: {$version}
+inspect-format-armored = Format: ASCII armor
+inspect-format-binary = Format: binary
+inspect-header-size = Header size: {$size} bytes
+inspect-stanzas = Recipient stanzas: {$count}
+inspect-stanza-x25519 = X25519 (ephemeral share {$share})
+inspect-stanza-ssh = {$stanza_type} (key tag {$key_tag})
+inspect-stanza-scrypt = scrypt (work factor {$work_factor})
+inspect-stanza-other = {$stanza_type} (plugin or unknown recipient type) {$args}
+inspect-payload-size = Payload size: {$size} bytes
+inspect-chunks = Chunks: {$count}
+inspect-final-chunk-well-formed = Final chunk: well-formed
+inspect-final-chunk-malformed = Final chunk: malformed (the file is truncated or damaged)
+
+who-can-decrypt-found = {$recipient}: yes
+who-can-decrypt-not-found = {$recipient}: no
+who-can-decrypt-unknown = {$recipient}: unknown
+
+## Recovery messages
+
+recover-damage = Chunk {$index} could not be recovered ({$len} bytes at offset {$offset}, plaintext offset {$plaintext_offset}): {$reason}
+recover-damage-tag-mismatch = the chunk failed to authenticate
+recover-damage-truncated = the file is truncated
+recover-damage-missing-last-chunk = the last chunk is missing
+recover-damage-trailing-data = there is data after the last chunk
+
 ## rage-mount strings
 
 -flag-mnt-types = -t/--types
+-flag-mnt-read-write = --read-write
 
 info-decrypting = Decrypting {$filename}
+info-detected-type = Detected filesystem type "{$fs_type}"
 info-mounting-as-fuse = Mounting as FUSE filesystem
+info-unmodified = Filesystem was not modified
+info-reencrypting = Re-encrypting {$filename}
 
 err-mnt-missing-filename = Missing filename.
 err-mnt-missing-mountpoint = Missing mountpoint.
-err-mnt-missing-types = Missing {-flag-mnt-types}.
+err-mnt-undetected-type = Could not detect the filesystem type.
 err-mnt-unknown-type = Unknown filesystem type "{$fs_type}"
+rec-mnt-types = Specify one of the supported types with {-flag-mnt-types}: {$types}
+err-mnt-rw-unsupported-entries =
+    The archive contains entries that can't be mounted, and would be lost if it
+    was modified. Mount it without {-flag-mnt-read-write} instead.
+err-mnt-rw-saved = The modified filesystem was saved to {$saved} instead.
+
+warn-mnt-rw-recipients =
+    When unmounted, {$filename} will only be re-encrypted to the recipients of the
+    given identities. Any other recipients it is currently encrypted to will lose
+    access to it.
 
 ## Unstable features
 
@@ -136,7 +136,6 @@ info-mounting-as-fuse = Montando como sistema de archivos FUSE
 
 err-mnt-missing-filename = Falta el nombre de archivo.
 err-mnt-missing-mountpoint = Falta el punto de montaje.
-err-mnt-missing-types = Falta {-flag-mnt-types}.
 err-mnt-unknown-type = Tipo de sistema de archivos desconocido "{$fs_type}"
 
 ## Unstable features
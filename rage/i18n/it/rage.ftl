@@ -157,7 +157,6 @@ info-mounting-as-fuse = Montando come filesystem FUSE
 
 err-mnt-missing-filename = Nome del file mancante.
 err-mnt-missing-mountpoint = Punto di montaggio mancante.
-err-mnt-missing-types = {-flag-mnt-types} mancante.
 err-mnt-unknown-type = Tipo di filesystem sconosciuto "{$fs_type}"
 
 ## Unstable features
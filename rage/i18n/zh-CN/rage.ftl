@@ -133,7 +133,6 @@ info-mounting-as-fuse = 正挂载为 FUSE 文件系统
 
 err-mnt-missing-filename = 缺少文件名。
 err-mnt-missing-mountpoint = 缺少挂载点。
-err-mnt-missing-types = 缺少 {-flag-mnt-types} 。
 err-mnt-unknown-type = 未知的文件系统类型 "{$fs_type}"
 
 ## Unstable features
@@ -133,7 +133,6 @@ info-mounting-as-fuse = 正掛載為 FUSE 文件系統
 
 err-mnt-missing-filename = 缺少文件名。
 err-mnt-missing-mountpoint = 缺少掛載點。
-err-mnt-missing-types = 缺少 {-flag-mnt-types} 。
 err-mnt-unknown-type = 未知的文件系統類型 "{$fs_type}"
 
 ## Unstable features
@@ -0,0 +1,157 @@
+use i18n_embed_fl::fl;
+use std::fmt;
+use std::io;
+
+macro_rules! wlnfl {
+    ($f:ident, $message_id:literal) => {
+        writeln!($f, "{}", $crate::fl!($message_id))
+    };
+}
+
+pub(crate) enum Error {
+    Decryption(age::DecryptError),
+    Encryption(age::EncryptError),
+    FailedToOpenOutput(io::Error),
+    FailedToWriteOutput(io::Error),
+    IdentityEncryptedWithoutPassphrase(String),
+    IdentityNotEncrypted(String),
+    IdentityNotFound(String),
+    InvalidMnemonic(age::x25519::MnemonicError),
+    Io(io::Error),
+    MixedChangePassphraseAndGenerate,
+    MixedConvertAndPassphrase,
+    MixedConvertAndPq,
+    MixedFromMnemonic,
+    MixedMnemonic,
+    MnemonicUnsupportedIdentity(String),
+    MultipleChangePassphraseInputs,
+    MultipleMnemonicInputs,
+    PassphraseTimedOut,
+    PluginIdentity(String),
+    #[cfg(feature = "ssh")]
+    UnsupportedKey(String, age::ssh::UnsupportedKey),
+    UnexpectedInput,
+}
+
+impl From<age::DecryptError> for Error {
+    fn from(e: age::DecryptError) -> Self {
+        Error::Decryption(e)
+    }
+}
+
+impl From<age::EncryptError> for Error {
+    fn from(e: age::EncryptError) -> Self {
+        Error::Encryption(e)
+    }
+}
+
+impl From<age::x25519::MnemonicError> for Error {
+    fn from(e: age::x25519::MnemonicError) -> Self {
+        Error::InvalidMnemonic(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+// Rust only supports `fn main() -> Result<(), E: Debug>`, so we implement `Debug`
+// manually to provide the error output we want.
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decryption(e) => writeln!(f, "{}", e)?,
+            Error::Encryption(e) => writeln!(f, "{}", e)?,
+            Error::FailedToOpenOutput(e) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-failed-to-open-output",
+                    err = e.to_string()
+                )
+            )?,
+            Error::FailedToWriteOutput(e) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-failed-to-write-output",
+                    err = e.to_string()
+                )
+            )?,
+            Error::IdentityEncryptedWithoutPassphrase(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-dec-identity-encrypted-without-passphrase",
+                    filename = filename.as_str()
+                )
+            )?,
+            Error::IdentityNotEncrypted(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-keygen-identity-not-encrypted",
+                    filename = filename.as_str()
+                )
+            )?,
+            Error::IdentityNotFound(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-dec-identity-not-found",
+                    filename = filename.as_str()
+                )
+            )?,
+            Error::InvalidMnemonic(e) => writeln!(f, "{}", e)?,
+            Error::Io(e) => writeln!(f, "{}", e)?,
+            Error::MixedChangePassphraseAndGenerate => {
+                wlnfl!(f, "err-keygen-mixed-change-passphrase-generate")?
+            }
+            Error::MixedConvertAndPassphrase => wlnfl!(f, "err-keygen-mixed-convert-passphrase")?,
+            Error::MixedConvertAndPq => wlnfl!(f, "err-keygen-mixed-convert-pq")?,
+            Error::MixedFromMnemonic => wlnfl!(f, "err-keygen-mixed-from-mnemonic")?,
+            Error::MixedMnemonic => wlnfl!(f, "err-keygen-mixed-mnemonic")?,
+            Error::MnemonicUnsupportedIdentity(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-keygen-mnemonic-unsupported-identity",
+                    filename = filename.as_str()
+                )
+            )?,
+            Error::MultipleChangePassphraseInputs => {
+                wlnfl!(f, "err-keygen-change-passphrase-multiple-inputs")?
+            }
+            Error::MultipleMnemonicInputs => wlnfl!(f, "err-keygen-mnemonic-multiple-inputs")?,
+            Error::PassphraseTimedOut => wlnfl!(f, "err-passphrase-timed-out")?,
+            Error::PluginIdentity(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-keygen-plugin-identity",
+                    filename = filename.as_str()
+                )
+            )?,
+            #[cfg(feature = "ssh")]
+            Error::UnsupportedKey(filename, k) => k.display(f, Some(filename.as_str()))?,
+            Error::UnexpectedInput => wlnfl!(f, "err-keygen-unexpected-input")?,
+        }
+        writeln!(f)?;
+        writeln!(f, "[ {} ]", crate::fl!("err-ux-A"))?;
+        write!(
+            f,
+            "[ {}: https://str4d.xyz/rage/report {} ]",
+            crate::fl!("err-ux-B"),
+            crate::fl!("err-ux-C")
+        )
+    }
+}
@@ -1,15 +1,21 @@
 #![forbid(unsafe_code)]
 
-use age::{cli_common::file_io, secrecy::ExposeSecret};
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    cli_common::{file_io, read_or_generate_passphrase, read_secret, Passphrase, UiCallbacks},
+    secrecy::{ExposeSecret, SecretString, SecretVec},
+    IdentityFile, IdentityFileEntry,
+};
 use gumdrop::Options;
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
     DesktopLanguageRequester,
 };
 use lazy_static::lazy_static;
-use log::error;
 use rust_embed::RustEmbed;
-use std::io::Write;
+use std::io::{self, Read, Write};
+
+mod error;
 
 #[derive(RustEmbed)]
 #[folder = "i18n"]
@@ -21,6 +27,7 @@ lazy_static! {
     static ref LANGUAGE_LOADER: FluentLanguageLoader = fluent_language_loader!();
 }
 
+#[macro_export]
 macro_rules! fl {
     ($message_id:literal) => {{
         i18n_embed_fl::fl!($crate::LANGUAGE_LOADER, $message_id)
@@ -29,6 +36,12 @@ macro_rules! fl {
 
 #[derive(Debug, Options)]
 struct AgeOptions {
+    #[options(
+        free,
+        help = "Identity files to convert with -y, to re-encrypt with --change-passphrase, or to encode with --mnemonic."
+    )]
+    input: Vec<String>,
+
     #[options(help = "Print this help message and exit.")]
     help: bool,
 
@@ -37,9 +50,384 @@ struct AgeOptions {
 
     #[options(help = "Write the result to the file at path OUTPUT. Defaults to standard output.")]
     output: Option<String>,
+
+    #[cfg(feature = "pq")]
+    #[options(
+        help = "Generate a post-quantum hybrid (ML-KEM-768 + X25519) key pair.",
+        no_short
+    )]
+    pq: bool,
+
+    #[options(help = "Encrypt the generated identity file with a passphrase.")]
+    passphrase: bool,
+
+    #[options(
+        help = "Change the passphrase of a passphrase-encrypted identity file.",
+        no_short
+    )]
+    change_passphrase: bool,
+
+    #[options(
+        help = "Print the 24-word mnemonic backup of an identity file.",
+        no_short
+    )]
+    mnemonic: bool,
+
+    #[options(
+        help = "Restore an identity file from its 24-word mnemonic backup.",
+        no_short
+    )]
+    from_mnemonic: bool,
+
+    #[options(
+        help = "Convert identity files to a list of recipients, one per line.",
+        short = "y",
+        no_long
+    )]
+    convert: bool,
+}
+
+impl AgeOptions {
+    /// Returns whether `--pq` was given, which is only an option when built with the
+    /// `pq` feature flag.
+    fn pq(&self) -> bool {
+        #[cfg(feature = "pq")]
+        {
+            self.pq
+        }
+
+        #[cfg(not(feature = "pq"))]
+        false
+    }
+}
+
+/// Generates a new identity, returning its encoding and the encoding of its recipient.
+#[allow(unused_variables)]
+fn generate(pq: bool) -> (SecretString, String) {
+    #[cfg(feature = "pq")]
+    if pq {
+        let sk = age::pq::Identity::generate();
+        return (sk.to_string(), sk.to_public().to_string());
+    }
+
+    let sk = age::x25519::Identity::generate();
+    (sk.to_string(), sk.to_public().to_string())
 }
 
-fn main() {
+/// Asks the user for a mnemonic, returning the encoding of the identity it restores and
+/// the encoding of its recipient.
+///
+/// Returns `Ok(None)` if the user cancelled the prompt.
+fn restore() -> Result<Option<(SecretString, String)>, error::Error> {
+    let mnemonic = match passphrase_result(read_secret(
+        &fl!("keygen-type-mnemonic"),
+        &fl!("prompt-mnemonic"),
+        None,
+    ))? {
+        Some(mnemonic) => mnemonic,
+        None => return Ok(None),
+    };
+
+    let sk = age::x25519::Identity::from_mnemonic(mnemonic.expose_secret())?;
+    Ok(Some((sk.to_string(), sk.to_public().to_string())))
+}
+
+/// Reads the file at `filename` (or standard input), returning its name and contents.
+///
+/// Identity files are small, so we read the whole file in order to try parsing it in
+/// several different ways.
+fn read_input(filename: Option<String>) -> Result<(String, Vec<u8>), error::Error> {
+    let name = filename.clone().unwrap_or_else(|| "-".to_owned());
+
+    let mut data = vec![];
+    file_io::InputReader::new(filename)
+        .and_then(|mut input| input.read_to_end(&mut data))
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => error::Error::IdentityNotFound(name.clone()),
+            _ => e.into(),
+        })?;
+
+    Ok((name, data))
+}
+
+/// Parses the age identities in `data`, which may be a passphrase-encrypted identity
+/// file.
+fn age_identities(name: &str, data: &[u8]) -> Result<Vec<IdentityFileEntry>, error::Error> {
+    // Try parsing as an encrypted age identity.
+    if let Ok(identity) = age::encrypted::Identity::from_buffer(
+        ArmoredReader::new(data),
+        Some(name.to_owned()),
+        UiCallbacks,
+        None,
+    ) {
+        return match identity {
+            Some(identity) => Ok(identity.identities()?),
+            None => Err(error::Error::IdentityEncryptedWithoutPassphrase(
+                name.to_owned(),
+            )),
+        };
+    }
+
+    // Try parsing as multiple single-line age identities.
+    Ok(IdentityFile::from_buffer(data)?.into_identities())
+}
+
+/// Returns the encodings of the recipients corresponding to the given identities.
+fn entry_recipients(
+    filename: &str,
+    entries: Vec<IdentityFileEntry>,
+) -> Result<Vec<String>, error::Error> {
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            IdentityFileEntry::Native(sk) => Ok(sk.to_public().to_string()),
+            #[cfg(feature = "pq")]
+            IdentityFileEntry::PostQuantum(sk) => Ok(sk.to_public().to_string()),
+            // The plugin protocol has no way to derive a recipient from an identity.
+            IdentityFileEntry::Plugin(_) => Err(error::Error::PluginIdentity(filename.to_owned())),
+        })
+        .collect()
+}
+
+/// Reads an identity file, returning the encodings of the recipients for the identities
+/// it contains.
+fn identity_file_recipients(filename: Option<String>) -> Result<Vec<String>, error::Error> {
+    let (name, data) = read_input(filename)?;
+
+    // Try parsing as a single multi-line SSH identity.
+    #[cfg(feature = "ssh")]
+    match age::ssh::Identity::from_buffer(&data[..], Some(name.clone())) {
+        Ok(age::ssh::Identity::Unsupported(k)) => {
+            return Err(error::Error::UnsupportedKey(name, k))
+        }
+        Ok(identity) => {
+            if let Ok(recipient) = age::ssh::Recipient::try_from(identity) {
+                return Ok(vec![recipient.to_string()]);
+            }
+        }
+        Err(_) => (),
+    }
+
+    entry_recipients(&name, age_identities(&name, &data)?)
+}
+
+/// Writes the recipients for the given identity files (or standard input) to `output`.
+fn convert(filenames: Vec<String>, output: Option<String>) -> Result<(), error::Error> {
+    let filenames = if filenames.is_empty() {
+        vec![None]
+    } else {
+        filenames.into_iter().map(Some).collect()
+    };
+
+    let mut recipients = vec![];
+    for filename in filenames {
+        recipients.extend(identity_file_recipients(filename)?);
+    }
+
+    let mut output = file_io::OutputWriter::new(output, file_io::OutputFormat::Text, 0o666, false)
+        .map_err(error::Error::FailedToOpenOutput)?;
+
+    recipients
+        .iter()
+        .try_for_each(|recipient| writeln!(output, "{}", recipient))
+        .map_err(error::Error::FailedToWriteOutput)
+}
+
+/// Converts the result of a passphrase prompt into our error type.
+///
+/// Returns `Ok(None)` if the user cancelled the prompt.
+fn passphrase_result<T>(res: pinentry::Result<T>) -> Result<Option<T>, error::Error> {
+    match res {
+        Ok(passphrase) => Ok(Some(passphrase)),
+        Err(pinentry::Error::Cancelled) => Ok(None),
+        Err(pinentry::Error::Timeout) => Err(error::Error::PassphraseTimedOut),
+        Err(pinentry::Error::Encoding(e)) => {
+            // Pretend it is an I/O error
+            Err(io::Error::new(io::ErrorKind::InvalidData, e).into())
+        }
+        Err(pinentry::Error::Gpg(e)) => {
+            // Pretend it is an I/O error
+            Err(io::Error::new(io::ErrorKind::Other, format!("{}", e)).into())
+        }
+        Err(pinentry::Error::Io(e)) => Err(e.into()),
+    }
+}
+
+/// Asks the user for a new passphrase, generating one if they don't provide it.
+///
+/// Returns `Ok(None)` if the user cancelled the prompt.
+fn read_new_passphrase() -> Result<Option<SecretString>, error::Error> {
+    Ok(
+        passphrase_result(read_or_generate_passphrase())?.map(|passphrase| match passphrase {
+            Passphrase::Typed(passphrase) => passphrase,
+            Passphrase::Generated(new_passphrase) => {
+                eprintln!("{}", fl!("autogenerated-passphrase"));
+                eprintln!("    {}", new_passphrase.expose_secret());
+                new_passphrase
+            }
+        }),
+    )
+}
+
+/// Writes an identity file, encrypted with `passphrase`, to `output`.
+///
+/// The identity file contents are written by `f`.
+fn write_encrypted(
+    output: file_io::OutputWriter,
+    passphrase: SecretString,
+    f: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), error::Error> {
+    let output = ArmoredWriter::wrap_output(output, Format::AsciiArmor)
+        .map_err(error::Error::FailedToWriteOutput)?;
+    let mut writer = age::Encryptor::with_user_passphrase(passphrase).wrap_output(output)?;
+
+    f(&mut writer)
+        .and_then(|()| writer.finish())
+        .and_then(|armor| armor.finish())
+        .and_then(|output| output.commit())
+        .map_err(error::Error::FailedToWriteOutput)
+}
+
+/// Writes a new identity file containing `sk` to `output`.
+fn write_identity(output: &mut dyn Write, sk: &SecretString, pk: &str) -> io::Result<()> {
+    writeln!(
+        output,
+        "# {}: {}",
+        fl!("identity-file-created"),
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    )?;
+    writeln!(output, "# {}: {}", fl!("identity-file-pubkey"), pk)?;
+    writeln!(output, "{}", sk.expose_secret())
+}
+
+/// Writes the mnemonic encoding of the identity file at `input` (or standard input) to
+/// `output`.
+fn mnemonic(input: Option<String>, output: Option<String>) -> Result<(), error::Error> {
+    let (name, data) = read_input(input)?;
+
+    let mnemonic = match &age_identities(&name, &data)?[..] {
+        [IdentityFileEntry::Native(sk)] => sk.to_mnemonic(),
+        _ => return Err(error::Error::MnemonicUnsupportedIdentity(name)),
+    };
+
+    let mut output = file_io::OutputWriter::new(output, file_io::OutputFormat::Text, 0o600, false)
+        .map_err(error::Error::FailedToOpenOutput)?;
+
+    writeln!(output, "{}", mnemonic.expose_secret()).map_err(error::Error::FailedToWriteOutput)
+}
+
+/// Generates a new identity (or restores one from its mnemonic) and writes it to
+/// `output`, optionally encrypted with a passphrase.
+fn keygen(
+    output: Option<String>,
+    pq: bool,
+    passphrase: bool,
+    from_mnemonic: bool,
+) -> Result<(), error::Error> {
+    let (sk, pk) = if from_mnemonic {
+        match restore()? {
+            Some(identity) => identity,
+            None => return Ok(()),
+        }
+    } else {
+        generate(pq)
+    };
+
+    let passphrase = if passphrase {
+        match read_new_passphrase()? {
+            Some(passphrase) => Some(passphrase),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+
+    let mut output = file_io::OutputWriter::new(output, file_io::OutputFormat::Text, 0o600, false)
+        .map_err(error::Error::FailedToOpenOutput)?;
+
+    match passphrase {
+        Some(passphrase) => {
+            // The public key comment is inside the encrypted identity file, so we always
+            // print the public key.
+            eprintln!("{}: {}", fl!("tty-pubkey"), pk);
+            write_encrypted(output, passphrase, |w| write_identity(w, &sk, &pk))
+        }
+        None => {
+            if !output.is_terminal() {
+                eprintln!("{}: {}", fl!("tty-pubkey"), pk);
+            }
+            write_identity(&mut output, &sk, &pk).map_err(error::Error::FailedToWriteOutput)
+        }
+    }
+}
+
+/// Decrypts a passphrase-encrypted identity file, checking that it contains identities.
+fn decrypt_identity_file<R: Read>(
+    decryptor: age::decryptor::PassphraseDecryptor<R>,
+    passphrase: &SecretString,
+) -> Result<SecretVec<u8>, error::Error> {
+    let mut plaintext = vec![];
+    decryptor
+        .decrypt(passphrase, None)
+        .map_err(|e| match e {
+            age::DecryptError::DecryptionFailed => age::DecryptError::KeyDecryptionFailed,
+            e => e,
+        })?
+        .read_to_end(&mut plaintext)?;
+    let plaintext = SecretVec::new(plaintext);
+
+    // Only re-encrypt files that actually contain identities.
+    IdentityFile::from_buffer(&plaintext.expose_secret()[..])?;
+
+    Ok(plaintext)
+}
+
+/// Re-encrypts the passphrase-encrypted identity file at `input` (or standard input)
+/// with a new passphrase, and writes it to `output`.
+fn change_passphrase(input: Option<String>, output: Option<String>) -> Result<(), error::Error> {
+    // Read the whole file before opening the output, so that the file can be re-encrypted
+    // in place.
+    let (name, data) = read_input(input)?;
+
+    let decryptor = match age::Decryptor::new(ArmoredReader::new(&data[..])) {
+        Ok(age::Decryptor::Passphrase(decryptor)) => decryptor,
+        _ => return Err(error::Error::IdentityNotEncrypted(name)),
+    };
+
+    let passphrase = match passphrase_result(read_secret(
+        &i18n_embed_fl::fl!(
+            LANGUAGE_LOADER,
+            "keygen-current-passphrase",
+            filename = name.as_str()
+        ),
+        &fl!("prompt-passphrase"),
+        None,
+    ))? {
+        Some(passphrase) => passphrase,
+        None => return Ok(()),
+    };
+
+    let plaintext = decrypt_identity_file(decryptor, &passphrase)?;
+
+    let new_passphrase = match read_new_passphrase()? {
+        Some(passphrase) => passphrase,
+        None => return Ok(()),
+    };
+
+    // Write to a temporary file that only replaces the output once the identity file has
+    // been re-encrypted, so that a failure can't destroy the input when they are the same.
+    let output = match output {
+        Some(filename) if filename != "-" => file_io::OutputWriter::new_in_place(filename, 0o600),
+        output => file_io::OutputWriter::new(output, file_io::OutputFormat::Text, 0o600, false),
+    }
+    .map_err(error::Error::FailedToOpenOutput)?;
+
+    write_encrypted(output, new_passphrase, |w| {
+        w.write_all(plaintext.expose_secret())
+    })
+}
+
+fn main() -> Result<(), error::Error> {
     env_logger::builder()
         .format_timestamp(None)
         .filter_level(log::LevelFilter::Off)
@@ -57,49 +445,112 @@ fn main() {
 
     if opts.version {
         println!("rage-keygen {}", env!("CARGO_PKG_VERSION"));
-        return;
+        return Ok(());
     }
 
-    let mut output =
-        match file_io::OutputWriter::new(opts.output, file_io::OutputFormat::Text, 0o600, false) {
-            Ok(output) => output,
-            Err(e) => {
-                error!(
-                    "{}",
-                    i18n_embed_fl::fl!(
-                        LANGUAGE_LOADER,
-                        "err-failed-to-open-output",
-                        err = e.to_string()
-                    )
-                );
-                return;
-            }
-        };
+    let pq = opts.pq();
 
-    let sk = age::x25519::Identity::generate();
-    let pk = sk.to_public();
+    if opts.from_mnemonic && (opts.convert || opts.change_passphrase || pq) {
+        return Err(error::Error::MixedFromMnemonic);
+    }
 
-    if let Err(e) = (|| {
-        if !output.is_terminal() {
-            eprintln!("{}: {}", fl!("tty-pubkey"), pk);
+    if opts.mnemonic {
+        if opts.convert || opts.change_passphrase || opts.from_mnemonic || opts.passphrase || pq {
+            return Err(error::Error::MixedMnemonic);
         }
+        if opts.input.len() > 1 {
+            return Err(error::Error::MultipleMnemonicInputs);
+        }
+        mnemonic(opts.input.into_iter().next(), opts.output)
+    } else if opts.convert {
+        if pq {
+            return Err(error::Error::MixedConvertAndPq);
+        }
+        if opts.passphrase || opts.change_passphrase {
+            return Err(error::Error::MixedConvertAndPassphrase);
+        }
+        convert(opts.input, opts.output)
+    } else if opts.change_passphrase {
+        if pq || opts.passphrase {
+            return Err(error::Error::MixedChangePassphraseAndGenerate);
+        }
+        if opts.input.len() > 1 {
+            return Err(error::Error::MultipleChangePassphraseInputs);
+        }
+        change_passphrase(opts.input.into_iter().next(), opts.output)
+    } else if !opts.input.is_empty() {
+        Err(error::Error::UnexpectedInput)
+    } else {
+        keygen(opts.output, pq, opts.passphrase, opts.from_mnemonic)
+    }
+}
 
-        writeln!(
-            output,
-            "# {}: {}",
-            fl!("identity-file-created"),
-            chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
-        )?;
-        writeln!(output, "# {}: {}", fl!("identity-file-pubkey"), pk)?;
-        writeln!(output, "{}", sk.to_string().expose_secret())
-    })() {
-        error!(
-            "{}",
-            i18n_embed_fl::fl!(
-                LANGUAGE_LOADER,
-                "err-failed-to-write-output",
-                err = e.to_string()
-            )
+#[cfg(test)]
+mod tests {
+    use age::{
+        armor::ArmoredReader,
+        cli_common::file_io,
+        secrecy::{ExposeSecret, SecretString, SecretVec},
+    };
+    use std::fs;
+    use std::io::{self, Write};
+    use std::path::Path;
+
+    use super::{decrypt_identity_file, write_encrypted};
+    use crate::error;
+
+    const TEST_SK: &str =
+        "AGE-SECRET-KEY-1GQ9778VQXMMJVE8SK7J6VT8UJ4HDQAJUVSFCWCM02D8GEWQ72PVQ2Y5J33";
+
+    fn encrypt(
+        path: &Path,
+        passphrase: &str,
+        f: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    ) -> Result<(), error::Error> {
+        let output =
+            file_io::OutputWriter::new_in_place(path.to_str().unwrap().to_owned(), 0o600).unwrap();
+        write_encrypted(output, SecretString::new(passphrase.to_owned()), f)
+    }
+
+    fn decrypt(path: &Path, passphrase: &str) -> Result<SecretVec<u8>, error::Error> {
+        let data = fs::read(path).unwrap();
+        match age::Decryptor::new(ArmoredReader::new(&data[..])).unwrap() {
+            age::Decryptor::Passphrase(d) => {
+                decrypt_identity_file(d, &SecretString::new(passphrase.to_owned()))
+            }
+            age::Decryptor::Recipients(_) => panic!("identity file is not passphrase-encrypted"),
+        }
+    }
+
+    #[test]
+    fn change_passphrase_in_place() {
+        let dir = std::env::temp_dir().join(format!("rage-keygen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("key.txt.age");
+        let identity = format!("{}\n", TEST_SK);
+
+        encrypt(&path, "old", |w| w.write_all(identity.as_bytes())).unwrap();
+
+        let plaintext = decrypt(&path, "old").unwrap();
+        encrypt(&path, "new", |w| w.write_all(plaintext.expose_secret())).unwrap();
+        assert!(decrypt(&path, "old").is_err());
+        assert_eq!(
+            decrypt(&path, "new").unwrap().expose_secret(),
+            identity.as_bytes()
         );
+
+        // A failed re-encryption leaves the existing identity file untouched.
+        assert!(encrypt(&path, "newer", |w| {
+            w.write_all(b"# partial")?;
+            Err(io::Error::new(io::ErrorKind::Other, "failed"))
+        })
+        .is_err());
+        assert_eq!(
+            decrypt(&path, "new").unwrap().expose_secret(),
+            identity.as_bytes()
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,902 @@
+//! Random access to compressed archives.
+//!
+//! Compressed data can only be decompressed from the start, so [`DecompressingReader`]
+//! periodically saves the state of its decompressor as it reads. Seeking restores the
+//! closest checkpoint before the target, instead of decompressing everything before it
+//! again.
+//!
+//! The state of the zstd and xz decompressors can't be saved, so for those formats the
+//! decompressed data is instead spilled to a temporary file as it is read (see
+//! [`Spill`]), and data before the decompressor's position is read back from there.
+
+use chacha20::{
+    cipher::{NewCipher, StreamCipher, StreamCipherSeek},
+    ChaCha20, Key, Nonce,
+};
+use miniz_oxide::inflate::{
+    core::{decompress, inflate_flags, DecompressorOxide},
+    TINFLStatus,
+};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+/// The amount of decompressed data between checkpoints.
+const CHECKPOINT_INTERVAL: u64 = 4 * 1024 * 1024;
+
+/// The size of the buffer for compressed data read from the underlying reader.
+const INPUT_BUF_SIZE: usize = 64 * 1024;
+
+/// A compression format for tar archives.
+#[derive(Clone, Copy, Debug)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Returns the compression format for the given filesystem type, such as `tar.gz`.
+    pub fn from_tar_type(fs_type: &str) -> Option<Self> {
+        match fs_type {
+            "tar.gz" => Some(Compression::Gzip),
+            "tar.zst" => Some(Compression::Zstd),
+            "tar.xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the state of this format's decoder can be saved anywhere in the
+    /// compressed data.
+    fn can_checkpoint(self) -> bool {
+        matches!(self, Compression::Gzip)
+    }
+
+    fn decoder(self) -> io::Result<Box<dyn Decoder>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(GzipDecoder::new()),
+            Compression::Zstd => Box::new(ZstdDecoder::new()?),
+            Compression::Xz => Box::new(XzDecoder::new()?),
+        })
+    }
+
+    /// Compresses everything written by `f` to `output`.
+    pub fn compress<W: Write>(
+        self,
+        output: W,
+        f: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(output, Default::default());
+                f(&mut encoder)?;
+                encoder.finish().map(|_| ())
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, 0)?;
+                f(&mut encoder)?;
+                encoder.finish().map(|_| ())
+            }
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(output, 6);
+                f(&mut encoder)?;
+                encoder.finish().map(|_| ())
+            }
+        }
+    }
+}
+
+/// A decompressor.
+trait Decoder: Send {
+    /// Decompresses from `input` into `output`, returning the number of bytes of input
+    /// consumed and of output written.
+    ///
+    /// `eof` is `true` if `input` contains the remainder of the compressed data.
+    fn decode(&mut self, input: &[u8], eof: bool, output: &mut [u8]) -> io::Result<(usize, usize)>;
+
+    /// Returns `true` if the compressed data can validly end at this point.
+    fn is_finished(&self) -> bool;
+
+    /// Returns a copy of this decoder's current state, if it can be saved here.
+    fn snapshot(&self) -> io::Result<Option<Box<dyn Decoder>>>;
+}
+
+/// A saved decoder state that decompression can be resumed from.
+struct Checkpoint {
+    /// The offset in the compressed data that `decoder` resumes from.
+    in_pos: u64,
+    /// The offset in the decompressed data that `decoder` resumes from.
+    out_pos: u64,
+    decoder: Box<dyn Decoder>,
+}
+
+/// The decompressed data that has been read so far, kept in an anonymous temporary
+/// file so that it can be read again without decompressing it.
+///
+/// The data is encrypted with an ephemeral key before it is written, so the plaintext
+/// of the archive is never written to disk.
+struct Spill {
+    file: File,
+    key: Key,
+    len: u64,
+}
+
+impl Spill {
+    fn new() -> io::Result<Self> {
+        Ok(Spill {
+            file: tempfile::tempfile()?,
+            key: *Key::from_slice(&rand::random::<[u8; 32]>()),
+            len: 0,
+        })
+    }
+
+    /// Applies the keystream at `offset` to `buf`. The key is only used for this file,
+    /// so the nonce is fixed.
+    fn apply_keystream(&self, offset: u64, buf: &mut [u8]) {
+        let mut cipher = ChaCha20::new(&self.key, &Nonce::default());
+        cipher.seek(offset);
+        cipher.apply_keystream(buf);
+    }
+
+    /// Appends `data`, which must immediately follow the data already spilled.
+    fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut buf = data.to_vec();
+        self.apply_keystream(self.len, &mut buf);
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&buf)?;
+        self.len += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Fills `buf` with the spilled data at `offset`.
+    fn read_exact_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(buf)?;
+        self.apply_keystream(offset, buf);
+        Ok(())
+    }
+}
+
+/// A reader that decompresses its input, and supports seeking within the decompressed
+/// data.
+pub struct DecompressingReader<R> {
+    inner: R,
+    decoder: Box<dyn Decoder>,
+    /// Compressed data read from `inner`; `buf[buf_range]` has not been decoded yet.
+    buf: Vec<u8>,
+    buf_range: Range<usize>,
+    /// Whether `inner` has no more data.
+    eof: bool,
+    /// The offset in the compressed data of the next byte for the decoder.
+    in_pos: u64,
+    /// The offset in the decompressed data of the next byte from the decoder.
+    out_pos: u64,
+    /// The checkpoints taken so far, in order.
+    checkpoints: Vec<Checkpoint>,
+    /// The decompressed data before `out_pos`, if the decoder can't be checkpointed.
+    spill: Option<Spill>,
+    /// The length of the decompressed data, once it is known.
+    len: Option<u64>,
+    /// The offset in the decompressed data that the next read will start at.
+    pos: u64,
+}
+
+impl<R: Read + Seek> DecompressingReader<R> {
+    /// Decompresses `inner`, which must be at the start of the compressed data.
+    pub fn new(inner: R, compression: Compression) -> io::Result<Self> {
+        let decoder = compression.decoder()?;
+        let start = Checkpoint {
+            in_pos: 0,
+            out_pos: 0,
+            decoder: decoder
+                .snapshot()?
+                .expect("decoders can be saved at the start"),
+        };
+        let spill = if compression.can_checkpoint() {
+            None
+        } else {
+            Some(Spill::new()?)
+        };
+
+        Ok(DecompressingReader {
+            inner,
+            decoder,
+            buf: vec![0; INPUT_BUF_SIZE],
+            buf_range: 0..0,
+            eof: false,
+            in_pos: 0,
+            out_pos: 0,
+            checkpoints: vec![start],
+            spill,
+            len: None,
+            pos: 0,
+        })
+    }
+
+    /// Decompresses into `output`, returning the number of bytes written. Returns 0 at
+    /// the end of the decompressed data.
+    fn decode(&mut self, output: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.buf_range.is_empty() && !self.eof {
+                let n = self.inner.read(&mut self.buf)?;
+                self.buf_range = 0..n;
+                self.eof = n == 0;
+            }
+
+            let (consumed, written) =
+                self.decoder
+                    .decode(&self.buf[self.buf_range.clone()], self.eof, output)?;
+            self.buf_range.start += consumed;
+            self.in_pos += consumed as u64;
+            self.out_pos += written as u64;
+            match &mut self.spill {
+                Some(spill) => spill.append(&output[..written])?,
+                None => self.checkpoint()?,
+            }
+
+            if written > 0 {
+                return Ok(written);
+            } else if consumed == 0 && self.buf_range.is_empty() && self.eof {
+                return if self.decoder.is_finished() {
+                    self.len = Some(self.out_pos);
+                    Ok(0)
+                } else {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                };
+            } else if consumed == 0 && !self.buf_range.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Decompressor made no progress",
+                ));
+            }
+        }
+    }
+
+    /// Saves the decoder's state, if we have decompressed far enough past the last
+    /// checkpoint.
+    fn checkpoint(&mut self) -> io::Result<()> {
+        let last = self
+            .checkpoints
+            .last()
+            .expect("there is always a checkpoint");
+        if self.out_pos >= last.out_pos + CHECKPOINT_INTERVAL {
+            if let Some(decoder) = self.decoder.snapshot()? {
+                self.checkpoints.push(Checkpoint {
+                    in_pos: self.in_pos,
+                    out_pos: self.out_pos,
+                    decoder,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the decoder to `self.pos`, restoring a checkpoint if that is closer than
+    /// the decoder's current position. Returns `false` if `self.pos` is past the end of
+    /// the decompressed data.
+    fn reposition(&mut self) -> io::Result<bool> {
+        let i = match self
+            .checkpoints
+            .binary_search_by_key(&self.pos, |c| c.out_pos)
+        {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let checkpoint = &self.checkpoints[i];
+
+        if self.pos < self.out_pos || checkpoint.out_pos > self.out_pos {
+            self.decoder = checkpoint.decoder.snapshot()?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Other, "Checkpoint can't be restored")
+            })?;
+            self.inner.seek(SeekFrom::Start(checkpoint.in_pos))?;
+            self.in_pos = checkpoint.in_pos;
+            self.out_pos = checkpoint.out_pos;
+            self.buf_range = 0..0;
+            self.eof = false;
+        }
+
+        // Discard the decompressed data before the target.
+        let mut scratch = vec![0; INPUT_BUF_SIZE];
+        while self.out_pos < self.pos {
+            let to_skip = usize::min(scratch.len(), (self.pos - self.out_pos) as usize);
+            if self.decode(&mut scratch[..to_skip])? == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Read for DecompressingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(spill) = &mut self.spill {
+            if self.pos < self.out_pos {
+                // We've already decompressed this data.
+                let n = usize::min(buf.len(), (self.out_pos - self.pos) as usize);
+                spill.read_exact_at(self.pos, &mut buf[..n])?;
+                self.pos += n as u64;
+                return Ok(n);
+            }
+        }
+
+        if buf.is_empty() || (self.pos != self.out_pos && !self.reposition()?) {
+            return Ok(0);
+        }
+
+        let n = self.decode(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for DecompressingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.pos = offset;
+                return Ok(offset);
+            }
+            SeekFrom::Current(offset) => (self.pos, offset),
+            SeekFrom::End(offset) => {
+                let len = match self.len {
+                    Some(len) => len,
+                    None => {
+                        // Decompress the remainder to find the length.
+                        self.pos = u64::max(self.pos, self.out_pos);
+                        self.reposition()?;
+                        let mut scratch = vec![0; INPUT_BUF_SIZE];
+                        while self.decode(&mut scratch)? > 0 {}
+                        self.out_pos
+                    }
+                };
+                (len, offset)
+            }
+        };
+
+        let pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+//
+// gzip
+//
+
+/// The size of the DEFLATE window, which is all the decompressed data a DEFLATE stream
+/// can refer back to.
+const DEFLATE_WINDOW_SIZE: usize = 32 * 1024;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_METHOD_DEFLATE: u8 = 8;
+const GZIP_FHCRC: u8 = 0x02;
+const GZIP_FEXTRA: u8 = 0x04;
+const GZIP_FNAME: u8 = 0x08;
+const GZIP_FCOMMENT: u8 = 0x10;
+const GZIP_TRAILER_LEN: usize = 8;
+
+/// Returns the length of the gzip member header at the start of `buf`, or `None` if
+/// `buf` doesn't contain all of it yet.
+fn gzip_header_len(buf: &[u8]) -> io::Result<Option<usize>> {
+    if buf.len() < 10 {
+        return Ok(None);
+    }
+    if buf[..2] != GZIP_MAGIC || buf[2] != GZIP_METHOD_DEFLATE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a gzip-compressed file",
+        ));
+    }
+
+    let flags = buf[3];
+    let mut len = 10;
+    if flags & GZIP_FEXTRA != 0 {
+        match buf.get(len..len + 2) {
+            Some(xlen) => len += 2 + u16::from_le_bytes([xlen[0], xlen[1]]) as usize,
+            None => return Ok(None),
+        }
+    }
+    for flag in [GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            // These fields are zero-terminated.
+            match buf.get(len..).and_then(|b| b.iter().position(|&c| c == 0)) {
+                Some(i) => len += i + 1,
+                None => return Ok(None),
+            }
+        }
+    }
+    if flags & GZIP_FHCRC != 0 {
+        len += 2;
+    }
+
+    Ok(if buf.len() >= len { Some(len) } else { None })
+}
+
+/// The state of a DEFLATE stream within a gzip member.
+#[derive(Clone)]
+struct Inflater {
+    state: Box<DecompressorOxide>,
+    /// The decompressed data, which doubles as the DEFLATE window.
+    window: Box<[u8]>,
+    /// Where the next decompressed data will be written to `window`.
+    window_pos: usize,
+    /// Decompressed data in `window` that hasn't been returned yet.
+    pending: Range<usize>,
+    /// Whether the end of the DEFLATE stream has been reached.
+    finished: bool,
+}
+
+impl Inflater {
+    fn new() -> Self {
+        Inflater {
+            state: Box::new(DecompressorOxide::new()),
+            window: vec![0; DEFLATE_WINDOW_SIZE].into_boxed_slice(),
+            window_pos: 0,
+            pending: 0..0,
+            finished: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum GzipState {
+    /// Reading a member header; contains the header bytes read so far.
+    Header(Vec<u8>),
+    Deflate(Inflater),
+    /// Skipping a member trailer; contains the number of bytes left to skip.
+    Trailer(usize),
+    /// Between members.
+    MemberEnd,
+    /// Ignoring data after the last member, as `gzip` does.
+    TrailingData,
+}
+
+/// A gzip decoder, which can be saved at any point.
+///
+/// We don't check the CRC in each member's trailer, because age already authenticates
+/// the compressed data.
+#[derive(Clone)]
+struct GzipDecoder {
+    state: GzipState,
+}
+
+impl GzipDecoder {
+    fn new() -> Self {
+        GzipDecoder {
+            state: GzipState::Header(vec![]),
+        }
+    }
+}
+
+impl Decoder for GzipDecoder {
+    fn decode(&mut self, input: &[u8], eof: bool, output: &mut [u8]) -> io::Result<(usize, usize)> {
+        let mut consumed = 0;
+        let mut written = 0;
+
+        loop {
+            match &mut self.state {
+                GzipState::Header(header) => {
+                    if consumed == input.len() {
+                        break;
+                    }
+                    header.push(input[consumed]);
+                    consumed += 1;
+                    if gzip_header_len(header)?.is_some() {
+                        self.state = GzipState::Deflate(Inflater::new());
+                    }
+                }
+                GzipState::Deflate(inflater) => {
+                    // Return the data we've already decompressed first.
+                    if !inflater.pending.is_empty() {
+                        if written == output.len() {
+                            break;
+                        }
+                        let n = usize::min(inflater.pending.len(), output.len() - written);
+                        let start = inflater.pending.start;
+                        output[written..written + n]
+                            .copy_from_slice(&inflater.window[start..start + n]);
+                        inflater.pending.start += n;
+                        written += n;
+                        continue;
+                    }
+                    if inflater.finished {
+                        self.state = GzipState::Trailer(GZIP_TRAILER_LEN);
+                        continue;
+                    }
+
+                    let flags = if eof {
+                        0
+                    } else {
+                        inflate_flags::TINFL_FLAG_HAS_MORE_INPUT
+                    };
+                    let (status, in_n, out_n) = decompress(
+                        &mut inflater.state,
+                        &input[consumed..],
+                        &mut inflater.window,
+                        inflater.window_pos,
+                        flags,
+                    );
+                    consumed += in_n;
+                    inflater.pending = inflater.window_pos..inflater.window_pos + out_n;
+                    inflater.window_pos = (inflater.window_pos + out_n) % DEFLATE_WINDOW_SIZE;
+
+                    match status {
+                        TINFLStatus::Done => inflater.finished = true,
+                        TINFLStatus::HasMoreOutput => (),
+                        TINFLStatus::NeedsMoreInput => {
+                            if out_n == 0 {
+                                break;
+                            }
+                        }
+                        TINFLStatus::FailedCannotMakeProgress => {
+                            return Err(io::ErrorKind::UnexpectedEof.into())
+                        }
+                        _ => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Corrupt gzip-compressed data",
+                            ))
+                        }
+                    }
+                }
+                GzipState::Trailer(remaining) => {
+                    if consumed == input.len() {
+                        break;
+                    }
+                    let n = usize::min(*remaining, input.len() - consumed);
+                    consumed += n;
+                    *remaining -= n;
+                    if *remaining == 0 {
+                        self.state = GzipState::MemberEnd;
+                    }
+                }
+                GzipState::MemberEnd => {
+                    if consumed == input.len() {
+                        break;
+                    }
+                    self.state = if input[consumed] == GZIP_MAGIC[0] {
+                        GzipState::Header(vec![])
+                    } else {
+                        GzipState::TrailingData
+                    };
+                }
+                GzipState::TrailingData => {
+                    consumed = input.len();
+                    break;
+                }
+            }
+        }
+
+        Ok((consumed, written))
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.state, GzipState::MemberEnd | GzipState::TrailingData)
+    }
+
+    fn snapshot(&self) -> io::Result<Option<Box<dyn Decoder>>> {
+        Ok(Some(Box::new(self.clone())))
+    }
+}
+
+//
+// zstd
+//
+
+/// A zstd decoder, which can be saved between frames.
+///
+/// The state of the zstd library's decoder can't be copied, and files compressed by
+/// `zstd` usually contain a single frame, so [`DecompressingReader`] spills the
+/// decompressed data instead of taking checkpoints.
+struct ZstdDecoder {
+    inner: zstd::stream::raw::Decoder<'static>,
+    at_frame_start: bool,
+}
+
+impl ZstdDecoder {
+    fn new() -> io::Result<Self> {
+        Ok(ZstdDecoder {
+            inner: zstd::stream::raw::Decoder::new()?,
+            at_frame_start: true,
+        })
+    }
+}
+
+impl Decoder for ZstdDecoder {
+    fn decode(
+        &mut self,
+        input: &[u8],
+        _eof: bool,
+        output: &mut [u8],
+    ) -> io::Result<(usize, usize)> {
+        use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+
+        let mut in_buf = InBuffer::around(input);
+        let mut out_buf = OutBuffer::around(output);
+        let hint = self.inner.run(&mut in_buf, &mut out_buf)?;
+
+        let (consumed, written) = (in_buf.pos(), out_buf.pos());
+        if consumed > 0 || written > 0 {
+            // A hint of zero means that a frame was fully decoded and flushed.
+            self.at_frame_start = hint == 0;
+        }
+        Ok((consumed, written))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.at_frame_start
+    }
+
+    fn snapshot(&self) -> io::Result<Option<Box<dyn Decoder>>> {
+        if self.at_frame_start {
+            Ok(Some(Box::new(ZstdDecoder::new()?)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+//
+// xz
+//
+
+/// An xz decoder, which can be saved between streams.
+///
+/// The state of liblzma's decoder can't be copied, so [`DecompressingReader`] spills
+/// the decompressed data instead of taking checkpoints.
+struct XzDecoder {
+    inner: xz2::stream::Stream,
+    at_stream_start: bool,
+}
+
+impl XzDecoder {
+    fn new() -> io::Result<Self> {
+        Ok(XzDecoder {
+            inner: xz2::stream::Stream::new_stream_decoder(u64::max_value(), 0)?,
+            at_stream_start: true,
+        })
+    }
+}
+
+impl Decoder for XzDecoder {
+    fn decode(
+        &mut self,
+        input: &[u8],
+        _eof: bool,
+        output: &mut [u8],
+    ) -> io::Result<(usize, usize)> {
+        if input.is_empty() && self.at_stream_start {
+            return Ok((0, 0));
+        }
+
+        let (total_in, total_out) = (self.inner.total_in(), self.inner.total_out());
+        let status = self
+            .inner
+            .process(input, output, xz2::stream::Action::Run)?;
+        let consumed = (self.inner.total_in() - total_in) as usize;
+        let written = (self.inner.total_out() - total_out) as usize;
+
+        if let xz2::stream::Status::StreamEnd = status {
+            // Any remaining input is another stream.
+            *self = XzDecoder::new()?;
+        } else if consumed > 0 || written > 0 {
+            self.at_stream_start = false;
+        }
+        Ok((consumed, written))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.at_stream_start
+    }
+
+    fn snapshot(&self) -> io::Result<Option<Box<dyn Decoder>>> {
+        if self.at_stream_start {
+            Ok(Some(Box::new(XzDecoder::new()?)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+    use super::{
+        gzip_header_len, Compression, DecompressingReader, CHECKPOINT_INTERVAL, GZIP_FCOMMENT,
+        GZIP_FEXTRA, GZIP_FHCRC, GZIP_FNAME,
+    };
+
+    /// Returns `len` bytes of data that compresses well, but not trivially.
+    fn data(len: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(len);
+        let mut state = 1u32;
+        while data.len() < len {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            writeln!(data, "{} {}", data.len(), (state >> 16) % 1000).unwrap();
+        }
+        data.truncate(len);
+        data
+    }
+
+    fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+        let mut compressed = vec![];
+        compression
+            .compress(&mut compressed, |output| output.write_all(data))
+            .unwrap();
+        compressed
+    }
+
+    fn open(compressed: &[u8], compression: Compression) -> DecompressingReader<Cursor<Vec<u8>>> {
+        DecompressingReader::new(Cursor::new(compressed.to_vec()), compression).unwrap()
+    }
+
+    /// Reads up to `len` bytes at `offset`.
+    fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: u64) -> Vec<u8> {
+        assert_eq!(reader.seek(SeekFrom::Start(offset)).unwrap(), offset);
+        let mut buf = vec![];
+        reader.take(len).read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    /// Checks reads at offsets in no particular order, including backwards seeks.
+    fn check_seeks<R: Read + Seek>(reader: &mut R, data: &[u8]) {
+        let len = data.len() as u64;
+        for &offset in &[len - 100, 0, len / 2 + 7, 1, len / 3, len - 1, len / 2, len] {
+            let end = usize::min(offset as usize + 1000, data.len());
+            assert!(
+                read_at(reader, offset, 1000) == data[offset as usize..end],
+                "Wrong data at offset {}",
+                offset,
+            );
+        }
+        assert_eq!(read_at(reader, len + 10, 1000), Vec::<u8>::new());
+
+        reader.seek(SeekFrom::Start(len / 2)).unwrap();
+        assert_eq!(reader.seek(SeekFrom::Current(-10)).unwrap(), len / 2 - 10);
+        assert!(reader.seek(SeekFrom::Current(-(len as i64))).is_err());
+    }
+
+    /// Checks that `compressed` decompresses to `data`, both when it is read
+    /// sequentially and when it is read at random, and returns the sequential reader.
+    fn check(
+        compressed: &[u8],
+        compression: Compression,
+        data: &[u8],
+    ) -> DecompressingReader<Cursor<Vec<u8>>> {
+        // Read in small chunks, so that we know where checkpoints will be taken.
+        let mut reader = open(compressed, compression);
+        let mut buf = vec![];
+        let mut chunk = [0; 8 * 1024];
+        loop {
+            match reader.read(&mut chunk).unwrap() {
+                0 => break,
+                n => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+        assert!(buf == data);
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), data.len() as u64);
+        check_seeks(&mut reader, data);
+
+        // Reads can also start anywhere without a sequential read first.
+        let mut fresh = open(compressed, compression);
+        assert_eq!(
+            fresh.seek(SeekFrom::End(-10)).unwrap(),
+            data.len() as u64 - 10
+        );
+        check_seeks(&mut fresh, data);
+
+        reader
+    }
+
+    #[test]
+    fn gzip_header() {
+        let basic = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+        assert_eq!(gzip_header_len(&basic[..9]).unwrap(), None);
+        assert_eq!(gzip_header_len(&basic).unwrap(), Some(10));
+        assert_eq!(
+            gzip_header_len(&[&basic[..], b"data"].concat()).unwrap(),
+            Some(10)
+        );
+
+        let mut full = basic.to_vec();
+        full[3] = GZIP_FEXTRA | GZIP_FNAME | GZIP_FCOMMENT | GZIP_FHCRC;
+        // The extra field can contain zero bytes.
+        full.extend_from_slice(&[3, 0, 1, 0, 2]);
+        full.extend_from_slice(b"name\0comment\0");
+        full.extend_from_slice(&[0xaa, 0xbb]);
+        for i in 0..full.len() {
+            assert_eq!(gzip_header_len(&full[..i]).unwrap(), None);
+        }
+        assert_eq!(gzip_header_len(&full).unwrap(), Some(full.len()));
+        assert_eq!(
+            gzip_header_len(&[&full[..], b"data"].concat()).unwrap(),
+            Some(full.len())
+        );
+
+        let mut not_deflate = basic;
+        not_deflate[2] = 0;
+        assert!(gzip_header_len(&not_deflate).is_err());
+        assert!(gzip_header_len(b"not gzip-compressed").is_err());
+    }
+
+    #[test]
+    fn gzip() {
+        let data = data(2 * CHECKPOINT_INTERVAL as usize + 100_000);
+        let reader = check(
+            &compress(Compression::Gzip, &data),
+            Compression::Gzip,
+            &data,
+        );
+
+        // Reads were served from the checkpoints, which include the DEFLATE window.
+        assert_eq!(reader.checkpoints.len(), 3);
+    }
+
+    #[test]
+    fn gzip_members() {
+        let (first, second) = (data(100_000), data(50_000));
+        let mut compressed = flate2::GzBuilder::new()
+            .filename("archive.tar")
+            .comment("comment")
+            .extra(vec![1, 2, 3])
+            .write(vec![], Default::default());
+        compressed.write_all(&first).unwrap();
+        let mut compressed = compressed.finish().unwrap();
+        compressed.extend_from_slice(&compress(Compression::Gzip, &second));
+        // Data after the last member is ignored, as `gzip` does.
+        compressed.extend_from_slice(&[0; 100]);
+
+        check(&compressed, Compression::Gzip, &[first, second].concat());
+    }
+
+    #[test]
+    fn gzip_truncated() {
+        let data = data(100_000);
+        let compressed = compress(Compression::Gzip, &data);
+
+        for len in [compressed.len() - 4, compressed.len() / 2] {
+            let mut reader = open(&compressed[..len], Compression::Gzip);
+            let err = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    /// Checks a format whose decompressed data is spilled.
+    fn check_spilled(compression: Compression) {
+        let data = data(1024 * 1024);
+        let reader = check(&compress(compression, &data), compression, &data);
+
+        // Earlier data was read back from the spill, without restarting the decoder.
+        assert_eq!(reader.checkpoints.len(), 1);
+        assert_eq!(reader.out_pos, data.len() as u64);
+
+        // Concatenated frames or streams.
+        let (first, second) = (data[..1000].to_vec(), data[1000..].to_vec());
+        let mut compressed = compress(compression, &first);
+        compressed.extend_from_slice(&compress(compression, &second));
+        check(&compressed, compression, &data);
+    }
+
+    #[test]
+    fn zstd() {
+        check_spilled(Compression::Zstd);
+    }
+
+    #[test]
+    fn xz() {
+        check_spilled(Compression::Xz);
+    }
+}
@@ -0,0 +1,126 @@
+//! Detection of the filesystem type of a decrypted archive.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZSTD_MAGIC: &[u8] = b"\x28\xb5\x2f\xfd";
+const XZ_MAGIC: &[u8] = b"\xfd7zXZ\x00";
+
+/// The magic of POSIX (`ustar\0`) and GNU (`ustar  `) tar headers, at offset 257.
+const USTAR_MAGIC: &[u8] = b"ustar";
+const USTAR_MAGIC_OFFSET: usize = 257;
+
+const ZIP_EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+/// The end of central directory record is 22 bytes, followed by a comment of up to
+/// 64 KiB, so it must start within this many bytes of the end of a ZIP archive.
+const ZIP_EOCD_MAX_DISTANCE: u64 = 22 + 0xffff;
+
+/// Detects the filesystem type of the archive in `stream` from its contents, returning
+/// `None` if it isn't one that we support.
+///
+/// Compressed archives are assumed to contain tar archives. `stream` is rewound to the
+/// start afterwards.
+pub fn detect_type<R: Read + Seek>(stream: &mut R) -> io::Result<Option<&'static str>> {
+    let mut header = vec![];
+    stream.seek(SeekFrom::Start(0))?;
+    (&mut *stream).take(512).read_to_end(&mut header)?;
+
+    let detected = if header.starts_with(GZIP_MAGIC) {
+        Some("tar.gz")
+    } else if header.starts_with(ZSTD_MAGIC) {
+        Some("tar.zst")
+    } else if header.starts_with(XZ_MAGIC) {
+        Some("tar.xz")
+    } else if header.get(USTAR_MAGIC_OFFSET..USTAR_MAGIC_OFFSET + USTAR_MAGIC.len())
+        == Some(USTAR_MAGIC)
+    {
+        Some("tar")
+    } else {
+        // ZIP archives are read from the end, and may have arbitrary data prepended
+        // (such as in self-extracting archives).
+        let len = stream.seek(SeekFrom::End(0))?;
+        stream.seek(SeekFrom::Start(len.saturating_sub(ZIP_EOCD_MAX_DISTANCE)))?;
+        let mut tail = vec![];
+        stream.read_to_end(&mut tail)?;
+
+        if tail
+            .windows(ZIP_EOCD_SIGNATURE.len())
+            .any(|w| w == ZIP_EOCD_SIGNATURE)
+        {
+            Some("zip")
+        } else {
+            None
+        }
+    };
+
+    stream.seek(SeekFrom::Start(0))?;
+    Ok(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::read::GzDecoder;
+    use std::io::{Cursor, Read, Write};
+
+    use super::detect_type;
+
+    const GNU_TAR: &[u8] = include_bytes!("../../../tests/testdata/gnu.tar.gz");
+    const BSDTAR_TAR: &[u8] = include_bytes!("../../../tests/testdata/bsdtar.tar.gz");
+
+    fn detect(data: Vec<u8>) -> Option<&'static str> {
+        let mut stream = Cursor::new(data);
+        stream.set_position(10);
+        let detected = detect_type(&mut stream).unwrap();
+        assert_eq!(stream.position(), 0);
+        detected
+    }
+
+    fn gunzip(data: &[u8]) -> Vec<u8> {
+        let mut buf = vec![];
+        GzDecoder::new(data).read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn tar_archive() {
+        assert_eq!(detect(gunzip(GNU_TAR)), Some("tar"));
+        assert_eq!(detect(gunzip(BSDTAR_TAR)), Some("tar"));
+    }
+
+    #[test]
+    fn compressed_archive() {
+        assert_eq!(detect(GNU_TAR.to_vec()), Some("tar.gz"));
+        assert_eq!(
+            detect(zstd::encode_all(&gunzip(GNU_TAR)[..], 0).unwrap()),
+            Some("tar.zst")
+        );
+
+        let mut xz = xz2::write::XzEncoder::new(vec![], 6);
+        xz.write_all(&gunzip(GNU_TAR)).unwrap();
+        assert_eq!(detect(xz.finish().unwrap()), Some("tar.xz"));
+    }
+
+    #[test]
+    fn zip_archive() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        writer
+            .start_file("file.txt", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(b"hello\n").unwrap();
+        writer.set_comment("a comment");
+        let archive = writer.finish().unwrap().into_inner();
+        assert_eq!(detect(archive.clone()), Some("zip"));
+
+        // Data can be prepended to ZIP archives.
+        let mut prepended = vec![0x42; 1024];
+        prepended.extend_from_slice(&archive);
+        assert_eq!(detect(prepended), Some("zip"));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(detect(vec![]), None);
+        assert_eq!(detect(b"hello\n".to_vec()), None);
+        assert_eq!(detect(vec![0; 100 * 1024]), None);
+    }
+}
@@ -1,10 +1,16 @@
 #![forbid(unsafe_code)]
 
 use age::{
-    armor::ArmoredReader,
-    cli_common::{read_identities, read_secret},
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    cli_common::{
+        file_io, read_identities, read_identities_with_recipients, read_secret, UiCallbacks,
+    },
+    plugin,
+    secrecy::{ExposeSecret, SecretString},
     stream::StreamReader,
+    Identity, Recipient,
 };
+use age_core::format::{FileKey, Stanza};
 use fuse_mt::FilesystemMT;
 use gumdrop::Options;
 use i18n_embed::{
@@ -17,8 +23,13 @@ use rust_embed::RustEmbed;
 use std::ffi::OsStr;
 use std::fmt;
 use std::fs::File;
-use std::io;
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
+mod compressed;
+mod detect;
+mod rw;
 mod tar;
 mod zip;
 
@@ -52,13 +63,17 @@ macro_rules! wlnfl {
 
 enum Error {
     Age(age::DecryptError),
+    Encrypt(age::EncryptError),
     IdentityRead(age::cli_common::ReadError),
     Io(io::Error),
     MissingFilename,
     MissingIdentities,
     MissingMountpoint,
-    MissingType,
+    MixedIdentityAndPluginName,
+    ReencryptFailed { err: Box<Error>, saved: PathBuf },
+    UndetectedType,
     UnknownType(String),
+    UnsupportedEntries,
 }
 
 impl From<age::DecryptError> for Error {
@@ -67,6 +82,12 @@ impl From<age::DecryptError> for Error {
     }
 }
 
+impl From<age::EncryptError> for Error {
+    fn from(e: age::EncryptError) -> Self {
+        Error::Encrypt(e)
+    }
+}
+
 impl From<age::cli_common::ReadError> for Error {
     fn from(e: age::cli_common::ReadError) -> Self {
         Error::IdentityRead(e)
@@ -79,10 +100,9 @@ impl From<io::Error> for Error {
     }
 }
 
-// Rust only supports `fn main() -> Result<(), E: Debug>`, so we implement `Debug`
-// manually to provide the error output we want.
-impl fmt::Debug for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Error {
+    /// Writes the message for this error, without the footer.
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Age(e) => match e {
                 age::DecryptError::ExcessiveWork { required, .. } => {
@@ -99,6 +119,7 @@ impl fmt::Debug for Error {
                 }
                 _ => write!(f, "{}", e),
             },
+            Error::Encrypt(e) => write!(f, "{}", e),
             Error::IdentityRead(e) => write!(f, "{}", e),
             Error::Io(e) => write!(f, "{}", e),
             Error::MissingFilename => wfl!(f, "err-mnt-missing-filename"),
@@ -107,17 +128,48 @@ impl fmt::Debug for Error {
                 wlnfl!(f, "rec-dec-missing-identities")
             }
             Error::MissingMountpoint => wfl!(f, "err-mnt-missing-mountpoint"),
-            Error::MissingType => wfl!(f, "err-mnt-missing-types"),
-            Error::UnknownType(t) => write!(
-                f,
-                "{}",
-                i18n_embed_fl::fl!(
-                    LANGUAGE_LOADER,
-                    "err-mnt-unknown-type",
-                    fs_type = t.as_str()
+            Error::MixedIdentityAndPluginName => {
+                wfl!(f, "err-mixed-identity-and-plugin-name")
+            }
+            Error::ReencryptFailed { err, saved } => {
+                err.fmt_message(f)?;
+                writeln!(f)?;
+                write!(
+                    f,
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        LANGUAGE_LOADER,
+                        "err-mnt-rw-saved",
+                        saved = saved.display().to_string()
+                    )
                 )
-            ),
-        }?;
+            }
+            Error::UndetectedType => {
+                wlnfl!(f, "err-mnt-undetected-type")?;
+                write_supported_types(f)
+            }
+            Error::UnknownType(t) => {
+                writeln!(
+                    f,
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        LANGUAGE_LOADER,
+                        "err-mnt-unknown-type",
+                        fs_type = t.as_str()
+                    )
+                )?;
+                write_supported_types(f)
+            }
+            Error::UnsupportedEntries => wfl!(f, "err-mnt-rw-unsupported-entries"),
+        }
+    }
+}
+
+// Rust only supports `fn main() -> Result<(), E: Debug>`, so we implement `Debug`
+// manually to provide the error output we want.
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
         writeln!(f)?;
         writeln!(f, "[ {} ]", fl!("err-ux-A"))?;
         write!(
@@ -129,6 +181,21 @@ impl fmt::Debug for Error {
     }
 }
 
+/// The filesystem types that can be given with `-t/--types`.
+const FS_TYPES: &[&str] = &["tar", "tar.gz", "tar.zst", "tar.xz", "zip"];
+
+fn write_supported_types(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+        f,
+        "{}",
+        i18n_embed_fl::fl!(
+            LANGUAGE_LOADER,
+            "rec-mnt-types",
+            types = FS_TYPES.join(", ")
+        )
+    )
+}
+
 #[derive(Debug, Options)]
 struct AgeMountOptions {
     #[options(free, help = "The encrypted filesystem to mount.")]
@@ -143,7 +210,9 @@ struct AgeMountOptions {
     #[options(help = "Print version info and exit.", short = "V")]
     version: bool,
 
-    #[options(help = "Indicates the filesystem type (one of \"tar\", \"zip\").")]
+    #[options(
+        help = "Indicates the filesystem type (one of \"tar\", \"tar.gz\", \"tar.zst\", \"tar.xz\", \"zip\"). Detected from the contents if not given."
+    )]
     types: String,
 
     #[options(
@@ -155,6 +224,163 @@ struct AgeMountOptions {
 
     #[options(help = "Use the private key file at IDENTITY. May be repeated.")]
     identity: Vec<String>,
+
+    #[options(
+        help = "Use age-plugin-PLUGIN-NAME in its default mode as an identity.",
+        no_long,
+        short = "j"
+    )]
+    plugin_name: String,
+
+    #[options(
+        help = "Allow modifications, which are re-encrypted to the file when unmounted.",
+        no_short
+    )]
+    read_write: bool,
+}
+
+/// A recipient that can be shared between several encryptors.
+struct SharedRecipient(Rc<dyn Recipient>);
+
+impl Recipient for SharedRecipient {
+    fn wrap_file_key(&self, file_key: &FileKey) -> Result<Vec<Stanza>, age::EncryptError> {
+        self.0.wrap_file_key(file_key)
+    }
+}
+
+/// The passphrase or recipients to re-encrypt to.
+enum EncryptionKeys {
+    Passphrase(SecretString),
+    Recipients(Vec<Rc<dyn Recipient>>),
+}
+
+impl EncryptionKeys {
+    /// Returns an encryptor for a single file.
+    fn encryptor(&self) -> age::Encryptor {
+        match self {
+            EncryptionKeys::Passphrase(passphrase) => age::Encryptor::with_user_passphrase(
+                SecretString::new(passphrase.expose_secret().clone()),
+            ),
+            EncryptionKeys::Recipients(recipients) => age::Encryptor::with_recipients(
+                recipients
+                    .iter()
+                    .map(|r| Box::new(SharedRecipient(r.clone())) as Box<dyn Recipient>)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// How to re-encrypt a filesystem that was mounted with `--read-write`.
+struct Reencrypt {
+    filename: String,
+    keys: EncryptionKeys,
+    format: Format,
+}
+
+impl Reencrypt {
+    /// Re-encrypts to the file at `filename` with `keys`, in the same format as the
+    /// file is currently in.
+    fn new(filename: String, keys: EncryptionKeys) -> io::Result<Self> {
+        let format = if is_armored(&filename)? {
+            Format::AsciiArmor
+        } else {
+            Format::Binary
+        };
+
+        Ok(Reencrypt {
+            filename,
+            keys,
+            format,
+        })
+    }
+
+    /// Encrypts the current contents of `fs` to the file at `filename`, replacing it
+    /// only once it has been completely written.
+    fn save(&self, fs: &rw::AgeRwFs, filename: String) -> Result<(), Error> {
+        let output = file_io::OutputWriter::new_in_place(filename, 0o600)?;
+        let mut output = self
+            .keys
+            .encryptor()
+            .wrap_output(ArmoredWriter::wrap_output(output, self.format)?)?;
+        fs.write_archive(&mut output)?;
+        output.finish().and_then(|armor| armor.finish())?.commit()?;
+        Ok(())
+    }
+
+    /// Returns the path that the filesystem is saved to if it can't be re-encrypted to
+    /// the file it was mounted from.
+    fn fallback_path(&self) -> PathBuf {
+        let name = Path::new(&self.filename)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "archive.age".to_owned());
+        std::env::temp_dir().join(format!("rage-mount-{}-{}", std::process::id(), name))
+    }
+}
+
+/// Returns `true` if the age file at `filename` is armored.
+fn is_armored(filename: &str) -> io::Result<bool> {
+    const ARMORED_BEGIN_MARKER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+    let mut start = vec![];
+    File::open(filename)?.take(1024).read_to_end(&mut start)?;
+    Ok(String::from_utf8_lossy(&start)
+        .trim_start()
+        .starts_with(ARMORED_BEGIN_MARKER))
+}
+
+/// Checks that identity files and a plugin name weren't both given, as only one of
+/// them can be used.
+fn check_identity_args(identity: &[String], plugin_name: &str) -> Result<(), Error> {
+    if identity.is_empty() || plugin_name.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MixedIdentityAndPluginName)
+    }
+}
+
+/// Reads the identities to decrypt with from the identity files, or constructs the
+/// default identity for the given plugin.
+///
+/// If `with_recipients` is `true`, the recipients of the identities are also returned,
+/// so that the filesystem can be re-encrypted to them.
+fn read_mount_identities(
+    identity: Vec<String>,
+    plugin_name: &str,
+    max_work_factor: Option<u8>,
+    with_recipients: bool,
+) -> Result<(Vec<Box<dyn Identity>>, Vec<Box<dyn Recipient>>), Error> {
+    let (identities, recipients) = if !plugin_name.is_empty() {
+        // Construct the default plugin.
+        let plugin_identity = plugin::Identity::default_for_plugin(plugin_name);
+        let recipients = if with_recipients {
+            vec![Box::new(plugin::RecipientPluginV1::new(
+                plugin_name,
+                &[],
+                &[plugin_identity.clone()],
+                UiCallbacks,
+            )?) as Box<dyn Recipient>]
+        } else {
+            vec![]
+        };
+        let identities = vec![Box::new(plugin::IdentityPluginV1::new(
+            plugin_name,
+            &[plugin_identity],
+            UiCallbacks,
+        )?) as Box<dyn Identity>];
+        (identities, recipients)
+    } else if with_recipients {
+        read_identities_with_recipients(identity, max_work_factor)?
+    } else {
+        (read_identities(identity, max_work_factor)?, vec![])
+    };
+
+    if identities.is_empty() {
+        return Err(Error::MissingIdentities);
+    }
+
+    Ok((identities, recipients))
 }
 
 fn mount_fs<T: FilesystemMT + Send + Sync + 'static, F>(
@@ -172,15 +398,108 @@ where
     Ok(())
 }
 
+/// Mounts the archive returned by `open` read-write, and then re-encrypts it if it was
+/// modified once it is unmounted.
+fn mount_rw<A: rw::Archive, F>(
+    open: F,
+    format: rw::Format,
+    mountpoint: String,
+    reencrypt: Reencrypt,
+) -> Result<(), Error>
+where
+    F: FnOnce() -> io::Result<A>,
+{
+    let fuse_args: Vec<&OsStr> = vec![OsStr::new("-o"), OsStr::new("rw,auto_unmount")];
+
+    let archive = open()?;
+    if archive.has_unsupported_entries() {
+        return Err(Error::UnsupportedEntries);
+    }
+    let fs = rw::AgeRwFs::new(archive, format);
+    info!("{}", fl!("info-mounting-as-fuse"));
+    fuse_mt::mount(fuse_mt::FuseMT::new(fs.clone(), 1), &mountpoint, &fuse_args)?;
+
+    if !fs.is_modified() {
+        info!("{}", fl!("info-unmodified"));
+        return Ok(());
+    }
+
+    info!(
+        "{}",
+        i18n_embed_fl::fl!(
+            LANGUAGE_LOADER,
+            "info-reencrypting",
+            filename = reencrypt.filename.as_str()
+        )
+    );
+    // The modifications only exist in memory, so if they can't be written to the
+    // mounted file, save them elsewhere before they are lost.
+    reencrypt
+        .save(&fs, reencrypt.filename.clone())
+        .map_err(|err| {
+            let saved = reencrypt.fallback_path();
+            match reencrypt.save(&fs, saved.to_string_lossy().into_owned()) {
+                Ok(()) => Error::ReencryptFailed {
+                    err: Box::new(err),
+                    saved,
+                },
+                Err(_) => err,
+            }
+        })
+}
+
+fn mount_tar<R: Read + Seek + Send + 'static>(
+    stream: R,
+    compression: Option<compressed::Compression>,
+    mountpoint: String,
+    reencrypt: Option<Reencrypt>,
+) -> Result<(), Error> {
+    match reencrypt {
+        None => mount_fs(|| crate::tar::AgeTarFs::open(stream), mountpoint),
+        Some(reencrypt) => mount_rw(
+            || crate::tar::AgeTarFs::open(stream),
+            rw::Format::Tar(compression),
+            mountpoint,
+            reencrypt,
+        ),
+    }
+}
+
 fn mount_stream(
-    stream: StreamReader<ArmoredReader<io::BufReader<File>>>,
+    mut stream: StreamReader<ArmoredReader<io::BufReader<File>>>,
     types: String,
     mountpoint: String,
+    reencrypt: Option<Reencrypt>,
 ) -> Result<(), Error> {
-    match types.as_str() {
-        "tar" => mount_fs(|| crate::tar::AgeTarFs::open(stream), mountpoint),
-        "zip" => mount_fs(|| crate::zip::AgeZipFs::open(stream), mountpoint),
-        _ => Err(Error::UnknownType(types)),
+    let types = if types.is_empty() {
+        let detected = detect::detect_type(&mut stream)?.ok_or(Error::UndetectedType)?;
+        info!(
+            "{}",
+            i18n_embed_fl::fl!(LANGUAGE_LOADER, "info-detected-type", fs_type = detected)
+        );
+        detected.to_owned()
+    } else {
+        types
+    };
+
+    match (types.as_str(), reencrypt) {
+        ("tar", reencrypt) => mount_tar(stream, None, mountpoint, reencrypt),
+        ("zip", None) => mount_fs(|| crate::zip::AgeZipFs::open(stream), mountpoint),
+        ("zip", Some(reencrypt)) => mount_rw(
+            || crate::zip::AgeZipFs::open(stream),
+            rw::Format::Zip,
+            mountpoint,
+            reencrypt,
+        ),
+        (fs_type, reencrypt) => match compressed::Compression::from_tar_type(fs_type) {
+            Some(compression) => mount_tar(
+                compressed::DecompressingReader::new(stream, compression)?,
+                Some(compression),
+                mountpoint,
+                reencrypt,
+            ),
+            None => Err(Error::UnknownType(types)),
+        },
     }
 }
 
@@ -224,9 +543,7 @@ fn main() -> Result<(), Error> {
     if opts.mountpoint.is_empty() {
         return Err(Error::MissingMountpoint);
     }
-    if opts.types.is_empty() {
-        return Err(Error::MissingType);
-    }
+    check_identity_args(&opts.identity, &opts.plugin_name)?;
 
     info!(
         "{}",
@@ -236,32 +553,170 @@ fn main() -> Result<(), Error> {
             filename = opts.filename.as_str()
         )
     );
-    let file = File::open(opts.filename)?;
+    let file = File::open(&opts.filename)?;
 
+    let filename = opts.filename;
     let types = opts.types;
     let mountpoint = opts.mountpoint;
 
     match age::Decryptor::new(ArmoredReader::new(file))? {
         age::Decryptor::Passphrase(decryptor) => {
             match read_secret(&fl!("type-passphrase"), &fl!("prompt-passphrase"), None) {
-                Ok(passphrase) => decryptor
-                    .decrypt(&passphrase, opts.max_work_factor)
-                    .map_err(|e| e.into())
-                    .and_then(|stream| mount_stream(stream, types, mountpoint)),
+                Ok(passphrase) => {
+                    let stream = decryptor.decrypt(&passphrase, opts.max_work_factor)?;
+
+                    // Re-encrypt with the same passphrase.
+                    let reencrypt = if opts.read_write {
+                        Some(Reencrypt::new(
+                            filename,
+                            EncryptionKeys::Passphrase(passphrase),
+                        )?)
+                    } else {
+                        None
+                    };
+
+                    mount_stream(stream, types, mountpoint, reencrypt)
+                }
                 Err(_) => Ok(()),
             }
         }
         age::Decryptor::Recipients(decryptor) => {
-            let identities = read_identities(opts.identity, opts.max_work_factor)?;
+            // Re-encrypt to the recipients of the identities, which we read upfront so
+            // that any problems with them are found before anything is modified.
+            let (identities, recipients) = read_mount_identities(
+                opts.identity,
+                &opts.plugin_name,
+                opts.max_work_factor,
+                opts.read_write,
+            )?;
+
+            let stream = decryptor.decrypt(identities.iter().map(|i| &**i))?;
+            let reencrypt = if opts.read_write {
+                // We can only re-encrypt to recipients that we know about, which might
+                // not be all of the recipients that the file is currently encrypted to.
+                eprintln!(
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        LANGUAGE_LOADER,
+                        "warning-msg",
+                        warning = i18n_embed_fl::fl!(
+                            LANGUAGE_LOADER,
+                            "warn-mnt-rw-recipients",
+                            filename = filename.as_str()
+                        )
+                    )
+                );
 
-            if identities.is_empty() {
-                return Err(Error::MissingIdentities);
+                Some(Reencrypt::new(
+                    filename,
+                    EncryptionKeys::Recipients(recipients.into_iter().map(Rc::from).collect()),
+                )?)
+            } else {
+                None
+            };
+
+            mount_stream(stream, types, mountpoint, reencrypt)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use age::Encryptor;
+    use std::fs;
+    use std::io::{Read, Write};
+
+    use super::{check_identity_args, read_mount_identities, Error};
+
+    const TEST_SK: &str =
+        "AGE-SECRET-KEY-1GQ9778VQXMMJVE8SK7J6VT8UJ4HDQAJUVSFCWCM02D8GEWQ72PVQ2Y5J33";
+
+    #[test]
+    fn identity_args() {
+        let identity = vec!["key.txt".to_owned()];
+        assert!(check_identity_args(&[], "").is_ok());
+        assert!(check_identity_args(&identity, "").is_ok());
+        assert!(check_identity_args(&[], "yubikey").is_ok());
+        assert!(matches!(
+            check_identity_args(&identity, "yubikey"),
+            Err(Error::MixedIdentityAndPluginName)
+        ));
+    }
+
+    #[test]
+    fn plugin_identity() {
+        // The plugin is only used for decryption if the filesystem is read-only.
+        match read_mount_identities(vec![], "ragemountmissing", None, false) {
+            Err(Error::Age(age::DecryptError::MissingPlugin { binary_name })) => {
+                assert_eq!(binary_name, "age-plugin-ragemountmissing")
             }
+            _ => panic!("expected a missing plugin error"),
+        }
 
-            decryptor
-                .decrypt(identities.iter().map(|i| &**i))
-                .map_err(|e| e.into())
-                .and_then(|stream| mount_stream(stream, types, mountpoint))
+        // The plugin's recipient is needed to re-encrypt a read-write filesystem.
+        match read_mount_identities(vec![], "ragemountmissing", None, true) {
+            Err(Error::Encrypt(age::EncryptError::MissingPlugin { binary_name })) => {
+                assert_eq!(binary_name, "age-plugin-ragemountmissing")
+            }
+            _ => panic!("expected a missing plugin error"),
         }
     }
+
+    #[test]
+    fn identity_file_recipients() {
+        let dir = std::env::temp_dir().join(format!("rage-mount-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key = dir.join("key.txt");
+        fs::write(&key, TEST_SK).unwrap();
+        let key = key.to_str().unwrap().to_owned();
+
+        assert!(matches!(
+            read_mount_identities(vec![], "", None, true),
+            Err(Error::MissingIdentities)
+        ));
+
+        let (identities, recipients) =
+            match read_mount_identities(vec![key.clone()], "", None, false) {
+                Ok(res) => res,
+                Err(e) => panic!("{:?}", e),
+            };
+        assert_eq!(identities.len(), 1);
+        assert!(recipients.is_empty());
+
+        // A read-write filesystem is re-encrypted to the recipients of the identities.
+        let (identities, recipients) = match read_mount_identities(vec![key], "", None, true) {
+            Ok(res) => res,
+            Err(e) => panic!("{:?}", e),
+        };
+        assert_eq!(recipients.len(), 1);
+
+        let mut encrypted = vec![];
+        let mut writer = Encryptor::with_recipients(recipients)
+            .wrap_output(&mut encrypted)
+            .unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap();
+
+        let decryptor = match age::Decryptor::new(&encrypted[..]).unwrap() {
+            age::Decryptor::Recipients(d) => d,
+            _ => panic!("expected recipients"),
+        };
+        let stanzas: Vec<_> = decryptor
+            .header()
+            .stanzas()
+            .iter()
+            .filter(|s| !s.tag.ends_with("-grease"))
+            .collect();
+        assert_eq!(stanzas.len(), 1);
+        assert_eq!(stanzas[0].tag, "X25519");
+        let mut decrypted = vec![];
+        decryptor
+            .decrypt(identities.iter().map(|i| &**i))
+            .unwrap()
+            .read_to_end(&mut decrypted)
+            .unwrap();
+        assert_eq!(decrypted, b"hello");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
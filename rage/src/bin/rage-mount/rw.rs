@@ -0,0 +1,935 @@
+//! Read-write mounting of archives.
+//!
+//! Changes to a read-write filesystem are staged in memory, and written to a new
+//! archive when the filesystem is unmounted.
+
+use fuse_mt::*;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use time::Timespec;
+
+use crate::compressed::Compression;
+
+/// An archive that can be the base of a read-write filesystem.
+pub trait Archive: Send + Sync + 'static {
+    /// Returns the paths and attributes of the files and directories in the archive.
+    fn entries(&self) -> Vec<(PathBuf, FileAttr)>;
+
+    /// Returns `true` if the archive contains entries that are not in
+    /// [`Archive::entries`], and would be lost if the archive was rewritten.
+    fn has_unsupported_entries(&self) -> bool;
+
+    /// Reads up to `size` bytes of the file at `path`, starting at `offset`.
+    fn read_at(&self, path: &Path, offset: u64, size: usize) -> io::Result<Vec<u8>>;
+
+    /// Calls `f` with a reader over the entire contents of the file at `path`.
+    fn read_file(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(&mut dyn Read) -> io::Result<()>,
+    ) -> io::Result<()>;
+}
+
+/// The format to write the modified archive in.
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Tar(Option<Compression>),
+    Zip,
+}
+
+fn rw_path(path: &Path) -> &Path {
+    path.strip_prefix("/").unwrap()
+}
+
+/// Returns `path` without any leading `./` or `/` components, which archives commonly
+/// contain.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
+
+fn dir_attr(perm: u16, uid: u32, gid: u32, now: Timespec) -> FileAttr {
+    FileAttr {
+        size: 0,
+        blocks: 0,
+        atime: now,
+        mtime: now,
+        ctime: now,
+        crtime: Timespec { sec: 0, nsec: 0 },
+        kind: FileType::Directory,
+        perm,
+        nlink: 1,
+        uid,
+        gid,
+        rdev: 0,
+        flags: 0,
+    }
+}
+
+enum Contents {
+    Directory,
+    /// The unmodified contents of the file at this path in the original archive.
+    Archived(PathBuf),
+    /// Contents that have been written since the filesystem was mounted.
+    Staged(Vec<u8>),
+}
+
+struct Node {
+    attr: FileAttr,
+    contents: Contents,
+}
+
+struct Inner {
+    archive: Box<dyn Archive>,
+    format: Format,
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+    modified: AtomicBool,
+}
+
+/// A filesystem that stages changes to an archive in memory.
+///
+/// This is cheaply cloneable, so that a clone can be kept to write out the changes
+/// after the filesystem has been unmounted.
+#[derive(Clone)]
+pub struct AgeRwFs(Arc<Inner>);
+
+impl AgeRwFs {
+    pub fn new<A: Archive>(archive: A, format: Format) -> Self {
+        let now = time::get_time();
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            PathBuf::new(),
+            Node {
+                attr: dir_attr(0o0755, 1000, 1000, now),
+                contents: Contents::Directory,
+            },
+        );
+
+        for (orig, attr) in archive.entries() {
+            let path = normalize(&orig);
+            if path.parent().is_none() {
+                // This is the root directory.
+                continue;
+            }
+
+            // Archives aren't required to contain entries for parent directories.
+            for ancestor in path.ancestors().skip(1) {
+                nodes.entry(ancestor.to_path_buf()).or_insert_with(|| Node {
+                    attr: dir_attr(0o0755, attr.uid, attr.gid, attr.mtime),
+                    contents: Contents::Directory,
+                });
+            }
+
+            let contents = match attr.kind {
+                FileType::Directory => Contents::Directory,
+                _ => Contents::Archived(orig),
+            };
+            nodes.insert(path, Node { attr, contents });
+        }
+
+        AgeRwFs(Arc::new(Inner {
+            archive: Box::new(archive),
+            format,
+            nodes: Mutex::new(nodes),
+            modified: AtomicBool::new(false),
+        }))
+    }
+
+    /// Returns `true` if the filesystem has been modified since it was mounted.
+    pub fn is_modified(&self) -> bool {
+        self.0.modified.load(Ordering::SeqCst)
+    }
+
+    /// Writes the current contents of the filesystem to `output` as a new archive.
+    ///
+    /// ZIP archives are built in memory before being written, because writing them
+    /// requires seeking.
+    pub fn write_archive<W: Write>(&self, output: W) -> io::Result<()> {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        match self.0.format {
+            Format::Tar(None) => self.write_tar(&nodes, output),
+            Format::Tar(Some(compression)) => {
+                compression.compress(output, |output| self.write_tar(&nodes, output))
+            }
+            Format::Zip => self.write_zip(&nodes, output),
+        }
+    }
+
+    fn write_tar<W: Write>(&self, nodes: &BTreeMap<PathBuf, Node>, output: W) -> io::Result<()> {
+        let mut builder = tar::Builder::new(output);
+
+        // Parents sort before their children, so directories are created first.
+        for (path, node) in nodes.iter().skip(1) {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(node.attr.perm as u32);
+            header.set_uid(node.attr.uid as u64);
+            header.set_gid(node.attr.gid as u64);
+            header.set_mtime(node.attr.mtime.sec.max(0) as u64);
+
+            match &node.contents {
+                Contents::Directory => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_size(0);
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                Contents::Archived(orig) => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_size(node.attr.size);
+                    self.0.archive.read_file(orig, &mut |data: &mut dyn Read| {
+                        builder.append_data(&mut header, path, data)
+                    })?;
+                }
+                Contents::Staged(data) => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_size(data.len() as u64);
+                    builder.append_data(&mut header, path, &data[..])?;
+                }
+            }
+        }
+
+        builder.into_inner().map(|_| ())
+    }
+
+    fn write_zip<W: Write>(
+        &self,
+        nodes: &BTreeMap<PathBuf, Node>,
+        mut output: W,
+    ) -> io::Result<()> {
+        let zip_err = |e| io::Error::new(io::ErrorKind::Other, e);
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(vec![]));
+
+        for (path, node) in nodes.iter().skip(1) {
+            let name = path.to_str().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("'{}' is not valid UTF-8", path.display()),
+                )
+            })?;
+
+            let mut options = zip::write::FileOptions::default().last_modified_time(
+                zip::DateTime::from_time(time::at_utc(node.attr.mtime)).unwrap_or_default(),
+            );
+            if node.attr.perm != 0 {
+                options = options.unix_permissions(node.attr.perm as u32);
+            }
+
+            match &node.contents {
+                Contents::Directory => zip.add_directory(name, options).map_err(zip_err)?,
+                Contents::Archived(orig) => {
+                    zip.start_file(name, options).map_err(zip_err)?;
+                    self.0.archive.read_file(orig, &mut |data: &mut dyn Read| {
+                        io::copy(data, &mut zip).map(|_| ())
+                    })?;
+                }
+                Contents::Staged(data) => {
+                    zip.start_file(name, options).map_err(zip_err)?;
+                    zip.write_all(data)?;
+                }
+            }
+        }
+
+        let zip = zip.finish().map_err(zip_err)?;
+        output.write_all(&zip.into_inner())
+    }
+
+    fn mark_modified(&self) {
+        self.0.modified.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns the contents of `node` for modification, reading them from the archive
+    /// if they haven't been modified yet.
+    fn staged<'a>(&self, node: &'a mut Node) -> Result<&'a mut Vec<u8>, libc::c_int> {
+        if let Contents::Archived(orig) = &node.contents {
+            let mut data = Vec::with_capacity(node.attr.size as usize);
+            self.0
+                .archive
+                .read_file(orig, &mut |r: &mut dyn Read| {
+                    r.read_to_end(&mut data).map(|_| ())
+                })
+                .map_err(|_| libc::EIO)?;
+            node.contents = Contents::Staged(data);
+        }
+
+        match &mut node.contents {
+            Contents::Staged(data) => Ok(data),
+            Contents::Directory => Err(libc::EISDIR),
+            Contents::Archived(_) => unreachable!(),
+        }
+    }
+
+    /// Reads up to `size` bytes of the file at `path`, starting at `offset`, and passes
+    /// them to `f`.
+    ///
+    /// Reading from an offset past the end of the file returns no data.
+    fn read_with<T>(
+        &self,
+        path: &Path,
+        offset: u64,
+        size: u32,
+        f: impl FnOnce(Result<&[u8], libc::c_int>) -> T,
+    ) -> T {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        let node = match nodes.get(rw_path(path)) {
+            Some(node) => node,
+            None => return f(Err(libc::ENOENT)),
+        };
+        let offset = u64::min(offset, node.attr.size);
+        let to_read = usize::min(size as usize, (node.attr.size - offset) as usize);
+
+        match &node.contents {
+            Contents::Directory => f(Err(libc::EISDIR)),
+            Contents::Archived(_) if to_read == 0 => f(Ok(&[])),
+            Contents::Archived(orig) => match self.0.archive.read_at(orig, offset, to_read) {
+                Ok(buf) => f(Ok(&buf)),
+                Err(_) => f(Err(libc::EIO)),
+            },
+            Contents::Staged(data) => {
+                let offset = offset as usize;
+                f(Ok(&data[offset..offset + to_read]))
+            }
+        }
+    }
+
+    /// Adds a new node at `parent/name`.
+    fn insert(&self, parent: &Path, name: &OsStr, node: Node) -> ResultEntry {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let parent = rw_path(parent);
+        match nodes.get(parent) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) => (),
+            Some(_) => return Err(libc::ENOTDIR),
+            None => return Err(libc::ENOENT),
+        }
+
+        let path = parent.join(name);
+        if nodes.contains_key(&path) {
+            return Err(libc::EEXIST);
+        }
+
+        let attr = node.attr;
+        nodes.insert(path, node);
+        self.mark_modified();
+        Ok((TTL, attr))
+    }
+
+    /// Applies `f` to the attributes of the node at `path`.
+    fn set_attr(&self, path: &Path, f: impl FnOnce(&mut FileAttr)) -> ResultEmpty {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let node = nodes.get_mut(rw_path(path)).ok_or(libc::ENOENT)?;
+        f(&mut node.attr);
+        node.attr.ctime = time::get_time();
+        self.mark_modified();
+        Ok(())
+    }
+}
+
+/// Returns `true` if `nodes` contains any children of `dir`.
+fn has_children(nodes: &BTreeMap<PathBuf, Node>, dir: &Path) -> bool {
+    nodes
+        .range::<Path, _>((Bound::Excluded(dir), Bound::Unbounded))
+        .next()
+        .map_or(false, |(path, _)| path.starts_with(dir))
+}
+
+const TTL: Timespec = Timespec { sec: 1, nsec: 0 };
+
+impl FilesystemMT for AgeRwFs {
+    fn getattr(&self, _req: RequestInfo, path: &Path, _fh: Option<u64>) -> ResultEntry {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        nodes
+            .get(rw_path(path))
+            .map(|node| (TTL, node.attr))
+            .ok_or(libc::ENOENT)
+    }
+
+    fn chmod(&self, _req: RequestInfo, path: &Path, _fh: Option<u64>, mode: u32) -> ResultEmpty {
+        self.set_attr(path, |attr| attr.perm = (mode & 0o7777) as u16)
+    }
+
+    fn chown(
+        &self,
+        _req: RequestInfo,
+        path: &Path,
+        _fh: Option<u64>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> ResultEmpty {
+        self.set_attr(path, |attr| {
+            attr.uid = uid.unwrap_or(attr.uid);
+            attr.gid = gid.unwrap_or(attr.gid);
+        })
+    }
+
+    fn truncate(&self, _req: RequestInfo, path: &Path, _fh: Option<u64>, size: u64) -> ResultEmpty {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let node = nodes.get_mut(rw_path(path)).ok_or(libc::ENOENT)?;
+        self.staged(node)?.resize(size as usize, 0);
+        let now = time::get_time();
+        node.attr.size = size;
+        node.attr.mtime = now;
+        node.attr.ctime = now;
+        self.mark_modified();
+        Ok(())
+    }
+
+    fn utimens(
+        &self,
+        _req: RequestInfo,
+        path: &Path,
+        _fh: Option<u64>,
+        atime: Option<Timespec>,
+        mtime: Option<Timespec>,
+    ) -> ResultEmpty {
+        self.set_attr(path, |attr| {
+            attr.atime = atime.unwrap_or(attr.atime);
+            attr.mtime = mtime.unwrap_or(attr.mtime);
+        })
+    }
+
+    fn mkdir(&self, req: RequestInfo, parent: &Path, name: &OsStr, mode: u32) -> ResultEntry {
+        self.insert(
+            parent,
+            name,
+            Node {
+                attr: dir_attr((mode & 0o7777) as u16, req.uid, req.gid, time::get_time()),
+                contents: Contents::Directory,
+            },
+        )
+    }
+
+    fn unlink(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let path = rw_path(parent).join(name);
+        match nodes.get(&path) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) => Err(libc::EISDIR),
+            Some(_) => {
+                nodes.remove(&path);
+                self.mark_modified();
+                Ok(())
+            }
+            None => Err(libc::ENOENT),
+        }
+    }
+
+    fn rmdir(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let path = rw_path(parent).join(name);
+        match nodes.get(&path) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) => {
+                if has_children(&nodes, &path) {
+                    Err(libc::ENOTEMPTY)
+                } else {
+                    nodes.remove(&path);
+                    self.mark_modified();
+                    Ok(())
+                }
+            }
+            Some(_) => Err(libc::ENOTDIR),
+            None => Err(libc::ENOENT),
+        }
+    }
+
+    fn rename(
+        &self,
+        _req: RequestInfo,
+        parent: &Path,
+        name: &OsStr,
+        newparent: &Path,
+        newname: &OsStr,
+    ) -> ResultEmpty {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let from = rw_path(parent).join(name);
+        let to = rw_path(newparent).join(newname);
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(&from) {
+            // A directory can't be moved inside itself.
+            return Err(libc::EINVAL);
+        }
+
+        let is_dir = match nodes.get(&from) {
+            Some(node) => matches!(node.contents, Contents::Directory),
+            None => return Err(libc::ENOENT),
+        };
+        match nodes.get(&to) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) if !is_dir => return Err(libc::EISDIR),
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) if has_children(&nodes, &to) => return Err(libc::ENOTEMPTY),
+            Some(Node {
+                contents: Contents::Archived(_) | Contents::Staged(_),
+                ..
+            }) if is_dir => return Err(libc::ENOTDIR),
+            _ => (),
+        }
+        nodes.remove(&to);
+
+        // Move the node and everything beneath it.
+        let moved: Vec<_> = nodes
+            .range::<Path, _>((Bound::Included(from.as_path()), Bound::Unbounded))
+            .take_while(|(path, _)| path.starts_with(&from))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in moved {
+            let node = nodes.remove(&path).expect("path was just listed");
+            let new_path = to.join(path.strip_prefix(&from).expect("path is beneath from"));
+            nodes.insert(new_path, node);
+        }
+
+        self.mark_modified();
+        Ok(())
+    }
+
+    fn open(&self, _req: RequestInfo, path: &Path, _flags: u32) -> ResultOpen {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        match nodes.get(rw_path(path)) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) => Err(libc::EISDIR),
+            Some(_) => Ok((0, 0)),
+            None => Err(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &self,
+        _req: RequestInfo,
+        path: &Path,
+        _fh: u64,
+        offset: u64,
+        size: u32,
+        callback: impl FnOnce(ResultSlice<'_>) -> CallbackResult,
+    ) -> CallbackResult {
+        self.read_with(path, offset, size, callback)
+    }
+
+    fn write(
+        &self,
+        _req: RequestInfo,
+        path: &Path,
+        _fh: u64,
+        offset: u64,
+        data: Vec<u8>,
+        _flags: u32,
+    ) -> ResultWrite {
+        let mut nodes = self.0.nodes.lock().unwrap();
+
+        let node = nodes.get_mut(rw_path(path)).ok_or(libc::ENOENT)?;
+        let contents = self.staged(node)?;
+        let offset = offset as usize;
+        let end = offset + data.len();
+        if contents.len() < end {
+            contents.resize(end, 0);
+        }
+        contents[offset..end].copy_from_slice(&data);
+        let size = contents.len() as u64;
+
+        let now = time::get_time();
+        node.attr.size = size;
+        node.attr.mtime = now;
+        node.attr.ctime = now;
+        self.mark_modified();
+        Ok(data.len() as u32)
+    }
+
+    fn flush(&self, _req: RequestInfo, _path: &Path, _fh: u64, _lock_owner: u64) -> ResultEmpty {
+        // Changes are only written out when the filesystem is unmounted.
+        Ok(())
+    }
+
+    fn release(
+        &self,
+        _req: RequestInfo,
+        _path: &Path,
+        _fh: u64,
+        _flags: u32,
+        _lock_owner: u64,
+        _flush: bool,
+    ) -> ResultEmpty {
+        Ok(())
+    }
+
+    fn fsync(&self, _req: RequestInfo, _path: &Path, _fh: u64, _datasync: bool) -> ResultEmpty {
+        Ok(())
+    }
+
+    fn opendir(&self, _req: RequestInfo, path: &Path, _flags: u32) -> ResultOpen {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        match nodes.get(rw_path(path)) {
+            Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) => Ok((0, 0)),
+            Some(_) => Err(libc::ENOTDIR),
+            None => Err(libc::ENOENT),
+        }
+    }
+
+    fn readdir(&self, _req: RequestInfo, path: &Path, _fh: u64) -> ResultReaddir {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        let dir = rw_path(path);
+        Ok(nodes
+            .range::<Path, _>((Bound::Excluded(dir), Bound::Unbounded))
+            .take_while(|(path, _)| path.starts_with(dir))
+            .filter(|(path, _)| path.parent() == Some(dir))
+            .map(|(path, node)| DirectoryEntry {
+                name: path.file_name().expect("not the root").to_owned(),
+                kind: node.attr.kind,
+            })
+            .collect())
+    }
+
+    fn releasedir(&self, _req: RequestInfo, _path: &Path, _fh: u64, _flags: u32) -> ResultEmpty {
+        Ok(())
+    }
+
+    fn statfs(&self, _req: RequestInfo, _path: &Path) -> ResultStatfs {
+        let nodes = self.0.nodes.lock().unwrap();
+
+        Ok(Statfs {
+            blocks: nodes.len() as u64,
+            bfree: 0,
+            bavail: 0,
+            files: nodes.len() as u64,
+            ffree: 0,
+            bsize: 64 * 1024,
+            namelen: u32::max_value(),
+            frsize: 64 * 1024,
+        })
+    }
+
+    fn create(
+        &self,
+        req: RequestInfo,
+        parent: &Path,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+    ) -> ResultCreate {
+        let now = time::get_time();
+        let (ttl, attr) = self.insert(
+            parent,
+            name,
+            Node {
+                attr: FileAttr {
+                    size: 0,
+                    blocks: 1,
+                    atime: now,
+                    mtime: now,
+                    ctime: now,
+                    crtime: Timespec { sec: 0, nsec: 0 },
+                    kind: FileType::RegularFile,
+                    perm: (mode & 0o7777) as u16,
+                    nlink: 1,
+                    uid: req.uid,
+                    gid: req.gid,
+                    rdev: 0,
+                    flags: 0,
+                },
+                contents: Contents::Staged(vec![]),
+            },
+        )?;
+
+        Ok(CreatedEntry {
+            ttl,
+            attr,
+            fh: 0,
+            flags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::read::GzDecoder;
+    use fuse_mt::{FileType, FilesystemMT, RequestInfo};
+    use std::ffi::OsStr;
+    use std::io::{Cursor, Read};
+    use std::path::Path;
+
+    use super::{AgeRwFs, Format};
+    use crate::{compressed::Compression, tar::AgeTarFs};
+
+    const REQ: RequestInfo = RequestInfo {
+        unique: 0,
+        uid: 0,
+        gid: 0,
+        pid: 0,
+    };
+
+    /// Returns a tar archive containing `dir/a.txt`, `dir/sub/` and `b.txt`.
+    fn archive() -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        let mut append = |path: &str, entry_type: tar::EntryType, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_mode(0o755);
+            header.set_size(data.len() as u64);
+            builder.append_data(&mut header, path, data).unwrap();
+        };
+        append("dir", tar::EntryType::Directory, b"");
+        append("dir/a.txt", tar::EntryType::Regular, b"hello");
+        append("dir/sub", tar::EntryType::Directory, b"");
+        append("b.txt", tar::EntryType::Regular, b"world");
+        builder.into_inner().unwrap()
+    }
+
+    fn open(tar: Vec<u8>, format: Format) -> AgeRwFs {
+        AgeRwFs::new(AgeTarFs::open(Cursor::new(tar)).unwrap(), format)
+    }
+
+    fn read(fs: &AgeRwFs, path: &str, offset: u64) -> Result<Vec<u8>, libc::c_int> {
+        fs.read_with(Path::new(path), offset, 1024, |res| {
+            res.map(|data| data.to_vec())
+        })
+    }
+
+    fn names(fs: &AgeRwFs, path: &str) -> Vec<String> {
+        let mut names: Vec<_> = fs
+            .readdir(REQ, Path::new(path), 0)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name.into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn create_write_truncate() {
+        let fs = open(archive(), Format::Tar(None));
+        let create = |parent: &str, name: &str| {
+            fs.create(REQ, Path::new(parent), OsStr::new(name), 0o644, 0)
+                .map(|created| created.attr)
+        };
+        let write = |path: &str, offset: u64, data: &[u8]| {
+            fs.write(REQ, Path::new(path), 0, offset, data.to_vec(), 0)
+        };
+
+        // Reading past the end of a file returns no data.
+        assert_eq!(read(&fs, "/dir/a.txt", 3), Ok(b"lo".to_vec()));
+        assert_eq!(read(&fs, "/dir/a.txt", 5), Ok(vec![]));
+        assert_eq!(read(&fs, "/dir/a.txt", 100), Ok(vec![]));
+        assert_eq!(read(&fs, "/dir", 0), Err(libc::EISDIR));
+        assert_eq!(read(&fs, "/missing.txt", 0), Err(libc::ENOENT));
+        assert!(!fs.is_modified());
+
+        let attr = create("/dir", "new.txt").unwrap();
+        assert_eq!(attr.kind, FileType::RegularFile);
+        assert_eq!(attr.perm, 0o644);
+        assert_eq!(attr.size, 0);
+        assert!(fs.is_modified());
+        assert_eq!(create("/dir", "new.txt").err(), Some(libc::EEXIST));
+        assert_eq!(create("/b.txt", "new.txt").err(), Some(libc::ENOTDIR));
+        assert_eq!(create("/missing", "new.txt").err(), Some(libc::ENOENT));
+
+        assert_eq!(write("/dir/new.txt", 0, b"new data"), Ok(8));
+        assert_eq!(write("/dir/new.txt", 10, b"!"), Ok(1));
+        assert_eq!(read(&fs, "/dir/new.txt", 0), Ok(b"new data\0\0!".to_vec()));
+        assert_eq!(read(&fs, "/dir/new.txt", 11), Ok(vec![]));
+        assert_eq!(read(&fs, "/dir/new.txt", 100), Ok(vec![]));
+        assert_eq!(
+            fs.getattr(REQ, Path::new("/dir/new.txt"), None)
+                .unwrap()
+                .1
+                .size,
+            11
+        );
+        assert_eq!(write("/missing.txt", 0, b""), Err(libc::ENOENT));
+        assert_eq!(write("/dir", 0, b""), Err(libc::EISDIR));
+
+        // Files from the archive are copied before they are modified.
+        assert_eq!(write("/dir/a.txt", 0, b"J"), Ok(1));
+        assert_eq!(read(&fs, "/dir/a.txt", 0), Ok(b"Jello".to_vec()));
+
+        let truncate = |path: &str, size: u64| fs.truncate(REQ, Path::new(path), None, size);
+        assert_eq!(truncate("/b.txt", 3), Ok(()));
+        assert_eq!(read(&fs, "/b.txt", 0), Ok(b"wor".to_vec()));
+        assert_eq!(truncate("/b.txt", 5), Ok(()));
+        assert_eq!(read(&fs, "/b.txt", 0), Ok(b"wor\0\0".to_vec()));
+        assert_eq!(truncate("/dir/new.txt", 0), Ok(()));
+        assert_eq!(read(&fs, "/dir/new.txt", 0), Ok(vec![]));
+        assert_eq!(truncate("/dir", 0), Err(libc::EISDIR));
+        assert_eq!(truncate("/missing.txt", 0), Err(libc::ENOENT));
+    }
+
+    #[test]
+    fn rename_over_existing() {
+        let fs = open(archive(), Format::Tar(None));
+        let rename = |from: &str, to: &str| {
+            let (from, to) = (Path::new(from), Path::new(to));
+            fs.rename(
+                REQ,
+                from.parent().unwrap(),
+                from.file_name().unwrap(),
+                to.parent().unwrap(),
+                to.file_name().unwrap(),
+            )
+        };
+
+        // A file replaces an existing file.
+        assert_eq!(rename("/b.txt", "/dir/a.txt"), Ok(()));
+        assert_eq!(names(&fs, "/"), ["dir"]);
+        assert_eq!(names(&fs, "/dir"), ["a.txt", "sub"]);
+        assert_eq!(read(&fs, "/dir/a.txt", 0), Ok(b"world".to_vec()));
+
+        fs.mkdir(REQ, Path::new("/"), OsStr::new("empty"), 0o755)
+            .unwrap();
+        assert_eq!(rename("/dir/a.txt", "/dir/sub"), Err(libc::EISDIR));
+        assert_eq!(rename("/empty", "/dir/a.txt"), Err(libc::ENOTDIR));
+        assert_eq!(rename("/empty", "/dir"), Err(libc::ENOTEMPTY));
+        assert_eq!(rename("/dir", "/dir/sub/dir"), Err(libc::EINVAL));
+        assert_eq!(rename("/missing", "/dir/a.txt"), Err(libc::ENOENT));
+
+        // A directory replaces an empty directory, and takes its contents with it.
+        assert_eq!(rename("/dir", "/empty"), Ok(()));
+        assert_eq!(names(&fs, "/"), ["empty"]);
+        assert_eq!(names(&fs, "/empty"), ["a.txt", "sub"]);
+        assert_eq!(read(&fs, "/empty/a.txt", 0), Ok(b"world".to_vec()));
+    }
+
+    #[test]
+    fn rmdir_non_empty() {
+        let fs = open(archive(), Format::Tar(None));
+        let root = Path::new("/");
+        let dir = Path::new("/dir");
+
+        assert_eq!(fs.rmdir(REQ, root, OsStr::new("dir")), Err(libc::ENOTEMPTY));
+        assert_eq!(fs.rmdir(REQ, root, OsStr::new("b.txt")), Err(libc::ENOTDIR));
+        assert_eq!(
+            fs.rmdir(REQ, root, OsStr::new("missing")),
+            Err(libc::ENOENT)
+        );
+        assert_eq!(fs.unlink(REQ, root, OsStr::new("dir")), Err(libc::EISDIR));
+        assert!(!fs.is_modified());
+
+        // Siblings that share a prefix with the directory are not its children.
+        fs.create(REQ, root, OsStr::new("dir.txt"), 0o644, 0)
+            .unwrap();
+        assert_eq!(fs.unlink(REQ, dir, OsStr::new("a.txt")), Ok(()));
+        assert_eq!(fs.rmdir(REQ, root, OsStr::new("dir")), Err(libc::ENOTEMPTY));
+        assert_eq!(fs.rmdir(REQ, dir, OsStr::new("sub")), Ok(()));
+        assert_eq!(fs.rmdir(REQ, root, OsStr::new("dir")), Ok(()));
+        assert_eq!(names(&fs, "/"), ["b.txt", "dir.txt"]);
+    }
+
+    /// Modifies the tree returned by [`archive`], leaving `b.txt` unmodified apart
+    /// from its permissions.
+    fn modify(fs: &AgeRwFs) {
+        fs.write(REQ, Path::new("/dir/a.txt"), 0, 5, b", world".to_vec(), 0)
+            .unwrap();
+        fs.create(REQ, Path::new("/dir/sub"), OsStr::new("c.txt"), 0o600, 0)
+            .unwrap();
+        fs.write(REQ, Path::new("/dir/sub/c.txt"), 0, 0, b"new".to_vec(), 0)
+            .unwrap();
+        fs.mkdir(REQ, Path::new("/"), OsStr::new("empty"), 0o700)
+            .unwrap();
+        fs.chmod(REQ, Path::new("/b.txt"), None, 0o640).unwrap();
+    }
+
+    #[test]
+    fn write_tar() {
+        for compression in [None, Some(Compression::Gzip)] {
+            let fs = open(archive(), Format::Tar(compression));
+            modify(&fs);
+            let mut written = vec![];
+            fs.write_archive(&mut written).unwrap();
+
+            let tar = match compression {
+                None => written,
+                Some(_) => {
+                    let mut tar = vec![];
+                    GzDecoder::new(&written[..]).read_to_end(&mut tar).unwrap();
+                    tar
+                }
+            };
+            let fs = open(tar, Format::Tar(None));
+            let attr = |path: &str| fs.getattr(REQ, Path::new(path), None).unwrap().1;
+
+            assert_eq!(names(&fs, "/"), ["b.txt", "dir", "empty"]);
+            assert_eq!(names(&fs, "/dir"), ["a.txt", "sub"]);
+            assert_eq!(names(&fs, "/dir/sub"), ["c.txt"]);
+            assert_eq!(names(&fs, "/empty"), Vec::<String>::new());
+            assert_eq!(read(&fs, "/dir/a.txt", 0), Ok(b"hello, world".to_vec()));
+            assert_eq!(read(&fs, "/dir/sub/c.txt", 0), Ok(b"new".to_vec()));
+            assert_eq!(read(&fs, "/b.txt", 0), Ok(b"world".to_vec()));
+            assert_eq!(attr("/dir/sub/c.txt").perm, 0o600);
+            assert_eq!(attr("/b.txt").perm, 0o640);
+            assert_eq!(attr("/empty").kind, FileType::Directory);
+            assert_eq!(attr("/empty").perm, 0o700);
+        }
+    }
+
+    #[test]
+    fn write_zip() {
+        let fs = open(archive(), Format::Zip);
+        modify(&fs);
+        let mut written = vec![];
+        fs.write_archive(&mut written).unwrap();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(written)).unwrap();
+        let mut read = |index: usize| {
+            let mut file = zip.by_index(index).unwrap();
+            let mut data = vec![];
+            file.read_to_end(&mut data).unwrap();
+            (
+                file.name().to_owned(),
+                String::from_utf8(data).unwrap(),
+                file.unix_mode().map(|mode| mode & 0o7777),
+            )
+        };
+        let entries: Vec<_> = (0..6).map(read).collect();
+
+        // Parents are written before their children.
+        assert_eq!(
+            entries,
+            [
+                ("b.txt".to_owned(), "world".to_owned(), Some(0o640)),
+                ("dir/".to_owned(), String::new(), Some(0o755)),
+                (
+                    "dir/a.txt".to_owned(),
+                    "hello, world".to_owned(),
+                    Some(0o755)
+                ),
+                ("dir/sub/".to_owned(), String::new(), Some(0o755)),
+                ("dir/sub/c.txt".to_owned(), "new".to_owned(), Some(0o600)),
+                ("empty/".to_owned(), String::new(), Some(0o700)),
+            ]
+        );
+        assert_eq!(zip.len(), 6);
+    }
+}
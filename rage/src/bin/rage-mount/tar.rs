@@ -1,35 +1,347 @@
-use age::{armor::ArmoredReader, stream::StreamReader};
 use fuse_mt::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufReader, Read, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Mutex;
-use tar::{Archive, Entry, EntryType};
+use tar::{Archive, Entry, EntryType, GnuExtSparseHeader, GnuSparseHeader, Header};
 use time::Timespec;
 
+#[cfg(target_os = "macos")]
+const ENOATTR: libc::c_int = libc::ENOATTR;
+#[cfg(not(target_os = "macos"))]
+const ENOATTR: libc::c_int = libc::ENODATA;
+
 fn tar_path(path: &Path) -> &Path {
     path.strip_prefix("/").unwrap()
 }
 
-fn tar_to_filetype<R: Read>(entry: &Entry<R>) -> Option<FileType> {
+/// Returns `path` without any `.` or leading `/` components, or `None` if it refers to
+/// a parent directory (and so could escape the mountpoint).
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => (),
+            Component::ParentDir => return None,
+        }
+    }
+    Some(normalized)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn tar_to_filetype(entry_type: EntryType) -> Option<FileType> {
     // Only map filetypes we support
-    match entry.header().entry_type() {
+    match entry_type {
         EntryType::Regular => Some(FileType::RegularFile),
         EntryType::Directory => Some(FileType::Directory),
         EntryType::Continuous => Some(FileType::RegularFile),
-        EntryType::GNULongName => Some(FileType::RegularFile),
+        EntryType::GNUSparse => Some(FileType::RegularFile),
+        EntryType::Symlink => Some(FileType::Symlink),
+        EntryType::Char => Some(FileType::CharDevice),
+        EntryType::Block => Some(FileType::BlockDevice),
+        EntryType::Fifo => Some(FileType::NamedPipe),
         _ => None,
     }
 }
 
-fn tar_to_fuse<R: Read>(entry: &Entry<R>) -> io::Result<FileAttr> {
-    let kind = tar_to_filetype(entry)
-        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Unsupported filetype"))?;
-    let perm = (entry.header().mode()? & 0o7777) as u16;
+/// Encodes a device number in the format used by the kernel.
+#[cfg(target_os = "macos")]
+fn makedev(major: u32, minor: u32) -> u32 {
+    (major << 24) | (minor & 0xff_ffff)
+}
+
+/// Encodes a device number in the format used by the kernel.
+#[cfg(not(target_os = "macos"))]
+fn makedev(major: u32, minor: u32) -> u32 {
+    ((minor & 0xfff00) << 12) | ((major & 0xfff) << 8) | (minor & 0xff)
+}
+
+/// The PAX extended header records of an entry, which override its header fields.
+struct PaxRecords(Vec<(String, Vec<u8>)>);
+
+impl PaxRecords {
+    fn read<R: Read>(entry: &mut Entry<'_, R>) -> io::Result<Self> {
+        let mut records = vec![];
+        if let Some(extensions) = entry.pax_extensions()? {
+            for extension in extensions {
+                let extension = extension?;
+                records.push((
+                    String::from_utf8_lossy(extension.key_bytes()).into_owned(),
+                    extension.value_bytes().to_vec(),
+                ));
+            }
+        }
+        Ok(PaxRecords(records))
+    }
+
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        // Later records take precedence.
+        self.0
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| &v[..])
+    }
+
+    fn get_u64(&self, key: &str) -> io::Result<Option<u64>> {
+        self.get(key)
+            .map(|value| parse_decimal(value, "Invalid PAX record"))
+            .transpose()
+    }
+
+    /// Parses a timestamp, which may have a fractional part.
+    fn get_time(&self, key: &str) -> io::Result<Option<Timespec>> {
+        self.get(key)
+            .map(|value| {
+                let value = std::str::from_utf8(value)
+                    .map_err(|_| invalid_data("Invalid PAX timestamp"))?;
+                let (sec, frac) = match value.find('.') {
+                    Some(i) => (&value[..i], &value[i + 1..]),
+                    None => (value, ""),
+                };
+                let sec = sec
+                    .parse()
+                    .map_err(|_| invalid_data("Invalid PAX timestamp"))?;
+                let nsec = frac
+                    .chars()
+                    .chain(std::iter::repeat('0'))
+                    .take(9)
+                    .collect::<String>()
+                    .parse()
+                    .map_err(|_| invalid_data("Invalid PAX timestamp"))?;
+                Ok(Timespec::new(sec, nsec))
+            })
+            .transpose()
+    }
+
+    /// Returns the extended attributes recorded in the `SCHILY.xattr` namespace.
+    fn xattrs(&self) -> Vec<(OsString, Vec<u8>)> {
+        let mut xattrs: Vec<(OsString, Vec<u8>)> = vec![];
+        for (key, value) in &self.0 {
+            if let Some(name) = key.strip_prefix("SCHILY.xattr.") {
+                xattrs.retain(|(n, _)| n != name);
+                xattrs.push((name.into(), value.clone()));
+            }
+        }
+        xattrs
+    }
+}
+
+fn parse_decimal(value: &[u8], msg: &str) -> io::Result<u64> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_data(msg))
+}
+
+/// A run of a file's contents that is stored in the archive.
+///
+/// The parts of a sparse file that are not covered by an extent are holes, and read as
+/// zeroes.
+#[derive(Clone, Copy)]
+struct Extent {
+    /// The offset of the run within the file.
+    offset: u64,
+    len: u64,
+    /// The position of the run within the archive.
+    pos: u64,
+}
+
+/// Lays out the runs of a sparse map, which are stored contiguously from `pos`.
+fn extents_from_map(map: &[(u64, u64)], mut pos: u64) -> Vec<Extent> {
+    map.iter()
+        .map(|&(offset, len)| {
+            let extent = Extent { offset, len, pos };
+            pos += len;
+            extent
+        })
+        .collect()
+}
+
+/// Reads the sparse map of a file in the GNU PAX 1.0 sparse format, which is stored at
+/// the start of its data. Returns the map and its padded length.
+fn read_sparse_map<R: Read>(data: &mut R) -> io::Result<(Vec<(u64, u64)>, u64)> {
+    let mut read = 0;
+    let mut next_number = || -> io::Result<u64> {
+        let mut digits = vec![];
+        loop {
+            let mut byte = [0];
+            data.read_exact(&mut byte)?;
+            read += 1;
+            if byte[0] == b'\n' {
+                break parse_decimal(&digits, "Invalid sparse map");
+            }
+            digits.push(byte[0]);
+        }
+    };
+
+    let count = next_number()?;
+    let map = (0..count)
+        .map(|_| Ok((next_number()?, next_number()?)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // The map is padded to a multiple of the block size.
+    Ok((map, (read + 511) / 512 * 512))
+}
+
+/// Returns the sparse map of a file in one of the GNU PAX sparse formats, the position
+/// of its first run, and its size. Returns `None` if the entry is not a sparse file.
+fn pax_sparse<R: Read>(
+    entry: &mut Entry<'_, R>,
+    pax: &PaxRecords,
+) -> io::Result<Option<(Vec<(u64, u64)>, u64, u64)>> {
+    let pos = entry.raw_file_position();
+    let (map, pos) = match (pax.get("GNU.sparse.major"), pax.get("GNU.sparse.minor")) {
+        // Format 1.0 stores the map at the start of the data.
+        (Some(b"1"), Some(b"0")) => {
+            let (map, len) = read_sparse_map(entry)?;
+            (map, pos + len)
+        }
+        // Format 0.1 stores the map in a single record.
+        _ if pax.get("GNU.sparse.map").is_some() => {
+            let numbers = pax
+                .get("GNU.sparse.map")
+                .unwrap()
+                .split(|&b| b == b',')
+                .map(|n| parse_decimal(n, "Invalid sparse map"))
+                .collect::<io::Result<Vec<_>>>()?;
+            if numbers.len() % 2 != 0 {
+                return Err(invalid_data("Invalid sparse map"));
+            }
+            (numbers.chunks(2).map(|r| (r[0], r[1])).collect(), pos)
+        }
+        // Format 0.0 stores each run in a pair of records.
+        _ if pax.get("GNU.sparse.offset").is_some() => {
+            let mut map = vec![];
+            let mut offset = None;
+            for (key, value) in &pax.0 {
+                match (key.as_str(), offset) {
+                    ("GNU.sparse.offset", None) => {
+                        offset = Some(parse_decimal(value, "Invalid sparse map")?)
+                    }
+                    ("GNU.sparse.numbytes", Some(o)) => {
+                        map.push((o, parse_decimal(value, "Invalid sparse map")?));
+                        offset = None;
+                    }
+                    ("GNU.sparse.offset", _) | ("GNU.sparse.numbytes", _) => {
+                        return Err(invalid_data("Invalid sparse map"))
+                    }
+                    _ => (),
+                }
+            }
+            (map, pos)
+        }
+        _ => return Ok(None),
+    };
+
+    let size = match pax.get_u64("GNU.sparse.realsize")? {
+        Some(size) => size,
+        None => pax
+            .get_u64("GNU.sparse.size")?
+            .ok_or_else(|| invalid_data("Sparse file is missing its size"))?,
+    };
+
+    Ok(Some((map, pos, size)))
+}
+
+/// Reads the sparse map of a file in the old GNU sparse format, from its header at
+/// `header_pos` and any extension headers that follow it. Returns the map and the
+/// position of its first run, which follows the extension headers.
+fn read_gnu_sparse_map<R: Read + Seek>(
+    stream: &mut R,
+    header_pos: u64,
+) -> io::Result<(Vec<(u64, u64)>, u64)> {
+    let mut header = Header::new_gnu();
+    stream.seek(SeekFrom::Start(header_pos))?;
+    stream.read_exact(header.as_mut_bytes())?;
+    let header = header
+        .as_gnu()
+        .ok_or_else(|| invalid_data("Sparse file has a non-GNU header"))?;
+
+    let mut map = vec![];
+    let mut add_runs = |runs: &[GnuSparseHeader]| -> io::Result<()> {
+        for run in runs.iter().take_while(|run| !run.is_empty()) {
+            map.push((run.offset()?, run.length()?));
+        }
+        Ok(())
+    };
+
+    add_runs(&header.sparse)?;
+    let mut data_pos = header_pos + 512;
+    let mut extended = header.is_extended();
+    while extended {
+        let mut ext = GnuExtSparseHeader::new();
+        stream.read_exact(ext.as_mut_bytes())?;
+        add_runs(ext.sparse())?;
+        data_pos += 512;
+        extended = ext.is_extended();
+    }
+
+    Ok((map, data_pos))
+}
+
+/// Reads up to `size` bytes of a file from `offset`.
+fn read_extents<R: Read + Seek>(
+    stream: &mut R,
+    file: &TarFile,
+    offset: u64,
+    size: usize,
+) -> io::Result<Vec<u8>> {
+    let end = u64::min(file.attr.size, offset.saturating_add(size as u64));
+    let mut buf = vec![0; end.saturating_sub(offset) as usize];
+
+    // Holes are left as zeroes.
+    for extent in &file.extents {
+        let start = u64::max(offset, extent.offset);
+        let stop = u64::min(end, extent.offset + extent.len);
+        if start < stop {
+            stream.seek(SeekFrom::Start(extent.pos + (start - extent.offset)))?;
+            stream.read_exact(&mut buf[(start - offset) as usize..(stop - offset) as usize])?;
+        }
+    }
+
+    Ok(buf)
+}
+
+/// A [`Read`] over the contents of a file, including any holes.
+struct FileReader<'a, R> {
+    stream: &'a mut R,
+    file: &'a TarFile,
+    offset: u64,
+}
+
+impl<'a, R: Read + Seek> Read for FileReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = read_extents(self.stream, self.file, self.offset, buf.len())?;
+        buf[..data.len()].copy_from_slice(&data);
+        self.offset += data.len() as u64;
+        Ok(data.len())
+    }
+}
 
-    let mtime = Timespec::new(entry.header().mtime()? as i64, 0);
-    let ctime = if let Some(header) = entry.header().as_gnu() {
+fn tar_to_fuse<R: Read>(
+    entry: &Entry<'_, R>,
+    pax: &PaxRecords,
+    kind: FileType,
+    size: u64,
+) -> io::Result<FileAttr> {
+    let header = entry.header();
+    let perm = (header.mode()? & 0o7777) as u16;
+
+    let mtime = match pax.get_time("mtime")? {
+        Some(mtime) => mtime,
+        None => Timespec::new(header.mtime()? as i64, 0),
+    };
+    let ctime = if let Some(ctime) = pax.get_time("ctime")? {
+        ctime
+    } else if let Some(header) = header.as_gnu() {
         header
             .ctime()
             .map(|ctime| Timespec::new(ctime as i64, 0))
@@ -37,7 +349,9 @@ fn tar_to_fuse<R: Read>(entry: &Entry<R>) -> io::Result<FileAttr> {
     } else {
         mtime
     };
-    let atime = if let Some(header) = entry.header().as_gnu() {
+    let atime = if let Some(atime) = pax.get_time("atime")? {
+        atime
+    } else if let Some(header) = header.as_gnu() {
         header
             .atime()
             .map(|atime| Timespec::new(atime as i64, 0))
@@ -46,8 +360,16 @@ fn tar_to_fuse<R: Read>(entry: &Entry<R>) -> io::Result<FileAttr> {
         mtime
     };
 
+    let rdev = match kind {
+        FileType::CharDevice | FileType::BlockDevice => makedev(
+            header.device_major()?.unwrap_or(0),
+            header.device_minor()?.unwrap_or(0),
+        ),
+        _ => 0,
+    };
+
     Ok(FileAttr {
-        size: entry.header().size()?,
+        size,
         blocks: 1,
         atime,
         mtime,
@@ -56,9 +378,9 @@ fn tar_to_fuse<R: Read>(entry: &Entry<R>) -> io::Result<FileAttr> {
         kind,
         perm,
         nlink: 1,
-        uid: entry.header().uid()? as u32,
-        gid: entry.header().gid()? as u32,
-        rdev: 0,
+        uid: pax.get_u64("uid")?.map_or_else(|| header.uid(), Ok)? as u32,
+        gid: pax.get_u64("gid")?.map_or_else(|| header.gid(), Ok)? as u32,
+        rdev,
         flags: 0,
     })
 }
@@ -94,88 +416,285 @@ fn add_to_dir_map(
         .expect("paths should have parents")
         .to_path_buf();
 
-    // tar files are listed in-order, so the parent has already been added.
-    dir_map
-        .entry(parent)
-        .or_default()
-        .push(DirectoryEntry { name, kind });
+    // Archives don't have to list parent directories before their contents (or at all).
+    if !dir_map.contains_key(&parent) {
+        add_to_dir_map(dir_map, &parent, FileType::Directory);
+    }
+
+    // Later entries for the same path replace earlier ones, as when extracting.
+    let entries = dir_map.entry(parent).or_default();
+    entries.retain(|entry| entry.name != name);
+    entries.push(DirectoryEntry { name, kind });
+}
+
+/// A file in the archive.
+#[derive(Clone)]
+struct TarFile {
+    attr: FileAttr,
+    extents: Vec<Extent>,
+    /// The target of a symbolic link.
+    link_target: Option<Vec<u8>>,
+    xattrs: Vec<(OsString, Vec<u8>)>,
+    /// Identifies the underlying file, which is shared by hard links to it.
+    inode: u64,
 }
 
-type OpenFile = (PathBuf, u64, u64);
+impl TarFile {
+    fn directory(attr: FileAttr, inode: u64) -> Self {
+        TarFile {
+            attr,
+            extents: vec![],
+            link_target: None,
+            xattrs: vec![],
+            inode,
+        }
+    }
+}
 
-pub struct AgeTarFs {
-    inner: Mutex<StreamReader<ArmoredReader<BufReader<File>>>>,
+pub struct AgeTarFs<R> {
+    inner: Mutex<R>,
     dir_map: HashMap<PathBuf, Vec<DirectoryEntry>>,
-    file_map: HashMap<PathBuf, (FileAttr, u64)>,
+    file_map: HashMap<PathBuf, TarFile>,
+    /// Whether the archive contains entries that `--read-write` can't preserve.
+    unsupported: bool,
     open_dirs: Mutex<(HashMap<u64, PathBuf>, u64)>,
-    open_files: Mutex<(HashMap<u64, OpenFile>, u64)>,
+    open_files: Mutex<(HashMap<u64, PathBuf>, u64)>,
 }
 
-impl AgeTarFs {
-    pub fn open(stream: StreamReader<ArmoredReader<BufReader<File>>>) -> io::Result<Self> {
+impl<R: Read + Seek> AgeTarFs<R> {
+    pub fn open(stream: R) -> io::Result<Self> {
         // Build a directory listing for the archive
         let mut dir_map: HashMap<PathBuf, Vec<DirectoryEntry>> = HashMap::new();
         dir_map.insert(PathBuf::new(), vec![]); // the root
 
         // Build a file map for the archive
-        let mut file_map: HashMap<PathBuf, (FileAttr, u64)> = HashMap::new();
+        let mut file_map: HashMap<PathBuf, TarFile> = HashMap::new();
+        let mut unsupported = false;
+        let mut next_inode = 0;
+
+        // Entries that we can only finish indexing once we've seen the whole archive.
+        let mut gnu_sparse_files = vec![];
+        let mut hard_links = vec![];
 
         let mut archive = Archive::new(stream);
-        for file in archive.entries().expect("StreamReader is at start") {
-            let file = file?;
-            if let Some(filetype) = tar_to_filetype(&file) {
-                let path = file.path()?;
-                add_to_dir_map(&mut dir_map, &path, filetype);
-                file_map.insert(
-                    path.to_path_buf(),
-                    (tar_to_fuse(&file)?, file.raw_file_position()),
-                );
+        for entry in archive.entries().expect("stream is at start") {
+            let mut entry = entry?;
+            let pax = PaxRecords::read(&mut entry)?;
+            let entry_type = entry.header().entry_type();
+
+            // Sparse files in the PAX formats are stored under a placeholder name.
+            let path = match pax.get("GNU.sparse.name") {
+                Some(name) => Cow::Borrowed(Path::new(OsStr::from_bytes(name))),
+                None => entry.path()?,
+            };
+            let path = match normalize(&path) {
+                Some(path) => path,
+                None => {
+                    unsupported = true;
+                    continue;
+                }
+            };
+
+            if entry_type == EntryType::Link {
+                match entry.link_name()?.and_then(|target| normalize(&target)) {
+                    Some(target) => hard_links.push((path, target)),
+                    None => unsupported = true,
+                }
+                continue;
+            }
+
+            let kind = match tar_to_filetype(entry_type) {
+                Some(kind) => kind,
+                None => {
+                    // Global PAX headers only hold defaults for the entries that follow.
+                    unsupported |= !entry_type.is_pax_global_extensions();
+                    continue;
+                }
+            };
+
+            let (extents, size) = if entry_type == EntryType::GNUSparse {
+                let header = entry
+                    .header()
+                    .as_gnu()
+                    .ok_or_else(|| invalid_data("Sparse file has a non-GNU header"))?;
+                // The sparse map may continue past the header, so read it later.
+                gnu_sparse_files.push((path.clone(), entry.raw_header_position()));
+                (vec![], header.real_size()?)
+            } else if let Some((map, pos, size)) = pax_sparse(&mut entry, &pax)? {
+                (extents_from_map(&map, pos), size)
+            } else if kind == FileType::RegularFile {
+                let size = pax
+                    .get_u64("size")?
+                    .map_or_else(|| entry.header().size(), Ok)?;
+                let extent = Extent {
+                    offset: 0,
+                    len: size,
+                    pos: entry.raw_file_position(),
+                };
+                (vec![extent], size)
+            } else {
+                (vec![], 0)
+            };
+
+            let link_target = if kind == FileType::Symlink {
+                entry.link_name_bytes().map(|target| target.into_owned())
+            } else {
+                None
+            };
+            let xattrs = pax.xattrs();
+
+            // We can only write back regular files and directories, without any of
+            // their extended attributes.
+            unsupported |=
+                !xattrs.is_empty() || !matches!(kind, FileType::RegularFile | FileType::Directory);
+
+            let file = TarFile {
+                attr: tar_to_fuse(&entry, &pax, kind, size)?,
+                extents,
+                link_target,
+                xattrs,
+                inode: next_inode,
+            };
+            next_inode += 1;
+
+            // The archive may contain an entry for the root directory itself.
+            if path.parent().is_some() {
+                add_to_dir_map(&mut dir_map, &path, kind);
+            }
+            file_map.insert(path, file);
+        }
+
+        let mut stream = archive.into_inner();
+        for (path, header_pos) in gnu_sparse_files {
+            let (map, data_pos) = read_gnu_sparse_map(&mut stream, header_pos)?;
+            if let Some(file) = file_map.get_mut(&path) {
+                file.extents = extents_from_map(&map, data_pos);
+            }
+        }
+
+        // Hard links share everything with their target.
+        unsupported |= !hard_links.is_empty();
+        for (path, target) in hard_links {
+            match file_map.get(&target) {
+                Some(file) if file.attr.kind != FileType::Directory => {
+                    let file = file.clone();
+                    add_to_dir_map(&mut dir_map, &path, file.attr.kind);
+                    file_map.insert(path, file);
+                }
+                _ => (),
             }
         }
 
+        // Directories that the archive only implies still need attributes.
+        for path in dir_map.keys() {
+            if !file_map.contains_key(path) {
+                file_map.insert(path.clone(), TarFile::directory(ROOT_ATTR, next_inode));
+                next_inode += 1;
+            }
+        }
+
+        let mut links: HashMap<u64, u32> = HashMap::new();
+        for file in file_map.values() {
+            *links.entry(file.inode).or_default() += 1;
+        }
+        for file in file_map.values_mut() {
+            file.attr.nlink = links[&file.inode];
+        }
+
         Ok(AgeTarFs {
-            inner: Mutex::new(archive.into_inner()),
+            inner: Mutex::new(stream),
             dir_map,
             file_map,
+            unsupported,
             open_dirs: Mutex::new((HashMap::new(), 0)),
             open_files: Mutex::new((HashMap::new(), 0)),
         })
     }
 }
 
+impl<R: Read + Seek + Send + 'static> crate::rw::Archive for AgeTarFs<R> {
+    fn entries(&self) -> Vec<(PathBuf, FileAttr)> {
+        self.file_map
+            .iter()
+            .filter(|(_, file)| {
+                matches!(file.attr.kind, FileType::RegularFile | FileType::Directory)
+            })
+            .map(|(path, file)| (path.clone(), file.attr))
+            .collect()
+    }
+
+    fn has_unsupported_entries(&self) -> bool {
+        self.unsupported
+    }
+
+    fn read_at(&self, path: &Path, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let file = self
+            .file_map
+            .get(path)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let mut inner = self.inner.lock().unwrap();
+
+        read_extents(&mut *inner, file, offset, size)
+    }
+
+    fn read_file(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(&mut dyn Read) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let file = self
+            .file_map
+            .get(path)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let mut inner = self.inner.lock().unwrap();
+
+        f(&mut FileReader {
+            stream: &mut *inner,
+            file,
+            offset: 0,
+        })
+    }
+}
+
+/// Returns the size of an extended attribute value or list, or the data itself if it
+/// fits in `size` bytes.
+fn xattr_reply(data: Vec<u8>, size: u32) -> ResultXattr {
+    if size == 0 {
+        Ok(Xattr::Size(data.len() as u32))
+    } else if data.len() <= size as usize {
+        Ok(Xattr::Data(data))
+    } else {
+        Err(libc::ERANGE)
+    }
+}
+
 const TTL: Timespec = Timespec { sec: 1, nsec: 0 };
 
-impl FilesystemMT for AgeTarFs {
+impl<R: Read + Seek + Send + 'static> FilesystemMT for AgeTarFs<R> {
     fn getattr(&self, _req: RequestInfo, path: &Path, fh: Option<u64>) -> ResultEntry {
         let open_dirs = self.open_dirs.lock().unwrap();
         let open_files = self.open_files.lock().unwrap();
 
-        if let Some(fh) = fh {
-            if let Some((attr, _)) = open_dirs
+        let path = if let Some(fh) = fh {
+            open_dirs
                 .0
                 .get(&fh)
-                .and_then(|path| self.file_map.get(path))
-            {
-                Ok((TTL, *attr))
-            } else if let Some((attr, _)) = open_files
-                .0
-                .get(&fh)
-                .and_then(|(path, _, _)| self.file_map.get(path))
-            {
-                Ok((TTL, *attr))
-            } else {
-                Err(libc::EBADF)
-            }
+                .or_else(|| open_files.0.get(&fh))
+                .ok_or(libc::EBADF)?
+                .as_path()
         } else {
-            let path = tar_path(path);
-            if path.parent().is_none() {
-                Ok((TTL, ROOT_ATTR))
-            } else if let Some((attr, _)) = self.file_map.get(path) {
-                Ok((TTL, *attr))
-            } else {
-                Err(libc::ENOENT)
-            }
-        }
+            tar_path(path)
+        };
+
+        self.file_map
+            .get(path)
+            .map(|file| (TTL, file.attr))
+            .ok_or(libc::ENOENT)
+    }
+
+    fn readlink(&self, _req: RequestInfo, path: &Path) -> ResultData {
+        let file = self.file_map.get(tar_path(path)).ok_or(libc::ENOENT)?;
+        file.link_target.clone().ok_or(libc::EINVAL)
     }
 
     fn opendir(&self, _req: RequestInfo, path: &Path, _flags: u32) -> ResultOpen {
@@ -219,14 +738,29 @@ impl FilesystemMT for AgeTarFs {
         })
     }
 
+    fn getxattr(&self, _req: RequestInfo, path: &Path, name: &OsStr, size: u32) -> ResultXattr {
+        let file = self.file_map.get(tar_path(path)).ok_or(libc::ENOENT)?;
+        let (_, value) = file.xattrs.iter().find(|(n, _)| n == name).ok_or(ENOATTR)?;
+        xattr_reply(value.clone(), size)
+    }
+
+    fn listxattr(&self, _req: RequestInfo, path: &Path, size: u32) -> ResultXattr {
+        let file = self.file_map.get(tar_path(path)).ok_or(libc::ENOENT)?;
+        let mut names = vec![];
+        for (name, _) in &file.xattrs {
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        xattr_reply(names, size)
+    }
+
     fn open(&self, _req: RequestInfo, path: &Path, _flags: u32) -> ResultOpen {
         let mut open_files = self.open_files.lock().unwrap();
+        let path = tar_path(path);
 
-        if let Some((attr, pos)) = self.file_map.get(tar_path(path)) {
+        if self.file_map.contains_key(path) {
             let fh = open_files.1;
-            open_files
-                .0
-                .insert(fh, (path.to_path_buf(), *pos, attr.size));
+            open_files.0.insert(fh, path.to_path_buf());
             open_files.1 = open_files.1.wrapping_add(1);
             Ok((fh, 0))
         } else {
@@ -246,22 +780,17 @@ impl FilesystemMT for AgeTarFs {
         let mut inner = self.inner.lock().unwrap();
         let open_files = self.open_files.lock().unwrap();
 
-        if let Some((_, pos, file_size)) = open_files.0.get(&fh) {
-            if offset > *file_size {
+        if let Some(file) = open_files
+            .0
+            .get(&fh)
+            .and_then(|path| self.file_map.get(path))
+        {
+            if offset > file.attr.size {
                 return callback(Err(libc::EINVAL));
             }
 
-            // Skip to offset
-            if inner.seek(SeekFrom::Start(pos + offset)).is_err() {
-                return callback(Err(libc::EIO));
-            }
-
-            // Read bytes
-            let to_read = usize::min(size as usize, (file_size - offset) as usize);
-            let mut buf = vec![];
-            buf.resize(to_read, 0);
-            match inner.read_exact(&mut buf) {
-                Ok(_) => callback(Ok(&buf)),
+            match read_extents(&mut *inner, file, offset, size as usize) {
+                Ok(buf) => callback(Ok(&buf)),
                 Err(_) => callback(Err(libc::EIO)),
             }
         } else {
@@ -283,3 +812,178 @@ impl FilesystemMT for AgeTarFs {
         open_files.0.remove(&fh).map(|_| ()).ok_or(libc::EBADF)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use flate2::read::GzDecoder;
+    use fuse_mt::{FileType, FilesystemMT, RequestInfo, Xattr};
+    use std::ffi::OsStr;
+    use std::io::{Cursor, Read};
+    use std::path::Path;
+
+    use super::{makedev, AgeTarFs};
+    use crate::rw::Archive;
+
+    // These archives contain the same tree, created by GNU tar in its own format and
+    // the PAX format, and by bsdtar.
+    const GNU_TAR: &[u8] = include_bytes!("../../../tests/testdata/gnu.tar.gz");
+    const GNU_PAX_TAR: &[u8] = include_bytes!("../../../tests/testdata/gnu-pax.tar.gz");
+    const BSDTAR_TAR: &[u8] = include_bytes!("../../../tests/testdata/bsdtar.tar.gz");
+
+    const REQ: RequestInfo = RequestInfo {
+        unique: 0,
+        uid: 0,
+        gid: 0,
+        pid: 0,
+    };
+
+    fn open(archive: &[u8]) -> AgeTarFs<Cursor<Vec<u8>>> {
+        let mut tar = vec![];
+        GzDecoder::new(archive).read_to_end(&mut tar).unwrap();
+        AgeTarFs::open(Cursor::new(tar)).unwrap()
+    }
+
+    fn long_path() -> String {
+        format!("{0}/{0}/{1}.txt", "d".repeat(60), "f".repeat(70))
+    }
+
+    fn check_tree(fs: &AgeTarFs<Cursor<Vec<u8>>>) {
+        let attr = |path: &str| fs.getattr(REQ, Path::new(path), None).unwrap().1;
+
+        let (fh, _) = fs.opendir(REQ, Path::new("/"), 0).unwrap();
+        let mut names: Vec<_> = fs
+            .readdir(REQ, Path::new("/"), fh)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name.into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "chardev",
+                "d".repeat(60).as_str(),
+                "fifo",
+                "file.txt",
+                "hard",
+                "link",
+                "longlink",
+                "sparse.bin"
+            ]
+        );
+
+        let file = attr("/file.txt");
+        assert_eq!(file.kind, FileType::RegularFile);
+        assert_eq!(file.size, 6);
+        assert_eq!(file.mtime.sec, 1_600_000_000);
+        assert_eq!(file.nlink, 2);
+        assert_eq!(
+            fs.read_at(Path::new("file.txt"), 0, 100).unwrap(),
+            b"hello\n"
+        );
+
+        // Hard links share their target's contents and attributes.
+        assert_eq!(attr("/hard").nlink, 2);
+        assert_eq!(fs.read_at(Path::new("hard"), 0, 100).unwrap(), b"hello\n");
+
+        assert_eq!(attr("/link").kind, FileType::Symlink);
+        assert_eq!(fs.readlink(REQ, Path::new("/link")).unwrap(), b"file.txt");
+        assert_eq!(
+            fs.readlink(REQ, Path::new("/longlink")).unwrap(),
+            long_path().as_bytes()
+        );
+        assert_eq!(fs.readlink(REQ, Path::new("/file.txt")), Err(libc::EINVAL));
+
+        // Names longer than the header fields are stored in extension headers.
+        assert_eq!(
+            attr(&format!("/{}", "d".repeat(60))).kind,
+            FileType::Directory
+        );
+        assert_eq!(
+            fs.read_at(Path::new(&long_path()), 0, 100).unwrap(),
+            b"long\n"
+        );
+
+        assert_eq!(attr("/fifo").kind, FileType::NamedPipe);
+        let chardev = attr("/chardev");
+        assert_eq!(chardev.kind, FileType::CharDevice);
+        assert_eq!(chardev.rdev, makedev(1, 3));
+
+        // Holes in sparse files read as zeroes.
+        let sparse = Path::new("sparse.bin");
+        assert_eq!(attr("/sparse.bin").size, 1024 * 1024);
+        assert_eq!(fs.read_at(sparse, 0, 5).unwrap(), b"start");
+        assert_eq!(fs.read_at(sparse, 5, 1000).unwrap(), vec![0; 1000]);
+        assert_eq!(fs.read_at(sparse, 512 * 1024, 6).unwrap(), b"middle");
+        assert_eq!(fs.read_at(sparse, 1024 * 1024 - 3, 100).unwrap(), b"end");
+
+        let mut contents = vec![];
+        fs.read_file(sparse, &mut |r: &mut dyn Read| {
+            r.read_to_end(&mut contents).map(|_| ())
+        })
+        .unwrap();
+        assert_eq!(contents.len(), 1024 * 1024);
+        assert_eq!(&contents[512 * 1024..512 * 1024 + 6], b"middle");
+
+        // None of the special files can be written back to the archive.
+        assert!(fs.has_unsupported_entries());
+    }
+
+    fn check_xattrs(fs: &AgeTarFs<Cursor<Vec<u8>>>) {
+        let path = Path::new("/file.txt");
+        let name = OsStr::new("user.comment");
+
+        match fs.listxattr(REQ, path, 0) {
+            Ok(Xattr::Size(size)) => assert_eq!(size, 13),
+            _ => panic!("expected a size"),
+        }
+        match fs.listxattr(REQ, path, 13) {
+            Ok(Xattr::Data(names)) => assert_eq!(names, b"user.comment\0"),
+            _ => panic!("expected data"),
+        }
+
+        match fs.getxattr(REQ, path, name, 0) {
+            Ok(Xattr::Size(size)) => assert_eq!(size, 11),
+            _ => panic!("expected a size"),
+        }
+        match fs.getxattr(REQ, path, name, 11) {
+            Ok(Xattr::Data(value)) => assert_eq!(value, b"hello xattr"),
+            _ => panic!("expected data"),
+        }
+        assert!(matches!(fs.getxattr(REQ, path, name, 5), Err(libc::ERANGE)));
+        assert!(matches!(
+            fs.getxattr(REQ, path, OsStr::new("user.missing"), 0),
+            Err(super::ENOATTR)
+        ));
+    }
+
+    #[test]
+    fn gnu_tar() {
+        let fs = open(GNU_TAR);
+        check_tree(&fs);
+        assert_eq!(
+            fs.getattr(REQ, Path::new("/file.txt"), None).unwrap().1.uid,
+            1000
+        );
+
+        // The GNU format can't store extended attributes.
+        match fs.listxattr(REQ, Path::new("/file.txt"), 0) {
+            Ok(Xattr::Size(size)) => assert_eq!(size, 0),
+            _ => panic!("expected a size"),
+        }
+    }
+
+    #[test]
+    fn gnu_pax_tar() {
+        let fs = open(GNU_PAX_TAR);
+        check_tree(&fs);
+        check_xattrs(&fs);
+    }
+
+    #[test]
+    fn bsdtar_tar() {
+        let fs = open(BSDTAR_TAR);
+        check_tree(&fs);
+        check_xattrs(&fs);
+    }
+}
@@ -116,6 +116,85 @@ impl AgeZipFs {
     }
 }
 
+/// The filetype bits of a Unix mode.
+const S_IFMT: u32 = 0o170000;
+/// The filetype of a symbolic link.
+const S_IFLNK: u32 = 0o120000;
+
+fn zip_err(e: zip::result::ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+impl AgeZipFs {
+    /// Returns the index of the file at `path` in the archive.
+    fn index_of(
+        inner: &mut ZipArchive<StreamReader<ArmoredReader<BufReader<File>>>>,
+        path: &Path,
+    ) -> io::Result<usize> {
+        for i in 0..inner.len() {
+            if inner.by_index(i).map_err(zip_err)?.enclosed_name() == Some(path) {
+                return Ok(i);
+            }
+        }
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+impl crate::rw::Archive for AgeZipFs {
+    fn entries(&self) -> Vec<(PathBuf, FileAttr)> {
+        let mut inner = self.inner.lock().unwrap();
+
+        (0..inner.len())
+            .filter_map(|i| {
+                let zf = inner.by_index(i).ok()?;
+                zf.enclosed_name()
+                    .map(|path| (path.to_path_buf(), zipfile_to_fuse(&zf)))
+            })
+            .collect()
+    }
+
+    fn has_unsupported_entries(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+
+        // Entries with unsafe paths aren't mounted, and symbolic links are mounted as
+        // regular files.
+        (0..inner.len()).any(|i| match inner.by_index(i) {
+            Ok(zf) => {
+                zf.enclosed_name().is_none()
+                    || zf
+                        .unix_mode()
+                        .map_or(false, |mode| mode & S_IFMT == S_IFLNK)
+            }
+            Err(_) => true,
+        })
+    }
+
+    fn read_at(&self, path: &Path, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = Self::index_of(&mut inner, path)?;
+        let mut zf = inner.by_index(index).map_err(zip_err)?;
+
+        // Skip to offset
+        io::copy(&mut (&mut zf).take(offset), &mut io::sink())?;
+
+        let mut buf = vec![];
+        (&mut zf).take(size as u64).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_file(
+        &self,
+        path: &Path,
+        f: &mut dyn FnMut(&mut dyn Read) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = Self::index_of(&mut inner, path)?;
+        let mut zf = inner.by_index(index).map_err(zip_err)?;
+
+        f(&mut zf)
+    }
+}
+
 const TTL: Timespec = Timespec { sec: 1, nsec: 0 };
 
 impl FilesystemMT for AgeZipFs {
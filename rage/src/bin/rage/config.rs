@@ -0,0 +1,334 @@
+//! The `rage` configuration file.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{is_separator, Path, PathBuf};
+
+use crate::{error, AgeOptions};
+
+const CONFIG_DIR: &str = "rage";
+const CONFIG_FILE: &str = "config.toml";
+
+/// Prefix of a recipient alias on the command line, as in `-r @ops`.
+const ALIAS_PREFIX: char = '@';
+
+/// One or more recipients that a recipient alias expands to.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AliasRecipients {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl AliasRecipients {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            AliasRecipients::One(r) => std::slice::from_ref(r),
+            AliasRecipients::Many(r) => r,
+        }
+    }
+}
+
+/// The contents of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Config {
+    /// Identity files to decrypt with when neither `-i` nor `-j` is given.
+    pub(crate) identities: Vec<String>,
+    /// Whether to encrypt to the armored format by default.
+    armor: bool,
+    /// The maximum work factor to allow for passphrase decryption, when
+    /// `--max-work-factor` is not given.
+    max_work_factor: Option<u8>,
+    /// Named recipients, or groups of recipients, that can be used as `-r @NAME`.
+    recipients: BTreeMap<String, AliasRecipients>,
+}
+
+impl Config {
+    /// Loads the configuration file, if it exists.
+    pub(crate) fn load() -> Result<Self, error::ConfigError> {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&path, &contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(error::ConfigError::Io(path.display().to_string(), e)),
+        }
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<Self, error::ConfigError> {
+        let mut config: Config = toml::from_str(contents).map_err(|e| {
+            // Point at the line that failed to parse, if we know it.
+            let line = e.line_col().and_then(|(line, _)| {
+                contents
+                    .lines()
+                    .nth(line)
+                    .map(|text| (line + 1, text.to_owned()))
+            });
+            error::ConfigError::Parse {
+                path: path.display().to_string(),
+                line,
+                err: e.to_string(),
+            }
+        })?;
+
+        // Relative identity paths are relative to the directory of the config file.
+        let config_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for identity in &mut config.identities {
+            *identity = resolve_path(config_dir, identity).display().to_string();
+        }
+
+        Ok(config)
+    }
+
+    /// Applies the defaults in this configuration to the given options, and expands any
+    /// recipient aliases in them.
+    pub(crate) fn apply(&self, mut opts: AgeOptions) -> Result<AgeOptions, error::ConfigError> {
+        opts.recipient = self.expand_recipients(std::mem::take(&mut opts.recipient))?;
+
+        if opts.max_work_factor.is_none() {
+            opts.max_work_factor = self.max_work_factor;
+        }
+
+        // Armor only applies to modes that write an age file.
+        if self.armor && !(opts.decrypt || opts.inspect || opts.who_can_decrypt) {
+            opts.armor = true;
+        }
+
+        Ok(opts)
+    }
+
+    /// Replaces every recipient alias in `recipients` with the recipients it refers to.
+    fn expand_recipients(
+        &self,
+        recipients: Vec<String>,
+    ) -> Result<Vec<String>, error::ConfigError> {
+        let mut expanded = vec![];
+        for recipient in recipients {
+            if recipient.starts_with(ALIAS_PREFIX) {
+                self.expand_alias(&recipient, &mut vec![], &mut expanded)?;
+            } else {
+                expanded.push(recipient);
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Expands the recipient alias `alias` (including its prefix) into `expanded`.
+    ///
+    /// `seen` contains the aliases that are currently being expanded, so that we can
+    /// detect aliases that refer to themselves.
+    fn expand_alias(
+        &self,
+        alias: &str,
+        seen: &mut Vec<String>,
+        expanded: &mut Vec<String>,
+    ) -> Result<(), error::ConfigError> {
+        if seen.iter().any(|s| s == alias) {
+            return Err(error::ConfigError::AliasCycle(alias.to_owned()));
+        }
+
+        let recipients = self
+            .recipients
+            .get(&alias[ALIAS_PREFIX.len_utf8()..])
+            .ok_or_else(|| error::ConfigError::UnknownAlias(alias.to_owned()))?;
+
+        seen.push(alias.to_owned());
+        for recipient in recipients.as_slice() {
+            if recipient.starts_with(ALIAS_PREFIX) {
+                self.expand_alias(recipient, seen, expanded)?;
+            } else {
+                expanded.push(recipient.clone());
+            }
+        }
+        seen.pop();
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the configuration file.
+///
+/// This is `$XDG_CONFIG_HOME/rage/config.toml` (defaulting to `~/.config`), or
+/// `%APPDATA%\rage\config.toml` on Windows.
+fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/// Returns the user's configuration directory.
+#[cfg(not(windows))]
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".config")))
+}
+
+/// Returns the user's configuration directory.
+#[cfg(windows)]
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+/// Returns the user's home directory.
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    #[cfg(not(windows))]
+    let home = std::env::var_os("HOME");
+    #[cfg(windows)]
+    let home = std::env::var_os("USERPROFILE");
+
+    home.filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+/// Resolves `path` relative to `base`, expanding a leading `~` to the home directory.
+fn resolve_path(base: &Path, path: &str) -> PathBuf {
+    let expanded = match (path.strip_prefix("~"), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(is_separator) => {
+            home.join(rest.trim_start_matches(is_separator))
+        }
+        _ => PathBuf::from(path),
+    };
+    base.join(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{home_dir, resolve_path, Config};
+    use crate::error::ConfigError;
+
+    const ALICE: &str = "age1t7rxyev2z3rw82stdlrrepyc39nvn86l5078zqkf5uasdy86jp6svpy7pa";
+    const BOB: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+
+    fn parse(contents: &str) -> Config {
+        match Config::parse(Path::new("/config/rage/config.toml"), contents) {
+            Ok(config) => config,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn expand(config: &Config, recipients: &[&str]) -> Result<Vec<String>, ConfigError> {
+        config.expand_recipients(recipients.iter().map(|r| r.to_string()).collect())
+    }
+
+    fn expanded(config: &Config, recipients: &[&str]) -> Vec<String> {
+        match expand(config, recipients) {
+            Ok(expanded) => expanded,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn malformed_config() {
+        let contents = "armor = true\nidentities = \"a\" \"b\"\n";
+        match Config::parse(Path::new("config.toml"), contents) {
+            Err(ConfigError::Parse { path, line, .. }) => {
+                assert_eq!(path, "config.toml");
+                assert_eq!(line, Some((2, "identities = \"a\" \"b\"".to_owned())));
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        assert!(matches!(
+            Config::parse(Path::new("config.toml"), "unknown = 1\n"),
+            Err(ConfigError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn nested_aliases() {
+        let config = parse(&format!(
+            r#"
+[recipients]
+alice = "{}"
+bob = ["{}"]
+ops = ["@alice", "@bob"]
+all = ["@ops", "@alice"]
+"#,
+            ALICE, BOB,
+        ));
+
+        assert_eq!(expanded(&config, &[BOB]), [BOB]);
+        assert_eq!(expanded(&config, &["@alice"]), [ALICE]);
+        assert_eq!(expanded(&config, &["@ops"]), [ALICE, BOB]);
+        assert_eq!(expanded(&config, &["@all", BOB]), [ALICE, BOB, ALICE, BOB]);
+    }
+
+    #[test]
+    fn unknown_alias() {
+        let config = parse(&format!("[recipients]\nops = [\"{}\", \"@bob\"]\n", ALICE));
+
+        assert!(matches!(
+            expand(&config, &["@alice"]),
+            Err(ConfigError::UnknownAlias(alias)) if alias == "@alice"
+        ));
+        assert!(matches!(
+            expand(&config, &["@ops"]),
+            Err(ConfigError::UnknownAlias(alias)) if alias == "@bob"
+        ));
+    }
+
+    #[test]
+    fn alias_cycle() {
+        let config = parse(
+            r#"
+[recipients]
+self = "@self"
+a = ["@b"]
+b = ["@c"]
+c = "@a"
+"#,
+        );
+
+        assert!(matches!(
+            expand(&config, &["@self"]),
+            Err(ConfigError::AliasCycle(alias)) if alias == "@self"
+        ));
+        assert!(matches!(
+            expand(&config, &["@b"]),
+            Err(ConfigError::AliasCycle(alias)) if alias == "@b"
+        ));
+    }
+
+    #[test]
+    fn identity_paths() {
+        let base = Path::new("/config/rage");
+
+        assert_eq!(
+            resolve_path(base, "keys.txt"),
+            Path::new("/config/rage/keys.txt")
+        );
+        assert_eq!(
+            resolve_path(base, "../age/keys.txt"),
+            Path::new("/config/rage/../age/keys.txt")
+        );
+        // `~user` is not expanded.
+        assert_eq!(
+            resolve_path(base, "~user/keys.txt"),
+            Path::new("/config/rage/~user/keys.txt")
+        );
+        if let Some(home) = home_dir() {
+            assert_eq!(resolve_path(base, "~"), home);
+            assert_eq!(resolve_path(base, "~/keys.txt"), home.join("keys.txt"));
+        }
+
+        #[cfg(unix)]
+        assert_eq!(
+            resolve_path(base, "/etc/keys.txt"),
+            Path::new("/etc/keys.txt")
+        );
+
+        // Identity paths in the config file are resolved relative to its directory.
+        let config = parse("identities = [\"keys.txt\"]\n");
+        assert_eq!(
+            Path::new(&config.identities[0]),
+            Path::new("/config/rage/keys.txt")
+        );
+    }
+}
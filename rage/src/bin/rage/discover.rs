@@ -0,0 +1,274 @@
+//! Discovery of default identities, for decrypting without `-i` or `-j`.
+
+use age::{cli_common::read_identities, decryptor::HeaderInfo, DecryptError, Identity};
+use age_core::format::{FileKey, Stanza};
+use log::{debug, info};
+use std::path::PathBuf;
+
+use crate::{config, LANGUAGE_LOADER};
+
+const SSH_ED25519_TAG: &str = "ssh-ed25519";
+const SSH_RSA_TAG: &str = "ssh-rsa";
+const GREASE_SUFFIX: &str = "-grease";
+
+/// A well-known location of an identity file.
+struct DefaultIdentityFile {
+    path: PathBuf,
+    /// Returns `true` if an identity in this file might be able to unwrap a stanza with
+    /// the given tag.
+    unwraps: fn(&str) -> bool,
+}
+
+/// Returns the well-known identity file locations, in the order they are tried.
+fn default_identity_files() -> Vec<DefaultIdentityFile> {
+    let mut files = vec![];
+
+    // The identity file conventionally used by age.
+    if let Some(dir) = config::config_dir() {
+        files.push(DefaultIdentityFile {
+            path: dir.join("age").join("keys.txt"),
+            // This can contain native or plugin identities.
+            unwraps: |tag| tag != SSH_ED25519_TAG && tag != SSH_RSA_TAG,
+        });
+    }
+
+    // The default SSH keys.
+    #[cfg(feature = "ssh")]
+    if let Some(home) = config::home_dir() {
+        let ssh_dir = home.join(".ssh");
+        files.push(DefaultIdentityFile {
+            path: ssh_dir.join("id_ed25519"),
+            unwraps: |tag| tag == SSH_ED25519_TAG,
+        });
+        files.push(DefaultIdentityFile {
+            path: ssh_dir.join("id_rsa"),
+            unwraps: |tag| tag == SSH_RSA_TAG,
+        });
+    }
+
+    files
+}
+
+/// Reads the identities from the well-known identity files that exist, and might be
+/// able to decrypt a file with the given header.
+///
+/// Files that can't unwrap any of the header's stanzas are skipped without being read,
+/// so the user is only asked for the passphrase of an encrypted key that could be
+/// needed. Files that fail to parse are skipped with a warning.
+pub(crate) fn read_default_identities(
+    header: &HeaderInfo<'_>,
+    max_work_factor: Option<u8>,
+) -> Vec<Box<dyn Identity>> {
+    read_identity_files(
+        default_identity_files(),
+        &stanza_tags(header.stanzas()),
+        max_work_factor,
+    )
+    .into_iter()
+    .map(|identity| Box::new(identity) as Box<dyn Identity>)
+    .collect()
+}
+
+/// Returns the tags of the given stanzas, ignoring grease stanzas that no identity can
+/// unwrap.
+fn stanza_tags(stanzas: &[Stanza]) -> Vec<&str> {
+    stanzas
+        .iter()
+        .map(|s| s.tag.as_str())
+        .filter(|tag| !tag.ends_with(GREASE_SUFFIX))
+        .collect()
+}
+
+/// Reads the identities from those of `files` that exist, and might be able to unwrap a
+/// stanza with one of the given tags.
+fn read_identity_files(
+    files: Vec<DefaultIdentityFile>,
+    tags: &[&str],
+    max_work_factor: Option<u8>,
+) -> Vec<DefaultIdentity> {
+    let mut identities = vec![];
+
+    for file in files {
+        let filename = file.path.display().to_string();
+        if !file.path.is_file() {
+            continue;
+        }
+        if !tags.iter().any(|tag| (file.unwraps)(tag)) {
+            debug!(
+                "{}",
+                i18n_embed_fl::fl!(
+                    LANGUAGE_LOADER,
+                    "debug-default-identity-skipped",
+                    filename = filename.as_str()
+                )
+            );
+            continue;
+        }
+
+        match read_identities(vec![filename.clone()], max_work_factor) {
+            Ok(read) => identities.extend(read.into_iter().map(|inner| DefaultIdentity {
+                filename: filename.clone(),
+                inner,
+            })),
+            // This is printed rather than logged, so that the user can see why a key
+            // they expected to be used was not.
+            Err(e) => eprintln!(
+                "{}",
+                i18n_embed_fl::fl!(
+                    LANGUAGE_LOADER,
+                    "warning-msg",
+                    warning = i18n_embed_fl::fl!(
+                        LANGUAGE_LOADER,
+                        "warn-default-identity-unreadable",
+                        filename = filename.as_str(),
+                        err = e.to_string()
+                    )
+                )
+            ),
+        }
+    }
+
+    identities
+}
+
+/// An identity read from a well-known location, which logs when it is used.
+struct DefaultIdentity {
+    filename: String,
+    inner: Box<dyn Identity>,
+}
+
+impl DefaultIdentity {
+    fn log_success(
+        &self,
+        res: Option<Result<FileKey, DecryptError>>,
+    ) -> Option<Result<FileKey, DecryptError>> {
+        if let Some(Ok(_)) = &res {
+            info!(
+                "{}",
+                i18n_embed_fl::fl!(
+                    LANGUAGE_LOADER,
+                    "info-default-identity-used",
+                    filename = self.filename.as_str()
+                )
+            );
+        }
+        res
+    }
+}
+
+impl Identity for DefaultIdentity {
+    fn unwrap_stanza(&self, stanza: &Stanza) -> Option<Result<FileKey, DecryptError>> {
+        self.log_success(self.inner.unwrap_stanza(stanza))
+    }
+
+    fn unwrap_stanzas(&self, stanzas: &[Stanza]) -> Option<Result<FileKey, DecryptError>> {
+        self.log_success(self.inner.unwrap_stanzas(stanzas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use age_core::format::Stanza;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        default_identity_files, read_identity_files, stanza_tags, DefaultIdentityFile,
+        SSH_ED25519_TAG, SSH_RSA_TAG,
+    };
+
+    const TEST_SK: &str =
+        "AGE-SECRET-KEY-1GQ9778VQXMMJVE8SK7J6VT8UJ4HDQAJUVSFCWCM02D8GEWQ72PVQ2Y5J33";
+
+    #[test]
+    fn default_files_unwrap_by_tag() {
+        for file in default_identity_files() {
+            let unwraps = file.unwraps;
+            match file.path.file_name().unwrap().to_str().unwrap() {
+                "keys.txt" => {
+                    assert!(unwraps("X25519"));
+                    assert!(unwraps("scrypt"));
+                    assert!(unwraps("piv-p256"));
+                    assert!(!unwraps(SSH_ED25519_TAG));
+                    assert!(!unwraps(SSH_RSA_TAG));
+                }
+                "id_ed25519" => {
+                    assert!(unwraps(SSH_ED25519_TAG));
+                    assert!(!unwraps(SSH_RSA_TAG));
+                    assert!(!unwraps("X25519"));
+                }
+                "id_rsa" => {
+                    assert!(unwraps(SSH_RSA_TAG));
+                    assert!(!unwraps(SSH_ED25519_TAG));
+                    assert!(!unwraps("X25519"));
+                }
+                name => panic!("unexpected default identity file {}", name),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_files_by_stanza_tag() {
+        let dir = std::env::temp_dir().join(format!("rage-discover-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Every file contains a valid identity, so we can tell which files were read.
+        for name in &["keys.txt", "id_ed25519", "id_rsa"] {
+            fs::write(dir.join(name), TEST_SK).unwrap();
+        }
+        fs::write(dir.join("invalid.txt"), "not an identity").unwrap();
+
+        let files = |dir: &Path| {
+            vec![
+                DefaultIdentityFile {
+                    path: dir.join("keys.txt"),
+                    unwraps: |tag| tag != SSH_ED25519_TAG && tag != SSH_RSA_TAG,
+                },
+                DefaultIdentityFile {
+                    path: dir.join("missing.txt"),
+                    unwraps: |_| true,
+                },
+                DefaultIdentityFile {
+                    path: dir.join("invalid.txt"),
+                    unwraps: |tag| tag == "X25519",
+                },
+                DefaultIdentityFile {
+                    path: dir.join("id_ed25519"),
+                    unwraps: |tag| tag == SSH_ED25519_TAG,
+                },
+                DefaultIdentityFile {
+                    path: dir.join("id_rsa"),
+                    unwraps: |tag| tag == SSH_RSA_TAG,
+                },
+            ]
+        };
+        let read = |tags: &[&str]| -> Vec<PathBuf> {
+            read_identity_files(files(&dir), tags, None)
+                .into_iter()
+                .map(|identity| PathBuf::from(identity.filename))
+                .collect()
+        };
+
+        // Missing and unreadable files are skipped.
+        assert_eq!(read(&["X25519"]), [dir.join("keys.txt")]);
+        assert_eq!(read(&[SSH_ED25519_TAG]), [dir.join("id_ed25519")]);
+        assert_eq!(
+            read(&[SSH_RSA_TAG, "X25519"]),
+            [dir.join("keys.txt"), dir.join("id_rsa")]
+        );
+        assert_eq!(read(&[SSH_ED25519_TAG, SSH_RSA_TAG]).len(), 2);
+        assert!(read(&[]).is_empty());
+
+        // Grease stanzas don't cause keys.txt to be read for an SSH-only header.
+        let stanza = |tag: &str| Stanza {
+            tag: tag.to_owned(),
+            args: vec![],
+            body: vec![],
+        };
+        let stanzas = [stanza(SSH_ED25519_TAG), stanza("x+Q-grease")];
+        assert_eq!(stanza_tags(&stanzas), [SSH_ED25519_TAG]);
+        assert_eq!(read(&stanza_tags(&stanzas)), [dir.join("id_ed25519")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
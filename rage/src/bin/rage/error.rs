@@ -149,17 +149,21 @@ impl std::error::Error for DetectedPowerShellCorruptionError {}
 pub(crate) enum DecryptError {
     Age(age::DecryptError),
     ArmorFlag,
+    Damaged(usize),
     IdentityRead(age::cli_common::ReadError),
     Io(io::Error),
     MissingIdentities,
     MixedIdentityAndPassphrase,
     MixedIdentityAndPluginName,
+    MixedVerifyAndRecover,
     PassphraseFlag,
     PassphraseTimedOut,
     #[cfg(not(unix))]
     PassphraseWithoutFileArgument,
     RecipientFlag,
     RecipientsFileFlag,
+    Verify(age::VerifyError),
+    VerifyOutputFlag,
 }
 
 impl From<age::DecryptError> for DecryptError {
@@ -168,6 +172,15 @@ impl From<age::DecryptError> for DecryptError {
     }
 }
 
+impl From<age::VerifyError> for DecryptError {
+    fn from(e: age::VerifyError) -> Self {
+        match e {
+            age::VerifyError::Header(e) => DecryptError::Age(e),
+            _ => DecryptError::Verify(e),
+        }
+    }
+}
+
 impl From<age::cli_common::ReadError> for DecryptError {
     fn from(e: age::cli_common::ReadError) -> Self {
         DecryptError::IdentityRead(e)
@@ -202,6 +215,18 @@ impl fmt::Display for DecryptError {
                 wlnfl!(f, "err-dec-armor-flag")?;
                 wfl!(f, "rec-dec-armor-flag")
             }
+            DecryptError::Damaged(count) => {
+                writeln!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::LANGUAGE_LOADER,
+                        "err-dec-recover-damaged",
+                        count = *count
+                    )
+                )?;
+                wfl!(f, "rec-dec-recover-damaged")
+            }
             DecryptError::IdentityRead(e) => write!(f, "{}", e),
             DecryptError::Io(e) => write!(f, "{}", e),
             DecryptError::MissingIdentities => {
@@ -214,6 +239,7 @@ impl fmt::Display for DecryptError {
             DecryptError::MixedIdentityAndPluginName => {
                 wfl!(f, "err-mixed-identity-and-plugin-name")
             }
+            DecryptError::MixedVerifyAndRecover => wfl!(f, "err-dec-mixed-verify-recover"),
             DecryptError::PassphraseFlag => {
                 wlnfl!(f, "err-dec-passphrase-flag")?;
                 wfl!(f, "rec-dec-passphrase-flag")
@@ -231,16 +257,115 @@ impl fmt::Display for DecryptError {
                 wlnfl!(f, "err-dec-recipients-file-flag")?;
                 wfl!(f, "rec-dec-recipient-flag")
             }
+            DecryptError::Verify(e) => write!(f, "{}", e),
+            DecryptError::VerifyOutputFlag => wfl!(f, "err-dec-verify-output-flag"),
+        }
+    }
+}
+
+pub(crate) enum ConfigError {
+    AliasCycle(String),
+    Io(String, io::Error),
+    Parse {
+        path: String,
+        line: Option<(usize, String)>,
+        err: String,
+    },
+    UnknownAlias(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::AliasCycle(alias) => {
+                writeln!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::LANGUAGE_LOADER,
+                        "err-config-alias-cycle",
+                        alias = alias.as_str()
+                    )
+                )?;
+                wfl!(f, "rec-config")
+            }
+            ConfigError::Io(path, e) => {
+                writeln!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::LANGUAGE_LOADER,
+                        "err-config-read",
+                        path = path.as_str(),
+                        err = e.to_string()
+                    )
+                )?;
+                wfl!(f, "rec-config")
+            }
+            ConfigError::Parse { path, line, err } => {
+                writeln!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::LANGUAGE_LOADER,
+                        "err-config-parse",
+                        path = path.as_str(),
+                        err = err.as_str()
+                    )
+                )?;
+                // Show the line that failed to parse.
+                if let Some((line, text)) = line {
+                    writeln!(f, "  {} | {}", line, text)?;
+                }
+                wfl!(f, "rec-config")
+            }
+            ConfigError::UnknownAlias(alias) => {
+                writeln!(
+                    f,
+                    "{}",
+                    fl!(
+                        crate::LANGUAGE_LOADER,
+                        "err-config-unknown-alias",
+                        alias = alias.as_str()
+                    )
+                )?;
+                wfl!(f, "rec-config-unknown-alias")
+            }
         }
     }
 }
 
 pub(crate) enum Error {
+    Config(ConfigError),
     Decryption(DecryptError),
     Encryption(EncryptError),
     IdentityFlagAmbiguous,
+    InPlaceUnsupportedMode,
+    InPlaceWithoutInput,
+    InspectUnsupportedMode,
+    JsonWithoutInspect,
     MixedEncryptAndDecrypt,
+    MixedInPlaceAndOutput,
+    MixedInspectAndWhoCanDecrypt,
+    MixedRekeyAndEncryptOrDecrypt,
+    RecoverWithoutDecrypt,
+    Recursive {
+        decrypt: bool,
+        total: usize,
+        failures: Vec<(String, String)>,
+    },
+    RecursiveInputNotDirectory(String),
+    RecursiveUnsupportedMode,
+    RecursiveWithoutInput,
+    RecursiveWithoutOutput,
     SameInputAndOutput(String),
+    VerifyWithoutDecrypt,
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Error::Config(e)
+    }
 }
 
 impl From<DecryptError> for Error {
@@ -260,10 +385,64 @@ impl From<EncryptError> for Error {
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::Config(e) => writeln!(f, "{}", e)?,
             Error::Decryption(e) => writeln!(f, "{}", e)?,
             Error::Encryption(e) => writeln!(f, "{}", e)?,
             Error::IdentityFlagAmbiguous => wlnfl!(f, "err-identity-ambiguous")?,
+            Error::InPlaceUnsupportedMode => wlnfl!(f, "err-in-place-unsupported-mode")?,
+            Error::InPlaceWithoutInput => wlnfl!(f, "err-in-place-without-input")?,
+            Error::InspectUnsupportedMode => wlnfl!(f, "err-inspect-unsupported-mode")?,
+            Error::JsonWithoutInspect => wlnfl!(f, "err-json-without-inspect")?,
             Error::MixedEncryptAndDecrypt => wlnfl!(f, "err-mixed-encrypt-decrypt")?,
+            Error::MixedInPlaceAndOutput => wlnfl!(f, "err-mixed-in-place-output")?,
+            Error::MixedInspectAndWhoCanDecrypt => wlnfl!(f, "err-mixed-inspect-who-can-decrypt")?,
+            Error::MixedRekeyAndEncryptOrDecrypt => wlnfl!(f, "err-mixed-rekey")?,
+            Error::RecoverWithoutDecrypt => wlnfl!(f, "err-recover-without-decrypt")?,
+            Error::Recursive {
+                decrypt,
+                total,
+                failures,
+            } => {
+                let count = failures.len();
+                if *decrypt {
+                    writeln!(
+                        f,
+                        "{}",
+                        fl!(
+                            crate::LANGUAGE_LOADER,
+                            "err-recursive-decrypt-failures",
+                            count = count,
+                            total = *total
+                        )
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        "{}",
+                        fl!(
+                            crate::LANGUAGE_LOADER,
+                            "err-recursive-encrypt-failures",
+                            count = count,
+                            total = *total
+                        )
+                    )?;
+                }
+                for (filename, e) in failures {
+                    writeln!(f, "  {}: {}", filename, e.trim_end())?;
+                }
+            }
+            Error::RecursiveInputNotDirectory(filename) => writeln!(
+                f,
+                "{}",
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "err-recursive-input-not-directory",
+                    filename = filename.as_str()
+                )
+            )?,
+            Error::RecursiveUnsupportedMode => wlnfl!(f, "err-recursive-unsupported-mode")?,
+            Error::RecursiveWithoutInput => wlnfl!(f, "err-recursive-without-input")?,
+            Error::RecursiveWithoutOutput => wlnfl!(f, "err-recursive-without-output")?,
             Error::SameInputAndOutput(filename) => writeln!(
                 f,
                 "{}",
@@ -273,6 +452,7 @@ impl fmt::Debug for Error {
                     filename = filename.as_str()
                 )
             )?,
+            Error::VerifyWithoutDecrypt => wlnfl!(f, "err-verify-without-decrypt")?,
         }
         writeln!(f)?;
         writeln!(f, "[ {} ]", crate::fl!("err-ux-A"))?;
@@ -0,0 +1,420 @@
+//! Inspection of age files without decrypting them.
+
+use age::{
+    armor::ArmoredReader,
+    cli_common::file_io,
+    decryptor::HeaderInfo,
+    inspect::{Inspection, RecipientMatch},
+    plugin,
+};
+use age_core::format::Stanza;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+use crate::{error, AgeOptions};
+
+/// The non-secret details of a recipient stanza.
+enum StanzaDetails<'a> {
+    X25519 {
+        ephemeral_share: &'a str,
+    },
+    Ssh {
+        key_tag: &'a str,
+    },
+    Scrypt {
+        work_factor: Option<u8>,
+    },
+    /// A stanza created by a plugin, or of another type we don't know about. Plugin
+    /// stanzas are identified only by their tag, as age files don't record which plugin
+    /// created them.
+    Other,
+}
+
+impl<'a> StanzaDetails<'a> {
+    fn new(stanza: &'a Stanza) -> Self {
+        match (stanza.tag.as_str(), stanza.args.as_slice()) {
+            ("X25519", [ephemeral_share]) => StanzaDetails::X25519 { ephemeral_share },
+            ("ssh-ed25519" | "ssh-rsa", [key_tag, ..]) => StanzaDetails::Ssh { key_tag },
+            ("scrypt", [_salt, log_n]) => StanzaDetails::Scrypt {
+                work_factor: log_n.parse().ok(),
+            },
+            _ => StanzaDetails::Other,
+        }
+    }
+}
+
+fn stanza_json(stanza: &Stanza) -> serde_json::Value {
+    match StanzaDetails::new(stanza) {
+        StanzaDetails::X25519 { ephemeral_share } => json!({
+            "type": stanza.tag,
+            "ephemeral_share": ephemeral_share,
+        }),
+        StanzaDetails::Ssh { key_tag } => json!({
+            "type": stanza.tag,
+            "key_tag": key_tag,
+        }),
+        StanzaDetails::Scrypt { work_factor } => json!({
+            "type": stanza.tag,
+            "work_factor": work_factor,
+        }),
+        StanzaDetails::Other => json!({
+            "type": stanza.tag,
+            "args": stanza.args,
+        }),
+    }
+}
+
+fn stanza_text(stanza: &Stanza) -> String {
+    match StanzaDetails::new(stanza) {
+        StanzaDetails::X25519 { ephemeral_share } => i18n_embed_fl::fl!(
+            crate::LANGUAGE_LOADER,
+            "inspect-stanza-x25519",
+            share = ephemeral_share
+        ),
+        StanzaDetails::Ssh { key_tag } => i18n_embed_fl::fl!(
+            crate::LANGUAGE_LOADER,
+            "inspect-stanza-ssh",
+            stanza_type = stanza.tag.as_str(),
+            key_tag = key_tag
+        ),
+        StanzaDetails::Scrypt { work_factor } => i18n_embed_fl::fl!(
+            crate::LANGUAGE_LOADER,
+            "inspect-stanza-scrypt",
+            work_factor = work_factor.map_or_else(|| "?".to_owned(), |wf| wf.to_string())
+        ),
+        StanzaDetails::Other => i18n_embed_fl::fl!(
+            crate::LANGUAGE_LOADER,
+            "inspect-stanza-other",
+            stanza_type = stanza.tag.as_str(),
+            args = stanza.args.join(" ")
+        ),
+    }
+}
+
+/// Prints the structure of the age file at `opts.input`, without decrypting it.
+pub(crate) fn run(opts: AgeOptions) -> Result<(), error::DecryptError> {
+    let (input, mut output) =
+        crate::set_up_io(opts.input, opts.output, file_io::OutputFormat::Text, false)?;
+
+    write_inspection(input, opts.json, &mut output)?;
+    output.commit()?;
+    Ok(())
+}
+
+/// Writes the structure of the age file read from `input` to `output`, as text or as
+/// JSON.
+fn write_inspection(
+    input: impl Read,
+    json: bool,
+    mut output: impl Write,
+) -> Result<(), error::DecryptError> {
+    let mut input = ArmoredReader::new(input);
+    let inspection = Inspection::new(&mut input)?;
+    let armored = input.is_armored().unwrap_or(false);
+    let header = inspection.header();
+
+    if json {
+        let report = json!({
+            "version": inspection.version(),
+            "armored": armored,
+            "header_size": header.encoded_len(),
+            "stanzas": header.stanzas().iter().map(stanza_json).collect::<Vec<_>>(),
+            "payload_size": inspection.payload_len(),
+            "chunk_count": inspection.chunk_count(),
+            "final_chunk_well_formed": inspection.last_chunk_is_well_formed(),
+        });
+        writeln!(output, "{}", report)?;
+    } else {
+        writeln!(
+            output,
+            "{}",
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "inspect-version",
+                version = inspection.version()
+            )
+        )?;
+        writeln!(
+            output,
+            "{}",
+            if armored {
+                crate::fl!("inspect-format-armored")
+            } else {
+                crate::fl!("inspect-format-binary")
+            }
+        )?;
+        writeln!(
+            output,
+            "{}",
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "inspect-header-size",
+                size = header.encoded_len()
+            )
+        )?;
+        writeln!(
+            output,
+            "{}",
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "inspect-stanzas",
+                count = header.stanza_count()
+            )
+        )?;
+        for stanza in header.stanzas() {
+            writeln!(output, "  - {}", stanza_text(stanza))?;
+        }
+        writeln!(
+            output,
+            "{}",
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "inspect-payload-size",
+                size = inspection.payload_len()
+            )
+        )?;
+        writeln!(
+            output,
+            "{}",
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "inspect-chunks",
+                count = inspection.chunk_count()
+            )
+        )?;
+        writeln!(
+            output,
+            "{}",
+            if inspection.last_chunk_is_well_formed() {
+                crate::fl!("inspect-final-chunk-well-formed")
+            } else {
+                crate::fl!("inspect-final-chunk-malformed")
+            }
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A recipient that we can look for in an age file's header.
+enum KnownRecipient {
+    X25519(age::x25519::Recipient),
+    #[cfg(feature = "pq")]
+    PostQuantum(age::pq::Recipient),
+    #[cfg(feature = "ssh")]
+    Ssh(age::ssh::Recipient),
+    Plugin(plugin::Recipient),
+}
+
+impl KnownRecipient {
+    fn parse(filename: &str, s: &str) -> Result<Self, error::EncryptError> {
+        if let Ok(pk) = s.parse() {
+            return Ok(KnownRecipient::X25519(pk));
+        }
+
+        #[cfg(feature = "pq")]
+        if let Ok(pk) = s.parse() {
+            return Ok(KnownRecipient::PostQuantum(pk));
+        }
+
+        #[cfg(feature = "ssh")]
+        match s.parse() {
+            Ok(pk) => return Ok(KnownRecipient::Ssh(pk)),
+            Err(age::ssh::ParseRecipientKeyError::Unsupported(key_type)) => {
+                return Err(error::EncryptError::UnsupportedKey(
+                    filename.to_string(),
+                    age::ssh::UnsupportedKey::Type(key_type),
+                ))
+            }
+            Err(_) => (),
+        }
+
+        #[cfg(not(feature = "ssh"))]
+        let _ = filename;
+
+        s.parse()
+            .map(KnownRecipient::Plugin)
+            .map_err(|_| error::EncryptError::InvalidRecipient(s.to_owned()))
+    }
+
+    fn match_header(&self, header: &HeaderInfo<'_>) -> RecipientMatch {
+        match self {
+            KnownRecipient::X25519(pk) => header.match_x25519_recipient(pk),
+            #[cfg(feature = "pq")]
+            KnownRecipient::PostQuantum(pk) => header.match_pq_recipient(pk),
+            #[cfg(feature = "ssh")]
+            KnownRecipient::Ssh(pk) => header.match_ssh_recipient(pk),
+            KnownRecipient::Plugin(pk) => header.match_plugin_recipient(pk),
+        }
+    }
+}
+
+/// Reports which of the recipients in `opts` the age file at `opts.input` is encrypted
+/// to, without decrypting it.
+pub(crate) fn who_can_decrypt(opts: AgeOptions) -> Result<(), error::Error> {
+    let mut recipients = vec![];
+    for arg in opts.recipient {
+        let recipient = KnownRecipient::parse("", &arg)?;
+        recipients.push((arg, recipient));
+    }
+    for filename in opts.recipients_file {
+        let buf = BufReader::new(File::open(&filename).map_err(error::EncryptError::from)?);
+        crate::read_recipients_list(&filename, buf, |line| {
+            let recipient = KnownRecipient::parse(&filename, &line)?;
+            recipients.push((line, recipient));
+            Ok(())
+        })
+        .map_err(error::EncryptError::from)?;
+    }
+    if recipients.is_empty() {
+        return Err(error::EncryptError::MissingRecipients.into());
+    }
+
+    let (input, mut output) =
+        crate::set_up_io(opts.input, opts.output, file_io::OutputFormat::Text, false)
+            .map_err(error::DecryptError::from)?;
+    let decryptor =
+        age::Decryptor::new(ArmoredReader::new(input)).map_err(error::DecryptError::from)?;
+    let header = decryptor.header();
+
+    let matches = recipients
+        .iter()
+        .map(|(s, recipient)| (s.as_str(), recipient.match_header(&header)));
+
+    write_matches(matches, opts.json, &mut output)
+        .and_then(|()| output.commit())
+        .map_err(|e| error::DecryptError::from(e).into())
+}
+
+fn write_matches<'a>(
+    matches: impl Iterator<Item = (&'a str, RecipientMatch)>,
+    json: bool,
+    mut output: impl Write,
+) -> io::Result<()> {
+    if json {
+        let report = matches
+            .map(|(recipient, m)| {
+                json!({
+                    "recipient": recipient,
+                    "can_decrypt": match m {
+                        RecipientMatch::Found => "yes",
+                        RecipientMatch::NotFound => "no",
+                        RecipientMatch::Unknown => "unknown",
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        writeln!(output, "{}", serde_json::Value::from(report))
+    } else {
+        for (recipient, m) in matches {
+            let line = match m {
+                RecipientMatch::Found => i18n_embed_fl::fl!(
+                    crate::LANGUAGE_LOADER,
+                    "who-can-decrypt-found",
+                    recipient = recipient
+                ),
+                RecipientMatch::NotFound => i18n_embed_fl::fl!(
+                    crate::LANGUAGE_LOADER,
+                    "who-can-decrypt-not-found",
+                    recipient = recipient
+                ),
+                RecipientMatch::Unknown => i18n_embed_fl::fl!(
+                    crate::LANGUAGE_LOADER,
+                    "who-can-decrypt-unknown",
+                    recipient = recipient
+                ),
+            };
+            writeln!(output, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use i18n_embed::LanguageLoader;
+    use serde_json::json;
+
+    use super::write_inspection;
+    use crate::{LANGUAGE_LOADER, TRANSLATIONS};
+
+    /// An age file with an X25519 stanza and a grease stanza, and an empty payload.
+    fn test_file() -> Vec<u8> {
+        let mut file = b"age-encryption.org/v1
+-> X25519 /Gt0E6JT7yuYHlwsGW5LbpEEJawOc+QMeMAS+hoOIgw
+tsxuQrj+TuoGouNB1O0VshA9vsHGurn0Dtw5e7bkw9Q
+-> a-grease b
+c3RhbnphIGJvZHk
+--- jQNSF6blozj2QFYJ/2iqy0wUcPuz/8vCS7RgKH8wjNI
+"
+        .to_vec();
+        // The nonce, and a single empty chunk.
+        file.extend_from_slice(&[0; 32]);
+        file
+    }
+
+    fn inspect(file: &[u8], json: bool) -> Vec<u8> {
+        let mut output = vec![];
+        if let Err(e) = write_inspection(file, json, &mut output) {
+            panic!("{}", e);
+        }
+        output
+    }
+
+    #[test]
+    fn json_output() {
+        let report: serde_json::Value =
+            serde_json::from_slice(&inspect(&test_file(), true)).expect("output is valid JSON");
+        assert_eq!(
+            report,
+            json!({
+                "version": "v1",
+                "armored": false,
+                "header_size": 198,
+                "stanzas": [
+                    {
+                        "type": "X25519",
+                        "ephemeral_share": "/Gt0E6JT7yuYHlwsGW5LbpEEJawOc+QMeMAS+hoOIgw",
+                    },
+                    {
+                        "type": "a-grease",
+                        "args": ["b"],
+                    },
+                ],
+                "payload_size": 32,
+                "chunk_count": 1,
+                "final_chunk_well_formed": true,
+            })
+        );
+
+        // A truncated payload is reported as malformed.
+        let file = test_file();
+        let report: serde_json::Value =
+            serde_json::from_slice(&inspect(&file[..file.len() - 1], true)).unwrap();
+        assert_eq!(report["payload_size"], 31);
+        assert_eq!(report["final_chunk_well_formed"], false);
+    }
+
+    #[test]
+    fn text_output() {
+        LANGUAGE_LOADER
+            .load_languages(&TRANSLATIONS, &[LANGUAGE_LOADER.fallback_language()])
+            .unwrap();
+        LANGUAGE_LOADER.set_use_isolating(false);
+
+        assert_eq!(
+            String::from_utf8(inspect(&test_file(), false)).unwrap(),
+            "Version: v1
+Format: binary
+Header size: 198 bytes
+Recipient stanzas: 2
+  - X25519 (ephemeral share /Gt0E6JT7yuYHlwsGW5LbpEEJawOc+QMeMAS+hoOIgw)
+  - a-grease (plugin or unknown recipient type) b
+Payload size: 32 bytes
+Chunks: 1
+Final chunk: well-formed
+"
+        );
+    }
+}
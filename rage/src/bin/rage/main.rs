@@ -6,9 +6,10 @@ use age::{
         file_io, read_identities, read_or_generate_passphrase, read_secret, Passphrase, UiCallbacks,
     },
     plugin,
-    secrecy::ExposeSecret,
+    secrecy::{ExposeSecret, SecretString},
     Identity, IdentityFile, IdentityFileEntry, Recipient,
 };
+use age_core::format::{FileKey, Stanza};
 use gumdrop::{Options, ParsingStyle};
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
@@ -16,11 +17,18 @@ use i18n_embed::{
 };
 use lazy_static::lazy_static;
 use rust_embed::RustEmbed;
+use std::cell::Cell;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::path::Path;
+use std::rc::Rc;
 
+mod config;
+mod discover;
 mod error;
+mod inspect;
+mod progress;
+mod recursive;
 
 #[derive(RustEmbed)]
 #[folder = "i18n"]
@@ -61,6 +69,23 @@ fn parse_recipient(
 ) -> Result<(), error::EncryptError> {
     if let Ok(pk) = s.parse::<age::x25519::Recipient>() {
         recipients.push(Box::new(pk));
+    } else if let Some(pk) = {
+        #[cfg(feature = "pq")]
+        {
+            s.parse::<age::pq::Recipient>().ok()
+        }
+
+        #[cfg(not(feature = "pq"))]
+        None
+    } {
+        #[cfg(feature = "pq")]
+        {
+            recipients.push(Box::new(pk));
+        }
+
+        // Add a binding to provide a type when post-quantum keys are disabled.
+        #[cfg(not(feature = "pq"))]
+        let _: () = pk;
     } else if let Some(pk) = {
         #[cfg(feature = "ssh")]
         {
@@ -89,12 +114,11 @@ fn parse_recipient(
     Ok(())
 }
 
-/// Reads file contents as a list of recipients
+/// Reads file contents as a list of recipients, passing each one to `parse`.
 fn read_recipients_list<R: BufRead>(
     filename: &str,
     buf: R,
-    recipients: &mut Vec<Box<dyn Recipient>>,
-    plugin_recipients: &mut Vec<plugin::Recipient>,
+    mut parse: impl FnMut(String) -> Result<(), error::EncryptError>,
 ) -> io::Result<()> {
     for (line_number, line) in buf.lines().enumerate() {
         let line = line?;
@@ -102,7 +126,7 @@ fn read_recipients_list<R: BufRead>(
         // Skip empty lines and comments
         if line.is_empty() || line.find('#') == Some(0) {
             continue;
-        } else if let Err(e) = parse_recipient(filename, line, recipients, plugin_recipients) {
+        } else if let Err(e) = parse(line) {
             #[cfg(feature = "ssh")]
             if matches!(e, error::EncryptError::UnsupportedKey(_, _)) {
                 return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
@@ -142,7 +166,9 @@ fn read_recipients(
     for arg in recipients_file_strings {
         let f = File::open(&arg)?;
         let buf = BufReader::new(f);
-        read_recipients_list(&arg, buf, &mut recipients, &mut plugin_recipients)?;
+        read_recipients_list(&arg, buf, |line| {
+            parse_recipient(&arg, line, &mut recipients, &mut plugin_recipients)
+        })?;
     }
 
     for filename in identity_strings {
@@ -190,6 +216,8 @@ fn read_recipients(
         for entry in identity_file.into_identities() {
             match entry {
                 IdentityFileEntry::Native(i) => recipients.push(Box::new(i.to_public())),
+                #[cfg(feature = "pq")]
+                IdentityFileEntry::PostQuantum(i) => recipients.push(Box::new(i.to_public())),
                 IdentityFileEntry::Plugin(i) => plugin_identities.push(i),
             }
         }
@@ -234,6 +262,24 @@ struct AgeOptions {
     #[options(help = "Decrypt the input.")]
     decrypt: bool,
 
+    #[options(
+        help = "Verify that the input decrypts, without writing any output. Requires --decrypt.",
+        no_short
+    )]
+    verify: bool,
+
+    #[options(
+        help = "Recover as much of a damaged input as possible. Requires --decrypt.",
+        no_short
+    )]
+    recover: bool,
+
+    #[options(
+        help = "Re-encrypt the input's header to new recipients, without re-encrypting the payload.",
+        no_short
+    )]
+    rekey: bool,
+
     #[options(help = "Encrypt with a passphrase instead of recipients.")]
     passphrase: bool,
 
@@ -268,21 +314,94 @@ struct AgeOptions {
 
     #[options(help = "Write the result to the file at path OUTPUT.")]
     output: Option<String>,
+
+    #[options(
+        help = "Encrypt or decrypt every file in the directory INPUT to the directory OUTPUT.",
+        no_short
+    )]
+    recursive: bool,
+
+    #[options(
+        help = "Replace INPUT with the result, only once it has been written successfully.",
+        no_short
+    )]
+    in_place: bool,
+
+    #[options(
+        help = "Print the structure of the input, without decrypting it.",
+        no_short
+    )]
+    inspect: bool,
+
+    #[options(
+        help = "Report which of the given recipients the input is encrypted to, without decrypting it.",
+        no_short
+    )]
+    who_can_decrypt: bool,
+
+    #[options(
+        help = "Print the output of --inspect or --who-can-decrypt as JSON.",
+        no_short
+    )]
+    json: bool,
+
+    #[options(
+        help = "Show a progress bar while encrypting or decrypting, if standard error is a terminal.",
+        no_short
+    )]
+    progress: bool,
+
+    #[options(help = "Don't read the config file.", no_short)]
+    no_config: bool,
 }
 
 fn set_up_io(
     input: Option<String>,
     output: Option<String>,
     output_format: file_io::OutputFormat,
+    in_place: bool,
 ) -> io::Result<(file_io::InputReader, file_io::OutputWriter)> {
+    let output = match (in_place, &input) {
+        // Write to a temporary file that replaces the input when committed. It is only
+        // readable by the user until then, when it is given the input's permissions.
+        (true, Some(filename)) => Some(file_io::OutputWriter::new_in_place(
+            filename.clone(),
+            0o600,
+        )?),
+        _ => None,
+    };
+
     let input = file_io::InputReader::new(input)?;
 
     // Create an output to the user-requested location.
-    let output = file_io::OutputWriter::new(output, output_format, 0o666, input.is_terminal())?;
+    let output = match output {
+        Some(output) => output,
+        None => file_io::OutputWriter::new(output, output_format, 0o666, input.is_terminal())?,
+    };
 
     Ok((input, output))
 }
 
+/// Inputs at least this large are encrypted or decrypted in parallel.
+const PARALLEL_THRESHOLD: u64 = 4 * 1024 * 1024;
+
+/// The number of 64 KiB chunks to encrypt or decrypt at a time in parallel.
+const PARALLEL_CHUNKS: usize = 64;
+
+/// Returns `true` if the input is a file that is large enough to be worth encrypting or
+/// decrypting in parallel.
+fn is_large_input(input: &file_io::InputReader) -> bool {
+    input_len(input).map_or(false, |len| len >= PARALLEL_THRESHOLD)
+}
+
+/// Returns the length of the input, if it is a file.
+fn input_len(input: &file_io::InputReader) -> Option<u64> {
+    match input {
+        file_io::InputReader::File(f) => f.metadata().map(|metadata| metadata.len()).ok(),
+        file_io::InputReader::Stdin(_) => None,
+    }
+}
+
 type ReadCheckerMatchCase = (&'static [u8], Box<dyn FnOnce() -> io::Result<()>>);
 type ReadCheckerMatcher = Option<(&'static [u8], usize, Box<dyn FnOnce() -> io::Result<()>>)>;
 
@@ -326,34 +445,75 @@ impl<R: io::Read, const N: usize> io::Read for ReadChecker<R, N> {
     }
 }
 
-fn encrypt(opts: AgeOptions) -> Result<(), error::EncryptError> {
-    if !opts.plugin_name.is_empty() {
-        return Err(error::EncryptError::PluginNameFlag);
+/// A recipient that can be shared between several encryptors.
+struct SharedRecipient(Rc<dyn Recipient>);
+
+impl Recipient for SharedRecipient {
+    fn wrap_file_key(&self, file_key: &FileKey) -> Result<Vec<Stanza>, age::EncryptError> {
+        self.0.wrap_file_key(file_key)
+    }
+}
+
+/// The passphrase or recipients to encrypt to.
+///
+/// These are parsed (and any passphrase is requested) once, and can then be used to
+/// encrypt several files.
+enum EncryptionKeys {
+    Passphrase(SecretString),
+    Recipients(Vec<Rc<dyn Recipient>>),
+}
+
+impl EncryptionKeys {
+    /// Returns an encryptor for a single file.
+    fn encryptor(&self) -> age::Encryptor {
+        match self {
+            EncryptionKeys::Passphrase(passphrase) => age::Encryptor::with_user_passphrase(
+                SecretString::new(passphrase.expose_secret().clone()),
+            ),
+            EncryptionKeys::Recipients(recipients) => age::Encryptor::with_recipients(
+                recipients
+                    .iter()
+                    .map(|r| Box::new(SharedRecipient(r.clone())) as Box<dyn Recipient>)
+                    .collect(),
+            ),
+        }
     }
+}
 
-    let encryptor = if opts.passphrase {
-        if !opts.identity.is_empty() {
+/// Reads the keys to encrypt to from the given passphrase flag and recipient arguments.
+///
+/// Returns `Ok(None)` if the user cancelled the passphrase prompt.
+fn read_encryption_keys(
+    passphrase: bool,
+    has_file_argument: bool,
+    recipient: Vec<String>,
+    recipients_file: Vec<String>,
+    identity: Vec<String>,
+    max_work_factor: Option<u8>,
+) -> Result<Option<EncryptionKeys>, error::EncryptError> {
+    let keys = if passphrase {
+        if !identity.is_empty() {
             return Err(error::EncryptError::MixedIdentityAndPassphrase);
         }
-        if !opts.recipient.is_empty() {
+        if !recipient.is_empty() {
             return Err(error::EncryptError::MixedRecipientAndPassphrase);
         }
-        if !opts.recipients_file.is_empty() {
+        if !recipients_file.is_empty() {
             return Err(error::EncryptError::MixedRecipientsFileAndPassphrase);
         }
 
-        if opts.input.is_none() {
+        if !has_file_argument {
             return Err(error::EncryptError::PassphraseWithoutFileArgument);
         }
 
         match read_or_generate_passphrase() {
-            Ok(Passphrase::Typed(passphrase)) => age::Encryptor::with_user_passphrase(passphrase),
+            Ok(Passphrase::Typed(passphrase)) => EncryptionKeys::Passphrase(passphrase),
             Ok(Passphrase::Generated(new_passphrase)) => {
                 eprintln!("{}", fl!("autogenerated-passphrase"));
                 eprintln!("    {}", new_passphrase.expose_secret());
-                age::Encryptor::with_user_passphrase(new_passphrase)
+                EncryptionKeys::Passphrase(new_passphrase)
             }
-            Err(pinentry::Error::Cancelled) => return Ok(()),
+            Err(pinentry::Error::Cancelled) => return Ok(None),
             Err(pinentry::Error::Timeout) => return Err(error::EncryptError::PassphraseTimedOut),
             Err(pinentry::Error::Encoding(e)) => {
                 // Pretend it is an I/O error
@@ -372,33 +532,76 @@ fn encrypt(opts: AgeOptions) -> Result<(), error::EncryptError> {
             Err(pinentry::Error::Io(e)) => return Err(error::EncryptError::Io(e)),
         }
     } else {
-        if opts.recipient.is_empty() && opts.recipients_file.is_empty() && opts.identity.is_empty()
-        {
+        if recipient.is_empty() && recipients_file.is_empty() && identity.is_empty() {
             return Err(error::EncryptError::MissingRecipients);
         }
 
-        age::Encryptor::with_recipients(read_recipients(
-            opts.recipient,
-            opts.recipients_file,
-            opts.identity,
-            opts.max_work_factor,
-        )?)
+        EncryptionKeys::Recipients(
+            read_recipients(recipient, recipients_file, identity, max_work_factor)?
+                .into_iter()
+                .map(Rc::from)
+                .collect(),
+        )
     };
 
-    let (format, output_format) = if opts.armor {
+    Ok(Some(keys))
+}
+
+fn encrypt(opts: AgeOptions) -> Result<(), error::EncryptError> {
+    if !opts.plugin_name.is_empty() {
+        return Err(error::EncryptError::PluginNameFlag);
+    }
+
+    let keys = match read_encryption_keys(
+        opts.passphrase,
+        opts.input.is_some(),
+        opts.recipient,
+        opts.recipients_file,
+        opts.identity,
+        opts.max_work_factor,
+    )? {
+        Some(keys) => keys,
+        None => return Ok(()),
+    };
+
+    let (format, output_format) = output_format(opts.armor);
+
+    let (input, output) = set_up_io(opts.input, opts.output, output_format, opts.in_place)?;
+
+    encrypt_stream(keys.encryptor(), input, output, format, opts.progress)
+}
+
+/// Returns the armor format and output format for the given armor flag.
+fn output_format(armor: bool) -> (Format, file_io::OutputFormat) {
+    if armor {
         (Format::AsciiArmor, file_io::OutputFormat::Text)
     } else {
         (Format::Binary, file_io::OutputFormat::Binary)
-    };
-
-    let (input, output) = set_up_io(opts.input, opts.output, output_format)?;
+    }
+}
 
+/// Encrypts `input` to `output` with the given encryptor.
+///
+/// If `progress` is true, a progress bar is shown while encrypting.
+fn encrypt_stream(
+    encryptor: age::Encryptor,
+    input: file_io::InputReader,
+    output: file_io::OutputWriter,
+    format: Format,
+    progress: bool,
+) -> Result<(), error::EncryptError> {
     let is_stdout = match output {
         file_io::OutputWriter::File(..) => false,
         file_io::OutputWriter::Stdout(..) => true,
     };
 
     let mut output = encryptor.wrap_output(ArmoredWriter::wrap_output(output, format)?)?;
+    if is_large_input(&input) {
+        output = output.with_parallelism(PARALLEL_CHUNKS);
+    }
+    if let Some(bar) = progress.then(progress::ProgressBar::for_stderr).flatten() {
+        output = output.with_progress(input_len(&input), bar);
+    }
 
     // Give more useful errors specifically when writing to the output.
     let map_io_errors = |e: io::Error| match e.kind() {
@@ -430,21 +633,89 @@ fn encrypt(opts: AgeOptions) -> Result<(), error::EncryptError> {
     output
         .finish()
         .and_then(|armor| armor.finish())
+        .and_then(|output| output.commit())
         .map_err(map_io_errors)?;
 
     Ok(())
 }
 
-fn write_output<R: io::Read, W: io::Write>(
-    mut input: R,
-    mut output: W,
+/// Decrypts `input` to `output`.
+///
+/// If `progress` is true, a progress bar is shown while decrypting, out of `total` bytes
+/// if the length of the plaintext is known.
+fn write_output<R: io::Read>(
+    mut input: age::stream::StreamReader<R>,
+    mut output: file_io::OutputWriter,
+    parallel: bool,
+    progress: bool,
+    total: Option<u64>,
 ) -> Result<(), error::DecryptError> {
+    if parallel {
+        input = input.with_parallelism(PARALLEL_CHUNKS);
+    }
+    if let Some(bar) = progress.then(progress::ProgressBar::for_stderr).flatten() {
+        input = input.with_progress(total, bar);
+    }
     io::copy(&mut input, &mut output)?;
+    // Close the input before committing, as Windows won't replace an open file.
+    drop(input);
+    output.commit()?;
 
     Ok(())
 }
 
-fn decrypt(opts: AgeOptions) -> Result<(), error::DecryptError> {
+/// Writes the recoverable chunks of a damaged age file to `output`, and reports each
+/// damaged range to standard error.
+///
+/// Each damaged chunk is replaced with zeroes of the same length as its plaintext, so
+/// that recovered data stays at the same offsets as in the original plaintext.
+fn write_recovered_output<R: io::Read>(
+    chunks: age::stream::RecoveredChunks<R>,
+    mut output: file_io::OutputWriter,
+) -> Result<(), error::DecryptError> {
+    let mut damaged = 0;
+    for chunk in chunks {
+        match chunk? {
+            age::stream::RecoveredChunk::Authenticated { plaintext, .. } => {
+                output.write_all(plaintext.expose_secret())?
+            }
+            age::stream::RecoveredChunk::Damaged(damage) => {
+                let reason = match damage.kind() {
+                    age::stream::DamageKind::TagMismatch => fl!("recover-damage-tag-mismatch"),
+                    age::stream::DamageKind::Truncated => fl!("recover-damage-truncated"),
+                    age::stream::DamageKind::MissingLastChunk => {
+                        fl!("recover-damage-missing-last-chunk")
+                    }
+                    age::stream::DamageKind::TrailingData => fl!("recover-damage-trailing-data"),
+                };
+                eprintln!(
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        LANGUAGE_LOADER,
+                        "recover-damage",
+                        index = damage.chunk_index(),
+                        offset = damage.offset(),
+                        len = damage.len(),
+                        plaintext_offset = damage.plaintext_offset(),
+                        reason = reason,
+                    )
+                );
+                io::copy(&mut io::repeat(0).take(damage.plaintext_len()), &mut output)?;
+                damaged += 1;
+            }
+        }
+    }
+    output.commit()?;
+
+    if damaged > 0 {
+        Err(error::DecryptError::Damaged(damaged))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks for flags that can't be used when decrypting.
+fn check_decrypt_flags(opts: &AgeOptions) -> Result<(), error::DecryptError> {
     if opts.armor {
         return Err(error::DecryptError::ArmorFlag);
     }
@@ -463,10 +734,71 @@ fn decrypt(opts: AgeOptions) -> Result<(), error::DecryptError> {
         return Err(error::DecryptError::MixedIdentityAndPluginName);
     }
 
+    Ok(())
+}
+
+/// Reads the identities to decrypt with from the identity files, or constructs the
+/// default identity for the given plugin.
+///
+/// If neither identity files nor a plugin are given, the `default_identity` files (from
+/// the config file) are used instead, or if there are none of those, the identities in
+/// well-known locations that might be able to decrypt a file with the given `header`.
+fn read_decryption_identities(
+    identity: Vec<String>,
+    default_identity: Vec<String>,
+    plugin_name: &str,
+    max_work_factor: Option<u8>,
+    header: &age::decryptor::HeaderInfo<'_>,
+) -> Result<Vec<Box<dyn Identity>>, error::DecryptError> {
+    let identities = if !plugin_name.is_empty() {
+        // Construct the default plugin.
+        vec![Box::new(plugin::IdentityPluginV1::new(
+            plugin_name,
+            &[plugin::Identity::default_for_plugin(plugin_name)],
+            UiCallbacks,
+        )?) as Box<dyn Identity>]
+    } else if !identity.is_empty() {
+        read_identities(identity, max_work_factor)?
+    } else if !default_identity.is_empty() {
+        read_identities(default_identity, max_work_factor)?
+    } else {
+        discover::read_default_identities(header, max_work_factor)
+    };
+
+    if identities.is_empty() {
+        return Err(error::DecryptError::MissingIdentities);
+    }
+
+    Ok(identities)
+}
+
+fn decrypt(opts: AgeOptions, default_identity: Vec<String>) -> Result<(), error::DecryptError> {
+    check_decrypt_flags(&opts)?;
+
+    if opts.verify && opts.output.is_some() {
+        return Err(error::DecryptError::VerifyOutputFlag);
+    }
+    if opts.verify && opts.recover {
+        return Err(error::DecryptError::MixedVerifyAndRecover);
+    }
+
     #[cfg(not(unix))]
     let has_file_argument = opts.input.is_some();
 
-    let (input, output) = set_up_io(opts.input, opts.output, file_io::OutputFormat::Unknown)?;
+    // When verifying, we don't write any output.
+    let (input, output) = if opts.verify {
+        (file_io::InputReader::new(opts.input)?, None)
+    } else {
+        let (input, output) = set_up_io(
+            opts.input,
+            opts.output,
+            file_io::OutputFormat::Unknown,
+            opts.in_place,
+        )?;
+        (input, Some(output))
+    };
+    let parallel = is_large_input(&input);
+    let input_len = input_len(&input);
 
     // CRLF_MANGLED_INTRO and UTF16_MANGLED_INTRO are the intro lines of the age format after
     // mangling by various versions of PowerShell redirection, truncated to the length of the
@@ -481,15 +813,40 @@ fn decrypt(opts: AgeOptions) -> Result<(), error::DecryptError> {
         ))
     });
 
+    // We can only compute the plaintext length from the input length if the input is
+    // not armored.
+    const BINARY_INTRO: &[u8] = b"age-encryption.org/v1\n";
+    let is_binary = Rc::new(Cell::new(false));
+    let set_binary = {
+        let is_binary = is_binary.clone();
+        Box::new(move || {
+            is_binary.set(true);
+            Ok(())
+        })
+    };
+
     let input = ReadChecker::new(
         input,
         [
             (CRLF_MANGLED_INTRO, err_powershell_corruption.clone()),
             (UTF16_MANGLED_INTRO, err_powershell_corruption),
+            (BINARY_INTRO, set_binary),
         ],
     );
 
-    match age::Decryptor::new(ArmoredReader::new(input))? {
+    let decryptor = age::Decryptor::new(ArmoredReader::new(input))?;
+
+    // The payload follows the header and the 16-byte payload nonce.
+    let header_len = match &decryptor {
+        age::Decryptor::Recipients(d) => d.header().encoded_len() as u64,
+        age::Decryptor::Passphrase(d) => d.header().encoded_len() as u64,
+    };
+    let total = input_len
+        .filter(|_| is_binary.get())
+        .and_then(|len| len.checked_sub(header_len + 16))
+        .and_then(age::stream::plaintext_len);
+
+    match decryptor {
         age::Decryptor::Passphrase(decryptor) => {
             if !opts.identity.is_empty() {
                 return Err(error::DecryptError::MixedIdentityAndPassphrase);
@@ -505,10 +862,22 @@ fn decrypt(opts: AgeOptions) -> Result<(), error::DecryptError> {
             }
 
             match read_secret(&fl!("type-passphrase"), &fl!("prompt-passphrase"), None) {
-                Ok(passphrase) => decryptor
-                    .decrypt(&passphrase, opts.max_work_factor)
-                    .map_err(|e| e.into())
-                    .and_then(|input| write_output(input, output)),
+                Ok(passphrase) => match output {
+                    Some(output) if opts.recover => decryptor
+                        .recover(&passphrase, opts.max_work_factor)
+                        .map_err(|e| e.into())
+                        .and_then(|chunks| write_recovered_output(chunks, output)),
+                    Some(output) => decryptor
+                        .decrypt(&passphrase, opts.max_work_factor)
+                        .map_err(|e| e.into())
+                        .and_then(|input| {
+                            write_output(input, output, parallel, opts.progress, total)
+                        }),
+                    None => decryptor
+                        .verify(&passphrase, opts.max_work_factor)
+                        .map(|_| ())
+                        .map_err(|e| e.into()),
+                },
                 Err(pinentry::Error::Cancelled) => Ok(()),
                 Err(pinentry::Error::Timeout) => Err(error::DecryptError::PassphraseTimedOut),
                 Err(pinentry::Error::Encoding(e)) => {
@@ -529,27 +898,130 @@ fn decrypt(opts: AgeOptions) -> Result<(), error::DecryptError> {
             }
         }
         age::Decryptor::Recipients(decryptor) => {
-            let identities = if opts.plugin_name.is_empty() {
-                read_identities(opts.identity, opts.max_work_factor)?
-            } else {
-                // Construct the default plugin.
-                vec![Box::new(plugin::IdentityPluginV1::new(
-                    &opts.plugin_name,
-                    &[plugin::Identity::default_for_plugin(&opts.plugin_name)],
-                    UiCallbacks,
-                )?) as Box<dyn Identity>]
-            };
-
-            if identities.is_empty() {
-                return Err(error::DecryptError::MissingIdentities);
+            let identities = read_decryption_identities(
+                opts.identity,
+                default_identity,
+                &opts.plugin_name,
+                opts.max_work_factor,
+                &decryptor.header(),
+            )?;
+
+            let identities = identities.iter().map(|i| i.as_ref() as &dyn Identity);
+            match output {
+                Some(output) if opts.recover => decryptor
+                    .recover(identities)
+                    .map_err(|e| e.into())
+                    .and_then(|chunks| write_recovered_output(chunks, output)),
+                Some(output) => decryptor
+                    .decrypt(identities)
+                    .map_err(|e| e.into())
+                    .and_then(|input| write_output(input, output, parallel, opts.progress, total)),
+                None => decryptor
+                    .verify(identities)
+                    .map(|_| ())
+                    .map_err(|e| e.into()),
+            }
+        }
+    }
+}
+
+fn rekey(opts: AgeOptions, default_identity: Vec<String>) -> Result<(), error::Error> {
+    if !(opts.identity.is_empty() || opts.plugin_name.is_empty()) {
+        return Err(error::DecryptError::MixedIdentityAndPluginName.into());
+    }
+
+    let (format, output_format) = output_format(opts.armor);
+
+    let has_file_argument = opts.input.is_some();
+
+    let (input, output) = set_up_io(opts.input, opts.output, output_format, opts.in_place)
+        .map_err(error::DecryptError::from)?;
+
+    // Recover the file key from the existing header.
+    let decryptor =
+        age::Decryptor::new(ArmoredReader::new(input)).map_err(error::DecryptError::from)?;
+    let rekeyer = match decryptor {
+        age::Decryptor::Passphrase(decryptor) => {
+            if !opts.identity.is_empty() {
+                return Err(error::DecryptError::MixedIdentityAndPassphrase.into());
             }
 
+            // The `rpassword` crate opens `/dev/tty` directly on Unix, so we don't have
+            // any conflict with stdin.
+            #[cfg(not(unix))]
+            {
+                if !has_file_argument {
+                    return Err(error::DecryptError::PassphraseWithoutFileArgument.into());
+                }
+            }
+
+            match read_secret(&fl!("type-passphrase"), &fl!("prompt-passphrase"), None) {
+                Ok(passphrase) => decryptor
+                    .rekey(&passphrase, opts.max_work_factor)
+                    .map_err(error::DecryptError::from)?,
+                Err(pinentry::Error::Cancelled) => return Ok(()),
+                Err(pinentry::Error::Timeout) => {
+                    return Err(error::DecryptError::PassphraseTimedOut.into())
+                }
+                Err(pinentry::Error::Encoding(e)) => {
+                    // Pretend it is an I/O error
+                    return Err(error::DecryptError::Io(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        e,
+                    ))
+                    .into());
+                }
+                Err(pinentry::Error::Gpg(e)) => {
+                    // Pretend it is an I/O error
+                    return Err(error::DecryptError::Io(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{}", e),
+                    ))
+                    .into());
+                }
+                Err(pinentry::Error::Io(e)) => return Err(error::DecryptError::Io(e).into()),
+            }
+        }
+        age::Decryptor::Recipients(decryptor) => {
+            let identities = read_decryption_identities(
+                opts.identity,
+                default_identity,
+                &opts.plugin_name,
+                opts.max_work_factor,
+                &decryptor.header(),
+            )?;
+
             decryptor
-                .decrypt(identities.iter().map(|i| i.as_ref() as &dyn Identity))
-                .map_err(|e| e.into())
-                .and_then(|input| write_output(input, output))
+                .rekey(identities.iter().map(|i| i.as_ref() as &dyn Identity))
+                .map_err(error::DecryptError::from)?
         }
-    }
+    };
+
+    // Wrap the file key to the new recipients. Identities were used above to decrypt
+    // the existing header, so they are not treated as recipients here.
+    let keys = match read_encryption_keys(
+        opts.passphrase,
+        has_file_argument,
+        opts.recipient,
+        opts.recipients_file,
+        vec![],
+        opts.max_work_factor,
+    )? {
+        Some(keys) => keys,
+        None => return Ok(()),
+    };
+
+    rekeyer
+        .rekey_to(
+            keys.encryptor(),
+            ArmoredWriter::wrap_output(output, format).map_err(error::EncryptError::from)?,
+        )
+        .map_err(error::EncryptError::from)?
+        .finish()
+        .and_then(|output| output.commit())
+        .map_err(error::EncryptError::from)?;
+
+    Ok(())
 }
 
 fn main() -> Result<(), error::Error> {
@@ -588,6 +1060,10 @@ fn main() -> Result<(), error::Error> {
             "{} --decrypt [-i IDENTITY] [-o OUTPUT] [INPUT]",
             binary_name
         );
+        let usage_c = format!(
+            "{} --rekey [-i IDENTITY] -r RECIPIENT [-a] [-o OUTPUT] [INPUT]",
+            binary_name
+        );
         let example_a = format!("$ {} -o key.txt", keygen_name);
         let example_a_output = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
         let example_b = format!(
@@ -606,6 +1082,7 @@ fn main() -> Result<(), error::Error> {
                 "rage-usage",
                 usage_a = usage_a,
                 usage_b = usage_b,
+                usage_c = usage_c,
                 flags = AgeOptions::usage(),
                 keygen_name = keygen_name,
                 example_a = example_a,
@@ -625,9 +1102,50 @@ fn main() -> Result<(), error::Error> {
         if opts.encrypt && opts.decrypt {
             return Err(error::Error::MixedEncryptAndDecrypt);
         }
-        if !(opts.identity.is_empty() || opts.encrypt || opts.decrypt) {
+        if opts.rekey && (opts.encrypt || opts.decrypt) {
+            return Err(error::Error::MixedRekeyAndEncryptOrDecrypt);
+        }
+        if opts.verify && !opts.decrypt {
+            return Err(error::Error::VerifyWithoutDecrypt);
+        }
+        if opts.recover && !opts.decrypt {
+            return Err(error::Error::RecoverWithoutDecrypt);
+        }
+        if !(opts.identity.is_empty() || opts.encrypt || opts.decrypt || opts.rekey) {
             return Err(error::Error::IdentityFlagAmbiguous);
         }
+        if opts.recursive && (opts.rekey || opts.verify || opts.recover) {
+            return Err(error::Error::RecursiveUnsupportedMode);
+        }
+        if opts.json && !(opts.inspect || opts.who_can_decrypt) {
+            return Err(error::Error::JsonWithoutInspect);
+        }
+        if (opts.inspect || opts.who_can_decrypt)
+            && (opts.encrypt || opts.decrypt || opts.rekey || opts.recursive || opts.in_place)
+        {
+            return Err(error::Error::InspectUnsupportedMode);
+        }
+        if opts.inspect && opts.who_can_decrypt {
+            return Err(error::Error::MixedInspectAndWhoCanDecrypt);
+        }
+        if opts.in_place {
+            if opts.output.is_some() {
+                return Err(error::Error::MixedInPlaceAndOutput);
+            }
+            if opts.recursive || opts.verify || opts.recover {
+                return Err(error::Error::InPlaceUnsupportedMode);
+            }
+            if opts.input.is_none() || opts.input.as_deref() == Some("-") {
+                return Err(error::Error::InPlaceWithoutInput);
+            }
+        }
+
+        let config = if opts.no_config {
+            config::Config::default()
+        } else {
+            config::Config::load()?
+        };
+        let opts = config.apply(opts)?;
 
         if let (Some(in_file), Some(out_file)) = (&opts.input, &opts.output) {
             // Check that the given filenames do not correspond to the same file.
@@ -641,8 +1159,16 @@ fn main() -> Result<(), error::Error> {
             }
         }
 
-        if opts.decrypt {
-            decrypt(opts).map_err(error::Error::from)
+        if opts.inspect {
+            inspect::run(opts).map_err(error::Error::from)
+        } else if opts.who_can_decrypt {
+            inspect::who_can_decrypt(opts)
+        } else if opts.recursive {
+            recursive::run(opts, config.identities)
+        } else if opts.rekey {
+            rekey(opts, config.identities)
+        } else if opts.decrypt {
+            decrypt(opts, config.identities).map_err(error::Error::from)
         } else {
             encrypt(opts).map_err(error::Error::from)
         }
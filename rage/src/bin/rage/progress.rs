@@ -0,0 +1,154 @@
+//! Progress bar for `rage --progress`.
+
+use age::stream::{Progress, ProgressObserver};
+use console::Term;
+use std::time::{Duration, Instant};
+
+use crate::LANGUAGE_LOADER;
+
+/// The minimum time between redraws of the progress bar.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The width of the bar itself, when the terminal is wide enough.
+const BAR_WIDTH: usize = 30;
+
+/// Renders the progress of an encryption or decryption to standard error.
+pub(crate) struct ProgressBar {
+    term: Term,
+    start: Instant,
+    last_draw: Option<Instant>,
+    /// Progress that has not been drawn yet, because it arrived too soon after the
+    /// previous redraw.
+    pending: Option<Progress>,
+}
+
+impl ProgressBar {
+    /// Returns a progress bar, or `None` if standard error is not a terminal.
+    pub(crate) fn for_stderr() -> Option<Self> {
+        if console::user_attended_stderr() {
+            Some(ProgressBar {
+                term: Term::stderr(),
+                start: Instant::now(),
+                last_draw: None,
+                pending: None,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn draw(&mut self, progress: &Progress, now: Instant) {
+        let line = self.render(progress, now.duration_since(self.start));
+        // The progress bar is purely informational, so ignore errors drawing it.
+        let _ = self
+            .term
+            .clear_line()
+            .and_then(|()| self.term.write_str(&line));
+        self.last_draw = Some(now);
+        self.pending = None;
+    }
+
+    fn render(&self, progress: &Progress, elapsed: Duration) -> String {
+        let bytes = progress.bytes();
+        let secs = elapsed.as_secs_f64();
+        let rate = if secs > 0.0 {
+            (bytes as f64 / secs) as u64
+        } else {
+            0
+        };
+        let throughput = format!("{}/s", format_bytes(rate));
+
+        match progress.total_bytes() {
+            Some(total) if total > 0 => {
+                let fraction = (bytes as f64 / total as f64).min(1.0);
+                let filled = (fraction * BAR_WIDTH as f64) as usize;
+                let bar = format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+                let eta = if rate > 0 {
+                    format_duration(total.saturating_sub(bytes) / rate)
+                } else {
+                    "-:--".to_owned()
+                };
+
+                let line = i18n_embed_fl::fl!(
+                    LANGUAGE_LOADER,
+                    "progress-with-total",
+                    percent = format!("{:3.0}%", fraction * 100.0),
+                    bytes = format_bytes(bytes),
+                    total = format_bytes(total),
+                    throughput = throughput,
+                    eta = eta,
+                );
+
+                // Leave out the bar if the terminal is too narrow to show it.
+                let (_, cols) = self.term.size();
+                if line.chars().count() + BAR_WIDTH + 3 <= cols as usize {
+                    format!("{} {}", bar, line)
+                } else {
+                    line
+                }
+            }
+            _ => i18n_embed_fl::fl!(
+                LANGUAGE_LOADER,
+                "progress-without-total",
+                bytes = format_bytes(bytes),
+                throughput = throughput,
+            ),
+        }
+    }
+}
+
+impl ProgressObserver for ProgressBar {
+    fn on_progress(&mut self, progress: Progress) {
+        let now = Instant::now();
+        let finished = progress.total_bytes() == Some(progress.bytes());
+        if !finished
+            && self
+                .last_draw
+                .map_or(false, |last| now.duration_since(last) < REDRAW_INTERVAL)
+        {
+            self.pending = Some(progress);
+        } else {
+            self.draw(&progress, now);
+        }
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        if let Some(progress) = self.pending.take() {
+            self.draw(&progress, Instant::now());
+        }
+
+        // Move past the progress bar so it isn't overwritten by later output.
+        if self.last_draw.is_some() {
+            let _ = self.term.write_line("");
+        }
+    }
+}
+
+/// Formats a number of bytes using binary unit prefixes.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats a number of seconds as `H:MM:SS`, or `M:SS` if less than an hour.
+fn format_duration(secs: u64) -> String {
+    let (hours, mins, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, mins, secs)
+    } else {
+        format!("{}:{:02}", mins, secs)
+    }
+}
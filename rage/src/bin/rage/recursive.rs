@@ -0,0 +1,486 @@
+//! Encryption and decryption of directory trees.
+
+use age::{
+    armor::ArmoredReader,
+    cli_common::{file_io, read_secret},
+    decryptor::HeaderInfo,
+    secrecy::SecretString,
+    Identity,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{error, AgeOptions};
+
+/// The file extension of encrypted files.
+const AGE_EXTENSION: &str = "age";
+
+/// Returns the paths (relative to `root`) of every regular file in the directory tree at
+/// `root`, in sorted order.
+///
+/// Directories that can't be read are added to `failures`.
+fn collect_files(root: &Path, failures: &mut Vec<(String, String)>) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(root.join(&dir))
+            .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                failures.push((root.join(&dir).display().to_string(), e.to_string()));
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let path = dir.join(entry.file_name());
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => dirs.push(path),
+                Ok(file_type) if file_type.is_file() => files.push(path),
+                // Symlinks and special files are skipped, so that we never follow links
+                // out of the tree.
+                Ok(_) => eprintln!(
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        crate::LANGUAGE_LOADER,
+                        "recursive-skip-not-file",
+                        filename = root.join(&path).display().to_string()
+                    )
+                ),
+                Err(e) => failures.push((root.join(&path).display().to_string(), e.to_string())),
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Converts a path into the form expected by [`file_io`].
+fn path_arg(path: &Path) -> io::Result<String> {
+    path.to_str().map(|s| s.to_owned()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            i18n_embed_fl::fl!(
+                crate::LANGUAGE_LOADER,
+                "err-recursive-non-utf8-path",
+                filename = path.display().to_string()
+            ),
+        )
+    })
+}
+
+/// Opens `input`, and an output at `output` (creating its parent directories).
+///
+/// The output is written to a temporary file that only replaces `output` when it is
+/// committed, so that a failure never leaves partial output behind or destroys an
+/// existing file.
+fn set_up_io(
+    input: &Path,
+    output: &Path,
+) -> io::Result<(file_io::InputReader, file_io::OutputWriter)> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // A file that is being replaced gives the temporary file its permissions when
+    // committed, so until then the temporary file is only readable by the user.
+    let mode = if output.exists() { 0o600 } else { 0o666 };
+    let output = file_io::OutputWriter::new_in_place(path_arg(output)?, mode)?;
+    let input = file_io::InputReader::new(Some(path_arg(input)?))?;
+
+    Ok((input, output))
+}
+
+/// The outcome of processing the files in a directory tree.
+struct Summary {
+    total: usize,
+    failures: Vec<(String, String)>,
+}
+
+impl Summary {
+    fn into_result(self, decrypt: bool) -> Result<(), error::Error> {
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(error::Error::Recursive {
+                decrypt,
+                total: self.total,
+                failures: self.failures,
+            })
+        }
+    }
+}
+
+/// Encrypts every file in the directory tree at `input_dir` to the same relative path
+/// (with an `.age` suffix) in `output_dir`.
+fn encrypt(opts: AgeOptions, input_dir: &Path, output_dir: &Path) -> Result<(), error::Error> {
+    if !opts.plugin_name.is_empty() {
+        return Err(error::EncryptError::PluginNameFlag.into());
+    }
+
+    // Recipients are parsed (and any passphrase is requested) once for the whole tree.
+    let keys = match crate::read_encryption_keys(
+        opts.passphrase,
+        true,
+        opts.recipient,
+        opts.recipients_file,
+        opts.identity,
+        opts.max_work_factor,
+    )? {
+        Some(keys) => keys,
+        None => return Ok(()),
+    };
+
+    let (format, _) = crate::output_format(opts.armor);
+
+    let mut failures = vec![];
+    let files = collect_files(input_dir, &mut failures);
+
+    for file in &files {
+        let mut output = file.clone().into_os_string();
+        output.push(".");
+        output.push(AGE_EXTENSION);
+        let output = output_dir.join(output);
+
+        let res = (|| -> Result<(), error::EncryptError> {
+            let (input, output) = set_up_io(&input_dir.join(file), &output)?;
+            crate::encrypt_stream(keys.encryptor(), input, output, format, false)
+        })();
+        if let Err(e) = res {
+            failures.push((file.display().to_string(), e.to_string()));
+        }
+    }
+
+    Summary {
+        total: files.len(),
+        failures,
+    }
+    .into_result(false)
+}
+
+/// The identities or passphrase used to decrypt the files in a directory tree.
+///
+/// Each is only read (or requested from the user) the first time it is needed, and then
+/// reused for every other file.
+struct DecryptionKeys {
+    identity: Vec<String>,
+    default_identity: Vec<String>,
+    plugin_name: String,
+    max_work_factor: Option<u8>,
+    identities: Option<Vec<Box<dyn Identity>>>,
+    passphrase: Option<SecretString>,
+}
+
+impl DecryptionKeys {
+    /// Returns the identities to decrypt with.
+    ///
+    /// `header` is the header of the file being decrypted. If no identities were given,
+    /// it determines which identities in well-known locations are read.
+    fn identities(
+        &mut self,
+        header: &HeaderInfo<'_>,
+    ) -> Result<&[Box<dyn Identity>], error::DecryptError> {
+        if self.identities.is_none() {
+            self.identities = Some(crate::read_decryption_identities(
+                std::mem::take(&mut self.identity),
+                std::mem::take(&mut self.default_identity),
+                &self.plugin_name,
+                self.max_work_factor,
+                header,
+            )?);
+        }
+        Ok(self
+            .identities
+            .as_deref()
+            .expect("identities were just set"))
+    }
+
+    /// Returns `Ok(None)` if the user cancelled the passphrase prompt.
+    fn passphrase(&mut self) -> Result<Option<&SecretString>, error::DecryptError> {
+        if self.passphrase.is_none() {
+            match read_secret(
+                &crate::fl!("type-passphrase"),
+                &crate::fl!("prompt-passphrase"),
+                None,
+            ) {
+                Ok(passphrase) => self.passphrase = Some(passphrase),
+                Err(pinentry::Error::Cancelled) => return Ok(None),
+                Err(pinentry::Error::Timeout) => {
+                    return Err(error::DecryptError::PassphraseTimedOut)
+                }
+                Err(pinentry::Error::Encoding(e)) => {
+                    // Pretend it is an I/O error
+                    return Err(error::DecryptError::Io(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        e,
+                    )));
+                }
+                Err(pinentry::Error::Gpg(e)) => {
+                    // Pretend it is an I/O error
+                    return Err(error::DecryptError::Io(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{}", e),
+                    )));
+                }
+                Err(pinentry::Error::Io(e)) => return Err(error::DecryptError::Io(e)),
+            }
+        }
+        Ok(self.passphrase.as_ref())
+    }
+}
+
+/// Decrypts every file with an `.age` suffix in the directory tree at `input_dir` to the
+/// same relative path (without the suffix) in `output_dir`.
+fn decrypt(
+    opts: AgeOptions,
+    default_identity: Vec<String>,
+    input_dir: &Path,
+    output_dir: &Path,
+) -> Result<(), error::Error> {
+    crate::check_decrypt_flags(&opts)?;
+
+    let mut keys = DecryptionKeys {
+        identity: opts.identity,
+        default_identity,
+        plugin_name: opts.plugin_name,
+        max_work_factor: opts.max_work_factor,
+        identities: None,
+        passphrase: None,
+    };
+
+    let mut failures = vec![];
+    let files: Vec<_> = collect_files(input_dir, &mut failures)
+        .into_iter()
+        .filter(|file| {
+            let encrypted = file.extension().map_or(false, |ext| ext == AGE_EXTENSION);
+            if !encrypted {
+                eprintln!(
+                    "{}",
+                    i18n_embed_fl::fl!(
+                        crate::LANGUAGE_LOADER,
+                        "recursive-skip-not-encrypted",
+                        filename = input_dir.join(file).display().to_string()
+                    )
+                );
+            }
+            encrypted
+        })
+        .collect();
+
+    let max_work_factor = keys.max_work_factor;
+    for file in &files {
+        let output = output_dir.join(file.with_extension(""));
+
+        let res = (|| -> Result<Option<()>, error::DecryptError> {
+            let (input, output) = set_up_io(&input_dir.join(file), &output)?;
+            let parallel = crate::is_large_input(&input);
+
+            let input = match age::Decryptor::new(ArmoredReader::new(input))? {
+                age::Decryptor::Passphrase(decryptor) => {
+                    let passphrase = match keys.passphrase()? {
+                        Some(passphrase) => passphrase,
+                        None => return Ok(None),
+                    };
+                    decryptor.decrypt(passphrase, max_work_factor)?
+                }
+                age::Decryptor::Recipients(decryptor) => {
+                    let identities = keys.identities(&decryptor.header())?;
+                    decryptor.decrypt(identities.iter().map(|i| i.as_ref() as &dyn Identity))?
+                }
+            };
+
+            crate::write_output(input, output, parallel, false, None).map(Some)
+        })();
+
+        match res {
+            Ok(Some(())) => (),
+            // The user cancelled the passphrase prompt.
+            Ok(None) => break,
+            // Problems with the identities or the passphrase prompt would affect every
+            // file, so we stop immediately.
+            Err(
+                e
+                @
+                (error::DecryptError::IdentityEncryptedWithoutPassphrase(_)
+                | error::DecryptError::IdentityNotFound(_)
+                | error::DecryptError::MissingIdentities
+                | error::DecryptError::PassphraseTimedOut),
+            ) => return Err(e.into()),
+            Err(e) => failures.push((file.display().to_string(), e.to_string())),
+        }
+    }
+
+    Summary {
+        total: files.len(),
+        failures,
+    }
+    .into_result(true)
+}
+
+/// Encrypts or decrypts the directory tree at `opts.input` to `opts.output`.
+///
+/// `default_identity` is used to decrypt if no identities are given in `opts`.
+pub(crate) fn run(opts: AgeOptions, default_identity: Vec<String>) -> Result<(), error::Error> {
+    let input_dir = match &opts.input {
+        Some(input) => PathBuf::from(input),
+        None => return Err(error::Error::RecursiveWithoutInput),
+    };
+    let output_dir = match &opts.output {
+        Some(output) => PathBuf::from(output),
+        None => return Err(error::Error::RecursiveWithoutOutput),
+    };
+
+    if !input_dir.is_dir() {
+        return Err(error::Error::RecursiveInputNotDirectory(
+            input_dir.display().to_string(),
+        ));
+    }
+
+    if opts.decrypt {
+        decrypt(opts, default_identity, &input_dir, &output_dir)
+    } else {
+        encrypt(opts, &input_dir, &output_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gumdrop::Options;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{decrypt, encrypt};
+    use crate::{error, AgeOptions};
+
+    const TEST_SK: &str =
+        "AGE-SECRET-KEY-1GQ9778VQXMMJVE8SK7J6VT8UJ4HDQAJUVSFCWCM02D8GEWQ72PVQ2Y5J33";
+    const TEST_PK: &str = "age1t7rxyev2z3rw82stdlrrepyc39nvn86l5078zqkf5uasdy86jp6svpy7pa";
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rage-recursive-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn encrypt_tree(input: &Path, output: &Path) -> Result<(), error::Error> {
+        let opts = AgeOptions::parse_args_default(&["-r", TEST_PK]).unwrap();
+        encrypt(opts, input, output)
+    }
+
+    fn decrypt_tree(dir: &Path, input: &Path, output: &Path) -> Result<(), error::Error> {
+        let key = dir.join("key.txt");
+        fs::write(&key, TEST_SK).unwrap();
+        let opts = AgeOptions::parse_args_default(&["-d", "-i", key.to_str().unwrap()]).unwrap();
+        decrypt(opts, vec![], input, output)
+    }
+
+    #[test]
+    fn mirrors_directory_tree() {
+        let dir = test_dir("mirror");
+        write(&dir.join("plain").join("a.txt"), "a");
+        write(
+            &dir.join("plain").join("sub").join("dir").join("b.txt"),
+            "b",
+        );
+
+        assert!(encrypt_tree(&dir.join("plain"), &dir.join("enc")).is_ok());
+        assert!(dir.join("enc").join("a.txt.age").is_file());
+        assert!(dir
+            .join("enc")
+            .join("sub")
+            .join("dir")
+            .join("b.txt.age")
+            .is_file());
+        assert!(!dir.join("enc").join("a.txt").exists());
+
+        // Files without the `.age` suffix are skipped when decrypting, and the suffix is
+        // stripped from the others.
+        write(&dir.join("enc").join("notes.txt"), "not encrypted");
+        assert!(decrypt_tree(&dir, &dir.join("enc"), &dir.join("dec")).is_ok());
+        assert_eq!(
+            fs::read_to_string(dir.join("dec").join("a.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("dec").join("sub").join("dir").join("b.txt")).unwrap(),
+            "b"
+        );
+        assert!(!dir.join("dec").join("a.txt.age").exists());
+        assert!(!dir.join("dec").join("notes.txt").exists());
+        assert!(!dir.join("dec").join("notes").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_non_regular_files() {
+        let dir = test_dir("special");
+        write(&dir.join("plain").join("a.txt"), "a");
+        std::os::unix::fs::symlink("a.txt", dir.join("plain").join("link")).unwrap();
+        std::os::unix::fs::symlink("..", dir.join("plain").join("parent")).unwrap();
+
+        assert!(encrypt_tree(&dir.join("plain"), &dir.join("enc")).is_ok());
+        let mut entries: Vec<_> = fs::read_dir(dir.join("enc"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, ["a.txt.age"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failure_summary() {
+        let dir = test_dir("failures");
+        write(&dir.join("plain").join("good.txt"), "good");
+        write(&dir.join("plain").join("bad.txt"), "bad");
+        assert!(encrypt_tree(&dir.join("plain"), &dir.join("enc")).is_ok());
+
+        // Corrupt the payload of one of the files.
+        let bad = dir.join("enc").join("bad.txt.age");
+        let mut ciphertext = fs::read(&bad).unwrap();
+        *ciphertext.last_mut().unwrap() ^= 1;
+        fs::write(&bad, ciphertext).unwrap();
+
+        // An existing output is only replaced if its file decrypts successfully.
+        write(&dir.join("dec").join("bad.txt"), "existing");
+
+        match decrypt_tree(&dir, &dir.join("enc"), &dir.join("dec")) {
+            Err(error::Error::Recursive {
+                decrypt,
+                total,
+                failures,
+            }) => {
+                assert!(decrypt);
+                assert_eq!(total, 2);
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].0, "bad.txt");
+            }
+            _ => panic!("expected a summary of the failures"),
+        }
+        assert_eq!(
+            fs::read_to_string(dir.join("dec").join("good.txt")).unwrap(),
+            "good"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("dec").join("bad.txt")).unwrap(),
+            "existing"
+        );
+        assert_eq!(fs::read_dir(dir.join("dec")).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}